@@ -0,0 +1,81 @@
+/// # Пример поиска умных устройств с помощью языка запросов
+/// Запрос передаётся аргументами командной строки, например:
+/// `cargo run --example query -- 'kind=socket AND power>100 AND room~"Kitchen*"'`
+use iot_crate::house::House;
+use iot_crate::hvac::{HvacMode, SmartHvac};
+use iot_crate::room::Room;
+use iot_crate::smart_device::{SmartDevice, SmartDevicePowerState};
+use iot_crate::socket::SmartSocket;
+use iot_crate::thermometer::SmartThermometer;
+use iot_crate::units::{Celsius, Watts};
+use std::env;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let query: Vec<String> = env::args().skip(1).collect();
+    let query = query.join(" ");
+
+    let house = create_house();
+    match house.find_devices_str(&query) {
+        Ok(mut devices) => {
+            devices.sort();
+            for (room_name, device_name) in devices {
+                let path = house
+                    .get_device_path(&room_name, &device_name)
+                    .unwrap_or(device_name);
+                println!("{}", path);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("{}", query);
+            eprintln!("{}^", " ".repeat(error.position));
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Создание демонстрационного дома
+fn create_house() -> House {
+    let mut house = House::new("MyHouse", 3);
+
+    let mut kettle = SmartSocket::new("Kettle");
+    let mut lamp = SmartSocket::new("Lamp");
+    let mut thermometer = SmartThermometer::new("Thermometer");
+    let mut hvac = SmartHvac::new("Hvac");
+
+    if kettle
+        .set_power_state(SmartDevicePowerState::Enabled)
+        .and_then(|_| lamp.set_power_state(SmartDevicePowerState::Enabled))
+        .and_then(|_| thermometer.set_power_state(SmartDevicePowerState::Enabled))
+        .and_then(|_| hvac.set_power_state(SmartDevicePowerState::Enabled))
+        .and_then(|_| hvac.set_mode(HvacMode::Heat))
+        .is_err()
+    {
+        println!("Failed to enable devices!");
+    }
+    kettle.set_power_consumption(Watts(2000.0));
+    lamp.set_power_consumption(Watts(60.0));
+    thermometer.set_temperature(Celsius(19.5));
+
+    for room_name in ["Kitchen", "LivingRoom"] {
+        if house.add_room(Room::new(room_name, 3)).is_err() {
+            println!("Failed to add a {} to a {}!", room_name, house.name);
+        }
+    }
+
+    let devices: [(&str, Box<dyn SmartDevice>); 4] = [
+        ("Kitchen", Box::new(kettle)),
+        ("Kitchen", Box::new(thermometer)),
+        ("LivingRoom", Box::new(lamp)),
+        ("LivingRoom", Box::new(hvac)),
+    ];
+    for (room_name, device) in devices {
+        if house.add_device(room_name, device).is_err() {
+            println!("Failed to add a device to a {}!", room_name);
+        }
+    }
+    house.update_climate();
+    house
+}
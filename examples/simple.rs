@@ -3,11 +3,16 @@
 /// 2. Приведён пример добавления умных девайсов в комнаты
 /// 3. Показан пример управления подачей питания к умному устройству
 /// 4. Пример создания отчета о состоянии дома
+use iot_crate::blinds::SmartBlinds;
 use iot_crate::house::House;
+use iot_crate::hvac::{HvacMode, SmartHvac};
+use iot_crate::lock::SmartLock;
 use iot_crate::room::Room;
 use iot_crate::smart_device::{SmartDevice, SmartDevicePowerState};
 use iot_crate::socket::SmartSocket;
 use iot_crate::thermometer::SmartThermometer;
+use iot_crate::units::Percent;
+use std::time::SystemTime;
 
 fn main() {
     // Создание инстанса умного дома
@@ -20,6 +25,7 @@ fn main() {
     let thermometer1 = SmartThermometer::new("Thermometer1");
     let thermometer2 = SmartThermometer::new("Thermometer2");
     let thermometer3 = SmartThermometer::new("Thermometer3");
+    let mut blinds1 = SmartBlinds::new("Blinds1");
 
     // Включение розетки
     if socket1
@@ -29,6 +35,15 @@ fn main() {
         println!("Socket1 is enabled");
     }
 
+    // Открытие жалюзи наполовину
+    if blinds1
+        .set_power_state(SmartDevicePowerState::Enabled)
+        .and_then(|_| blinds1.set_position(Percent::new(50)))
+        .is_ok()
+    {
+        println!("Blinds1 are moving");
+    }
+
     // Создание комнат (Первый способ)
     let mut living_room = Room::new("LivingRoom", 7);
     let mut kitchen = Room::new("Kitchen", 5);
@@ -40,6 +55,9 @@ fn main() {
     if living_room.add_device(Box::new(thermometer1)).is_err() {
         println!("Failed to add Thermometer1 to a {}!", living_room.name);
     }
+    if living_room.add_device(Box::new(blinds1)).is_err() {
+        println!("Failed to add Blinds1 to a {}!", living_room.name);
+    }
     if kitchen.add_device(Box::new(socket2)).is_err() {
         println!("Failed to add Socket2 to a {}!", kitchen.name);
     }
@@ -62,7 +80,7 @@ fn main() {
         println!("Failed to create a new room!");
     } else {
         // Получение ссылки на инстанс комнаты
-        if let Some(room) = my_house.get_room("Bedroom") {
+        if let Some(room) = my_house.get_room_mut("Bedroom") {
             // Добавление умных девайсов в комнату
             if room.add_device(Box::new(socket3)).is_err() {
                 println!("Failed to add Socket3 to a {}!", room.name);
@@ -71,11 +89,46 @@ fn main() {
             if room.add_device(Box::new(thermometer3)).is_err() {
                 println!("Failed to add Thermometer3 to a {}!", room.name);
             }
+
+            // Кондиционер в режиме автоматического поддержания температуры
+            let mut hvac1 = SmartHvac::new("Hvac1");
+            if hvac1
+                .set_power_state(SmartDevicePowerState::Enabled)
+                .and_then(|_| hvac1.set_mode(HvacMode::Auto))
+                .is_err()
+            {
+                println!("Failed to configure Hvac1!");
+            }
+            if room.add_device(Box::new(hvac1)).is_err() {
+                println!("Failed to add Hvac1 to a {}!", room.name);
+            }
         } else {
             println!("Failed to get a room!");
         }
     }
 
+    // Прихожая с умным замком на входной двери
+    let mut front_door = SmartLock::new("FrontDoor");
+    front_door.add_pin_code("Owner", "2024", SystemTime::now(), None);
+    if front_door
+        .set_power_state(SmartDevicePowerState::Enabled)
+        .and_then(|_| front_door.unlock_with_pin("2024"))
+        .is_ok()
+    {
+        println!("FrontDoor is unlocked");
+    }
+
+    let mut hallway = Room::new("Hallway", 2);
+    if hallway.add_device(Box::new(front_door)).is_err() {
+        println!("Failed to add FrontDoor to a {}!", hallway.name);
+    }
+    if my_house.add_room(hallway).is_err() {
+        println!("Failed to add a Hallway to a {}!", my_house.name);
+    }
+
+    // Передача показаний термометров кондиционерам
+    my_house.update_climate();
+
     // Создание отчета о состоянии дома
     let report = my_house.create_report();
     println!("{}", report);
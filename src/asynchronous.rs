@@ -0,0 +1,603 @@
+//! Модуль содержит асинхронный (tokio) интерфейс умных устройств и умного дома
+//!
+//! > Модуль доступен при включённой cargo-функции `async`. Операции с устройствами
+//! > (изменение состояния питания, получение статуса и показаний) возвращают futures,
+//! > поэтому устройства, опрашиваемые по сети, не блокируют поток исполнения.
+//! > Обычные (синхронные) устройства подключаются через обёртку [`LocalDevice`],
+//! > вызовы которой выполняются в пуле блокирующих задач tokio. Изменения публикуются
+//! > на шине событий дома так же, как в [`SharedHouse`](crate::shared::SharedHouse).
+//!
+use crate::command::Capability;
+use crate::containers::house::House;
+use crate::containers::shared::{recover, unshared_state, ConversionError};
+use crate::containers::{ContainerIOHistory, ContainerName, ErrorReason};
+use crate::events::{EventBus, EventPayload, HouseEvent};
+use crate::metadata::DeviceMetadata;
+use crate::smart_device::{
+    SmartDevice, SmartDeviceErrorCode, SmartDevicePowerState, SmartDeviceStatus,
+};
+use crate::units::Watts;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+
+/// Alias для future, возвращаемого асинхронными операциями устройств
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Трейт, определяющий асинхронный интерфейс умного устройства
+pub trait AsyncSmartDevice: Send + Sync {
+    /// Получение имени устройства
+    fn get_name(&self) -> &str;
+
+    /// Получение метаданных устройства
+    fn get_metadata(&self) -> &DeviceMetadata;
+
+    /// Получение текущего статуса работы устройства
+    fn get_device_status(&self) -> BoxFuture<'_, SmartDeviceStatus>;
+
+    /// Попытка включения/выключения устройства
+    fn set_power_state(
+        &mut self,
+        state: SmartDevicePowerState,
+    ) -> BoxFuture<'_, Result<(), SmartDeviceErrorCode>>;
+
+    /// Получение текущих числовых показаний устройства
+    fn get_readings(&self) -> BoxFuture<'_, Vec<(Capability, f32)>>;
+
+    /// Получение текстовой информации о состоянии устройства
+    fn get_text_report(&self) -> BoxFuture<'_, String>;
+}
+
+/// Обёртка, предоставляющая асинхронный интерфейс синхронного умного устройства
+///
+/// Вызовы устройства выполняются через `tokio::task::spawn_blocking`, поэтому устройство
+/// может выполнять блокирующий ввод-вывод, не задерживая другие задачи; обёртку
+/// можно использовать только внутри среды исполнения tokio
+pub struct LocalDevice {
+    /// Синхронное устройство
+    device: Arc<std::sync::Mutex<Box<dyn SmartDevice>>>,
+
+    /// Имя устройства на момент создания обёртки
+    name: String,
+
+    /// Метаданные устройства на момент создания обёртки
+    metadata: DeviceMetadata,
+}
+
+impl LocalDevice {
+    /// Создание обёртки для устройства `device`
+    pub fn new(device: Box<dyn SmartDevice>) -> Self {
+        Self {
+            name: device.get_name().to_string(),
+            metadata: device.get_metadata().clone(),
+            device: Arc::new(std::sync::Mutex::new(device)),
+        }
+    }
+
+    /// Получение синхронного устройства
+    ///
+    /// Если вызов устройства ещё выполняется в пуле блокирующих задач, получение
+    /// дожидается его окончания
+    pub fn into_inner(self) -> Box<dyn SmartDevice> {
+        let mut shared = self.device;
+        loop {
+            match Arc::try_unwrap(shared) {
+                Ok(device) => return recover(device.into_inner()),
+                Err(still_shared) => {
+                    drop(recover(still_shared.lock()));
+                    shared = still_shared;
+                    std::thread::yield_now();
+                }
+            }
+        }
+    }
+
+    /// Выполнение действия `action` с устройством в пуле блокирующих задач
+    fn run<R, F>(&self, action: F) -> BoxFuture<'static, R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut dyn SmartDevice) -> R + Send + 'static,
+    {
+        let device = Arc::clone(&self.device);
+        Box::pin(async move {
+            let task = tokio::task::spawn_blocking(move || action(recover(device.lock()).as_mut()));
+            match task.await {
+                Ok(result) => result,
+                Err(error) => std::panic::resume_unwind(error.into_panic()),
+            }
+        })
+    }
+}
+
+impl AsyncSmartDevice for LocalDevice {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_metadata(&self) -> &DeviceMetadata {
+        &self.metadata
+    }
+
+    fn get_device_status(&self) -> BoxFuture<'_, SmartDeviceStatus> {
+        self.run(|device| device.get_device_status())
+    }
+
+    fn set_power_state(
+        &mut self,
+        state: SmartDevicePowerState,
+    ) -> BoxFuture<'_, Result<(), SmartDeviceErrorCode>> {
+        self.run(move |device| device.set_power_state(state))
+    }
+
+    fn get_readings(&self) -> BoxFuture<'_, Vec<(Capability, f32)>> {
+        self.run(|device| device.get_readings())
+    }
+
+    fn get_text_report(&self) -> BoxFuture<'_, String> {
+        self.run(|device| device.get_text_report())
+    }
+}
+
+/// Alias для разделяемого асинхронного умного устройства
+type SharedAsyncDevice = Arc<Mutex<Box<dyn AsyncSmartDevice>>>;
+
+/// Комната асинхронного дома
+struct AsyncRoom {
+    /// Умные устройства комнаты
+    devices: RwLock<HashMap<ContainerName, SharedAsyncDevice>>,
+
+    /// Максимальное количество умных устройств в комнате
+    device_limit: usize,
+}
+
+/// Содержимое асинхронного дома
+struct AsyncHouseInner {
+    /// Название дома
+    name: ContainerName,
+
+    /// Комнаты дома
+    rooms: RwLock<HashMap<ContainerName, Arc<AsyncRoom>>>,
+
+    /// Максимальное количество комнат в доме
+    room_limit: usize,
+
+    /// Шина событий дома
+    events: std::sync::Mutex<EventBus>,
+}
+
+/// Тип, описывающий асинхронный дескриптор умного дома
+///
+/// Клоны дескриптора ссылаются на один и тот же дом; блокировки раздельные
+/// для списка комнат, списка устройств комнаты и каждого устройства
+#[derive(Clone)]
+pub struct AsyncHouse {
+    /// Разделяемое содержимое дома
+    inner: Arc<AsyncHouseInner>,
+}
+
+impl AsyncHouse {
+    /// Создание нового дома с именем `name`, в котором может быть не более `limit` комнат
+    pub fn new(name: &str, limit: usize) -> Self {
+        Self {
+            inner: Arc::new(AsyncHouseInner {
+                name: name.to_string(),
+                rooms: RwLock::new(HashMap::new()),
+                room_limit: limit,
+                events: std::sync::Mutex::new(EventBus::new()),
+            }),
+        }
+    }
+
+    /// Получение названия дома
+    pub fn get_name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// Создание новой пустой комнаты, в которой может быть не более `device_limit` устройств
+    pub async fn add_room(
+        &self,
+        room_name: &str,
+        device_limit: usize,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let mut rooms = self.inner.rooms.write().await;
+        if rooms.len() >= self.inner.room_limit {
+            return Err(ErrorReason::ItemLimitExceeded);
+        }
+        if rooms.contains_key(room_name) {
+            return Err(ErrorReason::ItemAlreadyPresented);
+        }
+
+        rooms.insert(
+            room_name.to_string(),
+            Arc::new(AsyncRoom {
+                devices: RwLock::new(HashMap::new()),
+                device_limit,
+            }),
+        );
+        drop(rooms);
+
+        self.publish(HouseEvent::room(room_name, EventPayload::RoomAdded));
+        Ok(format!(
+            "Room {} has been registered in house {}",
+            room_name, self.inner.name
+        ))
+    }
+
+    /// Удаление комнаты из дома
+    pub async fn remove_room(&self, room_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        self.inner
+            .rooms
+            .write()
+            .await
+            .remove(room_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+
+        self.publish(HouseEvent::room(room_name, EventPayload::RoomRemoved));
+        Ok(format!(
+            "Room {} has been removed from house {}",
+            room_name, self.inner.name
+        ))
+    }
+
+    /// Получение списка комнат в доме
+    pub async fn get_room_list(&self) -> Vec<ContainerName> {
+        self.inner.rooms.read().await.keys().cloned().collect()
+    }
+
+    /// Получение списка умных устройств в комнате
+    pub async fn get_device_list(&self, room_name: &str) -> Option<Vec<ContainerName>> {
+        let room = self.get_room(room_name).await.ok()?;
+        let devices = room.devices.read().await;
+        Some(devices.keys().cloned().collect())
+    }
+
+    /// Добавление асинхронного умного устройства в комнату дома
+    pub async fn add_device(
+        &self,
+        room_name: &str,
+        device: Box<dyn AsyncSmartDevice>,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let room = self.get_room(room_name).await?;
+        let mut devices = room.devices.write().await;
+        if devices.len() >= room.device_limit {
+            return Err(ErrorReason::ItemLimitExceeded);
+        }
+
+        let device_name = device.get_name().to_string();
+        if devices.contains_key(&device_name) {
+            return Err(ErrorReason::ItemAlreadyPresented);
+        }
+
+        let status = format!(
+            "Device {} has been registered in room {}",
+            device_name, room_name
+        );
+        devices.insert(device_name.clone(), Arc::new(Mutex::new(device)));
+        drop(devices);
+
+        self.publish(HouseEvent::device(
+            room_name,
+            &device_name,
+            EventPayload::DeviceAdded,
+        ));
+        Ok(status)
+    }
+
+    /// Добавление синхронного умного устройства в комнату дома
+    pub async fn add_local_device(
+        &self,
+        room_name: &str,
+        device: Box<dyn SmartDevice>,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        self.add_device(room_name, Box::new(LocalDevice::new(device)))
+            .await
+    }
+
+    /// Удаление умного устройства из комнаты дома
+    pub async fn remove_device(
+        &self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let room = self.get_room(room_name).await?;
+        room.devices
+            .write()
+            .await
+            .remove(device_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+
+        self.publish(HouseEvent::device(
+            room_name,
+            device_name,
+            EventPayload::DeviceRemoved,
+        ));
+        Ok(format!(
+            "Device {} has been removed from room {}",
+            device_name, room_name
+        ))
+    }
+
+    /// Получение текущего статуса работы умного устройства
+    pub async fn get_device_status(
+        &self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<SmartDeviceStatus, ErrorReason> {
+        let device = self.get_device(room_name, device_name).await?;
+        let device = device.lock().await;
+        Ok(device.get_device_status().await)
+    }
+
+    /// Включение/выключение умного устройства
+    pub async fn set_power_state(
+        &self,
+        room_name: &str,
+        device_name: &str,
+        state: SmartDevicePowerState,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let device = self.get_device(room_name, device_name).await?;
+        let mut device = device.lock().await;
+        // Событие публикуется под блокировкой устройства, как в `SharedHouse`
+        let result = device.set_power_state(state.clone()).await;
+        let payload = match &result {
+            Ok(()) => Some(EventPayload::PowerStateChanged(state.clone())),
+            Err(code) if code.is_fault() => Some(EventPayload::Malfunction(code.clone())),
+            Err(_) => None,
+        };
+        if let Some(payload) = payload {
+            self.publish(HouseEvent::device(room_name, device_name, payload));
+        }
+        drop(device);
+        result.map_err(ErrorReason::DeviceFailure)?;
+
+        Ok(format!(
+            "Device {} in room {} has been switched to {}",
+            device_name, room_name, state
+        ))
+    }
+
+    /// Получение текущих числовых показаний умного устройства
+    pub async fn get_readings(
+        &self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<Vec<(Capability, f32)>, ErrorReason> {
+        let device = self.get_device(room_name, device_name).await?;
+        let device = device.lock().await;
+        Ok(device.get_readings().await)
+    }
+
+    /// Получение суммарной мощности, потребляемой умными устройствами в доме
+    pub async fn get_power_consumption(&self) -> Watts {
+        let mut total = Watts::default();
+        for device in self.get_all_devices().await {
+            let readings = device.lock().await.get_readings().await;
+            total = readings
+                .into_iter()
+                .filter(|(capability, _)| *capability == Capability::PowerMetering)
+                .fold(total, |total, (_, value)| total + Watts(value));
+        }
+        total
+    }
+
+    /// Создание отчёта о состоянии умных устройств дома
+    pub async fn create_report(&self) -> String {
+        let mut report = vec![format!("Smart house instance: {}.\n", self.inner.name)];
+
+        let mut room_names = self.get_room_list().await;
+        room_names.sort();
+        for room_name in room_names {
+            let Some(mut device_names) = self.get_device_list(&room_name).await else {
+                continue;
+            };
+            device_names.sort();
+
+            report.push(format!("Room: {}\n", room_name));
+            for device_name in device_names {
+                if let Ok(device) = self.get_device(&room_name, &device_name).await {
+                    let text = device.lock().await.get_text_report().await;
+                    report.push(format!("Device: {}: {}", device_name, text));
+                }
+            }
+        }
+        report.join(" ")
+    }
+
+    /// Выполнение действия `action` с шиной событий дома (подписка, отписка, настройка)
+    ///
+    /// Обработчики событий вызываются под блокировкой шины, поэтому они не должны
+    /// обращаться к шине событий этого же дома
+    pub fn with_event_bus<R, F>(&self, action: F) -> R
+    where
+        F: FnOnce(&mut EventBus) -> R,
+    {
+        let mut events = recover(self.inner.events.lock());
+        action(&mut events)
+    }
+
+    /// Преобразование синхронного дома в асинхронный
+    ///
+    /// Переносятся комнаты и устройства с их состоянием, а также шина событий с подписками.
+    /// Дом с этажами, зонами, группами, метками или историей показаний не преобразуется
+    pub fn from_house(house: House) -> Result<Self, ConversionError> {
+        let unshared = unshared_state(&house);
+        if !unshared.is_empty() {
+            return Err(ConversionError {
+                house: Box::new(house),
+                unshared,
+            });
+        }
+
+        let parts = house.into_parts();
+        let rooms = parts
+            .rooms
+            .into_iter()
+            .map(|(room_name, room)| {
+                let device_limit = room.get_device_limit();
+                let devices = room
+                    .into_devices()
+                    .into_iter()
+                    .map(|(device_name, device)| {
+                        let device: Box<dyn AsyncSmartDevice> = Box::new(LocalDevice::new(device));
+                        (device_name, Arc::new(Mutex::new(device)))
+                    })
+                    .collect();
+                let room = AsyncRoom {
+                    devices: RwLock::new(devices),
+                    device_limit,
+                };
+                (room_name, Arc::new(room))
+            })
+            .collect();
+
+        Ok(Self {
+            inner: Arc::new(AsyncHouseInner {
+                name: parts.name,
+                rooms: RwLock::new(rooms),
+                room_limit: parts.room_limit,
+                events: std::sync::Mutex::new(parts.events),
+            }),
+        })
+    }
+
+    /// Публикация события на шине событий дома
+    fn publish(&self, event: HouseEvent) {
+        recover(self.inner.events.lock()).publish(event);
+    }
+
+    /// Получение комнаты по имени
+    async fn get_room(&self, room_name: &str) -> Result<Arc<AsyncRoom>, ErrorReason> {
+        self.inner
+            .rooms
+            .read()
+            .await
+            .get(room_name)
+            .cloned()
+            .ok_or(ErrorReason::ItemDoesntExist)
+    }
+
+    /// Получение умного устройства по имени комнаты и устройства
+    async fn get_device(
+        &self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<SharedAsyncDevice, ErrorReason> {
+        let room = self.get_room(room_name).await?;
+        let devices = room.devices.read().await;
+        devices
+            .get(device_name)
+            .cloned()
+            .ok_or(ErrorReason::ItemDoesntExist)
+    }
+
+    /// Получение всех умных устройств дома
+    async fn get_all_devices(&self) -> Vec<SharedAsyncDevice> {
+        let rooms: Vec<Arc<AsyncRoom>> = self.inner.rooms.read().await.values().cloned().collect();
+        let mut devices = Vec::new();
+        for room in rooms {
+            devices.extend(room.devices.read().await.values().cloned());
+        }
+        devices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::EventFilter;
+    use crate::metadata::DeviceKind;
+    use crate::room::Room;
+    use crate::socket::SmartSocket;
+    use std::time::Duration;
+
+    /// Устройство, отвечающее с задержкой (имитация опроса по сети)
+    struct RemoteSwitch {
+        metadata: DeviceMetadata,
+        state: SmartDevicePowerState,
+    }
+
+    impl AsyncSmartDevice for RemoteSwitch {
+        fn get_name(&self) -> &str {
+            "Remote"
+        }
+
+        fn get_metadata(&self) -> &DeviceMetadata {
+            &self.metadata
+        }
+
+        fn get_device_status(&self) -> BoxFuture<'_, SmartDeviceStatus> {
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                SmartDeviceStatus::PowerState(self.state.clone())
+            })
+        }
+
+        fn set_power_state(
+            &mut self,
+            state: SmartDevicePowerState,
+        ) -> BoxFuture<'_, Result<(), SmartDeviceErrorCode>> {
+            Box::pin(async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                self.state = state;
+                Ok(())
+            })
+        }
+
+        fn get_readings(&self) -> BoxFuture<'_, Vec<(Capability, f32)>> {
+            Box::pin(async move { vec![(Capability::PowerMetering, 5.0)] })
+        }
+
+        fn get_text_report(&self) -> BoxFuture<'_, String> {
+            Box::pin(async move { format!("Remote switch: {}", self.state) })
+        }
+    }
+
+    #[tokio::test]
+    async fn remote_and_local_devices_share_async_house() {
+        let mut house = House::new("House", 2);
+        assert!(house.add_room(Room::new("Kitchen", 2)).is_ok());
+        assert!(house
+            .add_device("Kitchen", Box::new(SmartSocket::new("Socket")))
+            .is_ok());
+
+        let async_house = AsyncHouse::from_house(house).unwrap();
+        let (_, receiver) =
+            async_house.with_event_bus(|events| events.subscribe_channel(EventFilter::all()));
+        let remote = RemoteSwitch {
+            metadata: DeviceMetadata::new(DeviceKind::Other, "Remote switch", "remote-switch"),
+            state: SmartDevicePowerState::Disabled,
+        };
+        assert!(async_house
+            .add_device("Kitchen", Box::new(remote))
+            .await
+            .is_ok());
+
+        let (local, remote) = tokio::join!(
+            async_house.set_power_state("Kitchen", "Socket", SmartDevicePowerState::Enabled),
+            async_house.set_power_state("Kitchen", "Remote", SmartDevicePowerState::Enabled),
+        );
+        assert!(local.is_ok() && remote.is_ok());
+        let events: Vec<&str> = receiver
+            .try_iter()
+            .map(|event| event.payload.get_name())
+            .collect();
+        assert_eq!(
+            events,
+            vec!["device_added", "power_state_changed", "power_state_changed"]
+        );
+
+        assert_eq!(
+            async_house.get_device_status("Kitchen", "Remote").await,
+            Ok(SmartDeviceStatus::PowerState(
+                SmartDevicePowerState::Enabled
+            ))
+        );
+        assert_eq!(async_house.get_power_consumption().await, Watts(5.0));
+        assert_eq!(
+            async_house.get_readings("Hall", "Remote").await,
+            Err(ErrorReason::ItemDoesntExist)
+        );
+    }
+}
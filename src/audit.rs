@@ -0,0 +1,726 @@
+//! Модуль содержит журнал аудита операций с содержимым умного дома
+//!
+//! > Каждая операция, выполняемая через `House` (добавление, удаление, переименование
+//! > комнат, устройств, этажей и зон, перенос устройств, размещение комнат на этажах
+//! > и в зонах, метки и группы устройств, изменение состояния питания, выполнение команд),
+//! > записывается в журнал
+//! > вместе с моментом выполнения, инициатором и результатом, включая ошибки. Журнал
+//! > можно фильтровать и выгружать в формате JSON Lines (одна JSON-запись на строку).
+//! > Размер журнала ограничен: при переполнении вытесняются самые старые записи.
+//! > Чтобы вытесненные записи не терялись, журнал может дописывать каждую запись
+//! > в приёмник (например, файл) сразу после выполнения операции.
+//!
+use crate::command::{SmartDeviceCommandError, SmartDeviceResponse};
+use crate::containers::{ContainerIOHistory, ContainerName, ErrorReason};
+use crate::json::{device_error_name, error_name, json_string, power_state_name};
+use crate::smart_device::SmartDevicePowerState;
+use std::collections::VecDeque;
+use std::fmt::{self, Debug};
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Инициатор операций по умолчанию
+pub const DEFAULT_ACTOR: &str = "system";
+
+/// Максимальное количество записей журнала по умолчанию
+pub const DEFAULT_AUDIT_CAPACITY: usize = 10_000;
+
+/// Перечисление операций, записываемых в журнал аудита
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuditOperation {
+    /// Добавление комнаты
+    AddRoom,
+    /// Удаление комнаты
+    RemoveRoom,
+    /// Добавление устройства
+    AddDevice,
+    /// Удаление устройства
+    RemoveDevice,
+    /// Изменение состояния питания устройства
+    SetPowerState(SmartDevicePowerState),
+    /// Переименование комнаты
+    RenameRoom {
+        /// Новое название комнаты
+        new_name: ContainerName,
+    },
+    /// Переименование устройства
+    RenameDevice {
+        /// Новое название устройства
+        new_name: ContainerName,
+    },
+    /// Перенос устройства в другую комнату
+    MoveDevice {
+        /// Комната, в которую переносится устройство
+        to_room: ContainerName,
+    },
+    /// Добавление этажа
+    AddFloor {
+        /// Название этажа
+        floor: ContainerName,
+    },
+    /// Удаление этажа
+    RemoveFloor {
+        /// Название этажа
+        floor: ContainerName,
+    },
+    /// Размещение комнаты на этаже
+    PlaceRoom {
+        /// Этаж, на котором размещается комната
+        floor: ContainerName,
+    },
+    /// Добавление зоны
+    AddZone {
+        /// Название зоны
+        zone: ContainerName,
+    },
+    /// Удаление зоны
+    RemoveZone {
+        /// Название зоны
+        zone: ContainerName,
+    },
+    /// Включение комнаты в зону
+    AddRoomToZone {
+        /// Название зоны
+        zone: ContainerName,
+    },
+    /// Исключение комнаты из зоны
+    RemoveRoomFromZone {
+        /// Название зоны
+        zone: ContainerName,
+    },
+    /// Добавление метки устройству
+    AddTag {
+        /// Метка
+        tag: String,
+    },
+    /// Удаление метки устройства
+    RemoveTag {
+        /// Метка
+        tag: String,
+    },
+    /// Добавление группы устройств
+    AddGroup {
+        /// Название группы
+        group: ContainerName,
+    },
+    /// Удаление группы устройств
+    RemoveGroup {
+        /// Название группы
+        group: ContainerName,
+    },
+    /// Выполнение универсальной команды устройством
+    Execute {
+        /// Имя команды (аргументы, например PIN-код, не записываются)
+        command: String,
+    },
+}
+
+impl AuditOperation {
+    /// Получение имени операции в формате snake_case
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::AddRoom => "add_room",
+            Self::RemoveRoom => "remove_room",
+            Self::AddDevice => "add_device",
+            Self::RemoveDevice => "remove_device",
+            Self::SetPowerState(_) => "set_power_state",
+            Self::RenameRoom { .. } => "rename_room",
+            Self::RenameDevice { .. } => "rename_device",
+            Self::MoveDevice { .. } => "move_device",
+            Self::AddFloor { .. } => "add_floor",
+            Self::RemoveFloor { .. } => "remove_floor",
+            Self::PlaceRoom { .. } => "place_room",
+            Self::AddZone { .. } => "add_zone",
+            Self::RemoveZone { .. } => "remove_zone",
+            Self::AddRoomToZone { .. } => "add_room_to_zone",
+            Self::RemoveRoomFromZone { .. } => "remove_room_from_zone",
+            Self::AddTag { .. } => "add_tag",
+            Self::RemoveTag { .. } => "remove_tag",
+            Self::AddGroup { .. } => "add_group",
+            Self::RemoveGroup { .. } => "remove_group",
+            Self::Execute { .. } => "execute",
+        }
+    }
+}
+
+/// Получение результата выполнения команды `command` для записи в журнал аудита
+pub(crate) fn command_outcome(
+    room_name: &str,
+    device_name: &str,
+    command: &str,
+    result: &Result<Result<SmartDeviceResponse, SmartDeviceCommandError>, ErrorReason>,
+) -> Result<ContainerIOHistory, ErrorReason> {
+    match result {
+        Ok(Ok(_)) => Ok(format!(
+            "Device {} in room {} has executed command {}",
+            device_name, room_name, command
+        )),
+        Ok(Err(error)) => Err(error.clone().into()),
+        Err(reason) => Err(reason.clone()),
+    }
+}
+
+/// Запись журнала аудита
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditEntry {
+    /// Порядковый номер записи
+    pub sequence: u64,
+
+    /// Момент выполнения операции
+    pub timestamp: SystemTime,
+
+    /// Инициатор операции
+    pub actor: String,
+
+    /// Комната, над которой (или в которой) выполнялась операция
+    /// (пустая строка - операция над этажом или зоной)
+    pub room: ContainerName,
+
+    /// Устройство, над которым выполнялась операция (`None` - операция над комнатой)
+    pub device: Option<ContainerName>,
+
+    /// Операция
+    pub operation: AuditOperation,
+
+    /// Результат операции
+    pub outcome: Result<ContainerIOHistory, ErrorReason>,
+}
+
+impl AuditEntry {
+    /// Проверка, завершилась ли операция успешно
+    pub fn is_success(&self) -> bool {
+        self.outcome.is_ok()
+    }
+
+    /// Представление записи в виде одной строки JSON (без перевода строки)
+    pub fn to_json_line(&self) -> String {
+        let millis = self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis())
+            .unwrap_or_default();
+
+        let mut fields = vec![
+            format!("\"sequence\":{}", self.sequence),
+            format!("\"timestamp\":{}", millis),
+            format!("\"actor\":{}", json_string(&self.actor)),
+            format!("\"operation\":\"{}\"", self.operation.get_name()),
+            format!("\"room\":{}", json_string(&self.room)),
+            format!(
+                "\"device\":{}",
+                self.device
+                    .as_deref()
+                    .map_or("null".to_string(), json_string)
+            ),
+        ];
+
+        match &self.operation {
+            AuditOperation::SetPowerState(state) => {
+                fields.push(format!("\"power_state\":\"{}\"", power_state_name(state)));
+            }
+            AuditOperation::RenameRoom { new_name } | AuditOperation::RenameDevice { new_name } => {
+                fields.push(format!("\"new_name\":{}", json_string(new_name)));
+            }
+            AuditOperation::MoveDevice { to_room } => {
+                fields.push(format!("\"to_room\":{}", json_string(to_room)));
+            }
+            AuditOperation::AddFloor { floor }
+            | AuditOperation::RemoveFloor { floor }
+            | AuditOperation::PlaceRoom { floor } => {
+                fields.push(format!("\"floor\":{}", json_string(floor)));
+            }
+            AuditOperation::AddZone { zone }
+            | AuditOperation::RemoveZone { zone }
+            | AuditOperation::AddRoomToZone { zone }
+            | AuditOperation::RemoveRoomFromZone { zone } => {
+                fields.push(format!("\"zone\":{}", json_string(zone)));
+            }
+            AuditOperation::AddTag { tag } | AuditOperation::RemoveTag { tag } => {
+                fields.push(format!("\"tag\":{}", json_string(tag)));
+            }
+            AuditOperation::AddGroup { group } | AuditOperation::RemoveGroup { group } => {
+                fields.push(format!("\"group\":{}", json_string(group)));
+            }
+            AuditOperation::Execute { command } => {
+                fields.push(format!("\"command\":{}", json_string(command)));
+            }
+            _ => {}
+        }
+
+        match &self.outcome {
+            Ok(message) => {
+                fields.push("\"outcome\":\"ok\"".to_string());
+                fields.push(format!("\"message\":{}", json_string(message)));
+            }
+            Err(reason) => {
+                fields.push("\"outcome\":\"error\"".to_string());
+                fields.push(format!("\"error\":\"{}\"", error_name(reason)));
+                if let ErrorReason::DeviceFailure(code) = reason {
+                    fields.push(format!("\"device_error\":\"{}\"", device_error_name(code)));
+                }
+            }
+        }
+
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+/// Фильтр записей журнала аудита
+///
+/// Пустой список (или `None`) означает "любое значение"
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AuditFilter {
+    /// Инициаторы операций
+    pub actors: Vec<String>,
+
+    /// Комнаты
+    pub rooms: Vec<ContainerName>,
+
+    /// Устройства
+    pub devices: Vec<ContainerName>,
+
+    /// Начало интервала времени (включительно)
+    pub from: Option<SystemTime>,
+
+    /// Конец интервала времени (включительно)
+    pub to: Option<SystemTime>,
+
+    /// Только неуспешные операции
+    pub failures_only: bool,
+}
+
+impl AuditFilter {
+    /// Создание фильтра, пропускающего все записи
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Добавление инициатора в фильтр
+    pub fn actor(mut self, actor: &str) -> Self {
+        self.actors.push(actor.to_string());
+        self
+    }
+
+    /// Добавление комнаты в фильтр
+    pub fn room(mut self, room: &str) -> Self {
+        self.rooms.push(room.to_string());
+        self
+    }
+
+    /// Добавление устройства в фильтр
+    pub fn device(mut self, device: &str) -> Self {
+        self.devices.push(device.to_string());
+        self
+    }
+
+    /// Ограничение интервала времени `[from, to]`
+    pub fn between(mut self, from: SystemTime, to: SystemTime) -> Self {
+        self.from = Some(from);
+        self.to = Some(to);
+        self
+    }
+
+    /// Отбор только неуспешных операций
+    pub fn failures(mut self) -> Self {
+        self.failures_only = true;
+        self
+    }
+
+    /// Проверка, удовлетворяет ли запись фильтру
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        let actor_matches = self.actors.is_empty() || self.actors.contains(&entry.actor);
+        let room_matches = self.rooms.is_empty() || self.rooms.contains(&entry.room);
+        let device_matches = self.devices.is_empty()
+            || entry
+                .device
+                .as_ref()
+                .is_some_and(|device| self.devices.contains(device));
+        let time_matches = self.from.is_none_or(|from| entry.timestamp >= from)
+            && self.to.is_none_or(|to| entry.timestamp <= to);
+        let outcome_matches = !self.failures_only || !entry.is_success();
+
+        actor_matches && room_matches && device_matches && time_matches && outcome_matches
+    }
+}
+
+/// Тип, описывающий журнал аудита
+pub struct AuditLog {
+    /// Записи журнала в порядке выполнения операций
+    entries: VecDeque<AuditEntry>,
+
+    /// Максимальное количество хранимых записей
+    capacity: usize,
+
+    /// Порядковый номер следующей записи
+    next_sequence: u64,
+
+    /// Приёмник, в который дописывается каждая запись в формате JSON Lines
+    sink: Option<Box<dyn Write + Send>>,
+
+    /// Количество записей, которые не удалось дописать в приёмник
+    sink_errors: usize,
+}
+
+/// Копия журнала содержит те же записи, но не дописывает новые в приёмник оригинала
+impl Clone for AuditLog {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            capacity: self.capacity,
+            next_sequence: self.next_sequence,
+            sink: None,
+            sink_errors: 0,
+        }
+    }
+}
+
+impl Debug for AuditLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("entries", &self.entries)
+            .field("capacity", &self.capacity)
+            .field("next_sequence", &self.next_sequence)
+            .field("sink", &self.sink.is_some())
+            .field("sink_errors", &self.sink_errors)
+            .finish()
+    }
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_AUDIT_CAPACITY)
+    }
+}
+
+impl AuditLog {
+    /// Создание пустого журнала вместимостью `DEFAULT_AUDIT_CAPACITY` записей
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Создание пустого журнала, хранящего не более `capacity` последних записей
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity,
+            next_sequence: 0,
+            sink: None,
+            sink_errors: 0,
+        }
+    }
+
+    /// Установка приёмника `writer`, в который дописывается каждая новая запись
+    /// в формате JSON Lines
+    ///
+    /// Записи, вытесненные из памяти, остаются в приёмнике
+    pub fn with_sink<W: Write + Send + 'static>(mut self, writer: W) -> Self {
+        self.set_sink(writer);
+        self
+    }
+
+    /// Замена приёмника новых записей на `writer`
+    pub fn set_sink<W: Write + Send + 'static>(&mut self, writer: W) {
+        self.sink = Some(Box::new(writer));
+    }
+
+    /// Получение количества записей, которые не удалось дописать в приёмник
+    pub fn get_sink_errors(&self) -> usize {
+        self.sink_errors
+    }
+
+    /// Изменение максимального количества хранимых записей
+    ///
+    /// Лишние самые старые записи вытесняются сразу
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    /// Получение максимального количества хранимых записей
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Вытеснение самых старых записей сверх вместимости журнала
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Запись результата операции в журнал
+    pub fn record(
+        &mut self,
+        actor: &str,
+        room: &str,
+        device: Option<&str>,
+        operation: AuditOperation,
+        outcome: &Result<ContainerIOHistory, ErrorReason>,
+    ) {
+        self.next_sequence += 1;
+        let entry = AuditEntry {
+            sequence: self.next_sequence,
+            timestamp: SystemTime::now(),
+            actor: actor.to_string(),
+            room: room.to_string(),
+            device: device.map(str::to_string),
+            operation,
+            outcome: outcome.clone(),
+        };
+
+        if let Some(sink) = self.sink.as_mut() {
+            let written = writeln!(sink, "{}", entry.to_json_line()).and_then(|_| sink.flush());
+            if written.is_err() {
+                self.sink_errors += 1;
+            }
+        }
+
+        self.entries.push_back(entry);
+        self.evict();
+    }
+
+    /// Получение всех хранимых записей журнала
+    pub fn get_entries(&self) -> Vec<&AuditEntry> {
+        self.entries.iter().collect()
+    }
+
+    /// Получение записей журнала, удовлетворяющих фильтру `filter`
+    pub fn query(&self, filter: &AuditFilter) -> Vec<&AuditEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| filter.matches(entry))
+            .collect()
+    }
+
+    /// Выгрузка записей, удовлетворяющих фильтру `filter`, в формате JSON Lines
+    ///
+    /// Возвращает количество выгруженных записей
+    pub fn export_json_lines<W: Write>(
+        &self,
+        writer: &mut W,
+        filter: &AuditFilter,
+    ) -> io::Result<usize> {
+        let mut exported = 0;
+        for entry in self.query(filter) {
+            writeln!(writer, "{}", entry.to_json_line())?;
+            exported += 1;
+        }
+        Ok(exported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::house::House;
+    use crate::room::Room;
+    use crate::smart_device::SmartDeviceErrorCode;
+    use crate::socket::SmartSocket;
+
+    #[test]
+    fn house_operations_are_audited() {
+        let mut house = House::new("House", 1);
+        house.set_actor("operator");
+        assert!(house.add_room(Room::new("Kitchen", 1)).is_ok());
+        assert!(house.add_room(Room::new("Hall", 1)).is_err());
+        assert!(house
+            .add_device("Kitchen", Box::new(SmartSocket::new("Socket")))
+            .is_ok());
+        house.set_actor("automation");
+        assert!(house
+            .set_power_state("Kitchen", "Socket", SmartDevicePowerState::Enabled)
+            .is_ok());
+        assert!(house.remove_device("Kitchen", "Lamp").is_err());
+
+        let log = house.get_audit_log();
+        let operations: Vec<&str> = log
+            .get_entries()
+            .iter()
+            .map(|entry| entry.operation.get_name())
+            .collect();
+        assert_eq!(
+            operations,
+            vec![
+                "add_room",
+                "add_room",
+                "add_device",
+                "set_power_state",
+                "remove_device"
+            ]
+        );
+
+        let failures = log.query(&AuditFilter::all().failures());
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].outcome, Err(ErrorReason::ItemLimitExceeded));
+        assert_eq!(log.query(&AuditFilter::all().actor("automation")).len(), 2);
+        assert_eq!(log.query(&AuditFilter::all().device("Socket")).len(), 2);
+    }
+
+    #[test]
+    fn group_commands_are_audited_per_device() {
+        use crate::command::{SmartDeviceCommand, SmartDeviceCommandError};
+        use crate::events::EventFilter;
+        use crate::group::DeviceGroup;
+        use crate::thermometer::SmartThermometer;
+        use std::sync::{Arc, Mutex};
+
+        let mut house = House::new("House", 1);
+        assert!(house.add_room(Room::new("Kitchen", 2)).is_ok());
+        assert!(house
+            .add_device("Kitchen", Box::new(SmartSocket::new("Socket")))
+            .is_ok());
+        assert!(house
+            .add_device("Kitchen", Box::new(SmartThermometer::new("Sensor")))
+            .is_ok());
+        assert!(house.add_tag("Kitchen", "Socket", "all").is_ok());
+        assert!(house.add_tag("Kitchen", "Sensor", "all").is_ok());
+        assert!(house
+            .add_group(DeviceGroup::new("everything").with_tag("all"))
+            .is_ok());
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        house
+            .get_event_bus()
+            .subscribe(EventFilter::all(), move |event| {
+                sink.lock().unwrap().push(event.payload.get_name())
+            });
+
+        house.set_actor("operator");
+        let results = house
+            .execute_group(
+                "everything",
+                SmartDeviceCommand::SetPowerState(SmartDevicePowerState::Enabled),
+            )
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        let results = house
+            .execute_group("everything", SmartDeviceCommand::Stop)
+            .unwrap();
+        assert!(results
+            .iter()
+            .all(|(_, _, result)| result == &Err(SmartDeviceCommandError::Unsupported)));
+
+        let log = house.get_audit_log();
+        let commands = log.query(&AuditFilter::all().actor("operator"));
+        assert_eq!(commands.len(), 4);
+        assert!(commands
+            .iter()
+            .all(|entry| entry.operation.get_name() == "execute"));
+        assert_eq!(log.query(&AuditFilter::all().device("Sensor")).len(), 4);
+        assert_eq!(
+            log.query(&AuditFilter::all().failures())[0].outcome,
+            Err(ErrorReason::UnsupportedCommand)
+        );
+        assert!(commands[0]
+            .to_json_line()
+            .contains("\"operation\":\"execute\",\"room\":\"Kitchen\""));
+        assert!(commands[0]
+            .to_json_line()
+            .contains("\"command\":\"set_power_state\""));
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["power_state_changed", "power_state_changed"]
+        );
+    }
+
+    #[test]
+    fn oldest_entries_are_evicted_when_log_is_full() {
+        let mut log = AuditLog::with_capacity(2);
+        for room in ["Kitchen", "Hall", "Lobby"] {
+            log.record(
+                "operator",
+                room,
+                None,
+                AuditOperation::AddRoom,
+                &Ok(String::new()),
+            );
+        }
+
+        let sequences: Vec<u64> = log
+            .get_entries()
+            .iter()
+            .map(|entry| entry.sequence)
+            .collect();
+        assert_eq!(sequences, vec![2, 3]);
+
+        log.set_capacity(1);
+        assert_eq!(log.get_entries()[0].room, "Lobby");
+        assert_eq!(log.get_entries().len(), 1);
+    }
+
+    #[test]
+    fn evicted_entries_are_kept_in_sink() {
+        use std::sync::{Arc, Mutex};
+
+        /// Приёмник, содержимое которого доступно тесту
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for Buffer {
+            fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(data);
+                Ok(data.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = Buffer::default();
+        let mut house = House::new("House", 1);
+        house.set_audit_capacity(1);
+        house.set_audit_sink(buffer.clone());
+        assert!(house.add_room(Room::new("Kitchen", 1)).is_ok());
+        assert!(house.remove_room_by_name("Kitchen").is_ok());
+
+        assert_eq!(house.get_audit_log().get_entries().len(), 1);
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("\"operation\":\"add_room\""));
+        assert!(lines[1].contains("\"operation\":\"remove_room\""));
+        assert_eq!(house.get_audit_log().get_sink_errors(), 0);
+    }
+
+    #[test]
+    fn entries_are_exported_as_json_lines() {
+        let mut log = AuditLog::new();
+        log.record(
+            "operator \"admin\"",
+            "Kitchen",
+            Some("Socket"),
+            AuditOperation::SetPowerState(SmartDevicePowerState::Enabled),
+            &Err(ErrorReason::DeviceFailure(SmartDeviceErrorCode::Overheat)),
+        );
+        log.record(
+            "operator",
+            "Kitchen",
+            None,
+            AuditOperation::RemoveRoom,
+            &Ok("Room Kitchen has been removed".to_string()),
+        );
+
+        let mut output = Vec::new();
+        assert_eq!(
+            log.export_json_lines(&mut output, &AuditFilter::all())
+                .unwrap(),
+            2
+        );
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("{\"sequence\":1,\"timestamp\":"));
+        assert!(lines[0].contains("\"actor\":\"operator \\\"admin\\\"\""));
+        assert!(lines[0].ends_with(
+            "\"operation\":\"set_power_state\",\"room\":\"Kitchen\",\"device\":\"Socket\",\
+             \"power_state\":\"enabled\",\"outcome\":\"error\",\"error\":\"device_failure\",\
+             \"device_error\":\"overheat\"}"
+        ));
+        assert!(lines[1].ends_with(
+            "\"device\":null,\"outcome\":\"ok\",\"message\":\"Room Kitchen has been removed\"}"
+        ));
+    }
+}
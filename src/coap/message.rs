@@ -0,0 +1,318 @@
+//! Модуль содержит сообщения протокола CoAP (RFC 7252) и их двоичное представление
+//!
+//! > Сообщение состоит из заголовка (версия, тип, длина токена, код, идентификатор
+//! > сообщения), токена, упорядоченных по номеру опций и необязательного содержимого,
+//! > отделённого маркером `0xFF`.
+//!
+use std::io;
+
+/// Версия протокола CoAP
+const VERSION: u8 = 1;
+
+/// Маркер начала содержимого
+const PAYLOAD_MARKER: u8 = 0xFF;
+
+/// Максимальная длина токена
+const MAX_TOKEN_LENGTH: usize = 8;
+
+/// Коды запросов и ответов (`класс << 5 | детализация`)
+pub mod code {
+    /// Пустое сообщение
+    pub const EMPTY: u8 = 0x00;
+    /// Запрос GET
+    pub const GET: u8 = 0x01;
+    /// Запрос POST
+    pub const POST: u8 = 0x02;
+    /// Запрос PUT
+    pub const PUT: u8 = 0x03;
+    /// Запрос DELETE
+    pub const DELETE: u8 = 0x04;
+    /// 2.04 Changed
+    pub const CHANGED: u8 = 0x44;
+    /// 2.05 Content
+    pub const CONTENT: u8 = 0x45;
+    /// 4.00 Bad Request
+    pub const BAD_REQUEST: u8 = 0x80;
+    /// 4.03 Forbidden
+    pub const FORBIDDEN: u8 = 0x83;
+    /// 4.04 Not Found
+    pub const NOT_FOUND: u8 = 0x84;
+    /// 4.05 Method Not Allowed
+    pub const METHOD_NOT_ALLOWED: u8 = 0x85;
+    /// 5.03 Service Unavailable
+    pub const SERVICE_UNAVAILABLE: u8 = 0xA3;
+}
+
+/// Номера опций
+pub mod option {
+    /// Регистрация наблюдения (RFC 7641)
+    pub const OBSERVE: u16 = 6;
+    /// Сегмент пути ресурса
+    pub const URI_PATH: u16 = 11;
+    /// Формат содержимого
+    pub const CONTENT_FORMAT: u16 = 12;
+}
+
+/// Форматы содержимого
+pub mod content_format {
+    /// `text/plain; charset=utf-8`
+    pub const TEXT: u16 = 0;
+    /// `application/link-format`
+    pub const LINK_FORMAT: u16 = 40;
+    /// `application/json`
+    pub const JSON: u16 = 50;
+}
+
+/// Перечисление типов сообщений
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoapType {
+    /// Сообщение, требующее подтверждения
+    Confirmable,
+    /// Сообщение, не требующее подтверждения
+    NonConfirmable,
+    /// Подтверждение
+    Acknowledgement,
+    /// Отказ от обработки сообщения
+    Reset,
+}
+
+/// Сообщение CoAP
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoapMessage {
+    /// Тип сообщения
+    pub message_type: CoapType,
+
+    /// Код запроса или ответа (см. модуль [`code`])
+    pub code: u8,
+
+    /// Идентификатор сообщения
+    pub message_id: u16,
+
+    /// Токен, связывающий запрос и ответы
+    pub token: Vec<u8>,
+
+    /// Опции (номер, значение) в порядке возрастания номера
+    pub options: Vec<(u16, Vec<u8>)>,
+
+    /// Содержимое
+    pub payload: Vec<u8>,
+}
+
+impl CoapMessage {
+    /// Создание сообщения без токена, опций и содержимого
+    pub fn new(message_type: CoapType, code: u8, message_id: u16) -> Self {
+        Self {
+            message_type,
+            code,
+            message_id,
+            token: Vec::new(),
+            options: Vec::new(),
+            payload: Vec::new(),
+        }
+    }
+
+    /// Установка токена
+    pub fn with_token(mut self, token: &[u8]) -> Self {
+        self.token = token.to_vec();
+        self
+    }
+
+    /// Добавление опции (с сохранением порядка номеров)
+    pub fn with_option(mut self, number: u16, value: Vec<u8>) -> Self {
+        let index = self.options.partition_point(|(other, _)| *other <= number);
+        self.options.insert(index, (number, value));
+        self
+    }
+
+    /// Добавление опции с целочисленным значением
+    pub fn with_uint_option(self, number: u16, value: u32) -> Self {
+        let bytes = value.to_be_bytes();
+        let skip = bytes.iter().take_while(|byte| **byte == 0).count();
+        self.with_option(number, bytes[skip..].to_vec())
+    }
+
+    /// Добавление сегментов пути ресурса
+    pub fn with_path(self, path: &[&str]) -> Self {
+        path.iter().fold(self, |message, segment| {
+            message.with_option(option::URI_PATH, segment.as_bytes().to_vec())
+        })
+    }
+
+    /// Установка содержимого с форматом `format`
+    pub fn with_payload(self, format: u16, payload: Vec<u8>) -> Self {
+        let mut message = self.with_uint_option(option::CONTENT_FORMAT, format as u32);
+        message.payload = payload;
+        message
+    }
+
+    /// Получение значений опции `number`
+    pub fn get_options(&self, number: u16) -> impl Iterator<Item = &[u8]> {
+        self.options
+            .iter()
+            .filter(move |(other, _)| *other == number)
+            .map(|(_, value)| value.as_slice())
+    }
+
+    /// Получение целочисленного значения опции `number`
+    pub fn get_uint_option(&self, number: u16) -> Option<u32> {
+        let value = self.get_options(number).next()?;
+        (value.len() <= 4).then(|| {
+            value
+                .iter()
+                .fold(0u32, |result, byte| (result << 8) | *byte as u32)
+        })
+    }
+
+    /// Получение сегментов пути ресурса (`None` - сегмент не в UTF-8)
+    pub fn get_path(&self) -> Option<Vec<String>> {
+        self.get_options(option::URI_PATH)
+            .map(|segment| String::from_utf8(segment.to_vec()).ok())
+            .collect()
+    }
+
+    /// Кодирование сообщения
+    pub fn encode(&self) -> Vec<u8> {
+        let message_type = match self.message_type {
+            CoapType::Confirmable => 0,
+            CoapType::NonConfirmable => 1,
+            CoapType::Acknowledgement => 2,
+            CoapType::Reset => 3,
+        };
+        let token = &self.token[..self.token.len().min(MAX_TOKEN_LENGTH)];
+
+        let mut bytes = vec![
+            VERSION << 6 | message_type << 4 | token.len() as u8,
+            self.code,
+        ];
+        bytes.extend_from_slice(&self.message_id.to_be_bytes());
+        bytes.extend_from_slice(token);
+
+        let mut previous = 0;
+        for (number, value) in &self.options {
+            let (delta, delta_extension) = option_field(number - previous);
+            let (length, length_extension) = option_field(value.len() as u16);
+            bytes.push(delta << 4 | length);
+            bytes.extend_from_slice(&delta_extension);
+            bytes.extend_from_slice(&length_extension);
+            bytes.extend_from_slice(value);
+            previous = *number;
+        }
+
+        if !self.payload.is_empty() {
+            bytes.push(PAYLOAD_MARKER);
+            bytes.extend_from_slice(&self.payload);
+        }
+        bytes
+    }
+
+    /// Разбор сообщения
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let [first, code, id_high, id_low, rest @ ..] = bytes else {
+            return Err(invalid("message is too short"));
+        };
+        if first >> 6 != VERSION {
+            return Err(invalid("unsupported version"));
+        }
+        let message_type = match (first >> 4) & 0x03 {
+            0 => CoapType::Confirmable,
+            1 => CoapType::NonConfirmable,
+            2 => CoapType::Acknowledgement,
+            _ => CoapType::Reset,
+        };
+        let token_length = (first & 0x0F) as usize;
+        if token_length > MAX_TOKEN_LENGTH || rest.len() < token_length {
+            return Err(invalid("invalid token length"));
+        }
+        let (token, mut rest) = rest.split_at(token_length);
+
+        let mut options = Vec::new();
+        let mut number = 0u16;
+        let mut payload = Vec::new();
+        while let Some((&header, tail)) = rest.split_first() {
+            if header == PAYLOAD_MARKER {
+                if tail.is_empty() {
+                    return Err(invalid("empty payload after marker"));
+                }
+                payload = tail.to_vec();
+                break;
+            }
+            let (delta, tail) = read_option_field(header >> 4, tail)?;
+            let (length, tail) = read_option_field(header & 0x0F, tail)?;
+            if tail.len() < length as usize {
+                return Err(invalid("option value is truncated"));
+            }
+            number = number
+                .checked_add(delta)
+                .ok_or_else(|| invalid("option number is too large"))?;
+            let (value, tail) = tail.split_at(length as usize);
+            options.push((number, value.to_vec()));
+            rest = tail;
+        }
+
+        Ok(Self {
+            message_type,
+            code: *code,
+            message_id: u16::from_be_bytes([*id_high, *id_low]),
+            token: token.to_vec(),
+            options,
+            payload,
+        })
+    }
+}
+
+/// Кодирование разности номеров или длины опции: (4-битное поле, расширение)
+fn option_field(value: u16) -> (u8, Vec<u8>) {
+    match value {
+        0..=12 => (value as u8, Vec::new()),
+        13..=268 => (13, vec![(value - 13) as u8]),
+        _ => (14, (value - 269).to_be_bytes().to_vec()),
+    }
+}
+
+/// Разбор 4-битного поля опции с расширением
+fn read_option_field(field: u8, bytes: &[u8]) -> io::Result<(u16, &[u8])> {
+    match (field, bytes) {
+        (0..=12, _) => Ok((field as u16, bytes)),
+        (13, [extension, rest @ ..]) => Ok((*extension as u16 + 13, rest)),
+        (14, [high, low, rest @ ..]) => u16::from_be_bytes([*high, *low])
+            .checked_add(269)
+            .map(|value| (value, rest))
+            .ok_or_else(|| invalid("option field is too large")),
+        _ => Err(invalid("malformed option")),
+    }
+}
+
+/// Ошибка разбора сообщения
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_round_trip() {
+        let long_segment = "x".repeat(300);
+        let message = CoapMessage::new(CoapType::Confirmable, code::GET, 0xBEEF)
+            .with_token(&[1, 2, 3])
+            .with_path(&["Kitchen", &long_segment, "readings"])
+            .with_uint_option(option::OBSERVE, 0)
+            .with_payload(content_format::TEXT, b"ON".to_vec());
+
+        let bytes = message.encode();
+        assert_eq!(&bytes[..4], [0x43, code::GET, 0xBE, 0xEF]);
+        let decoded = CoapMessage::decode(&bytes).unwrap();
+        assert_eq!(decoded, message);
+        assert_eq!(decoded.get_uint_option(option::OBSERVE), Some(0));
+        assert_eq!(
+            decoded.get_path(),
+            Some(vec![
+                "Kitchen".to_string(),
+                long_segment,
+                "readings".to_string()
+            ])
+        );
+        assert!(CoapMessage::decode(&[0x40, 0x01, 0x00]).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+//! Модуль содержит сервер CoAP (UDP) для устройств умного дома
+//!
+//! > Модуль доступен при включённой cargo-функции `coap`. Ресурсы сервера:
+//! >
+//! > | Метод | Путь                        | Описание                                          |
+//! > |-------|-----------------------------|---------------------------------------------------|
+//! > | `GET` | `/.well-known/core`         | список ресурсов (`application/link-format`)       |
+//! > | `GET` | `/<room>/<device>`          | статус и показания устройства (JSON), наблюдаемый |
+//! > | `GET` | `/<room>/<device>/readings` | показания устройства (JSON), наблюдаемый          |
+//! > | `PUT` | `/<room>/<device>/power`    | `ON` / `OFF`, `enabled` / `disabled` или `{"state": ..}` |
+//! >
+//! > Наблюдение (RFC 7641) регистрируется запросом `GET` с опцией `Observe: 0` и
+//! > отменяется запросом с `Observe: 1` или ответом `RST` на уведомление. Уведомления
+//! > отправляются при событиях устройства на шине событий дома.
+//!
+pub mod message;
+pub mod server;
+
+use crate::containers::shared::SharedHouse;
+
+/// Сегмент пути ресурса показаний
+pub const READINGS_RESOURCE: &str = "readings";
+
+/// Сегмент пути ресурса питания
+pub const POWER_RESOURCE: &str = "power";
+
+/// Получение списка ресурсов дома в формате CoRE Link Format (RFC 6690)
+pub fn resource_links(house: &SharedHouse) -> String {
+    let mut room_names = house.get_room_list();
+    room_names.sort();
+
+    let mut links = Vec::new();
+    for room_name in room_names {
+        let mut device_names = house.get_device_list(&room_name).unwrap_or_default();
+        device_names.sort();
+        for device_name in device_names {
+            let Ok(kind) = house.with_device(&room_name, &device_name, |device| {
+                device.get_metadata().kind
+            }) else {
+                continue;
+            };
+            let path = format!("/{}/{}", uri_segment(&room_name), uri_segment(&device_name));
+            links.push(format!("<{}>;rt=\"{}\";ct=50;obs", path, kind));
+            links.push(format!("<{}/{}>;ct=50;obs", path, READINGS_RESOURCE));
+            links.push(format!("<{}/{}>", path, POWER_RESOURCE));
+        }
+    }
+    links.join(",")
+}
+
+/// Кодирование сегмента пути (символы, кроме латинских букв, цифр и `-._~`, кодируются `%XX`)
+fn uri_segment(name: &str) -> String {
+    name.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::house::House;
+    use crate::room::Room;
+    use crate::thermometer::SmartThermometer;
+
+    #[test]
+    fn resources_are_listed_in_link_format() {
+        let mut house = House::new("House", 1);
+        assert!(house.add_room(Room::new("Living Room", 1)).is_ok());
+        assert!(house
+            .add_device("Living Room", Box::new(SmartThermometer::new("Sensor")))
+            .is_ok());
+
+        assert_eq!(
+            resource_links(&SharedHouse::try_from(house).unwrap()),
+            "</Living%20Room/Sensor>;rt=\"thermometer\";ct=50;obs,\
+             </Living%20Room/Sensor/readings>;ct=50;obs,\
+             </Living%20Room/Sensor/power>"
+        );
+    }
+}
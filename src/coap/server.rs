@@ -0,0 +1,626 @@
+//! Модуль содержит сервер CoAP умного дома
+//!
+//! > Сервер обслуживает запросы и рассылает уведомления наблюдателям в одном потоке.
+//! > На подтверждаемый запрос (`CON`) отвечает подтверждением с вложенным ответом,
+//! > на неподтверждаемый (`NON`) - неподтверждаемым ответом. Уведомления отправляются
+//! > неподтверждаемыми сообщениями, но не реже одного раза за период
+//! > [`CoapServer::with_confirmable_interval`] - подтверждаемым (RFC 7641, раздел 4.5);
+//! > наблюдателю, давно не получавшему уведомлений, отправляется текущее представление.
+//! > Неподтверждённое уведомление отправляется повторно с удвоением времени ожидания,
+//! > а наблюдатель, не подтвердивший его после [`MAX_RETRANSMIT`] повторов, удаляется.
+//! > При удалении устройства наблюдатель получает ответ `4.04 Not Found`, и наблюдение
+//! > прекращается.
+//!
+use super::message::{code, content_format, option, CoapMessage, CoapType};
+use super::{resource_links, POWER_RESOURCE, READINGS_RESOURCE};
+use crate::containers::shared::SharedHouse;
+use crate::containers::{ContainerName, ErrorReason};
+use crate::events::{EventFilter, EventPayload, HouseEvent};
+use crate::json::{parse_power_command, readings_map_json, state_json};
+use crate::smart_device::SmartDeviceErrorCode;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Интервал проверки признака остановки и событий дома
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Максимальный размер принимаемого сообщения (RFC 7252, раздел 4.6)
+const MAX_MESSAGE_SIZE: usize = 1152;
+
+/// Максимальное количество наблюдателей
+const MAX_OBSERVERS: usize = 64;
+
+/// Значение опции `Observe` для регистрации наблюдения
+const OBSERVE_REGISTER: u32 = 0;
+
+/// Значение опции `Observe` для отмены наблюдения
+const OBSERVE_DEREGISTER: u32 = 1;
+
+/// Период по умолчанию, не реже которого уведомление отправляется подтверждаемым
+/// (RFC 7641, раздел 4.5)
+pub const DEFAULT_CONFIRMABLE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Начальное время ожидания подтверждения по умолчанию (RFC 7252, раздел 4.8)
+pub const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Максимальное количество повторных отправок подтверждаемого уведомления
+/// (RFC 7252, раздел 4.8)
+pub const MAX_RETRANSMIT: u32 = 4;
+
+/// Тип, описывающий сервер CoAP умного дома
+pub struct CoapServer {
+    /// Сокет сервера
+    socket: UdpSocket,
+
+    /// Обслуживаемый дом
+    house: SharedHouse,
+
+    /// Интервал опроса показаний устройств
+    sampling_interval: Option<Duration>,
+
+    /// Период, не реже которого уведомление отправляется подтверждаемым
+    confirmable_interval: Duration,
+
+    /// Начальное время ожидания подтверждения уведомления
+    ack_timeout: Duration,
+}
+
+impl CoapServer {
+    /// Создание сервера дома `house`, принимающего запросы на адресе `address`
+    pub fn bind<A: ToSocketAddrs>(address: A, house: SharedHouse) -> io::Result<Self> {
+        Ok(Self {
+            socket: UdpSocket::bind(address)?,
+            house,
+            sampling_interval: None,
+            confirmable_interval: DEFAULT_CONFIRMABLE_INTERVAL,
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+        })
+    }
+
+    /// Периодический опрос показаний устройств (см. [`SharedHouse::sample_readings`])
+    ///
+    /// Полученные показания рассылаются наблюдателям ресурсов устройств
+    pub fn with_sampling_interval(mut self, interval: Duration) -> Self {
+        self.sampling_interval = Some(interval);
+        self
+    }
+
+    /// Отправка уведомлений подтверждаемыми не реже одного раза за период `interval`
+    ///
+    /// Так сервер обнаруживает наблюдателей, которые больше не получают уведомления
+    pub fn with_confirmable_interval(mut self, interval: Duration) -> Self {
+        self.confirmable_interval = interval;
+        self
+    }
+
+    /// Установка начального времени ожидания подтверждения уведомления
+    pub fn with_ack_timeout(mut self, timeout: Duration) -> Self {
+        self.ack_timeout = timeout;
+        self
+    }
+
+    /// Получение адреса, на котором сервер принимает запросы
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Обслуживание запросов в текущем потоке (без возврата управления)
+    pub fn run(self) -> io::Result<()> {
+        self.serve(Arc::new(AtomicBool::new(true)))
+    }
+
+    /// Запуск обслуживания запросов в фоновом потоке
+    pub fn spawn(self) -> io::Result<CoapServerHandle> {
+        let address = self.local_addr()?;
+        let running = Arc::new(AtomicBool::new(true));
+        let flag = running.clone();
+        let thread = thread::Builder::new()
+            .name("iot-coap".to_string())
+            .spawn(move || self.serve(flag))?;
+
+        Ok(CoapServerHandle {
+            address,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// Обслуживание запросов и рассылка уведомлений, пока установлен признак `running`
+    fn serve(self, running: Arc<AtomicBool>) -> io::Result<()> {
+        self.socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        let (subscription, events) = self
+            .house
+            .with_event_bus(|bus| bus.subscribe_channel(EventFilter::all()));
+
+        let mut endpoint = Endpoint {
+            socket: self.socket,
+            house: self.house.clone(),
+            observers: Vec::new(),
+            next_message_id: 1,
+            confirmable_interval: self.confirmable_interval,
+            ack_timeout: self.ack_timeout,
+        };
+        let result = endpoint.serve_loop(&events, &running, self.sampling_interval);
+
+        self.house
+            .with_event_bus(|bus| bus.unsubscribe(subscription));
+        result
+    }
+}
+
+/// Дескриптор сервера CoAP, запущенного в фоновом потоке
+///
+/// Сервер останавливается вызовом [`CoapServerHandle::shutdown`] или при удалении дескриптора
+pub struct CoapServerHandle {
+    /// Адрес сервера
+    address: SocketAddr,
+
+    /// Признак работы сервера
+    running: Arc<AtomicBool>,
+
+    /// Поток сервера
+    thread: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl CoapServerHandle {
+    /// Получение адреса, на котором сервер принимает запросы
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Остановка сервера
+    pub fn shutdown(mut self) -> io::Result<()> {
+        self.stop()
+    }
+
+    /// Остановка сервера и ожидание завершения потока
+    fn stop(&mut self) -> io::Result<()> {
+        let Some(thread) = self.thread.take() else {
+            return Ok(());
+        };
+        self.running.store(false, Ordering::SeqCst);
+        thread
+            .join()
+            .map_err(|_| io::Error::other("CoAP server thread panicked"))?
+    }
+}
+
+impl Drop for CoapServerHandle {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Перечисление наблюдаемых ресурсов устройства
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Resource {
+    /// Статус и показания устройства
+    State,
+    /// Показания устройства
+    Readings,
+}
+
+/// Наблюдатель ресурса устройства
+struct Observer {
+    /// Адрес клиента
+    peer: SocketAddr,
+
+    /// Токен запроса регистрации
+    token: Vec<u8>,
+
+    /// Комната устройства
+    room: ContainerName,
+
+    /// Название устройства
+    device: ContainerName,
+
+    /// Наблюдаемый ресурс
+    resource: Resource,
+
+    /// Порядковый номер следующего уведомления
+    sequence: u32,
+
+    /// Идентификатор последнего отправленного уведомления
+    last_message_id: u16,
+
+    /// Момент последнего подтверждения наблюдателем (или регистрации)
+    confirmed_at: Instant,
+
+    /// Неподтверждённое подтверждаемое уведомление
+    pending: Option<PendingNotification>,
+}
+
+/// Подтверждаемое уведомление, ожидающее подтверждения
+struct PendingNotification {
+    /// Уведомление
+    message: CoapMessage,
+
+    /// Количество выполненных повторных отправок
+    retransmissions: u32,
+
+    /// Текущее время ожидания подтверждения
+    timeout: Duration,
+
+    /// Момент следующей повторной отправки
+    deadline: Instant,
+}
+
+/// Состояние сервера: сокет, дом и наблюдатели
+struct Endpoint {
+    /// Сокет сервера
+    socket: UdpSocket,
+
+    /// Обслуживаемый дом
+    house: SharedHouse,
+
+    /// Зарегистрированные наблюдатели
+    observers: Vec<Observer>,
+
+    /// Идентификатор следующего сообщения сервера
+    next_message_id: u16,
+
+    /// Период, не реже которого уведомление отправляется подтверждаемым
+    confirmable_interval: Duration,
+
+    /// Начальное время ожидания подтверждения уведомления
+    ack_timeout: Duration,
+}
+
+impl Endpoint {
+    /// Приём запросов, опрос показаний и рассылка уведомлений
+    fn serve_loop(
+        &mut self,
+        events: &Receiver<HouseEvent>,
+        running: &AtomicBool,
+        sampling_interval: Option<Duration>,
+    ) -> io::Result<()> {
+        let mut buffer = [0u8; MAX_MESSAGE_SIZE];
+        let mut last_sample = Instant::now();
+
+        while running.load(Ordering::SeqCst) {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((length, peer)) => {
+                    // Некорректные сообщения игнорируются (RFC 7252, раздел 4.2)
+                    if let Ok(message) = CoapMessage::decode(&buffer[..length]) {
+                        self.handle_message(message, peer);
+                    }
+                }
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::WouldBlock
+                            | io::ErrorKind::TimedOut
+                            | io::ErrorKind::ConnectionReset
+                            | io::ErrorKind::ConnectionRefused
+                    ) => {}
+                Err(error) => return Err(error),
+            }
+
+            if let Some(interval) = sampling_interval {
+                if last_sample.elapsed() >= interval {
+                    self.house.sample_readings();
+                    last_sample = Instant::now();
+                }
+            }
+
+            let changed: Vec<_> = events.try_iter().collect();
+            if !changed.is_empty() {
+                self.notify(changed);
+            }
+            self.check_observers();
+        }
+        Ok(())
+    }
+
+    /// Обработка входящего сообщения
+    fn handle_message(&mut self, message: CoapMessage, peer: SocketAddr) {
+        match message.message_type {
+            // Отказ от уведомления отменяет наблюдение
+            CoapType::Reset => {
+                self.observers.retain(|observer| {
+                    observer.peer != peer || observer.last_message_id != message.message_id
+                });
+            }
+            CoapType::Acknowledgement => {
+                for observer in &mut self.observers {
+                    let acknowledged = observer.peer == peer
+                        && observer.pending.as_ref().is_some_and(|pending| {
+                            pending.message.message_id == message.message_id
+                        });
+                    if acknowledged {
+                        observer.pending = None;
+                        observer.confirmed_at = Instant::now();
+                    }
+                }
+            }
+            // Пустое подтверждаемое сообщение - проверка доступности ("CoAP ping")
+            CoapType::Confirmable if message.code == code::EMPTY => self.send(
+                &CoapMessage::new(CoapType::Reset, code::EMPTY, message.message_id),
+                peer,
+            ),
+            message_type if message.code >> 5 == 0 && message.code != code::EMPTY => {
+                let response = self.handle_request(&message, peer);
+                let response = match message_type {
+                    CoapType::Confirmable => CoapMessage {
+                        message_type: CoapType::Acknowledgement,
+                        message_id: message.message_id,
+                        ..response
+                    },
+                    _ => CoapMessage {
+                        message_type: CoapType::NonConfirmable,
+                        message_id: self.next_message_id(),
+                        ..response
+                    },
+                };
+                self.send(&response, peer);
+            }
+            _ => {}
+        }
+    }
+
+    /// Выполнение запроса (тип и идентификатор ответа заполняет вызывающая сторона)
+    fn handle_request(&mut self, request: &CoapMessage, peer: SocketAddr) -> CoapMessage {
+        let respond = |code| CoapMessage::new(CoapType::Acknowledgement, code, 0);
+        let Some(path) = request.get_path() else {
+            return respond(code::BAD_REQUEST).with_token(&request.token);
+        };
+        let path: Vec<&str> = path.iter().map(String::as_str).collect();
+
+        let response = match (request.code, path.as_slice()) {
+            (code::GET, [".well-known", "core"]) => respond(code::CONTENT).with_payload(
+                content_format::LINK_FORMAT,
+                resource_links(&self.house).into_bytes(),
+            ),
+            (code::GET, [room, device]) => self.get(request, peer, room, device, Resource::State),
+            (code::GET, [room, device, READINGS_RESOURCE]) => {
+                self.get(request, peer, room, device, Resource::Readings)
+            }
+            (code::PUT, [room, device, POWER_RESOURCE]) => {
+                match parse_power_command(&request.payload) {
+                    Some(state) => match self.house.set_power_state(room, device, state) {
+                        Ok(_) => respond(code::CHANGED),
+                        Err(error) => respond(error_code(&error)),
+                    },
+                    None => respond(code::BAD_REQUEST),
+                }
+            }
+            (_, [".well-known", "core"] | [_, _] | [_, _, READINGS_RESOURCE | POWER_RESOURCE]) => {
+                respond(code::METHOD_NOT_ALLOWED)
+            }
+            _ => respond(code::NOT_FOUND),
+        };
+        response.with_token(&request.token)
+    }
+
+    /// Получение представления ресурса с регистрацией или отменой наблюдения
+    fn get(
+        &mut self,
+        request: &CoapMessage,
+        peer: SocketAddr,
+        room: &str,
+        device: &str,
+        resource: Resource,
+    ) -> CoapMessage {
+        let observe = request.get_uint_option(option::OBSERVE);
+        let is_observer =
+            |observer: &Observer| observer.peer == peer && observer.token == request.token;
+        if matches!(observe, Some(OBSERVE_REGISTER | OBSERVE_DEREGISTER)) {
+            self.observers.retain(|observer| !is_observer(observer));
+        }
+
+        let representation = match representation(&self.house, room, device, resource) {
+            Ok(representation) => representation,
+            Err(code) => return CoapMessage::new(CoapType::Acknowledgement, code, 0),
+        };
+        let response = CoapMessage::new(CoapType::Acknowledgement, code::CONTENT, 0);
+        if observe != Some(OBSERVE_REGISTER) || self.observers.len() >= MAX_OBSERVERS {
+            return response.with_payload(content_format::JSON, representation);
+        }
+
+        self.observers.push(Observer {
+            peer,
+            token: request.token.clone(),
+            room: room.to_string(),
+            device: device.to_string(),
+            resource,
+            sequence: 1,
+            last_message_id: 0,
+            confirmed_at: Instant::now(),
+            pending: None,
+        });
+        response
+            .with_uint_option(option::OBSERVE, 0)
+            .with_payload(content_format::JSON, representation)
+    }
+
+    /// Рассылка уведомлений наблюдателям устройств, затронутых событиями
+    ///
+    /// Каждый наблюдатель получает не более одного уведомления на пакет событий
+    fn notify(&mut self, events: Vec<HouseEvent>) {
+        let mut affected = vec![false; self.observers.len()];
+        for event in &events {
+            let mut touches = |room: &str, device: Option<&str>| {
+                for (index, observer) in self.observers.iter().enumerate() {
+                    if observer.room == room
+                        && device.is_none_or(|device| observer.device == device)
+                    {
+                        affected[index] = true;
+                    }
+                }
+            };
+            match (&event.payload, &event.device) {
+                (EventPayload::RoomRemoved, _) => touches(&event.room, None),
+                (EventPayload::RoomRenamed { old_name }, _) => touches(old_name, None),
+                (EventPayload::DeviceRenamed { old_name }, Some(device)) => {
+                    touches(&event.room, Some(old_name));
+                    touches(&event.room, Some(device));
+                }
+                (EventPayload::DeviceMoved { from_room }, Some(device)) => {
+                    touches(from_room, Some(device));
+                    touches(&event.room, Some(device));
+                }
+                (_, Some(device)) => touches(&event.room, Some(device)),
+                _ => {}
+            }
+        }
+
+        let observers = std::mem::take(&mut self.observers);
+        for (observer, affected) in observers.into_iter().zip(affected) {
+            if affected {
+                self.notify_observer(observer);
+            } else {
+                self.observers.push(observer);
+            }
+        }
+    }
+
+    /// Повторная отправка неподтверждённых уведомлений и удаление наблюдателей,
+    /// не подтвердивших уведомление после всех повторов
+    ///
+    /// Наблюдателю, не подтверждавшему уведомления дольше периода подтверждаемых
+    /// уведомлений, отправляется текущее представление ресурса
+    fn check_observers(&mut self) {
+        let now = Instant::now();
+        let observers = std::mem::take(&mut self.observers);
+        for mut observer in observers {
+            match &mut observer.pending {
+                Some(pending) if now >= pending.deadline => {
+                    if pending.retransmissions >= MAX_RETRANSMIT {
+                        continue;
+                    }
+                    pending.retransmissions += 1;
+                    pending.timeout *= 2;
+                    pending.deadline = now + pending.timeout;
+                    self.send(&pending.message, observer.peer);
+                }
+                None if observer.confirmed_at.elapsed() >= self.confirmable_interval => {
+                    self.notify_observer(observer);
+                    continue;
+                }
+                _ => {}
+            }
+            self.observers.push(observer);
+        }
+    }
+
+    /// Отправка уведомления с текущим представлением ресурса наблюдателю
+    ///
+    /// Уведомление подтверждаемое, если предыдущее подтверждаемое уведомление ещё
+    /// не подтверждено (новое заменяет его, RFC 7641, раздел 4.5.2) или истёк период
+    /// подтверждаемых уведомлений
+    fn notify_observer(&mut self, mut observer: Observer) {
+        let confirmable = observer.pending.is_some()
+            || observer.confirmed_at.elapsed() >= self.confirmable_interval;
+        let message_type = if confirmable {
+            CoapType::Confirmable
+        } else {
+            CoapType::NonConfirmable
+        };
+        let message_id = self.next_message_id();
+        let notification =
+            CoapMessage::new(message_type, code::CONTENT, message_id).with_token(&observer.token);
+
+        match representation(
+            &self.house,
+            &observer.room,
+            &observer.device,
+            observer.resource,
+        ) {
+            Ok(payload) => {
+                let notification = notification
+                    .with_uint_option(option::OBSERVE, observer.sequence)
+                    .with_payload(content_format::JSON, payload);
+                self.send(&notification, observer.peer);
+                observer.sequence = (observer.sequence + 1) & 0x00FF_FFFF;
+                observer.last_message_id = message_id;
+                if confirmable {
+                    let (retransmissions, timeout) = observer
+                        .pending
+                        .as_ref()
+                        .map_or((0, self.ack_timeout), |pending| {
+                            (pending.retransmissions, pending.timeout)
+                        });
+                    observer.pending = Some(PendingNotification {
+                        message: notification,
+                        retransmissions,
+                        timeout,
+                        deadline: Instant::now() + timeout,
+                    });
+                }
+                self.observers.push(observer);
+            }
+            // Ответ с кодом ошибки завершает наблюдение (RFC 7641, раздел 3.2)
+            Err(code) => {
+                let notification = CoapMessage {
+                    message_type: CoapType::NonConfirmable,
+                    code,
+                    ..notification
+                };
+                self.send(&notification, observer.peer);
+            }
+        }
+    }
+
+    /// Отправка сообщения клиенту `peer`
+    ///
+    /// Доставка по UDP не гарантируется, поэтому ошибки отправки не прерывают работу сервера
+    fn send(&self, message: &CoapMessage, peer: SocketAddr) {
+        let _ = self.socket.send_to(&message.encode(), peer);
+    }
+
+    /// Получение идентификатора для очередного сообщения сервера
+    fn next_message_id(&mut self) -> u16 {
+        let message_id = self.next_message_id;
+        self.next_message_id = self.next_message_id.wrapping_add(1);
+        message_id
+    }
+}
+
+/// Получение представления ресурса устройства в формате JSON (или кода ошибки)
+fn representation(
+    house: &SharedHouse,
+    room: &str,
+    device: &str,
+    resource: Resource,
+) -> Result<Vec<u8>, u8> {
+    house
+        .with_device(room, device, |device| match resource {
+            Resource::State => state_json(device),
+            Resource::Readings => readings_map_json(device),
+        })
+        .map(|value| value.to_string().into_bytes())
+        .map_err(|error| error_code(&error))
+}
+
+/// Код ответа CoAP для ошибки дома
+fn error_code(error: &ErrorReason) -> u8 {
+    match error {
+        ErrorReason::ItemDoesntExist => code::NOT_FOUND,
+        ErrorReason::DeviceFailure(device_error) => device_error_code(device_error),
+        ErrorReason::DeviceBusy => code::SERVICE_UNAVAILABLE,
+        _ => code::BAD_REQUEST,
+    }
+}
+
+/// Код ответа CoAP для ошибки устройства
+///
+/// Отказ в доступе соответствует 4.03, остальные ошибки (включая потерю связи
+/// с удалённым устройством) - 5.03
+fn device_error_code(error: &SmartDeviceErrorCode) -> u8 {
+    match error {
+        SmartDeviceErrorCode::AccessDenied => code::FORBIDDEN,
+        SmartDeviceErrorCode::Overcurrent
+        | SmartDeviceErrorCode::Overvoltage
+        | SmartDeviceErrorCode::Overheat
+        | SmartDeviceErrorCode::Underheat
+        | SmartDeviceErrorCode::Obstruction
+        | SmartDeviceErrorCode::PoweredOff
+        | SmartDeviceErrorCode::Jammed
+        | SmartDeviceErrorCode::Unreachable => code::SERVICE_UNAVAILABLE,
+    }
+}
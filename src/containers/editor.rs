@@ -0,0 +1,416 @@
+//! Модуль содержит историю правок структуры умного дома с возможностью отмены и повтора
+//!
+//! > Каждая правка (добавление, удаление и переименование комнат и устройств, перенос
+//! > устройства в другую комнату) выполняется через [`EditHistory`] и запоминается вместе
+//! > с обратной правкой. Удалённые комнаты и устройства не уничтожаются, а хранятся в истории,
+//! > поэтому отмена удаления восстанавливает их полное состояние.
+//!
+use super::house::House;
+use super::room::Room;
+use super::{ContainerIOHistory, ContainerName, ErrorReason};
+use crate::smart_device::SmartDevice;
+
+/// Максимальное количество запоминаемых правок по умолчанию
+pub const DEFAULT_EDIT_HISTORY_DEPTH: usize = 100;
+
+/// Перечисление правок структуры дома
+enum Edit {
+    /// Добавление комнаты
+    InsertRoom(Box<Room>),
+    /// Удаление комнаты
+    RemoveRoom(ContainerName),
+    /// Добавление устройства в комнату
+    InsertDevice(ContainerName, Box<dyn SmartDevice>),
+    /// Удаление устройства из комнаты
+    RemoveDevice(ContainerName, ContainerName),
+    /// Переименование комнаты (старое имя, новое имя)
+    RenameRoom(ContainerName, ContainerName),
+    /// Переименование устройства (комната, старое имя, новое имя)
+    RenameDevice(ContainerName, ContainerName, ContainerName),
+    /// Перенос устройства (исходная комната, целевая комната, устройство)
+    MoveDevice(ContainerName, ContainerName, ContainerName),
+}
+
+impl Edit {
+    /// Применение правки к дому
+    ///
+    /// Возвращает описание результата и обратную правку; при ошибке правка
+    /// возвращается вызывающему вместе со всеми хранимыми в ней комнатами и устройствами
+    fn apply(self, house: &mut House) -> Result<(ContainerIOHistory, Edit), (ErrorReason, Edit)> {
+        match self {
+            Self::InsertRoom(room) => {
+                let room_name = room.name.clone();
+                match house.try_add_room(*room) {
+                    Ok(status) => Ok((status, Self::RemoveRoom(room_name))),
+                    Err((reason, room)) => Err((reason, Self::InsertRoom(room))),
+                }
+            }
+            Self::RemoveRoom(room_name) => match house.take_room(&room_name) {
+                Ok(room) => {
+                    let status = format!(
+                        "Room {} has been removed from house {}",
+                        room_name, house.name
+                    );
+                    Ok((status, Self::InsertRoom(Box::new(room))))
+                }
+                Err(reason) => Err((reason, Self::RemoveRoom(room_name))),
+            },
+            Self::InsertDevice(room_name, device) => {
+                let device_name = device.get_name().to_string();
+                match house.try_add_device(&room_name, device) {
+                    Ok(status) => Ok((status, Self::RemoveDevice(room_name, device_name))),
+                    Err((reason, device)) => Err((reason, Self::InsertDevice(room_name, device))),
+                }
+            }
+            Self::RemoveDevice(room_name, device_name) => {
+                match house.take_device(&room_name, &device_name) {
+                    Ok(device) => {
+                        let status = format!(
+                            "Device {} has been removed from room {}",
+                            device_name, room_name
+                        );
+                        Ok((status, Self::InsertDevice(room_name, device)))
+                    }
+                    Err(reason) => Err((reason, Self::RemoveDevice(room_name, device_name))),
+                }
+            }
+            Self::RenameRoom(old_name, new_name) => match house.rename_room(&old_name, &new_name) {
+                Ok(status) => Ok((status, Self::RenameRoom(new_name, old_name))),
+                Err(reason) => Err((reason, Self::RenameRoom(old_name, new_name))),
+            },
+            Self::RenameDevice(room_name, old_name, new_name) => {
+                match house.rename_device(&room_name, &old_name, &new_name) {
+                    Ok(status) => Ok((status, Self::RenameDevice(room_name, new_name, old_name))),
+                    Err(reason) => Err((reason, Self::RenameDevice(room_name, old_name, new_name))),
+                }
+            }
+            Self::MoveDevice(from_room, to_room, device_name) => {
+                match house.move_device(&from_room, &to_room, &device_name) {
+                    Ok(status) => Ok((status, Self::MoveDevice(to_room, from_room, device_name))),
+                    Err(reason) => Err((reason, Self::MoveDevice(from_room, to_room, device_name))),
+                }
+            }
+        }
+    }
+}
+
+/// Тип, описывающий историю правок структуры дома
+///
+/// История не владеет домом: правки, отмена и повтор применяются к дому,
+/// переданному в вызов. Правки, выполненные в обход истории, могут сделать
+/// отмену невозможной - в этом случае возвращается ошибка, а правка остаётся в истории
+/// (вместе с удалёнными комнатами и устройствами) до устранения причины или вызова `clear`
+pub struct EditHistory {
+    /// Правки, доступные для отмены (хранятся обратные правки)
+    undo: Vec<Edit>,
+
+    /// Отменённые правки, доступные для повтора
+    redo: Vec<Edit>,
+
+    /// Максимальное количество запоминаемых правок
+    depth: usize,
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_EDIT_HISTORY_DEPTH)
+    }
+}
+
+impl EditHistory {
+    /// Создание пустой истории, запоминающей не более `depth` правок
+    pub fn new(depth: usize) -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            depth,
+        }
+    }
+
+    /// Проверка, есть ли правки для отмены
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Проверка, есть ли правки для повтора
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Очистка истории правок
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Добавление комнаты в дом
+    pub fn add_room(
+        &mut self,
+        house: &mut House,
+        room: Room,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        self.perform(house, Edit::InsertRoom(Box::new(room)))
+    }
+
+    /// Удаление комнаты из дома
+    pub fn remove_room(
+        &mut self,
+        house: &mut House,
+        room_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        self.perform(house, Edit::RemoveRoom(room_name.to_string()))
+    }
+
+    /// Добавление умного устройства в комнату дома
+    pub fn add_device(
+        &mut self,
+        house: &mut House,
+        room_name: &str,
+        device: Box<dyn SmartDevice>,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        self.perform(house, Edit::InsertDevice(room_name.to_string(), device))
+    }
+
+    /// Удаление умного устройства из комнаты дома
+    pub fn remove_device(
+        &mut self,
+        house: &mut House,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        self.perform(
+            house,
+            Edit::RemoveDevice(room_name.to_string(), device_name.to_string()),
+        )
+    }
+
+    /// Переименование комнаты дома
+    pub fn rename_room(
+        &mut self,
+        house: &mut House,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        self.perform(
+            house,
+            Edit::RenameRoom(old_name.to_string(), new_name.to_string()),
+        )
+    }
+
+    /// Переименование умного устройства в комнате дома
+    pub fn rename_device(
+        &mut self,
+        house: &mut House,
+        room_name: &str,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        self.perform(
+            house,
+            Edit::RenameDevice(
+                room_name.to_string(),
+                old_name.to_string(),
+                new_name.to_string(),
+            ),
+        )
+    }
+
+    /// Перенос умного устройства в другую комнату дома
+    pub fn move_device(
+        &mut self,
+        house: &mut House,
+        from_room: &str,
+        to_room: &str,
+        device_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        self.perform(
+            house,
+            Edit::MoveDevice(
+                from_room.to_string(),
+                to_room.to_string(),
+                device_name.to_string(),
+            ),
+        )
+    }
+
+    /// Отмена последней правки
+    pub fn undo(&mut self, house: &mut House) -> Result<ContainerIOHistory, ErrorReason> {
+        let edit = self.undo.pop().ok_or(ErrorReason::ItemDoesntExist)?;
+        match edit.apply(house) {
+            Ok((status, inverse)) => {
+                self.redo.push(inverse);
+                Ok(status)
+            }
+            Err((reason, edit)) => {
+                self.undo.push(edit);
+                Err(reason)
+            }
+        }
+    }
+
+    /// Повтор последней отменённой правки
+    pub fn redo(&mut self, house: &mut House) -> Result<ContainerIOHistory, ErrorReason> {
+        let edit = self.redo.pop().ok_or(ErrorReason::ItemDoesntExist)?;
+        match edit.apply(house) {
+            Ok((status, inverse)) => {
+                self.push_undo(inverse);
+                Ok(status)
+            }
+            Err((reason, edit)) => {
+                self.redo.push(edit);
+                Err(reason)
+            }
+        }
+    }
+
+    /// Выполнение новой правки; доступные для повтора правки сбрасываются
+    fn perform(
+        &mut self,
+        house: &mut House,
+        edit: Edit,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let (status, inverse) = edit.apply(house).map_err(|(reason, _)| reason)?;
+        self.redo.clear();
+        self.push_undo(inverse);
+        Ok(status)
+    }
+
+    /// Запоминание обратной правки с учётом глубины истории
+    fn push_undo(&mut self, inverse: Edit) {
+        self.undo.push(inverse);
+        if self.undo.len() > self.depth {
+            self.undo.remove(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::smart_device::SmartDevicePowerState;
+    use crate::socket::SmartSocket;
+    use crate::units::Watts;
+
+    fn house() -> House {
+        let mut house = House::new("House", 3);
+        assert!(house.add_room(Room::new("Kitchen", 2)).is_ok());
+        assert!(house.add_room(Room::new("Hall", 2)).is_ok());
+        house
+    }
+
+    #[test]
+    fn removed_device_is_restored_with_state() {
+        let mut house = house();
+        let mut history = EditHistory::default();
+
+        let mut socket = SmartSocket::new("Socket");
+        socket.set_power_consumption(Watts(42.0));
+        assert!(history
+            .add_device(&mut house, "Kitchen", Box::new(socket))
+            .is_ok());
+        assert!(house
+            .set_power_state("Kitchen", "Socket", SmartDevicePowerState::Enabled)
+            .is_ok());
+
+        assert!(history
+            .remove_device(&mut house, "Kitchen", "Socket")
+            .is_ok());
+        assert!(history.undo(&mut house).is_ok());
+
+        let kitchen = house.get_room("Kitchen").unwrap();
+        let socket = kitchen.get_device("Socket").unwrap();
+        assert_eq!(socket.get_power_consumption(), Watts(42.0));
+
+        assert!(history.undo(&mut house).is_ok());
+        assert!(house
+            .get_room("Kitchen")
+            .unwrap()
+            .get_device_list()
+            .is_empty());
+        assert!(!history.can_undo());
+        assert_eq!(history.undo(&mut house), Err(ErrorReason::ItemDoesntExist));
+
+        assert!(history.redo(&mut house).is_ok());
+        assert!(house
+            .get_room("Kitchen")
+            .unwrap()
+            .get_device("Socket")
+            .is_some());
+    }
+
+    #[test]
+    fn failed_undo_keeps_removed_device_in_history() {
+        let mut house = house();
+        let mut history = EditHistory::default();
+
+        let socket = SmartSocket::new("Socket").with_id("kitchen-socket");
+        assert!(house.add_device("Kitchen", Box::new(socket)).is_ok());
+        assert!(history
+            .remove_device(&mut house, "Kitchen", "Socket")
+            .is_ok());
+
+        // Место удалённого устройства занято правкой в обход истории
+        assert!(house
+            .add_device(
+                "Kitchen",
+                Box::new(SmartSocket::new("Socket").with_id("other"))
+            )
+            .is_ok());
+        assert_eq!(
+            history.undo(&mut house),
+            Err(ErrorReason::ItemAlreadyPresented)
+        );
+        assert!(history.can_undo());
+
+        assert!(house.remove_device("Kitchen", "Socket").is_ok());
+        assert!(history.undo(&mut house).is_ok());
+        let kitchen = house.get_room("Kitchen").unwrap();
+        let socket = kitchen.get_device("Socket").unwrap();
+        assert_eq!(socket.get_metadata().get_id(), "kitchen-socket");
+    }
+
+    #[test]
+    fn renames_and_moves_are_undone_in_reverse_order() {
+        let mut house = house();
+        let mut history = EditHistory::default();
+
+        assert!(history
+            .add_device(&mut house, "Kitchen", Box::new(SmartSocket::new("Socket")))
+            .is_ok());
+        assert!(history
+            .rename_device(&mut house, "Kitchen", "Socket", "Kettle")
+            .is_ok());
+        assert!(history
+            .move_device(&mut house, "Kitchen", "Hall", "Kettle")
+            .is_ok());
+        assert!(history.rename_room(&mut house, "Hall", "Lobby").is_ok());
+        assert_eq!(
+            history.rename_room(&mut house, "Lobby", "Kitchen"),
+            Err(ErrorReason::ItemAlreadyPresented)
+        );
+
+        let lobby = house.get_room("Lobby").unwrap();
+        assert_eq!(lobby.get_device("Kettle").unwrap().get_name(), "Kettle");
+
+        for _ in 0..3 {
+            assert!(history.undo(&mut house).is_ok());
+        }
+        assert!(house.get_room("Lobby").is_none());
+        assert!(house.get_room("Hall").unwrap().get_device_list().is_empty());
+        assert!(house
+            .get_room("Kitchen")
+            .unwrap()
+            .get_device("Socket")
+            .is_some());
+
+        assert!(history.redo(&mut house).is_ok());
+        assert!(house
+            .get_room("Kitchen")
+            .unwrap()
+            .get_device("Kettle")
+            .is_some());
+
+        assert!(history.remove_room(&mut house, "Hall").is_ok());
+        assert!(!history.can_redo());
+    }
+}
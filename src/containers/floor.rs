@@ -0,0 +1,204 @@
+//! Модуль содержит описание этажей и зон умного дома
+//!
+//! > Этаж - уровень иерархии между домом и комнатой: каждая комната находится не более
+//! > чем на одном этаже, количество комнат на этаже ограничено. Зона (например, "зона отопления А")
+//! > объединяет комнаты независимо от этажей, одна комната может входить в несколько зон.
+//! > Этажи и зоны хранят только названия комнат, сами комнаты принадлежат дому.
+//! > Названия этажей, зон, комнат и устройств не могут быть пустыми и содержать
+//! > разделитель пути `/`, а этаж и комната дома не могут называться одинаково.
+//!
+use super::{ContainerIOHistory, ContainerName, ErrorReason};
+use crate::units::{Celsius, TemperatureScale, Watts};
+use std::fmt::{self, Display};
+
+/// Разделитель элементов пути к комнате или устройству (`house/floor1/kitchen/socket2`)
+pub const PATH_SEPARATOR: char = '/';
+
+/// Проверка названия этажа, зоны, комнаты или устройства
+///
+/// Название, содержащее разделитель пути, сделало бы путь к элементу неоднозначным,
+/// а пустое (или состоящее из одних пробелов) - неотличимым от отсутствующего
+pub fn validate_name(name: &str) -> Result<(), ErrorReason> {
+    if name.trim().is_empty() || name.contains(PATH_SEPARATOR) {
+        return Err(ErrorReason::InvalidName);
+    }
+    Ok(())
+}
+
+/// Тип, описывающий этаж дома
+#[derive(Clone, Debug, PartialEq)]
+pub struct Floor {
+    /// Название этажа
+    pub name: ContainerName,
+
+    /// Комнаты этажа
+    rooms: Vec<ContainerName>,
+
+    /// Максимальное количество комнат на этаже
+    room_limit: usize,
+}
+
+impl Floor {
+    /// Создание пустого этажа с именем `name`, на котором может быть не более `limit` комнат
+    pub fn new(name: &str, limit: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            rooms: Vec::new(),
+            room_limit: limit,
+        }
+    }
+
+    /// Получение списка комнат этажа
+    pub fn get_room_list(&self) -> Vec<ContainerName> {
+        self.rooms.clone()
+    }
+
+    /// Получение максимального количества комнат на этаже
+    pub fn get_room_limit(&self) -> usize {
+        self.room_limit
+    }
+
+    /// Проверка, находится ли комната на этаже
+    pub fn contains_room(&self, room_name: &str) -> bool {
+        self.rooms.iter().any(|name| name == room_name)
+    }
+
+    /// Размещение комнаты на этаже
+    pub fn add_room(&mut self, room_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        validate_name(room_name)?;
+        if self.rooms.len() >= self.room_limit {
+            return Err(ErrorReason::ItemLimitExceeded);
+        }
+        if self.contains_room(room_name) {
+            return Err(ErrorReason::ItemAlreadyPresented);
+        }
+
+        self.rooms.push(room_name.to_string());
+        Ok(format!(
+            "Room {} has been placed on floor {}",
+            room_name, self.name
+        ))
+    }
+
+    /// Удаление комнаты с этажа
+    pub fn remove_room(&mut self, room_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        let count = self.rooms.len();
+        self.rooms.retain(|name| name != room_name);
+        if count == self.rooms.len() {
+            return Err(ErrorReason::ItemDoesntExist);
+        }
+
+        Ok(format!(
+            "Room {} has been removed from floor {}",
+            room_name, self.name
+        ))
+    }
+
+    /// Замена названия комнаты при её переименовании
+    pub fn rename_room(&mut self, old_name: &str, new_name: &str) {
+        for name in self.rooms.iter_mut().filter(|name| *name == old_name) {
+            *name = new_name.to_string();
+        }
+    }
+}
+
+/// Тип, описывающий зону дома, объединяющую комнаты разных этажей
+#[derive(Clone, Debug, PartialEq)]
+pub struct Zone {
+    /// Название зоны
+    pub name: ContainerName,
+
+    /// Комнаты зоны
+    rooms: Vec<ContainerName>,
+}
+
+impl Zone {
+    /// Создание пустой зоны с именем `name`
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            rooms: Vec::new(),
+        }
+    }
+
+    /// Получение списка комнат зоны
+    pub fn get_room_list(&self) -> Vec<ContainerName> {
+        self.rooms.clone()
+    }
+
+    /// Проверка, входит ли комната в зону
+    pub fn contains_room(&self, room_name: &str) -> bool {
+        self.rooms.iter().any(|name| name == room_name)
+    }
+
+    /// Включение комнаты в зону
+    pub fn add_room(&mut self, room_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        validate_name(room_name)?;
+        if self.contains_room(room_name) {
+            return Err(ErrorReason::ItemAlreadyPresented);
+        }
+
+        self.rooms.push(room_name.to_string());
+        Ok(format!(
+            "Room {} has been added to zone {}",
+            room_name, self.name
+        ))
+    }
+
+    /// Исключение комнаты из зоны
+    pub fn remove_room(&mut self, room_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        let count = self.rooms.len();
+        self.rooms.retain(|name| name != room_name);
+        if count == self.rooms.len() {
+            return Err(ErrorReason::ItemDoesntExist);
+        }
+
+        Ok(format!(
+            "Room {} has been removed from zone {}",
+            room_name, self.name
+        ))
+    }
+
+    /// Замена названия комнаты при её переименовании
+    pub fn rename_room(&mut self, old_name: &str, new_name: &str) {
+        for name in self.rooms.iter_mut().filter(|name| *name == old_name) {
+            *name = new_name.to_string();
+        }
+    }
+}
+
+/// Сводные показатели части дома (дома целиком, этажа, зоны или комнаты)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContainerSummary {
+    /// Количество комнат
+    pub rooms: usize,
+
+    /// Количество умных устройств
+    pub devices: usize,
+
+    /// Суммарная потребляемая мощность
+    pub power_consumption: Watts,
+
+    /// Средняя температура по комнатам, в которых она измеряется
+    pub temperature: Option<Celsius>,
+}
+
+impl ContainerSummary {
+    /// Форматирование сводки со средней температурой в шкале `scale`
+    pub fn display_in(&self, scale: TemperatureScale) -> String {
+        let mut text = format!(
+            "rooms: {}, devices: {}, power consumption: {}",
+            self.rooms, self.devices, self.power_consumption
+        );
+        if let Some(temperature) = self.temperature {
+            text.push_str(&format!(", temperature: {}", temperature.display_in(scale)));
+        }
+        text
+    }
+}
+
+impl Display for ContainerSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.display_in(TemperatureScale::Celsius))
+    }
+}
@@ -0,0 +1,57 @@
+//! Модуль содержит описание групп умных устройств
+//!
+//! > Группа объединяет устройства из разных комнат: явно (по уникальному идентификатору
+//! > устройства) и по меткам - в группу входят все устройства, отмеченные хотя бы одной
+//! > из меток группы (например, "heater" или "outdoor"). Метки и явные члены группы
+//! > привязаны к идентификатору устройства, поэтому сохраняются при переносе
+//! > и переименовании устройства.
+//!
+use super::{ContainerName, ErrorReason};
+use crate::metadata::DeviceId;
+
+/// Alias для метки умного устройства
+pub type Tag = String;
+
+/// Alias для результатов групповой операции: (комната, устройство, результат)
+pub type GroupResults<T, E = ErrorReason> = Vec<(ContainerName, ContainerName, Result<T, E>)>;
+
+/// Тип, описывающий именованную группу умных устройств
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeviceGroup {
+    /// Название группы
+    pub name: ContainerName,
+
+    /// Идентификаторы устройств, явно включённых в группу
+    pub devices: Vec<DeviceId>,
+
+    /// Метки, устройства с которыми входят в группу
+    pub tags: Vec<Tag>,
+}
+
+impl DeviceGroup {
+    /// Создание пустой группы с именем `name`
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Явное включение устройства с идентификатором `id` в группу
+    pub fn with_device(mut self, id: &str) -> Self {
+        self.devices.push(id.to_string());
+        self
+    }
+
+    /// Включение в группу всех устройств с меткой `tag`
+    pub fn with_tag(mut self, tag: &str) -> Self {
+        self.tags.push(tag.to_string());
+        self
+    }
+
+    /// Проверка, входит ли в группу устройство с идентификатором `id` и метками `tags`
+    pub fn contains(&self, id: &str, tags: &[Tag]) -> bool {
+        self.devices.iter().any(|device| device == id)
+            || self.tags.iter().any(|tag| tags.contains(tag))
+    }
+}
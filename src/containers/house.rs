@@ -1,10 +1,24 @@
 use super::{ContainerIOHistory, ContainerName, ErrorReason};
+use crate::audit::{command_outcome, AuditLog, AuditOperation, DEFAULT_ACTOR};
+use crate::command::Capability;
+use crate::command::{SmartDeviceCommand, SmartDeviceCommandError, SmartDeviceResponse};
+use crate::containers::floor::{validate_name, ContainerSummary, Floor, Zone, PATH_SEPARATOR};
+use crate::containers::group::{DeviceGroup, GroupResults, Tag};
 use crate::containers::room::Room;
+use crate::events::{EventBus, EventPayload, HouseEvent};
+use crate::history::{AggregateSample, Resolution, RetentionPolicy, Sample};
+use crate::metadata::{DeviceId, DeviceKind, DeviceMetadata};
+use crate::query::{DeviceContext, Query, QueryError};
+use crate::smart_device::{SmartDevice, SmartDevicePowerState, SmartDeviceStatus};
+use crate::storage::{TelemetryEvent, TelemetryRecord, TelemetryStore};
+use crate::units::{Celsius, TemperatureScale, Watts};
 /// Smart house
 ///
 ///
 ///
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::time::SystemTime;
 
 //TODO: SmartContainerManagementStatus -> Result<>
 
@@ -18,6 +32,66 @@ pub struct House {
 
     /// Максимальное количество комнат в доме
     room_limit: usize,
+
+    /// Шина событий дома
+    events: EventBus,
+
+    /// Устройства (комната, устройство), о неисправности которых уже сообщено
+    reported_malfunctions: HashSet<(ContainerName, ContainerName)>,
+
+    /// Журнал аудита операций
+    audit: AuditLog,
+
+    /// Инициатор выполняемых операций
+    actor: String,
+
+    /// Этажи дома
+    floors: HashMap<ContainerName, Floor>,
+
+    /// Максимальное количество этажей в доме
+    floor_limit: usize,
+
+    /// Зоны дома
+    zones: HashMap<ContainerName, Zone>,
+
+    /// Метки умных устройств (по идентификатору устройства)
+    tags: HashMap<DeviceId, Vec<Tag>>,
+
+    /// Группы умных устройств
+    groups: HashMap<ContainerName, DeviceGroup>,
+
+    /// Правила хранения истории показаний, применяемые ко всем комнатам дома
+    history_policy: Option<RetentionPolicy>,
+}
+
+/// Составные части дома, переносимые в разделяемый дом
+pub(crate) struct HouseParts {
+    /// Название дома
+    pub name: ContainerName,
+    /// Комнаты дома
+    pub rooms: HashMap<ContainerName, Room>,
+    /// Максимальное количество комнат в доме
+    pub room_limit: usize,
+    /// Шина событий дома
+    pub events: EventBus,
+    /// Устройства, о неисправности которых уже сообщено
+    pub reported_malfunctions: HashSet<(ContainerName, ContainerName)>,
+    /// Журнал аудита операций
+    pub audit: AuditLog,
+    /// Инициатор выполняемых операций
+    pub actor: String,
+}
+
+/// Элемент дома, на который указывает путь
+enum PathTarget {
+    /// Дом целиком
+    House,
+    /// Этаж
+    Floor(ContainerName),
+    /// Комната
+    Room(ContainerName),
+    /// Устройство (комната, устройство)
+    Device(ContainerName, ContainerName),
 }
 
 impl House {
@@ -32,6 +106,16 @@ impl House {
             name: name.to_string(),
             rooms: HashMap::with_capacity(limit),
             room_limit: limit,
+            events: EventBus::new(),
+            reported_malfunctions: HashSet::new(),
+            audit: AuditLog::new(),
+            actor: DEFAULT_ACTOR.to_string(),
+            floors: HashMap::new(),
+            floor_limit: usize::MAX,
+            zones: HashMap::new(),
+            tags: HashMap::new(),
+            groups: HashMap::new(),
+            history_policy: None,
         }
     }
 
@@ -41,26 +125,66 @@ impl House {
         room_name: &str,
         device_limit: usize,
     ) -> Result<ContainerName, ErrorReason> {
-        if self.rooms.len() >= self.room_limit {
-            return Err(ErrorReason::ItemLimitExceeded);
-        }
-
-        if self.rooms.contains_key(room_name) {
-            return Err(ErrorReason::ItemAlreadyPresented);
-        }
-
         let new_room = Room::new(room_name, device_limit);
         self.add_room(new_room)
     }
 
     /// Добавление комнаты в дом
     pub fn add_room(&mut self, room: Room) -> Result<ContainerIOHistory, ErrorReason> {
+        self.try_add_room(room).map_err(|(reason, _)| reason)
+    }
+
+    /// Добавление комнаты в дом; при ошибке комната возвращается вызывающему
+    pub(crate) fn try_add_room(
+        &mut self,
+        room: Room,
+    ) -> Result<ContainerIOHistory, (ErrorReason, Box<Room>)> {
+        let room_name = room.name.clone();
+        let result = self.insert_room(room);
+        let outcome = result
+            .as_ref()
+            .map(Clone::clone)
+            .map_err(|(reason, _)| reason.clone());
+        self.audit.record(
+            &self.actor,
+            &room_name,
+            None,
+            AuditOperation::AddRoom,
+            &outcome,
+        );
+        result
+    }
+
+    /// Добавление комнаты в дом без записи в журнал аудита
+    fn insert_room(
+        &mut self,
+        mut room: Room,
+    ) -> Result<ContainerIOHistory, (ErrorReason, Box<Room>)> {
         if self.rooms.len() >= self.room_limit {
-            return Err(ErrorReason::ItemLimitExceeded);
+            return Err((ErrorReason::ItemLimitExceeded, Box::new(room)));
         }
 
-        if self.rooms.contains_key(&room.name) {
-            return Err(ErrorReason::ItemAlreadyPresented);
+        let invalid_name = std::iter::once(room.name.clone())
+            .chain(room.get_device_list())
+            .any(|name| validate_name(&name).is_err());
+        if invalid_name {
+            return Err((ErrorReason::InvalidName, Box::new(room)));
+        }
+
+        if self.rooms.contains_key(&room.name) || self.floors.contains_key(&room.name) {
+            return Err((ErrorReason::ItemAlreadyPresented, Box::new(room)));
+        }
+
+        let has_known_device = room.get_device_list().iter().any(|device_name| {
+            room.get_device(device_name)
+                .is_some_and(|device| self.has_conflicting_id(device.get_metadata()))
+        });
+        if has_known_device {
+            return Err((ErrorReason::ItemAlreadyPresented, Box::new(room)));
+        }
+
+        if let Some(policy) = self.history_policy {
+            room.set_history_policy(policy);
         }
 
         let status = format!(
@@ -68,6 +192,8 @@ impl House {
             room.name, self.name
         );
 
+        self.events
+            .publish(HouseEvent::room(&room.name, EventPayload::RoomAdded));
         self.rooms.insert(room.name.clone(), room);
 
         Ok(status)
@@ -78,63 +204,2038 @@ impl House {
         &mut self,
         room_name: &str,
     ) -> Result<ContainerIOHistory, ErrorReason> {
-        if !self.rooms.contains_key(room_name) {
-            return Err(ErrorReason::ItemDoesntExist);
-        }
+        self.extract_room(room_name).map(|(_, status)| status)
+    }
+
+    /// Извлечение комнаты из дома
+    ///
+    /// Комната возвращается вызывающему вместе со всеми устройствами и историей показаний
+    pub fn take_room(&mut self, room_name: &str) -> Result<Room, ErrorReason> {
+        self.extract_room(room_name).map(|(room, _)| room)
+    }
+
+    /// Извлечение комнаты из дома с записью в журнал аудита
+    fn extract_room(&mut self, room_name: &str) -> Result<(Room, ContainerIOHistory), ErrorReason> {
+        let result = self.detach_room(room_name);
+        let outcome = result
+            .as_ref()
+            .map(|(_, status)| status.clone())
+            .map_err(Clone::clone);
+        self.audit.record(
+            &self.actor,
+            room_name,
+            None,
+            AuditOperation::RemoveRoom,
+            &outcome,
+        );
+        result
+    }
 
-        self.rooms.remove(room_name);
+    /// Извлечение комнаты из дома без записи в журнал аудита
+    fn detach_room(&mut self, room_name: &str) -> Result<(Room, ContainerIOHistory), ErrorReason> {
+        let room = self
+            .rooms
+            .remove(room_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        self.reported_malfunctions
+            .retain(|(reported_room, _)| reported_room != room_name);
+        for floor in self.floors.values_mut() {
+            let _ = floor.remove_room(room_name);
+        }
+        for zone in self.zones.values_mut() {
+            let _ = zone.remove_room(room_name);
+        }
+        self.events
+            .publish(HouseEvent::room(room_name, EventPayload::RoomRemoved));
 
         let status = format!(
             "Room {} has been removed from house {}",
             room_name, self.name
         );
 
-        Ok(status)
+        Ok((room, status))
     }
 
     /// Получение комнаты по имени
-    pub fn get_room(&mut self, room_name: &str) -> Option<&mut Room> {
+    pub fn get_room(&self, room_name: &str) -> Option<&Room> {
+        self.rooms.get(room_name)
+    }
+
+    /// Получение изменяемой ссылки на комнату по имени
+    ///
+    /// Изменения, внесённые напрямую через комнату, не записываются в журнал аудита
+    /// и не сопровождаются событиями; для них следует использовать методы `House`
+    pub fn get_room_mut(&mut self, room_name: &str) -> Option<&mut Room> {
         self.rooms.get_mut(room_name)
     }
 
+    /// Получение максимального количества комнат в доме
+    pub fn get_room_limit(&self) -> usize {
+        self.room_limit
+    }
+
     /// Получение списка комнат в доме
     pub fn get_room_list(&self) -> Vec<ContainerName> {
         self.rooms.keys().cloned().collect()
     }
 
-    /// Создание отчёта для в соответствии с типом поставщика данных
-    pub fn create_report(&self) -> String {
-        let mut report: Vec<String> = Vec::new();
+    /// Получение шины событий дома
+    ///
+    /// События публикуются операциями, выполняемыми через `House`
+    pub fn get_event_bus(&mut self) -> &mut EventBus {
+        &mut self.events
+    }
 
-        report.push(format!("Smart house instance: {}.\n", self.name));
+    /// Добавление умного устройства в комнату дома
+    pub fn add_device(
+        &mut self,
+        room_name: &str,
+        device: Box<dyn SmartDevice>,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        self.try_add_device(room_name, device)
+            .map_err(|(reason, _)| reason)
+    }
 
-        if self.rooms.is_empty() {
-            report.push("There are no registered rooms and devices.\n".to_string());
-            return report.join("\n");
+    /// Добавление умного устройства в комнату дома; при ошибке устройство возвращается вызывающему
+    pub(crate) fn try_add_device(
+        &mut self,
+        room_name: &str,
+        device: Box<dyn SmartDevice>,
+    ) -> Result<ContainerIOHistory, (ErrorReason, Box<dyn SmartDevice>)> {
+        let device_name = device.get_name().to_string();
+        let result = self.insert_device(room_name, device);
+        let outcome = result
+            .as_ref()
+            .map(Clone::clone)
+            .map_err(|(reason, _)| reason.clone());
+        self.audit.record(
+            &self.actor,
+            room_name,
+            Some(&device_name),
+            AuditOperation::AddDevice,
+            &outcome,
+        );
+        result
+    }
+
+    /// Добавление умного устройства в комнату дома без записи в журнал аудита
+    fn insert_device(
+        &mut self,
+        room_name: &str,
+        device: Box<dyn SmartDevice>,
+    ) -> Result<ContainerIOHistory, (ErrorReason, Box<dyn SmartDevice>)> {
+        if !self.rooms.contains_key(room_name) {
+            return Err((ErrorReason::ItemDoesntExist, device));
+        }
+        let device_name = device.get_name().to_string();
+        if let Err(reason) = validate_name(&device_name) {
+            return Err((reason, device));
         }
 
-        for (room_name, room) in self.rooms.iter() {
-            report.push(format!("Room: {}\n", room_name));
+        if self.has_conflicting_id(device.get_metadata()) {
+            return Err((ErrorReason::ItemAlreadyPresented, device));
+        }
 
-            if room.get_device_list().is_empty() {
-                report.push("There are no devices in this room".to_string());
-            } else {
-                for device_name in room.get_device_list() {
-                    report.push(format!("Device: {}: ", device_name));
+        let Some(room) = self.rooms.get_mut(room_name) else {
+            return Err((ErrorReason::ItemDoesntExist, device));
+        };
+        let status = room.try_add_device(device)?;
+        self.events.publish(HouseEvent::device(
+            room_name,
+            &device_name,
+            EventPayload::DeviceAdded,
+        ));
+        Ok(status)
+    }
 
-                    // Получение текстового отчёта о состоянии умного устройства
-                    match room.get_device(&device_name) {
-                        Some(device) => {
-                            report.push(device.get_text_report());
-                        }
-                        None => {
-                            report.push("Connection was refused!".to_string());
-                        }
-                    }
+    /// Удаление умного устройства из комнаты дома
+    pub fn remove_device(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        self.extract_device(room_name, device_name)
+            .map(|(_, status)| status)
+    }
+
+    /// Извлечение умного устройства из комнаты дома
+    ///
+    /// Устройство возвращается вызывающему вместе со своим состоянием
+    pub fn take_device(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<Box<dyn SmartDevice>, ErrorReason> {
+        self.extract_device(room_name, device_name)
+            .map(|(device, _)| device)
+    }
+
+    /// Извлечение умного устройства из комнаты дома с записью в журнал аудита
+    fn extract_device(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<(Box<dyn SmartDevice>, ContainerIOHistory), ErrorReason> {
+        let result = self.detach_device(room_name, device_name);
+        let outcome = result
+            .as_ref()
+            .map(|(_, status)| status.clone())
+            .map_err(Clone::clone);
+        self.audit.record(
+            &self.actor,
+            room_name,
+            Some(device_name),
+            AuditOperation::RemoveDevice,
+            &outcome,
+        );
+        result
+    }
+
+    /// Извлечение умного устройства из комнаты дома без записи в журнал аудита
+    fn detach_device(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<(Box<dyn SmartDevice>, ContainerIOHistory), ErrorReason> {
+        let room = self
+            .rooms
+            .get_mut(room_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+
+        let device = room.take_device(device_name)?;
+        let status = format!(
+            "Device {} has been removed from room {}",
+            device_name, room_name
+        );
+        self.reported_malfunctions
+            .remove(&(room_name.to_string(), device_name.to_string()));
+        self.events.publish(HouseEvent::device(
+            room_name,
+            device_name,
+            EventPayload::DeviceRemoved,
+        ));
+        Ok((device, status))
+    }
+
+    /// Включение/выключение умного устройства в комнате дома
+    pub fn set_power_state(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+        state: SmartDevicePowerState,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.switch_device(room_name, device_name, state.clone());
+        self.audit(
+            room_name,
+            Some(device_name),
+            AuditOperation::SetPowerState(state),
+            result,
+        )
+    }
+
+    /// Включение/выключение умного устройства без записи в журнал аудита
+    fn switch_device(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+        state: SmartDevicePowerState,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let device = self
+            .rooms
+            .get_mut(room_name)
+            .and_then(|room| room.get_device_mut(device_name))
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+
+        let key = (room_name.to_string(), device_name.to_string());
+        match device.set_power_state(state.clone()) {
+            Ok(()) => {
+                let status = format!(
+                    "Device {} in room {} has been switched to {}",
+                    device_name, room_name, state
+                );
+                self.reported_malfunctions.remove(&key);
+                self.events.publish(HouseEvent::device(
+                    room_name,
+                    device_name,
+                    EventPayload::PowerStateChanged(state),
+                ));
+                Ok(status)
+            }
+            Err(code) => {
+                // О неисправности сообщается один раз, как и при опросе показаний
+                if code.is_fault() && self.reported_malfunctions.insert(key) {
+                    self.events.publish(HouseEvent::device(
+                        room_name,
+                        device_name,
+                        EventPayload::Malfunction(code.clone()),
+                    ));
                 }
-                report.push("\n".to_string());
+                Err(ErrorReason::DeviceFailure(code))
             }
         }
-        report.join(" ")
+    }
+
+    /// Выполнение универсальной команды умным устройством в комнате дома
+    ///
+    /// Команда записывается в журнал аудита, а её выполнение сопровождается событием
+    pub fn execute(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+        command: SmartDeviceCommand,
+    ) -> Result<Result<SmartDeviceResponse, SmartDeviceCommandError>, ErrorReason> {
+        let command_name = command.get_name();
+        let result = self.run_command(room_name, device_name, command);
+        let outcome = command_outcome(room_name, device_name, command_name, &result);
+        let operation = AuditOperation::Execute {
+            command: command_name.to_string(),
+        };
+        self.audit.record(
+            &self.actor,
+            room_name,
+            Some(device_name),
+            operation,
+            &outcome,
+        );
+        result
+    }
+
+    /// Выполнение универсальной команды без записи в журнал аудита
+    fn run_command(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+        command: SmartDeviceCommand,
+    ) -> Result<Result<SmartDeviceResponse, SmartDeviceCommandError>, ErrorReason> {
+        let device = self
+            .rooms
+            .get_mut(room_name)
+            .and_then(|room| room.get_device_mut(device_name))
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+
+        let key = (room_name.to_string(), device_name.to_string());
+        let result = device.execute(command.clone());
+        match &result {
+            Ok(_) => {
+                if matches!(
+                    command,
+                    SmartDeviceCommand::SetPowerState(_) | SmartDeviceCommand::ResetFault
+                ) {
+                    self.reported_malfunctions.remove(&key);
+                }
+                if let Some(payload) = EventPayload::for_command(&command) {
+                    self.events
+                        .publish(HouseEvent::device(room_name, device_name, payload));
+                }
+            }
+            Err(SmartDeviceCommandError::Device(code)) => {
+                if code.is_fault() && self.reported_malfunctions.insert(key) {
+                    self.events.publish(HouseEvent::device(
+                        room_name,
+                        device_name,
+                        EventPayload::Malfunction(code.clone()),
+                    ));
+                }
+            }
+            Err(SmartDeviceCommandError::Unsupported) => {}
+        }
+        Ok(result)
+    }
+
+    /// Переименование комнаты дома
+    ///
+    /// При ошибке (комнаты нет или новое название занято) дом не изменяется
+    pub fn rename_room(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.relabel_room(old_name, new_name);
+        self.audit(
+            old_name,
+            None,
+            AuditOperation::RenameRoom {
+                new_name: new_name.to_string(),
+            },
+            result,
+        )
+    }
+
+    /// Переименование комнаты дома без записи в журнал аудита
+    fn relabel_room(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        if !self.rooms.contains_key(old_name) {
+            return Err(ErrorReason::ItemDoesntExist);
+        }
+        validate_name(new_name)?;
+        if self.rooms.contains_key(new_name) || self.floors.contains_key(new_name) {
+            return Err(ErrorReason::ItemAlreadyPresented);
+        }
+
+        let mut room = self
+            .rooms
+            .remove(old_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        room.name = new_name.to_string();
+        self.rooms.insert(new_name.to_string(), room);
+        for floor in self.floors.values_mut() {
+            floor.rename_room(old_name, new_name);
+        }
+        for zone in self.zones.values_mut() {
+            zone.rename_room(old_name, new_name);
+        }
+
+        self.reported_malfunctions = self
+            .reported_malfunctions
+            .drain()
+            .map(|(room_name, device_name)| {
+                if room_name == old_name {
+                    (new_name.to_string(), device_name)
+                } else {
+                    (room_name, device_name)
+                }
+            })
+            .collect();
+        self.events.publish(HouseEvent::room(
+            new_name,
+            EventPayload::RoomRenamed {
+                old_name: old_name.to_string(),
+            },
+        ));
+
+        let status = format!(
+            "Room {} in house {} has been renamed to {}",
+            old_name, self.name, new_name
+        );
+        Ok(status)
+    }
+
+    /// Переименование умного устройства в комнате дома
+    ///
+    /// При ошибке (устройства нет или новое название занято) дом не изменяется
+    pub fn rename_device(
+        &mut self,
+        room_name: &str,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.relabel_device(room_name, old_name, new_name);
+        self.audit(
+            room_name,
+            Some(old_name),
+            AuditOperation::RenameDevice {
+                new_name: new_name.to_string(),
+            },
+            result,
+        )
+    }
+
+    /// Переименование умного устройства без записи в журнал аудита
+    fn relabel_device(
+        &mut self,
+        room_name: &str,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let room = self
+            .rooms
+            .get_mut(room_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+
+        validate_name(new_name)?;
+        let status = room.rename_device(old_name, new_name)?;
+        if self
+            .reported_malfunctions
+            .remove(&(room_name.to_string(), old_name.to_string()))
+        {
+            self.reported_malfunctions
+                .insert((room_name.to_string(), new_name.to_string()));
+        }
+        self.events.publish(HouseEvent::device(
+            room_name,
+            new_name,
+            EventPayload::DeviceRenamed {
+                old_name: old_name.to_string(),
+            },
+        ));
+        Ok(status)
+    }
+
+    /// Перенос умного устройства из комнаты `from_room` в комнату `to_room`
+    ///
+    /// Ограничения целевой комнаты (количество устройств, уникальность названия)
+    /// проверяются до переноса; при ошибке дом не изменяется
+    pub fn move_device(
+        &mut self,
+        from_room: &str,
+        to_room: &str,
+        device_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.relocate_device(from_room, to_room, device_name);
+        self.audit(
+            from_room,
+            Some(device_name),
+            AuditOperation::MoveDevice {
+                to_room: to_room.to_string(),
+            },
+            result,
+        )
+    }
+
+    /// Перенос умного устройства без записи в журнал аудита
+    fn relocate_device(
+        &mut self,
+        from_room: &str,
+        to_room: &str,
+        device_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let source = self
+            .rooms
+            .get(from_room)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        if source.get_device(device_name).is_none() {
+            return Err(ErrorReason::ItemDoesntExist);
+        }
+
+        let target = self
+            .rooms
+            .get(to_room)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        if target.get_device(device_name).is_some() {
+            return Err(ErrorReason::ItemAlreadyPresented);
+        }
+        if target.get_device_list().len() >= target.get_device_limit() {
+            return Err(ErrorReason::ItemLimitExceeded);
+        }
+
+        // История показаний переносится вместе с устройством
+        let source = self
+            .rooms
+            .get_mut(from_room)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        let history = source.take_device_history(device_name);
+        let device = source.take_device(device_name)?;
+        let target = self
+            .rooms
+            .get_mut(to_room)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        target.add_device(device)?;
+        if let Some(history) = history {
+            target.set_device_history(device_name, history);
+        }
+
+        if self
+            .reported_malfunctions
+            .remove(&(from_room.to_string(), device_name.to_string()))
+        {
+            self.reported_malfunctions
+                .insert((to_room.to_string(), device_name.to_string()));
+        }
+        self.events.publish(HouseEvent::device(
+            to_room,
+            device_name,
+            EventPayload::DeviceMoved {
+                from_room: from_room.to_string(),
+            },
+        ));
+
+        let status = format!(
+            "Device {} has been moved from room {} to room {}",
+            device_name, from_room, to_room
+        );
+        Ok(status)
+    }
+
+    /// Установка максимального количества этажей в доме (по умолчанию не ограничено)
+    pub fn set_floor_limit(&mut self, limit: usize) {
+        self.floor_limit = limit;
+    }
+
+    /// Получение максимального количества этажей в доме
+    pub fn get_floor_limit(&self) -> usize {
+        self.floor_limit
+    }
+
+    /// Создание нового пустого этажа, на котором может быть не более `room_limit` комнат
+    pub fn add_floor(
+        &mut self,
+        floor_name: &str,
+        room_limit: usize,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.insert_floor(floor_name, room_limit);
+        let operation = AuditOperation::AddFloor {
+            floor: floor_name.to_string(),
+        };
+        self.audit("", None, operation, result)
+    }
+
+    /// Создание нового пустого этажа без записи в журнал аудита
+    fn insert_floor(
+        &mut self,
+        floor_name: &str,
+        room_limit: usize,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        validate_name(floor_name)?;
+        if self.floors.len() >= self.floor_limit {
+            return Err(ErrorReason::ItemLimitExceeded);
+        }
+        // Этаж и комната с одинаковым названием сделали бы путь `house/<name>` неоднозначным
+        if self.floors.contains_key(floor_name) || self.rooms.contains_key(floor_name) {
+            return Err(ErrorReason::ItemAlreadyPresented);
+        }
+
+        self.floors
+            .insert(floor_name.to_string(), Floor::new(floor_name, room_limit));
+        self.events
+            .publish(HouseEvent::house(EventPayload::FloorAdded {
+                floor: floor_name.to_string(),
+            }));
+        Ok(format!(
+            "Floor {} has been registered in house {}",
+            floor_name, self.name
+        ))
+    }
+
+    /// Удаление этажа; комнаты этажа остаются в доме без привязки к этажу
+    pub fn remove_floor(&mut self, floor_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.detach_floor(floor_name);
+        let operation = AuditOperation::RemoveFloor {
+            floor: floor_name.to_string(),
+        };
+        self.audit("", None, operation, result)
+    }
+
+    /// Удаление этажа без записи в журнал аудита
+    fn detach_floor(&mut self, floor_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        self.floors
+            .remove(floor_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        self.events
+            .publish(HouseEvent::house(EventPayload::FloorRemoved {
+                floor: floor_name.to_string(),
+            }));
+        Ok(format!(
+            "Floor {} has been removed from house {}",
+            floor_name, self.name
+        ))
+    }
+
+    /// Получение этажа по имени
+    pub fn get_floor(&self, floor_name: &str) -> Option<&Floor> {
+        self.floors.get(floor_name)
+    }
+
+    /// Получение списка этажей дома
+    pub fn get_floor_list(&self) -> Vec<ContainerName> {
+        self.floors.keys().cloned().collect()
+    }
+
+    /// Размещение комнаты дома на этаже
+    ///
+    /// Комната, уже размещённая на другом этаже, переносится; при ошибке дом не изменяется
+    pub fn place_room(
+        &mut self,
+        room_name: &str,
+        floor_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.locate_room(room_name, floor_name);
+        let operation = AuditOperation::PlaceRoom {
+            floor: floor_name.to_string(),
+        };
+        self.audit(room_name, None, operation, result)
+    }
+
+    /// Размещение комнаты дома на этаже без записи в журнал аудита
+    fn locate_room(
+        &mut self,
+        room_name: &str,
+        floor_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        if !self.rooms.contains_key(room_name) {
+            return Err(ErrorReason::ItemDoesntExist);
+        }
+
+        let floor = self
+            .floors
+            .get_mut(floor_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        let status = floor.add_room(room_name)?;
+
+        for floor in self.floors.values_mut() {
+            if floor.name != floor_name {
+                let _ = floor.remove_room(room_name);
+            }
+        }
+        self.events.publish(HouseEvent::room(
+            room_name,
+            EventPayload::RoomPlaced {
+                floor: floor_name.to_string(),
+            },
+        ));
+        Ok(status)
+    }
+
+    /// Добавление комнаты в дом с размещением на этаже
+    ///
+    /// Проверяются ограничения и дома, и этажа; при ошибке дом не изменяется
+    pub fn add_room_to_floor(
+        &mut self,
+        floor_name: &str,
+        room: Room,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let floor = self
+            .floors
+            .get(floor_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        if floor.get_room_list().len() >= floor.get_room_limit() {
+            return Err(ErrorReason::ItemLimitExceeded);
+        }
+
+        let room_name = room.name.clone();
+        let status = self.add_room(room)?;
+        self.place_room(&room_name, floor_name)?;
+        Ok(status)
+    }
+
+    /// Получение названия этажа, на котором находится комната
+    pub fn get_room_floor(&self, room_name: &str) -> Option<&str> {
+        self.floors
+            .values()
+            .find(|floor| floor.contains_room(room_name))
+            .map(|floor| floor.name.as_str())
+    }
+
+    /// Создание новой пустой зоны
+    pub fn add_zone(&mut self, zone_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.insert_zone(zone_name);
+        let operation = AuditOperation::AddZone {
+            zone: zone_name.to_string(),
+        };
+        self.audit("", None, operation, result)
+    }
+
+    /// Создание новой пустой зоны без записи в журнал аудита
+    fn insert_zone(&mut self, zone_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        validate_name(zone_name)?;
+        if self.zones.contains_key(zone_name) {
+            return Err(ErrorReason::ItemAlreadyPresented);
+        }
+
+        self.zones
+            .insert(zone_name.to_string(), Zone::new(zone_name));
+        self.events
+            .publish(HouseEvent::house(EventPayload::ZoneAdded {
+                zone: zone_name.to_string(),
+            }));
+        Ok(format!(
+            "Zone {} has been registered in house {}",
+            zone_name, self.name
+        ))
+    }
+
+    /// Удаление зоны; комнаты зоны остаются в доме
+    pub fn remove_zone(&mut self, zone_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.detach_zone(zone_name);
+        let operation = AuditOperation::RemoveZone {
+            zone: zone_name.to_string(),
+        };
+        self.audit("", None, operation, result)
+    }
+
+    /// Удаление зоны без записи в журнал аудита
+    fn detach_zone(&mut self, zone_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        self.zones
+            .remove(zone_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        self.events
+            .publish(HouseEvent::house(EventPayload::ZoneRemoved {
+                zone: zone_name.to_string(),
+            }));
+        Ok(format!(
+            "Zone {} has been removed from house {}",
+            zone_name, self.name
+        ))
+    }
+
+    /// Получение зоны по имени
+    pub fn get_zone(&self, zone_name: &str) -> Option<&Zone> {
+        self.zones.get(zone_name)
+    }
+
+    /// Получение списка зон дома
+    pub fn get_zone_list(&self) -> Vec<ContainerName> {
+        self.zones.keys().cloned().collect()
+    }
+
+    /// Включение комнаты дома в зону
+    pub fn add_room_to_zone(
+        &mut self,
+        zone_name: &str,
+        room_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.join_zone(zone_name, room_name);
+        let operation = AuditOperation::AddRoomToZone {
+            zone: zone_name.to_string(),
+        };
+        self.audit(room_name, None, operation, result)
+    }
+
+    /// Включение комнаты дома в зону без записи в журнал аудита
+    fn join_zone(
+        &mut self,
+        zone_name: &str,
+        room_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        if !self.rooms.contains_key(room_name) {
+            return Err(ErrorReason::ItemDoesntExist);
+        }
+
+        let status = self
+            .zones
+            .get_mut(zone_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?
+            .add_room(room_name)?;
+        self.events.publish(HouseEvent::room(
+            room_name,
+            EventPayload::RoomAddedToZone {
+                zone: zone_name.to_string(),
+            },
+        ));
+        Ok(status)
+    }
+
+    /// Исключение комнаты из зоны
+    pub fn remove_room_from_zone(
+        &mut self,
+        zone_name: &str,
+        room_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.leave_zone(zone_name, room_name);
+        let operation = AuditOperation::RemoveRoomFromZone {
+            zone: zone_name.to_string(),
+        };
+        self.audit(room_name, None, operation, result)
+    }
+
+    /// Исключение комнаты из зоны без записи в журнал аудита
+    fn leave_zone(
+        &mut self,
+        zone_name: &str,
+        room_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let status = self
+            .zones
+            .get_mut(zone_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?
+            .remove_room(room_name)?;
+        self.events.publish(HouseEvent::room(
+            room_name,
+            EventPayload::RoomRemovedFromZone {
+                zone: zone_name.to_string(),
+            },
+        ));
+        Ok(status)
+    }
+
+    /// Получение пути к комнате (`house/floor/room` или `house/room` для комнаты вне этажей)
+    pub fn get_room_path(&self, room_name: &str) -> Option<String> {
+        if !self.rooms.contains_key(room_name) {
+            return None;
+        }
+
+        let mut segments = vec![self.name.as_str()];
+        segments.extend(self.get_room_floor(room_name));
+        segments.push(room_name);
+        Some(segments.join(&PATH_SEPARATOR.to_string()))
+    }
+
+    /// Получение пути к умному устройству (`house/floor/room/device`)
+    pub fn get_device_path(&self, room_name: &str, device_name: &str) -> Option<String> {
+        self.rooms.get(room_name)?.get_device(device_name)?;
+        self.get_room_path(room_name)
+            .map(|path| format!("{}{}{}", path, PATH_SEPARATOR, device_name))
+    }
+
+    /// Получение комнаты по пути (`house/floor/room` или `house/room`)
+    pub fn get_room_by_path(&self, path: &str) -> Option<&Room> {
+        match self.resolve_path(path)? {
+            PathTarget::Room(room_name) => self.rooms.get(&room_name),
+            _ => None,
+        }
+    }
+
+    /// Получение умного устройства по пути (`house/floor/room/device` или `house/room/device`)
+    pub fn get_device_by_path(&self, path: &str) -> Option<&dyn SmartDevice> {
+        match self.resolve_path(path)? {
+            PathTarget::Device(room_name, device_name) => {
+                self.rooms.get(&room_name)?.get_device(&device_name)
+            }
+            _ => None,
+        }
+    }
+
+    /// Получение сводных показателей части дома по пути (дом, этаж, комната)
+    pub fn summarize(&self, path: &str) -> Option<ContainerSummary> {
+        let rooms = match self.resolve_path(path)? {
+            PathTarget::House => self.get_room_list(),
+            PathTarget::Floor(floor_name) => self.floors.get(&floor_name)?.get_room_list(),
+            PathTarget::Room(room_name) => vec![room_name],
+            PathTarget::Device(_, _) => return None,
+        };
+        Some(self.summarize_rooms(&rooms))
+    }
+
+    /// Получение сводных показателей зоны
+    pub fn summarize_zone(&self, zone_name: &str) -> Option<ContainerSummary> {
+        let rooms = self.zones.get(zone_name)?.get_room_list();
+        Some(self.summarize_rooms(&rooms))
+    }
+
+    /// Подсчёт сводных показателей по списку комнат
+    fn summarize_rooms(&self, room_names: &[ContainerName]) -> ContainerSummary {
+        let rooms: Vec<&Room> = room_names
+            .iter()
+            .filter_map(|room_name| self.rooms.get(room_name))
+            .collect();
+        let temperatures: Vec<f32> = rooms
+            .iter()
+            .filter_map(|room| room.get_temperature())
+            .map(|temperature| temperature.0)
+            .collect();
+
+        ContainerSummary {
+            rooms: rooms.len(),
+            devices: rooms.iter().map(|room| room.get_device_list().len()).sum(),
+            power_consumption: rooms.iter().map(|room| room.get_power_consumption()).sum(),
+            temperature: (!temperatures.is_empty())
+                .then(|| Celsius(temperatures.iter().sum::<f32>() / temperatures.len() as f32)),
+        }
+    }
+
+    /// Разбор пути к элементу дома
+    ///
+    /// Первый элемент пути - название дома; далее следуют этаж (необязательно),
+    /// комната и устройство
+    fn resolve_path(&self, path: &str) -> Option<PathTarget> {
+        let segments: Vec<&str> = path
+            .trim_matches(PATH_SEPARATOR)
+            .split(PATH_SEPARATOR)
+            .collect();
+        let (house_name, rest) = segments.split_first()?;
+        if *house_name != self.name {
+            return None;
+        }
+
+        let on_floor = |floor_name: &str, room_name: &str| {
+            self.floors
+                .get(floor_name)
+                .is_some_and(|floor| floor.contains_room(room_name))
+        };
+        let has_device = |room_name: &str, device_name: &str| {
+            self.rooms
+                .get(room_name)
+                .is_some_and(|room| room.get_device(device_name).is_some())
+        };
+
+        match *rest {
+            [] => Some(PathTarget::House),
+            [name] if self.floors.contains_key(name) => Some(PathTarget::Floor(name.to_string())),
+            [name] if self.rooms.contains_key(name) => Some(PathTarget::Room(name.to_string())),
+            [floor_name, room_name] if on_floor(floor_name, room_name) => {
+                Some(PathTarget::Room(room_name.to_string()))
+            }
+            [room_name, device_name] if has_device(room_name, device_name) => Some(
+                PathTarget::Device(room_name.to_string(), device_name.to_string()),
+            ),
+            [floor_name, room_name, device_name]
+                if on_floor(floor_name, room_name) && has_device(room_name, device_name) =>
+            {
+                Some(PathTarget::Device(
+                    room_name.to_string(),
+                    device_name.to_string(),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Добавление метки `tag` умному устройству
+    ///
+    /// Метка привязана к идентификатору устройства и сохраняется при его переносе,
+    /// переименовании, а также при удалении (для восстановления отменой правки)
+    pub fn add_tag(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+        tag: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.insert_tag(room_name, device_name, tag);
+        let operation = AuditOperation::AddTag {
+            tag: tag.to_string(),
+        };
+        self.audit(room_name, Some(device_name), operation, result)
+    }
+
+    /// Добавление метки умному устройству без записи в журнал аудита
+    fn insert_tag(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+        tag: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let id = self.get_device_id(room_name, device_name)?;
+        let tags = self.tags.entry(id).or_default();
+        if tags.iter().any(|existing| existing == tag) {
+            return Err(ErrorReason::ItemAlreadyPresented);
+        }
+
+        tags.push(tag.to_string());
+        self.events.publish(HouseEvent::device(
+            room_name,
+            device_name,
+            EventPayload::TagAdded {
+                tag: tag.to_string(),
+            },
+        ));
+        Ok(format!(
+            "Device {} in room {} has been tagged as {}",
+            device_name, room_name, tag
+        ))
+    }
+
+    /// Удаление метки `tag` умного устройства
+    pub fn remove_tag(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+        tag: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.detach_tag(room_name, device_name, tag);
+        let operation = AuditOperation::RemoveTag {
+            tag: tag.to_string(),
+        };
+        self.audit(room_name, Some(device_name), operation, result)
+    }
+
+    /// Удаление метки умного устройства без записи в журнал аудита
+    fn detach_tag(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+        tag: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let id = self.get_device_id(room_name, device_name)?;
+        let tags = self.tags.get_mut(&id).ok_or(ErrorReason::ItemDoesntExist)?;
+        let count = tags.len();
+        tags.retain(|existing| existing != tag);
+        if count == tags.len() {
+            return Err(ErrorReason::ItemDoesntExist);
+        }
+
+        self.events.publish(HouseEvent::device(
+            room_name,
+            device_name,
+            EventPayload::TagRemoved {
+                tag: tag.to_string(),
+            },
+        ));
+        Ok(format!(
+            "Tag {} has been removed from device {} in room {}",
+            tag, device_name, room_name
+        ))
+    }
+
+    /// Получение меток умного устройства
+    pub fn get_device_tags(&self, room_name: &str, device_name: &str) -> Vec<Tag> {
+        self.get_device_id(room_name, device_name)
+            .ok()
+            .and_then(|id| self.tags.get(&id).cloned())
+            .unwrap_or_default()
+    }
+
+    /// Поиск умных устройств с меткой `tag` во всех комнатах дома
+    ///
+    /// Возвращается список пар (название комнаты, название устройства)
+    pub fn find_devices_by_tag(&self, tag: &str) -> Vec<(ContainerName, ContainerName)> {
+        self.select_devices(|id| {
+            self.tags
+                .get(id)
+                .is_some_and(|tags| tags.iter().any(|existing| existing == tag))
+        })
+    }
+
+    /// Добавление группы умных устройств
+    pub fn add_group(&mut self, group: DeviceGroup) -> Result<ContainerIOHistory, ErrorReason> {
+        let operation = AuditOperation::AddGroup {
+            group: group.name.clone(),
+        };
+        let result = self.insert_group(group);
+        self.audit("", None, operation, result)
+    }
+
+    /// Добавление группы умных устройств без записи в журнал аудита
+    fn insert_group(&mut self, group: DeviceGroup) -> Result<ContainerIOHistory, ErrorReason> {
+        validate_name(&group.name)?;
+        if self.groups.contains_key(&group.name) {
+            return Err(ErrorReason::ItemAlreadyPresented);
+        }
+
+        let status = format!(
+            "Group {} has been registered in house {}",
+            group.name, self.name
+        );
+        self.events
+            .publish(HouseEvent::house(EventPayload::GroupAdded {
+                group: group.name.clone(),
+            }));
+        self.groups.insert(group.name.clone(), group);
+        Ok(status)
+    }
+
+    /// Удаление группы умных устройств (сами устройства не затрагиваются)
+    pub fn remove_group(&mut self, group_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.detach_group(group_name);
+        let operation = AuditOperation::RemoveGroup {
+            group: group_name.to_string(),
+        };
+        self.audit("", None, operation, result)
+    }
+
+    /// Удаление группы умных устройств без записи в журнал аудита
+    fn detach_group(&mut self, group_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        self.groups
+            .remove(group_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        self.events
+            .publish(HouseEvent::house(EventPayload::GroupRemoved {
+                group: group_name.to_string(),
+            }));
+        Ok(format!(
+            "Group {} has been removed from house {}",
+            group_name, self.name
+        ))
+    }
+
+    /// Получение группы по имени
+    pub fn get_group(&self, group_name: &str) -> Option<&DeviceGroup> {
+        self.groups.get(group_name)
+    }
+
+    /// Получение списка групп дома
+    pub fn get_group_list(&self) -> Vec<ContainerName> {
+        self.groups.keys().cloned().collect()
+    }
+
+    /// Получение списка умных устройств группы в виде пар (название комнаты, название устройства)
+    pub fn get_group_members(
+        &self,
+        group_name: &str,
+    ) -> Option<Vec<(ContainerName, ContainerName)>> {
+        let group = self.groups.get(group_name)?;
+        Some(
+            self.select_devices(|id| {
+                group.contains(id, self.tags.get(id).map_or(&[], Vec::as_slice))
+            }),
+        )
+    }
+
+    /// Включение/выключение всех умных устройств группы
+    ///
+    /// Возвращается результат операции для каждого устройства группы
+    pub fn set_group_power_state(
+        &mut self,
+        group_name: &str,
+        state: SmartDevicePowerState,
+    ) -> Result<GroupResults<ContainerIOHistory>, ErrorReason> {
+        let members = self
+            .get_group_members(group_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+
+        Ok(members
+            .into_iter()
+            .map(|(room_name, device_name)| {
+                let result = self.set_power_state(&room_name, &device_name, state.clone());
+                (room_name, device_name, result)
+            })
+            .collect())
+    }
+
+    /// Выполнение универсальной команды всеми умными устройствами группы
+    ///
+    /// Возвращается результат выполнения команды для каждого устройства группы
+    pub fn execute_group(
+        &mut self,
+        group_name: &str,
+        command: SmartDeviceCommand,
+    ) -> Result<GroupResults<SmartDeviceResponse, SmartDeviceCommandError>, ErrorReason> {
+        let members = self
+            .get_group_members(group_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+
+        Ok(members
+            .into_iter()
+            .filter_map(|(room_name, device_name)| {
+                let result = self
+                    .execute(&room_name, &device_name, command.clone())
+                    .ok()?;
+                Some((room_name, device_name, result))
+            })
+            .collect())
+    }
+
+    /// Получение сводных показателей группы умных устройств
+    pub fn summarize_group(&self, group_name: &str) -> Option<ContainerSummary> {
+        let members = self.get_group_members(group_name)?;
+        let devices: Vec<&dyn SmartDevice> = members
+            .iter()
+            .filter_map(|(room_name, device_name)| {
+                self.rooms.get(room_name)?.get_device(device_name)
+            })
+            .collect();
+        let rooms: HashSet<&ContainerName> =
+            members.iter().map(|(room_name, _)| room_name).collect();
+        let temperatures: Vec<f32> = devices
+            .iter()
+            .filter_map(|device| device.get_temperature())
+            .map(|temperature| temperature.0)
+            .collect();
+
+        Some(ContainerSummary {
+            rooms: rooms.len(),
+            devices: devices.len(),
+            power_consumption: devices
+                .iter()
+                .map(|device| device.get_power_consumption())
+                .sum(),
+            temperature: (!temperatures.is_empty())
+                .then(|| Celsius(temperatures.iter().sum::<f32>() / temperatures.len() as f32)),
+        })
+    }
+
+    /// Поиск умных устройств, удовлетворяющих запросу `query`, во всех комнатах дома
+    ///
+    /// Возвращается список пар (название комнаты, название устройства)
+    pub fn find_devices(&self, query: &Query) -> Vec<(ContainerName, ContainerName)> {
+        let mut found = Vec::new();
+        for (room_name, room) in self.rooms.iter() {
+            let floor = self.get_room_floor(room_name);
+            for device_name in room.get_device_list() {
+                let Some(device) = room.get_device(&device_name) else {
+                    continue;
+                };
+
+                let context = DeviceContext {
+                    room: room_name,
+                    floor,
+                    device,
+                    tags: self
+                        .tags
+                        .get(device.get_metadata().get_id())
+                        .map_or(&[], Vec::as_slice),
+                };
+                if query.matches(&context) {
+                    found.push((room_name.clone(), device_name));
+                }
+            }
+        }
+        found
+    }
+
+    /// Поиск умных устройств по текстовому запросу (см. модуль [`crate::query`])
+    ///
+    /// ## Пример
+    /// ```ignore
+    /// let heaters = my_house.find_devices_str("kind=hvac AND power>1000")?;
+    /// ```
+    pub fn find_devices_str(
+        &self,
+        query: &str,
+    ) -> Result<Vec<(ContainerName, ContainerName)>, QueryError> {
+        Ok(self.find_devices(&Query::parse(query)?))
+    }
+
+    /// Получение идентификатора умного устройства по названиям комнаты и устройства
+    fn get_device_id(&self, room_name: &str, device_name: &str) -> Result<DeviceId, ErrorReason> {
+        self.rooms
+            .get(room_name)
+            .and_then(|room| room.get_device(device_name))
+            .map(|device| device.get_metadata().get_id().to_string())
+            .ok_or(ErrorReason::ItemDoesntExist)
+    }
+
+    /// Отбор умных устройств дома по идентификатору
+    fn select_devices<F>(&self, predicate: F) -> Vec<(ContainerName, ContainerName)>
+    where
+        F: Fn(&str) -> bool,
+    {
+        self.rooms
+            .iter()
+            .flat_map(|(room_name, room)| {
+                room.get_device_list()
+                    .into_iter()
+                    .filter(|device_name| {
+                        room.get_device(device_name)
+                            .is_some_and(|device| predicate(device.get_metadata().get_id()))
+                    })
+                    .map(move |device_name| (room_name.clone(), device_name))
+            })
+            .collect()
+    }
+
+    /// Установка инициатора последующих операций (для журнала аудита)
+    pub fn set_actor(&mut self, actor: &str) {
+        self.actor = actor.to_string();
+    }
+
+    /// Получение инициатора выполняемых операций
+    pub fn get_actor(&self) -> &str {
+        &self.actor
+    }
+
+    /// Разбор дома на составные части без публикации событий и записи в журнал аудита
+    ///
+    /// Этажи, зоны, группы и метки отбрасываются
+    pub(crate) fn into_parts(self) -> HouseParts {
+        HouseParts {
+            name: self.name,
+            rooms: self.rooms,
+            room_limit: self.room_limit,
+            events: self.events,
+            reported_malfunctions: self.reported_malfunctions,
+            audit: self.audit,
+            actor: self.actor,
+        }
+    }
+
+    /// Получение журнала аудита операций над домом
+    pub fn get_audit_log(&self) -> &AuditLog {
+        &self.audit
+    }
+
+    /// Изменение максимального количества записей журнала аудита
+    pub fn set_audit_capacity(&mut self, capacity: usize) {
+        self.audit.set_capacity(capacity);
+    }
+
+    /// Установка приёмника `writer` (например, файла), в который журнал аудита
+    /// дописывает каждую новую запись в формате JSON Lines
+    pub fn set_audit_sink<W: Write + Send + 'static>(&mut self, writer: W) {
+        self.audit.set_sink(writer);
+    }
+
+    /// Запись результата операции в журнал аудита
+    fn audit(
+        &mut self,
+        room_name: &str,
+        device_name: Option<&str>,
+        operation: AuditOperation,
+        result: Result<ContainerIOHistory, ErrorReason>,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        self.audit
+            .record(&self.actor, room_name, device_name, operation, &result);
+        result
+    }
+
+    /// Поиск умных устройств заданного типа во всех комнатах дома
+    ///
+    /// Возвращается список пар (название комнаты, название устройства)
+    pub fn find_devices_by_kind(&self, kind: DeviceKind) -> Vec<(ContainerName, ContainerName)> {
+        self.rooms
+            .iter()
+            .flat_map(|(room_name, room)| {
+                room.find_devices_by_kind(kind)
+                    .into_iter()
+                    .map(move |device_name| (room_name.clone(), device_name))
+            })
+            .collect()
+    }
+
+    /// Поиск умного устройства по идентификатору во всех комнатах дома
+    ///
+    /// Возвращается название комнаты и ссылка на устройство; из нескольких устройств
+    /// с одинаковым идентификатором по умолчанию возвращается первое найденное
+    pub fn find_device_by_id(&self, id: &str) -> Option<(ContainerName, &dyn SmartDevice)> {
+        self.rooms.iter().find_map(|(room_name, room)| {
+            room.get_device_by_id(id)
+                .map(|device| (room_name.clone(), device))
+        })
+    }
+
+    /// Проверка, конфликтует ли идентификатор нового устройства с уже известными дому
+    ///
+    /// Явно заданный идентификатор адресует метки и группы, поэтому должен быть уникален
+    /// в доме; устройство с явным идентификатором всегда единственное с таким
+    /// идентификатором, поэтому достаточно проверить первое найденное
+    fn has_conflicting_id(&self, metadata: &DeviceMetadata) -> bool {
+        self.find_device_by_id(metadata.get_id())
+            .is_some_and(|(_, known)| known.get_metadata().conflicts_with(metadata))
+    }
+
+    /// Получение суммарной мощности, потребляемой умными устройствами в доме
+    pub fn get_power_consumption(&self) -> Watts {
+        self.rooms
+            .values()
+            .map(|room| room.get_power_consumption())
+            .sum()
+    }
+
+    /// Передача текущей температуры в каждой комнате умным устройствам этой комнаты
+    pub fn update_climate(&mut self) {
+        for room in self.rooms.values_mut() {
+            room.update_climate();
+        }
+    }
+
+    /// Установка правил хранения истории показаний для всех комнат дома
+    ///
+    /// Правила применяются и к комнатам, добавленным в дом позже
+    pub fn set_history_policy(&mut self, policy: RetentionPolicy) {
+        self.history_policy = Some(policy);
+        for room in self.rooms.values_mut() {
+            room.set_history_policy(policy);
+        }
+    }
+
+    /// Получение правил хранения истории показаний, установленных для дома
+    pub fn get_history_policy(&self) -> Option<RetentionPolicy> {
+        self.history_policy
+    }
+
+    /// Запись текущих показаний всех умных устройств дома в историю
+    ///
+    /// Показания публикуются на шине событий; о впервые обнаруженных
+    /// неисправностях устройств также публикуется событие
+    pub fn sample_readings(&mut self) {
+        let timestamp = SystemTime::now();
+
+        for (room_name, room) in self.rooms.iter_mut() {
+            room.sample_readings_at(timestamp);
+
+            for device_name in room.get_device_list() {
+                let Some(device) = room.get_device(&device_name) else {
+                    continue;
+                };
+
+                for (capability, value) in device.get_readings() {
+                    let mut event = HouseEvent::device(
+                        room_name,
+                        &device_name,
+                        EventPayload::Reading { capability, value },
+                    );
+                    event.timestamp = timestamp;
+                    self.events.publish(event);
+                }
+
+                let key = (room_name.clone(), device_name.clone());
+                match device.get_device_status() {
+                    SmartDeviceStatus::Malfunction(code) => {
+                        if self.reported_malfunctions.insert(key) {
+                            self.events.publish(HouseEvent::device(
+                                room_name,
+                                &device_name,
+                                EventPayload::Malfunction(code),
+                            ));
+                        }
+                    }
+                    SmartDeviceStatus::PowerState(_) => {
+                        self.reported_malfunctions.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Получение показаний вида `capability` всех устройств дома в интервале `[from, to]`
+    ///
+    /// Показания сгруппированы по комнатам и устройствам
+    pub fn query_history(
+        &self,
+        capability: Capability,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> HashMap<ContainerName, HashMap<ContainerName, Vec<Sample>>> {
+        self.rooms
+            .iter()
+            .map(|(room_name, room)| (room_name.clone(), room.query_history(capability, from, to)))
+            .filter(|(_, devices)| !devices.is_empty())
+            .collect()
+    }
+
+    /// Получение показаний вида `capability` всех устройств дома в интервале `[from, to]`,
+    /// агрегированных с разрешением `resolution`
+    pub fn query_history_aggregated(
+        &self,
+        capability: Capability,
+        from: SystemTime,
+        to: SystemTime,
+        resolution: Resolution,
+    ) -> HashMap<ContainerName, HashMap<ContainerName, Vec<AggregateSample>>> {
+        self.rooms
+            .iter()
+            .map(|(room_name, room)| {
+                (
+                    room_name.clone(),
+                    room.query_history_aggregated(capability, from, to, resolution),
+                )
+            })
+            .filter(|(_, devices)| !devices.is_empty())
+            .collect()
+    }
+
+    /// Запись текущих показаний и изменившихся статусов всех умных устройств дома в хранилище `store`
+    ///
+    /// Возвращает количество добавленных записей
+    pub fn write_telemetry(&self, store: &mut TelemetryStore) -> io::Result<usize> {
+        let timestamp = SystemTime::now();
+        let mut written = 0;
+
+        for (room_name, room) in self.rooms.iter() {
+            for device_name in room.get_device_list() {
+                let Some(device) = room.get_device(&device_name) else {
+                    continue;
+                };
+
+                if store.append_status(
+                    timestamp,
+                    room_name,
+                    &device_name,
+                    device.get_device_status(),
+                )? {
+                    written += 1;
+                }
+
+                for (capability, value) in device.get_readings() {
+                    store.append(&TelemetryRecord {
+                        timestamp,
+                        room: room_name.clone(),
+                        device: device_name.clone(),
+                        event: TelemetryEvent::Reading { capability, value },
+                    })?;
+                    written += 1;
+                }
+            }
+        }
+        Ok(written)
+    }
+
+    /// Создание отчёта для в соответствии с типом поставщика данных
+    ///
+    /// Температура в отчёте приводится в градусах Цельсия
+    pub fn create_report(&self) -> String {
+        self.create_report_in(TemperatureScale::Celsius)
+    }
+
+    /// Создание отчёта с температурами в шкале `scale`
+    pub fn create_report_in(&self, scale: TemperatureScale) -> String {
+        let mut report: Vec<String> = Vec::new();
+
+        report.push(format!("Smart house instance: {}.\n", self.name));
+
+        if self.rooms.is_empty() {
+            report.push("There are no registered rooms and devices.\n".to_string());
+            return report.join("\n");
+        }
+
+        for (room_name, room) in self.rooms.iter() {
+            report.push(format!("Room: {}\n", room_name));
+
+            if room.get_device_list().is_empty() {
+                report.push("There are no devices in this room".to_string());
+            } else {
+                for device_name in room.get_device_list() {
+                    // Получение текстового отчёта о состоянии умного устройства
+                    match room.get_device(&device_name) {
+                        Some(device) => {
+                            report.push(format!(
+                                "Device: {} [{}]: ",
+                                device_name,
+                                device.get_metadata()
+                            ));
+                            report.push(device.get_text_report_in(scale));
+                        }
+                        None => {
+                            report.push(format!("Device: {}: ", device_name));
+                            report.push("Connection was refused!".to_string());
+                        }
+                    }
+                }
+                report.push(format!(
+                    "Total power consumption: {}\n",
+                    room.get_power_consumption()
+                ));
+                report.push("\n".to_string());
+            }
+        }
+
+        for floor in self.floors.values() {
+            let summary = self.summarize_rooms(&floor.get_room_list());
+            report.push(format!(
+                "Floor: {} ({})\n",
+                floor.name,
+                summary.display_in(scale)
+            ));
+        }
+        for zone in self.zones.values() {
+            let summary = self.summarize_rooms(&zone.get_room_list());
+            report.push(format!(
+                "Zone: {} ({})\n",
+                zone.name,
+                summary.display_in(scale)
+            ));
+        }
+        for group_name in self.get_group_list() {
+            if let Some(summary) = self.summarize_group(&group_name) {
+                report.push(format!(
+                    "Group: {} ({})\n",
+                    group_name,
+                    summary.display_in(scale)
+                ));
+            }
+        }
+        report.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::socket::SmartSocket;
+    use crate::units::Watts;
+
+    #[test]
+    fn failed_move_and_rename_leave_house_untouched() {
+        let mut house = House::new("House", 2);
+        assert!(house.add_room(Room::new("Kitchen", 2)).is_ok());
+        assert!(house.add_room(Room::new("Hall", 1)).is_ok());
+
+        let mut socket = SmartSocket::new("Socket");
+        socket.set_power_consumption(Watts(10.0));
+        assert!(house.add_device("Kitchen", Box::new(socket)).is_ok());
+        assert!(house
+            .set_power_state("Kitchen", "Socket", SmartDevicePowerState::Enabled)
+            .is_ok());
+        assert!(house
+            .add_device("Kitchen", Box::new(SmartSocket::new("Kettle")))
+            .is_ok());
+        assert!(house
+            .add_device("Hall", Box::new(SmartSocket::new("Lamp")))
+            .is_ok());
+
+        assert_eq!(
+            house.move_device("Kitchen", "Hall", "Socket"),
+            Err(ErrorReason::ItemLimitExceeded)
+        );
+        assert_eq!(
+            house.rename_device("Kitchen", "Socket", "Kettle"),
+            Err(ErrorReason::ItemAlreadyPresented)
+        );
+        assert_eq!(
+            house.rename_room("Kitchen", "Hall"),
+            Err(ErrorReason::ItemAlreadyPresented)
+        );
+        assert_eq!(
+            house.get_room("Kitchen").unwrap().get_device_list().len(),
+            2
+        );
+
+        assert!(house.remove_device("Hall", "Lamp").is_ok());
+        assert!(house.move_device("Kitchen", "Hall", "Socket").is_ok());
+        assert!(house.rename_room("Hall", "Lobby").is_ok());
+        assert!(house.rename_device("Lobby", "Socket", "Heater").is_ok());
+
+        let lobby = house.get_room("Lobby").unwrap();
+        let heater = lobby.get_device("Heater").unwrap();
+        assert_eq!(heater.get_name(), "Heater");
+        assert_eq!(heater.get_power_consumption(), Watts(10.0));
+        assert!(house.get_room("Hall").is_none());
+    }
+
+    #[test]
+    fn rooms_and_devices_are_addressed_by_path() {
+        let mut house = House::new("house", 3);
+        assert!(house.add_floor("floor1", 1).is_ok());
+        assert!(house
+            .add_room_to_floor("floor1", Room::new("kitchen", 2))
+            .is_ok());
+        assert_eq!(
+            house.add_room_to_floor("floor1", Room::new("bedroom", 1)),
+            Err(ErrorReason::ItemLimitExceeded)
+        );
+        assert!(house.get_room("bedroom").is_none());
+        assert!(house.add_room(Room::new("garage", 1)).is_ok());
+
+        let mut socket = SmartSocket::new("socket2");
+        socket.set_power_consumption(Watts(100.0));
+        assert!(house.add_device("kitchen", Box::new(socket)).is_ok());
+        assert!(house
+            .set_power_state("kitchen", "socket2", SmartDevicePowerState::Enabled)
+            .is_ok());
+        assert!(house
+            .add_device("garage", Box::new(SmartSocket::new("charger")))
+            .is_ok());
+
+        assert_eq!(
+            house.get_device_path("kitchen", "socket2").as_deref(),
+            Some("house/floor1/kitchen/socket2")
+        );
+        assert!(house
+            .get_device_by_path("house/floor1/kitchen/socket2")
+            .is_some());
+        assert!(house.get_device_by_path("house/garage/charger").is_some());
+        assert!(house
+            .get_device_by_path("house/floor1/garage/charger")
+            .is_none());
+        assert!(house.get_room_by_path("house/floor1/kitchen").is_some());
+
+        let floor = house.summarize("house/floor1").unwrap();
+        assert_eq!((floor.rooms, floor.devices), (1, 1));
+        assert_eq!(floor.power_consumption, Watts(100.0));
+        assert_eq!(house.summarize("house").unwrap().devices, 2);
+
+        assert!(house.add_zone("Heating zone A").is_ok());
+        assert!(house.add_room_to_zone("Heating zone A", "garage").is_ok());
+        assert!(house.rename_room("garage", "workshop").is_ok());
+        assert_eq!(
+            house.get_zone("Heating zone A").unwrap().get_room_list(),
+            vec!["workshop".to_string()]
+        );
+        assert_eq!(house.summarize_zone("Heating zone A").unwrap().devices, 1);
+    }
+
+    #[test]
+    fn group_operations_return_per_device_results() {
+        use crate::hvac::SmartHvac;
+        use crate::lock::SmartLock;
+
+        let mut house = House::new("house", 2);
+        assert!(house.add_room(Room::new("kitchen", 2)).is_ok());
+        assert!(house.add_room(Room::new("yard", 2)).is_ok());
+        assert!(house
+            .add_device("kitchen", Box::new(SmartHvac::new("heater")))
+            .is_ok());
+        assert!(house
+            .add_device("yard", Box::new(SmartSocket::new("socket")))
+            .is_ok());
+        assert!(house
+            .add_device("yard", Box::new(SmartLock::new("gate")))
+            .is_ok());
+
+        assert!(house.add_tag("yard", "socket", "outdoor").is_ok());
+        assert!(house.add_tag("yard", "gate", "outdoor").is_ok());
+        assert_eq!(
+            house.add_tag("yard", "gate", "outdoor"),
+            Err(ErrorReason::ItemAlreadyPresented)
+        );
+
+        let gate_id = house.get_room("yard").unwrap().get_device("gate").unwrap();
+        let gate_id = gate_id.get_metadata().get_id().to_string();
+        assert!(house.remove_tag("yard", "gate", "outdoor").is_ok());
+        assert!(house
+            .add_group(
+                DeviceGroup::new("perimeter")
+                    .with_tag("outdoor")
+                    .with_device(&gate_id)
+            )
+            .is_ok());
+
+        assert!(house.move_device("yard", "kitchen", "socket").is_ok());
+        let mut members = house.get_group_members("perimeter").unwrap();
+        members.sort();
+        assert_eq!(
+            members,
+            vec![
+                ("kitchen".to_string(), "socket".to_string()),
+                ("yard".to_string(), "gate".to_string())
+            ]
+        );
+
+        let results = house
+            .set_group_power_state("perimeter", SmartDevicePowerState::Enabled)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|(_, _, result)| result.is_ok()));
+
+        let summary = house.summarize_group("perimeter").unwrap();
+        assert_eq!((summary.rooms, summary.devices), (2, 2));
+        assert!(house
+            .set_group_power_state("heaters", SmartDevicePowerState::Enabled)
+            .is_err());
+    }
+
+    #[test]
+    fn history_policy_applies_to_rooms_added_later() {
+        let policy = RetentionPolicy {
+            capacity: 10,
+            max_age: std::time::Duration::from_secs(60),
+        };
+        let mut house = House::new("House", 2);
+        assert!(house.add_room(Room::new("Kitchen", 1)).is_ok());
+        house.set_history_policy(policy);
+        assert!(house.add_room(Room::new("Hall", 1)).is_ok());
+
+        assert_eq!(house.get_history_policy(), Some(policy));
+        for room_name in ["Kitchen", "Hall"] {
+            assert_eq!(
+                house
+                    .get_room(room_name)
+                    .map(|room| room.get_history_policy()),
+                Some(policy)
+            );
+        }
+    }
+
+    #[test]
+    fn moved_device_keeps_reading_history() {
+        use crate::thermometer::SmartThermometer;
+
+        let mut house = House::new("House", 2);
+        assert!(house.add_room(Room::new("Kitchen", 1)).is_ok());
+        assert!(house.add_room(Room::new("Hall", 1)).is_ok());
+        assert!(house
+            .add_device("Kitchen", Box::new(SmartThermometer::new("Sensor")))
+            .is_ok());
+        assert!(house
+            .set_power_state("Kitchen", "Sensor", SmartDevicePowerState::Enabled)
+            .is_ok());
+        house.sample_readings();
+
+        assert!(house.move_device("Kitchen", "Hall", "Sensor").is_ok());
+        let history = house.get_room("Hall").unwrap().get_device_history("Sensor");
+        assert_eq!(
+            history.map(|history| history.get_capabilities()),
+            Some(vec![Capability::Temperature])
+        );
+        assert!(house
+            .get_room("Kitchen")
+            .unwrap()
+            .get_device_history("Sensor")
+            .is_none());
+    }
+
+    #[test]
+    fn report_displays_temperature_in_selected_scale() {
+        use crate::thermometer::SmartThermometer;
+
+        let mut thermometer = SmartThermometer::new("Thermometer");
+        assert!(thermometer
+            .set_power_state(SmartDevicePowerState::Enabled)
+            .is_ok());
+        thermometer.set_temperature(Celsius(20.0));
+
+        let mut house = House::new("house", 1);
+        assert!(house.add_floor("ground", 1).is_ok());
+        assert!(house
+            .add_room_to_floor("ground", Room::new("kitchen", 1))
+            .is_ok());
+        assert!(house.add_device("kitchen", Box::new(thermometer)).is_ok());
+
+        let report = house.create_report_in(TemperatureScale::Fahrenheit);
+        assert!(report.contains("Current temperature is 68 °F"));
+        assert!(report.contains("temperature: 68 °F"));
+        assert!(!report.contains(Celsius::SYMBOL));
+        assert!(house
+            .create_report()
+            .contains("Current temperature is 20 °C"));
+    }
+
+    #[test]
+    fn tag_and_group_changes_are_audited_and_published() {
+        use crate::events::EventFilter;
+
+        let mut house = House::new("house", 1);
+        assert!(house.add_room(Room::new("yard", 1)).is_ok());
+        assert!(house
+            .add_device("yard", Box::new(SmartSocket::new("socket")))
+            .is_ok());
+        let (_, receiver) = house.get_event_bus().subscribe_channel(EventFilter::all());
+
+        assert!(house.add_tag("yard", "socket", "outdoor").is_ok());
+        assert!(house.add_tag("yard", "socket", "outdoor").is_err());
+        assert!(house.remove_tag("yard", "socket", "outdoor").is_ok());
+        for name in ["", " ", "outdoor/lights"] {
+            assert_eq!(
+                house.add_group(DeviceGroup::new(name).with_tag("outdoor")),
+                Err(ErrorReason::InvalidName)
+            );
+        }
+        assert!(house
+            .add_group(DeviceGroup::new("lights").with_tag("outdoor"))
+            .is_ok());
+        assert!(house.remove_group("lights").is_ok());
+
+        let operations: Vec<(&str, bool)> = house
+            .get_audit_log()
+            .get_entries()
+            .iter()
+            .skip(2)
+            .map(|entry| (entry.operation.get_name(), entry.is_success()))
+            .collect();
+        assert_eq!(
+            operations,
+            vec![
+                ("add_tag", true),
+                ("add_tag", false),
+                ("remove_tag", true),
+                ("add_group", false),
+                ("add_group", false),
+                ("add_group", false),
+                ("add_group", true),
+                ("remove_group", true),
+            ]
+        );
+
+        let events: Vec<_> = receiver
+            .try_iter()
+            .map(|event| (event.device, event.payload))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (
+                    Some("socket".to_string()),
+                    EventPayload::TagAdded {
+                        tag: "outdoor".to_string()
+                    }
+                ),
+                (
+                    Some("socket".to_string()),
+                    EventPayload::TagRemoved {
+                        tag: "outdoor".to_string()
+                    }
+                ),
+                (
+                    None,
+                    EventPayload::GroupAdded {
+                        group: "lights".to_string()
+                    }
+                ),
+                (
+                    None,
+                    EventPayload::GroupRemoved {
+                        group: "lights".to_string()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn same_named_devices_are_accepted_in_different_rooms() {
+        let mut house = House::new("house", 3);
+        assert!(house.add_room(Room::new("kitchen", 2)).is_ok());
+        assert!(house.add_room(Room::new("hall", 2)).is_ok());
+        assert!(house
+            .add_device("kitchen", Box::new(SmartSocket::new("Socket")))
+            .is_ok());
+        assert!(house
+            .add_device("hall", Box::new(SmartSocket::new("Socket")))
+            .is_ok());
+
+        // Явно заданный идентификатор по-прежнему должен быть уникален в доме
+        assert!(house
+            .add_device(
+                "hall",
+                Box::new(SmartSocket::new("Lamp").with_id("socket-Socket"))
+            )
+            .is_err());
+        assert!(house
+            .add_device(
+                "kitchen",
+                Box::new(SmartSocket::new("Lamp").with_id("lamp"))
+            )
+            .is_ok());
+        assert!(house
+            .add_device("hall", Box::new(SmartSocket::new("Lamp").with_id("lamp")))
+            .is_err());
+
+        let mut room = Room::new("yard", 1);
+        assert!(room
+            .add_device(Box::new(SmartSocket::new("Socket")))
+            .is_ok());
+        assert!(house.add_room(room).is_ok());
+    }
+
+    #[test]
+    fn names_are_validated_and_floor_changes_are_audited() {
+        use crate::events::EventFilter;
+
+        let mut house = House::new("house", 2);
+        let (_, receiver) = house.get_event_bus().subscribe_channel(EventFilter::all());
+        assert!(house.add_floor("floor1", 1).is_ok());
+        assert_eq!(
+            house.add_room(Room::new("kitchen/pantry", 1)),
+            Err(ErrorReason::InvalidName)
+        );
+        assert_eq!(
+            house.add_room(Room::new("floor1", 1)),
+            Err(ErrorReason::ItemAlreadyPresented)
+        );
+        assert!(house.add_room(Room::new("kitchen", 1)).is_ok());
+        assert_eq!(
+            house.add_floor("kitchen", 1),
+            Err(ErrorReason::ItemAlreadyPresented)
+        );
+        assert_eq!(
+            house.add_device("kitchen", Box::new(SmartSocket::new("a/b"))),
+            Err(ErrorReason::InvalidName)
+        );
+        assert_eq!(
+            house.rename_room("kitchen", "floor1"),
+            Err(ErrorReason::ItemAlreadyPresented)
+        );
+
+        for name in ["", "   ", "heating/a"] {
+            assert_eq!(house.add_zone(name), Err(ErrorReason::InvalidName));
+            assert_eq!(house.add_floor(name, 1), Err(ErrorReason::InvalidName));
+            assert_eq!(
+                house.add_room(Room::new(name, 1)),
+                Err(ErrorReason::InvalidName)
+            );
+        }
+        let mut zone = Zone::new("lighting");
+        assert_eq!(zone.add_room(" "), Err(ErrorReason::InvalidName));
+        assert!(zone.add_room("kitchen").is_ok());
+
+        assert!(house.place_room("kitchen", "floor1").is_ok());
+        assert!(house.add_zone("heating").is_ok());
+        assert!(house.add_room_to_zone("heating", "kitchen").is_ok());
+        assert!(house.remove_floor("floor1").is_ok());
+
+        let operations: Vec<&str> = house
+            .get_audit_log()
+            .get_entries()
+            .iter()
+            .filter(|entry| entry.is_success())
+            .map(|entry| entry.operation.get_name())
+            .collect();
+        assert_eq!(
+            operations,
+            vec![
+                "add_floor",
+                "add_room",
+                "place_room",
+                "add_zone",
+                "add_room_to_zone",
+                "remove_floor"
+            ]
+        );
+        let events: Vec<&str> = receiver
+            .try_iter()
+            .map(|event| event.payload.get_name())
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                "floor_added",
+                "room_added",
+                "room_placed",
+                "zone_added",
+                "room_added_to_zone",
+                "floor_removed"
+            ]
+        );
     }
 }
@@ -1,7 +1,15 @@
+pub mod editor;
+pub mod floor;
+pub mod group;
 pub mod house;
 pub mod room;
+pub mod shared;
+
+use crate::command::SmartDeviceCommandError;
+use crate::smart_device::SmartDeviceErrorCode;
 
 /// Перечисление возможных ошибок set/get операций с содержимым умного дома/комнаты
+#[derive(Clone, Debug, PartialEq)]
 pub enum ErrorReason {
     /// Превышено максимальное количество элементов в контейнере
     ItemLimitExceeded,
@@ -11,8 +19,29 @@ pub enum ErrorReason {
 
     /// В контейнере отсутствует элемент с таким именем
     ItemDoesntExist,
+
+    /// Недопустимое название элемента (например, содержащее разделитель пути)
+    InvalidName,
+
+    /// Устройство не смогло выполнить операцию
+    DeviceFailure(SmartDeviceErrorCode),
+
+    /// Команда не поддерживается устройством
+    UnsupportedCommand,
+
+    /// Устройство занято операцией в другом потоке
+    DeviceBusy,
 }
 
 /// Alias для названия контейнера (умного дома, комнаты)
 pub type ContainerName = String;
 pub type ContainerIOHistory = String;
+
+impl From<SmartDeviceCommandError> for ErrorReason {
+    fn from(error: SmartDeviceCommandError) -> Self {
+        match error {
+            SmartDeviceCommandError::Unsupported => Self::UnsupportedCommand,
+            SmartDeviceCommandError::Device(code) => Self::DeviceFailure(code),
+        }
+    }
+}
@@ -1,6 +1,13 @@
 use super::{ContainerIOHistory, ContainerName, ErrorReason};
+use crate::command::Capability;
+use crate::history::{
+    aggregate, AggregateSample, ReadingHistory, Resolution, RetentionPolicy, Sample,
+};
+use crate::metadata::DeviceKind;
 use crate::smart_device::SmartDevice;
+use crate::units::{Celsius, Watts};
 use std::collections::HashMap;
+use std::time::SystemTime;
 
 //TODO: SmartContainerManagementStatus -> Result<>
 
@@ -16,6 +23,12 @@ pub struct Room {
 
     /// Максимальное количество умных устройств в комнате
     device_limit: usize,
+
+    /// История показаний умных устройств комнаты
+    history: HashMap<ContainerName, ReadingHistory>,
+
+    /// Правила хранения истории показаний
+    history_policy: RetentionPolicy,
 }
 
 impl Room {
@@ -25,6 +38,8 @@ impl Room {
             name: name.to_string(),
             devices: HashMap::with_capacity(limit),
             device_limit: limit,
+            history: HashMap::new(),
+            history_policy: RetentionPolicy::default(),
         }
     }
 
@@ -33,14 +48,22 @@ impl Room {
         &mut self,
         device: Box<dyn SmartDevice>,
     ) -> Result<ContainerIOHistory, ErrorReason> {
+        self.try_add_device(device).map_err(|(reason, _)| reason)
+    }
+
+    /// Добавление умного устройства в комнату; при ошибке устройство возвращается вызывающему
+    pub(crate) fn try_add_device(
+        &mut self,
+        device: Box<dyn SmartDevice>,
+    ) -> Result<ContainerIOHistory, (ErrorReason, Box<dyn SmartDevice>)> {
         if self.devices.len() >= self.device_limit {
-            return Err(ErrorReason::ItemLimitExceeded);
+            return Err((ErrorReason::ItemLimitExceeded, device));
         }
 
         let device_name = device.get_name();
 
         if self.devices.contains_key(device_name) {
-            return Err(ErrorReason::ItemAlreadyPresented);
+            return Err((ErrorReason::ItemAlreadyPresented, device));
         }
 
         let status = format!(
@@ -53,26 +76,226 @@ impl Room {
 
     /// Удаление умного устройства из комнаты
     pub fn remove_device(&mut self, device_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
-        if !self.devices.contains_key(device_name) {
-            return Err(ErrorReason::ItemDoesntExist);
-        }
+        self.take_device(device_name)?;
 
         let status = format!(
             "Device {} has been removed from room {}",
             device_name, self.name
         );
+        Ok(status)
+    }
+
+    /// Извлечение умного устройства из комнаты
+    ///
+    /// В отличие от [`Room::remove_device`] устройство не уничтожается, а возвращается
+    /// вызывающему вместе со своим состоянием; история показаний устройства удаляется
+    pub fn take_device(&mut self, device_name: &str) -> Result<Box<dyn SmartDevice>, ErrorReason> {
+        let device = self
+            .devices
+            .remove(device_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        self.history.remove(device_name);
+        Ok(device)
+    }
+
+    /// Переименование умного устройства в комнате
+    ///
+    /// История показаний устройства сохраняется под новым именем
+    pub fn rename_device(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        if self.devices.contains_key(new_name) {
+            return Err(ErrorReason::ItemAlreadyPresented);
+        }
 
-        self.devices.remove(device_name);
+        let mut device = self
+            .devices
+            .remove(old_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        device.set_name(new_name);
+        self.devices.insert(new_name.to_string(), device);
+
+        if let Some(history) = self.history.remove(old_name) {
+            self.history.insert(new_name.to_string(), history);
+        }
+
+        let status = format!(
+            "Device {} in room {} has been renamed to {}",
+            old_name, self.name, new_name
+        );
         Ok(status)
     }
 
+    /// Извлечение истории показаний умного устройства (например, для переноса вместе с ним)
+    pub(crate) fn take_device_history(&mut self, device_name: &str) -> Option<ReadingHistory> {
+        self.history.remove(device_name)
+    }
+
+    /// Установка истории показаний умного устройства
+    ///
+    /// К истории применяются правила хранения комнаты
+    pub(crate) fn set_device_history(&mut self, device_name: &str, mut history: ReadingHistory) {
+        history.set_policy(self.history_policy);
+        self.history.insert(device_name.to_string(), history);
+    }
+
+    /// Разбор комнаты на умные устройства (история показаний не сохраняется)
+    pub(crate) fn into_devices(self) -> HashMap<ContainerName, Box<dyn SmartDevice>> {
+        self.devices
+    }
+
     /// Получение умного устройства по имени
-    pub fn get_device(&self, device_name: &str) -> Option<&Box<dyn SmartDevice>> {
-        self.devices.get(device_name)
+    pub fn get_device(&self, device_name: &str) -> Option<&dyn SmartDevice> {
+        self.devices.get(device_name).map(|device| device.as_ref())
+    }
+
+    /// Получение изменяемой ссылки на умное устройство по имени
+    pub fn get_device_mut(&mut self, device_name: &str) -> Option<&mut dyn SmartDevice> {
+        match self.devices.get_mut(device_name) {
+            Some(device) => Some(device.as_mut()),
+            None => None,
+        }
+    }
+
+    /// Получение максимального количества умных устройств в комнате
+    pub fn get_device_limit(&self) -> usize {
+        self.device_limit
     }
 
     /// Получение списка умных устройств в комнате
     pub fn get_device_list(&self) -> Vec<ContainerName> {
         self.devices.keys().cloned().collect()
     }
+
+    /// Получение умного устройства по уникальному идентификатору
+    pub fn get_device_by_id(&self, id: &str) -> Option<&dyn SmartDevice> {
+        self.devices
+            .values()
+            .find(|device| device.get_metadata().get_id() == id)
+            .map(|device| device.as_ref())
+    }
+
+    /// Получение списка умных устройств заданного типа в комнате
+    pub fn find_devices_by_kind(&self, kind: DeviceKind) -> Vec<ContainerName> {
+        self.devices
+            .iter()
+            .filter(|(_, device)| device.get_metadata().kind == kind)
+            .map(|(device_name, _)| device_name.clone())
+            .collect()
+    }
+
+    /// Получение списка умных устройств в комнате, поддерживающих возможность `capability`
+    pub fn find_devices_by_capability(&self, capability: Capability) -> Vec<ContainerName> {
+        self.devices
+            .iter()
+            .filter(|(_, device)| device.get_capabilities().contains(&capability))
+            .map(|(device_name, _)| device_name.clone())
+            .collect()
+    }
+
+    /// Получение средней температуры по показаниям включённых термометров в комнате
+    pub fn get_temperature(&self) -> Option<Celsius> {
+        let readings: Vec<f32> = self
+            .devices
+            .values()
+            .filter_map(|device| device.get_temperature())
+            .map(|temperature| temperature.0)
+            .collect();
+
+        if readings.is_empty() {
+            return None;
+        }
+        Some(Celsius(
+            readings.iter().sum::<f32>() / readings.len() as f32,
+        ))
+    }
+
+    /// Получение суммарной мощности, потребляемой умными устройствами в комнате
+    pub fn get_power_consumption(&self) -> Watts {
+        self.devices
+            .values()
+            .map(|device| device.get_power_consumption())
+            .sum()
+    }
+
+    /// Передача текущей температуры в комнате всем умным устройствам комнаты
+    ///
+    /// Если в комнате нет включённых термометров, устройства не обновляются
+    pub fn update_climate(&mut self) {
+        if let Some(temperature) = self.get_temperature() {
+            for device in self.devices.values_mut() {
+                device.update_ambient_temperature(temperature);
+            }
+        }
+    }
+
+    /// Установка правил хранения истории показаний для всех устройств комнаты
+    pub fn set_history_policy(&mut self, policy: RetentionPolicy) {
+        self.history_policy = policy;
+        for history in self.history.values_mut() {
+            history.set_policy(policy);
+        }
+    }
+
+    /// Получение правил хранения истории показаний комнаты
+    pub fn get_history_policy(&self) -> RetentionPolicy {
+        self.history_policy
+    }
+
+    /// Запись текущих показаний всех умных устройств комнаты в историю
+    pub fn sample_readings(&mut self) {
+        self.sample_readings_at(SystemTime::now());
+    }
+
+    /// Запись показаний всех умных устройств комнаты в историю с меткой времени `timestamp`
+    pub fn sample_readings_at(&mut self, timestamp: SystemTime) {
+        for (device_name, device) in self.devices.iter() {
+            let history = self
+                .history
+                .entry(device_name.clone())
+                .or_insert_with(|| ReadingHistory::new(self.history_policy));
+
+            for (capability, value) in device.get_readings() {
+                history.record(capability, value, timestamp);
+            }
+        }
+    }
+
+    /// Получение истории показаний умного устройства по имени
+    pub fn get_device_history(&self, device_name: &str) -> Option<&ReadingHistory> {
+        self.history.get(device_name)
+    }
+
+    /// Получение показаний вида `capability` всех устройств комнаты в интервале `[from, to]`
+    pub fn query_history(
+        &self,
+        capability: Capability,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> HashMap<ContainerName, Vec<Sample>> {
+        self.history
+            .iter()
+            .map(|(device_name, history)| {
+                (device_name.clone(), history.query(capability, from, to))
+            })
+            .filter(|(_, samples)| !samples.is_empty())
+            .collect()
+    }
+
+    /// Получение показаний вида `capability` всех устройств комнаты в интервале `[from, to]`,
+    /// агрегированных с разрешением `resolution`
+    pub fn query_history_aggregated(
+        &self,
+        capability: Capability,
+        from: SystemTime,
+        to: SystemTime,
+        resolution: Resolution,
+    ) -> HashMap<ContainerName, Vec<AggregateSample>> {
+        self.query_history(capability, from, to)
+            .into_iter()
+            .map(|(device_name, samples)| (device_name, aggregate(&samples, resolution)))
+            .collect()
+    }
 }
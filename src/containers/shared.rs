@@ -0,0 +1,1116 @@
+//! Модуль содержит потокобезопасный разделяемый умный дом
+//!
+//! > [`SharedHouse`] - дешёвый в клонировании дескриптор дома, который можно передавать
+//! > в разные потоки (сетевой сервер, планировщик, пользовательский интерфейс).
+//! > Блокировки раздельные: список комнат, список устройств каждой комнаты и каждое
+//! > устройство защищены собственными блокировками, поэтому операции с разными
+//! > устройствами не мешают друг другу. Изменения публикуются на шине событий дома
+//! > и записываются в журнал аудита. Событие об изменении состояния питания публикуется
+//! > под блокировкой устройства, чтобы события об одном устройстве не менялись местами,
+//! > поэтому обработчики событий не должны обращаться к устройствам этого же дома.
+//!
+use super::floor::validate_name;
+use super::house::House;
+use super::{ContainerIOHistory, ContainerName, ErrorReason};
+use crate::audit::{command_outcome, AuditLog, AuditOperation, DEFAULT_ACTOR};
+use crate::command::{SmartDeviceCommand, SmartDeviceCommandError, SmartDeviceResponse};
+use crate::events::{EventBus, EventPayload, HouseEvent};
+use crate::metadata::DeviceMetadata;
+use crate::smart_device::{SmartDevice, SmartDevicePowerState, SmartDeviceStatus};
+use crate::units::{TemperatureScale, Watts};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug, Display};
+use std::sync::{Arc, LockResult, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Максимальное время ожидания окончания операций с извлекаемым устройством
+pub const DEVICE_RELEASE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Период проверки окончания операций с извлекаемым устройством
+const DEVICE_RELEASE_POLL: Duration = Duration::from_millis(1);
+
+/// Alias для разделяемого умного устройства
+type SharedDevice = Arc<Mutex<Box<dyn SmartDevice>>>;
+
+/// Комната разделяемого дома
+struct SharedRoom {
+    /// Умные устройства комнаты
+    devices: RwLock<HashMap<ContainerName, SharedDevice>>,
+
+    /// Максимальное количество умных устройств в комнате
+    device_limit: usize,
+}
+
+/// Содержимое разделяемого дома
+struct SharedHouseInner {
+    /// Название дома
+    name: ContainerName,
+
+    /// Комнаты дома
+    rooms: RwLock<HashMap<ContainerName, Arc<SharedRoom>>>,
+
+    /// Максимальное количество комнат в доме
+    room_limit: usize,
+
+    /// Шина событий дома
+    events: Mutex<EventBus>,
+
+    /// Устройства, о неисправности которых уже опубликовано событие
+    reported_malfunctions: Mutex<HashSet<(ContainerName, ContainerName)>>,
+
+    /// Журнал аудита операций
+    audit: Mutex<AuditLog>,
+
+    /// Инициатор выполняемых операций
+    actor: RwLock<String>,
+
+    /// Блокировка, под которой проверяется уникальность идентификатора и добавляется
+    /// устройство: иначе два потока могли бы одновременно добавить устройства
+    /// с одинаковым явным идентификатором в разные комнаты
+    registration: Mutex<()>,
+}
+
+/// Состояние дома, которое не поддерживается разделяемым домом
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnsharedState {
+    /// Этажи
+    Floors,
+    /// Зоны
+    Zones,
+    /// Группы устройств
+    Groups,
+    /// Метки устройств
+    Tags,
+    /// История показаний устройств
+    History,
+}
+
+/// Ошибка преобразования дома в разделяемый дом
+///
+/// Дом возвращается вызывающему без изменений
+pub struct ConversionError {
+    /// Исходный дом
+    pub house: Box<House>,
+
+    /// Состояние дома, которое было бы потеряно при преобразовании
+    pub unshared: Vec<UnsharedState>,
+}
+
+impl Debug for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ConversionError")
+            .field("house", &self.house.name)
+            .field("unshared", &self.unshared)
+            .finish()
+    }
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "house {} has state unsupported by shared house: {:?}",
+            self.house.name, self.unshared
+        )
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// Тип, описывающий потокобезопасный дескриптор умного дома
+///
+/// Клоны дескриптора ссылаются на один и тот же дом
+#[derive(Clone)]
+pub struct SharedHouse {
+    /// Разделяемое содержимое дома
+    inner: Arc<SharedHouseInner>,
+}
+
+impl SharedHouse {
+    /// Создание нового дома с именем `name`, в котором может быть не более `limit` комнат
+    pub fn new(name: &str, limit: usize) -> Self {
+        Self {
+            inner: Arc::new(SharedHouseInner {
+                name: name.to_string(),
+                rooms: RwLock::new(HashMap::new()),
+                room_limit: limit,
+                events: Mutex::new(EventBus::new()),
+                reported_malfunctions: Mutex::new(HashSet::new()),
+                audit: Mutex::new(AuditLog::new()),
+                actor: RwLock::new(DEFAULT_ACTOR.to_string()),
+                registration: Mutex::new(()),
+            }),
+        }
+    }
+
+    /// Получение названия дома
+    pub fn get_name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// Создание новой пустой комнаты, в которой может быть не более `device_limit` устройств
+    pub fn add_room(
+        &self,
+        room_name: &str,
+        device_limit: usize,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.insert_room(room_name, device_limit);
+        self.audit(room_name, None, AuditOperation::AddRoom, result)
+    }
+
+    /// Создание новой пустой комнаты без записи в журнал аудита
+    fn insert_room(
+        &self,
+        room_name: &str,
+        device_limit: usize,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        validate_name(room_name)?;
+        let mut rooms = recover(self.inner.rooms.write());
+        if rooms.len() >= self.inner.room_limit {
+            return Err(ErrorReason::ItemLimitExceeded);
+        }
+        if rooms.contains_key(room_name) {
+            return Err(ErrorReason::ItemAlreadyPresented);
+        }
+
+        rooms.insert(
+            room_name.to_string(),
+            Arc::new(SharedRoom {
+                devices: RwLock::new(HashMap::new()),
+                device_limit,
+            }),
+        );
+        drop(rooms);
+
+        self.publish(HouseEvent::room(room_name, EventPayload::RoomAdded));
+        Ok(format!(
+            "Room {} has been registered in house {}",
+            room_name, self.inner.name
+        ))
+    }
+
+    /// Удаление комнаты из дома
+    ///
+    /// Операции, уже начатые с устройствами комнаты в других потоках, завершаются штатно
+    pub fn remove_room(&self, room_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.detach_room(room_name);
+        self.audit(room_name, None, AuditOperation::RemoveRoom, result)
+    }
+
+    /// Удаление комнаты из дома без записи в журнал аудита
+    fn detach_room(&self, room_name: &str) -> Result<ContainerIOHistory, ErrorReason> {
+        recover(self.inner.rooms.write())
+            .remove(room_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        recover(self.inner.reported_malfunctions.lock()).retain(|(room, _)| room != room_name);
+
+        self.publish(HouseEvent::room(room_name, EventPayload::RoomRemoved));
+        Ok(format!(
+            "Room {} has been removed from house {}",
+            room_name, self.inner.name
+        ))
+    }
+
+    /// Получение списка комнат в доме
+    pub fn get_room_list(&self) -> Vec<ContainerName> {
+        recover(self.inner.rooms.read()).keys().cloned().collect()
+    }
+
+    /// Получение списка умных устройств в комнате
+    pub fn get_device_list(&self, room_name: &str) -> Option<Vec<ContainerName>> {
+        let room = self.get_room(room_name).ok()?;
+        let devices = recover(room.devices.read());
+        Some(devices.keys().cloned().collect())
+    }
+
+    /// Добавление умного устройства в комнату дома
+    pub fn add_device(
+        &self,
+        room_name: &str,
+        device: Box<dyn SmartDevice>,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let device_name = device.get_name().to_string();
+        let result = self.insert_device(room_name, device);
+        self.audit(
+            room_name,
+            Some(&device_name),
+            AuditOperation::AddDevice,
+            result,
+        )
+    }
+
+    /// Добавление умного устройства в комнату дома без записи в журнал аудита
+    fn insert_device(
+        &self,
+        room_name: &str,
+        device: Box<dyn SmartDevice>,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let device_name = device.get_name().to_string();
+        validate_name(&device_name)?;
+        let room = self.get_room(room_name)?;
+
+        let _registration = recover(self.inner.registration.lock());
+        if self.has_conflicting_id(device.get_metadata()) {
+            return Err(ErrorReason::ItemAlreadyPresented);
+        }
+
+        let mut devices = recover(room.devices.write());
+        if devices.len() >= room.device_limit {
+            return Err(ErrorReason::ItemLimitExceeded);
+        }
+        if devices.contains_key(&device_name) {
+            return Err(ErrorReason::ItemAlreadyPresented);
+        }
+
+        let status = format!(
+            "Device {} has been registered in room {}",
+            device_name, room_name
+        );
+        devices.insert(device_name.clone(), Arc::new(Mutex::new(device)));
+        drop(devices);
+
+        self.publish(HouseEvent::device(
+            room_name,
+            &device_name,
+            EventPayload::DeviceAdded,
+        ));
+        Ok(status)
+    }
+
+    /// Извлечение умного устройства из комнаты дома
+    ///
+    /// Если устройство в этот момент используется другим потоком, извлечение
+    /// дожидается окончания операции, но не дольше [`DEVICE_RELEASE_TIMEOUT`];
+    /// по истечении времени устройство остаётся в комнате и возвращается
+    /// [`ErrorReason::DeviceBusy`]
+    pub fn take_device(
+        &self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<Box<dyn SmartDevice>, ErrorReason> {
+        self.extract_device(room_name, device_name)
+            .map(|(device, _)| device)
+    }
+
+    /// Извлечение умного устройства из комнаты дома с записью в журнал аудита
+    fn extract_device(
+        &self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<(Box<dyn SmartDevice>, ContainerIOHistory), ErrorReason> {
+        let result = self.detach_device(room_name, device_name).map(|device| {
+            let status = format!(
+                "Device {} has been removed from room {}",
+                device_name, room_name
+            );
+            (device, status)
+        });
+        let outcome = result
+            .as_ref()
+            .map(|(_, status)| status.clone())
+            .map_err(Clone::clone);
+        let actor = self.get_actor();
+        recover(self.inner.audit.lock()).record(
+            &actor,
+            room_name,
+            Some(device_name),
+            AuditOperation::RemoveDevice,
+            &outcome,
+        );
+        result
+    }
+
+    /// Извлечение умного устройства из комнаты дома без записи в журнал аудита
+    fn detach_device(
+        &self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<Box<dyn SmartDevice>, ErrorReason> {
+        let room = self.get_room(room_name)?;
+        let mut devices = recover(room.devices.write());
+        let shared = devices
+            .get(device_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+
+        // Пока удерживается блокировка комнаты, новые операции с устройством
+        // не начнутся, а начатые ранее получают время на завершение
+        let deadline = Instant::now() + DEVICE_RELEASE_TIMEOUT;
+        while Arc::strong_count(shared) > 1 {
+            if Instant::now() >= deadline {
+                return Err(ErrorReason::DeviceBusy);
+            }
+            thread::sleep(DEVICE_RELEASE_POLL);
+        }
+        let shared = devices
+            .remove(device_name)
+            .ok_or(ErrorReason::ItemDoesntExist)?;
+        drop(devices);
+        let device = Arc::try_unwrap(shared).map_err(|_| ErrorReason::DeviceBusy)?;
+
+        recover(self.inner.reported_malfunctions.lock())
+            .remove(&(room_name.to_string(), device_name.to_string()));
+        self.publish(HouseEvent::device(
+            room_name,
+            device_name,
+            EventPayload::DeviceRemoved,
+        ));
+        Ok(recover(device.into_inner()))
+    }
+
+    /// Удаление умного устройства из комнаты дома
+    pub fn remove_device(
+        &self,
+        room_name: &str,
+        device_name: &str,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        self.extract_device(room_name, device_name)
+            .map(|(_, status)| status)
+    }
+
+    /// Выполнение действия `action` с умным устройством
+    ///
+    /// На время действия блокируется только это устройство. Устройство доступно только
+    /// для чтения: его состояние изменяется методами `SharedHouse`, которые записывают
+    /// операции в журнал аудита и публикуют события
+    pub fn with_device<R, F>(
+        &self,
+        room_name: &str,
+        device_name: &str,
+        action: F,
+    ) -> Result<R, ErrorReason>
+    where
+        F: FnOnce(&dyn SmartDevice) -> R,
+    {
+        self.with_device_mut(room_name, device_name, |device| action(device))
+    }
+
+    /// Выполнение изменяющего действия `action` с умным устройством без записи в журнал аудита
+    fn with_device_mut<R, F>(
+        &self,
+        room_name: &str,
+        device_name: &str,
+        action: F,
+    ) -> Result<R, ErrorReason>
+    where
+        F: FnOnce(&mut dyn SmartDevice) -> R,
+    {
+        let device = self.get_device(room_name, device_name)?;
+        let mut device = recover(device.lock());
+        Ok(action(device.as_mut()))
+    }
+
+    /// Включение/выключение умного устройства
+    pub fn set_power_state(
+        &self,
+        room_name: &str,
+        device_name: &str,
+        state: SmartDevicePowerState,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let result = self.switch_device(room_name, device_name, state.clone());
+        self.audit(
+            room_name,
+            Some(device_name),
+            AuditOperation::SetPowerState(state),
+            result,
+        )
+    }
+
+    /// Включение/выключение умного устройства без записи в журнал аудита
+    fn switch_device(
+        &self,
+        room_name: &str,
+        device_name: &str,
+        state: SmartDevicePowerState,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        // Событие публикуется под блокировкой устройства: иначе события о переключениях
+        // из разных потоков могли бы прийти в порядке, отличном от порядка переключений
+        self.with_device_mut(room_name, device_name, |device| {
+            let key = (room_name.to_string(), device_name.to_string());
+            match device.set_power_state(state.clone()) {
+                Ok(()) => {
+                    recover(self.inner.reported_malfunctions.lock()).remove(&key);
+                    self.publish(HouseEvent::device(
+                        room_name,
+                        device_name,
+                        EventPayload::PowerStateChanged(state.clone()),
+                    ));
+                    Ok(())
+                }
+                Err(code) => {
+                    // О неисправности сообщается один раз, как и при опросе показаний
+                    let reported = code.is_fault()
+                        && recover(self.inner.reported_malfunctions.lock()).insert(key);
+                    if reported {
+                        self.publish(HouseEvent::device(
+                            room_name,
+                            device_name,
+                            EventPayload::Malfunction(code.clone()),
+                        ));
+                    }
+                    Err(ErrorReason::DeviceFailure(code))
+                }
+            }
+        })??;
+
+        Ok(format!(
+            "Device {} in room {} has been switched to {}",
+            device_name, room_name, state
+        ))
+    }
+
+    /// Выполнение универсальной команды умным устройством
+    ///
+    /// Команда записывается в журнал аудита, а её выполнение сопровождается событием
+    pub fn execute(
+        &self,
+        room_name: &str,
+        device_name: &str,
+        command: SmartDeviceCommand,
+    ) -> Result<Result<SmartDeviceResponse, SmartDeviceCommandError>, ErrorReason> {
+        let command_name = command.get_name();
+        let result = self.run_command(room_name, device_name, command);
+        let outcome = command_outcome(room_name, device_name, command_name, &result);
+        let operation = AuditOperation::Execute {
+            command: command_name.to_string(),
+        };
+        let _ = self.audit(room_name, Some(device_name), operation, outcome);
+        result
+    }
+
+    /// Выполнение универсальной команды без записи в журнал аудита
+    fn run_command(
+        &self,
+        room_name: &str,
+        device_name: &str,
+        command: SmartDeviceCommand,
+    ) -> Result<Result<SmartDeviceResponse, SmartDeviceCommandError>, ErrorReason> {
+        // Событие публикуется под блокировкой устройства, как и при переключении питания
+        self.with_device_mut(room_name, device_name, |device| {
+            let key = (room_name.to_string(), device_name.to_string());
+            let result = device.execute(command.clone());
+            match &result {
+                Ok(_) => {
+                    if matches!(
+                        command,
+                        SmartDeviceCommand::SetPowerState(_) | SmartDeviceCommand::ResetFault
+                    ) {
+                        recover(self.inner.reported_malfunctions.lock()).remove(&key);
+                    }
+                    if let Some(payload) = EventPayload::for_command(&command) {
+                        self.publish(HouseEvent::device(room_name, device_name, payload));
+                    }
+                }
+                Err(SmartDeviceCommandError::Device(code)) => {
+                    let reported = code.is_fault()
+                        && recover(self.inner.reported_malfunctions.lock()).insert(key);
+                    if reported {
+                        self.publish(HouseEvent::device(
+                            room_name,
+                            device_name,
+                            EventPayload::Malfunction(code.clone()),
+                        ));
+                    }
+                }
+                Err(SmartDeviceCommandError::Unsupported) => {}
+            }
+            result
+        })
+    }
+
+    /// Получение суммарной мощности, потребляемой умными устройствами в доме
+    ///
+    /// Устройства опрашиваются по очереди, поэтому результат не является атомарным снимком
+    pub fn get_power_consumption(&self) -> Watts {
+        self.get_all_devices()
+            .iter()
+            .map(|device| recover(device.lock()).get_power_consumption())
+            .sum()
+    }
+
+    /// Установка инициатора последующих операций, записываемого в журнал аудита
+    pub fn set_actor(&self, actor: &str) {
+        *recover(self.inner.actor.write()) = actor.to_string();
+    }
+
+    /// Получение инициатора выполняемых операций
+    pub fn get_actor(&self) -> String {
+        recover(self.inner.actor.read()).clone()
+    }
+
+    /// Выполнение действия `action` с журналом аудита дома (выборка, выгрузка, настройка)
+    pub fn with_audit_log<R, F>(&self, action: F) -> R
+    where
+        F: FnOnce(&mut AuditLog) -> R,
+    {
+        let mut audit = recover(self.inner.audit.lock());
+        action(&mut audit)
+    }
+
+    /// Выполнение действия `action` с шиной событий дома (подписка, отписка, настройка)
+    ///
+    /// Обработчики событий вызываются под блокировкой шины, поэтому они не должны
+    /// обращаться к шине событий этого же дома
+    pub fn with_event_bus<R, F>(&self, action: F) -> R
+    where
+        F: FnOnce(&mut EventBus) -> R,
+    {
+        let mut events = recover(self.inner.events.lock());
+        action(&mut events)
+    }
+
+    /// Публикация текущих показаний всех умных устройств дома на шине событий
+    ///
+    /// О впервые обнаруженных неисправностях устройств также публикуется событие
+    pub fn sample_readings(&self) {
+        let mut room_names = self.get_room_list();
+        room_names.sort();
+
+        for room_name in room_names {
+            let Some(mut device_names) = self.get_device_list(&room_name) else {
+                continue;
+            };
+            device_names.sort();
+
+            for device_name in device_names {
+                let Ok((readings, status)) = self.with_device(&room_name, &device_name, |device| {
+                    (device.get_readings(), device.get_device_status())
+                }) else {
+                    continue;
+                };
+
+                for (capability, value) in readings {
+                    self.publish(HouseEvent::device(
+                        &room_name,
+                        &device_name,
+                        EventPayload::Reading { capability, value },
+                    ));
+                }
+
+                let key = (room_name.clone(), device_name.clone());
+                let mut reported = recover(self.inner.reported_malfunctions.lock());
+                match status {
+                    SmartDeviceStatus::Malfunction(code) => {
+                        if reported.insert(key) {
+                            drop(reported);
+                            self.publish(HouseEvent::device(
+                                &room_name,
+                                &device_name,
+                                EventPayload::Malfunction(code),
+                            ));
+                        }
+                    }
+                    SmartDeviceStatus::PowerState(_) => {
+                        reported.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Создание отчёта о состоянии умных устройств дома (температура в градусах Цельсия)
+    pub fn create_report(&self) -> String {
+        self.create_report_in(TemperatureScale::Celsius)
+    }
+
+    /// Создание отчёта о состоянии умных устройств дома с температурами в шкале `scale`
+    pub fn create_report_in(&self, scale: TemperatureScale) -> String {
+        let mut report = vec![format!("Smart house instance: {}.\n", self.inner.name)];
+
+        let mut room_names = self.get_room_list();
+        room_names.sort();
+        for room_name in room_names {
+            let Some(mut device_names) = self.get_device_list(&room_name) else {
+                continue;
+            };
+            device_names.sort();
+
+            report.push(format!("Room: {}\n", room_name));
+            for device_name in device_names {
+                if let Ok(text) = self.with_device(&room_name, &device_name, |device| {
+                    device.get_text_report_in(scale)
+                }) {
+                    report.push(format!("Device: {}: {}", device_name, text));
+                }
+            }
+        }
+        report.join(" ")
+    }
+
+    /// Запись результата операции в журнал аудита
+    fn audit(
+        &self,
+        room_name: &str,
+        device_name: Option<&str>,
+        operation: AuditOperation,
+        result: Result<ContainerIOHistory, ErrorReason>,
+    ) -> Result<ContainerIOHistory, ErrorReason> {
+        let actor = self.get_actor();
+        recover(self.inner.audit.lock()).record(&actor, room_name, device_name, operation, &result);
+        result
+    }
+
+    /// Публикация события на шине событий дома
+    fn publish(&self, event: HouseEvent) {
+        recover(self.inner.events.lock()).publish(event);
+    }
+
+    /// Получение комнаты по имени
+    fn get_room(&self, room_name: &str) -> Result<Arc<SharedRoom>, ErrorReason> {
+        recover(self.inner.rooms.read())
+            .get(room_name)
+            .cloned()
+            .ok_or(ErrorReason::ItemDoesntExist)
+    }
+
+    /// Получение умного устройства по имени комнаты и устройства
+    fn get_device(&self, room_name: &str, device_name: &str) -> Result<SharedDevice, ErrorReason> {
+        let room = self.get_room(room_name)?;
+        let devices = recover(room.devices.read());
+        devices
+            .get(device_name)
+            .cloned()
+            .ok_or(ErrorReason::ItemDoesntExist)
+    }
+
+    /// Проверка, конфликтует ли идентификатор нового устройства с уже известными дому
+    ///
+    /// Используется то же правило, что и в [`House`]: уникальны только явно заданные идентификаторы
+    fn has_conflicting_id(&self, metadata: &DeviceMetadata) -> bool {
+        self.get_all_devices().iter().any(|device| {
+            recover(device.lock())
+                .get_metadata()
+                .conflicts_with(metadata)
+        })
+    }
+
+    /// Получение всех умных устройств дома
+    fn get_all_devices(&self) -> Vec<SharedDevice> {
+        let rooms: Vec<Arc<SharedRoom>> =
+            recover(self.inner.rooms.read()).values().cloned().collect();
+        rooms
+            .iter()
+            .flat_map(|room| {
+                recover(room.devices.read())
+                    .values()
+                    .cloned()
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<House> for SharedHouse {
+    type Error = ConversionError;
+
+    /// Преобразование дома в разделяемый дом
+    ///
+    /// Переносятся комнаты и устройства с их состоянием, шина событий с подписками
+    /// и журнал аудита. Дом с этажами, зонами, группами, метками или историей показаний
+    /// не преобразуется: разделяемый дом их не поддерживает, и они были бы потеряны
+    fn try_from(house: House) -> Result<Self, Self::Error> {
+        let unshared = unshared_state(&house);
+        if !unshared.is_empty() {
+            return Err(ConversionError {
+                house: Box::new(house),
+                unshared,
+            });
+        }
+
+        let parts = house.into_parts();
+        let rooms = parts
+            .rooms
+            .into_iter()
+            .map(|(room_name, room)| {
+                let device_limit = room.get_device_limit();
+                let devices = room
+                    .into_devices()
+                    .into_iter()
+                    .map(|(device_name, device)| (device_name, Arc::new(Mutex::new(device))))
+                    .collect();
+                let room = SharedRoom {
+                    devices: RwLock::new(devices),
+                    device_limit,
+                };
+                (room_name, Arc::new(room))
+            })
+            .collect();
+
+        // Дом переносится целиком, без публикации событий о добавлении комнат и устройств
+        Ok(Self {
+            inner: Arc::new(SharedHouseInner {
+                name: parts.name,
+                rooms: RwLock::new(rooms),
+                room_limit: parts.room_limit,
+                events: Mutex::new(parts.events),
+                reported_malfunctions: Mutex::new(parts.reported_malfunctions),
+                audit: Mutex::new(parts.audit),
+                actor: RwLock::new(parts.actor),
+                registration: Mutex::new(()),
+            }),
+        })
+    }
+}
+
+/// Определение состояния дома, которое не поддерживается разделяемым домом
+pub(crate) fn unshared_state(house: &House) -> Vec<UnsharedState> {
+    let devices: Vec<(ContainerName, ContainerName)> = house
+        .get_room_list()
+        .into_iter()
+        .flat_map(|room_name| {
+            let device_names = house
+                .get_room(&room_name)
+                .map(|room| room.get_device_list())
+                .unwrap_or_default();
+            device_names
+                .into_iter()
+                .map(move |device_name| (room_name.clone(), device_name))
+        })
+        .collect();
+    let has_history = devices.iter().any(|(room_name, device_name)| {
+        house
+            .get_room(room_name)
+            .and_then(|room| room.get_device_history(device_name))
+            .is_some_and(|history| !history.get_capabilities().is_empty())
+    });
+    let has_tags = devices
+        .iter()
+        .any(|(room_name, device_name)| !house.get_device_tags(room_name, device_name).is_empty());
+
+    [
+        (UnsharedState::Floors, !house.get_floor_list().is_empty()),
+        (UnsharedState::Zones, !house.get_zone_list().is_empty()),
+        (UnsharedState::Groups, !house.get_group_list().is_empty()),
+        (UnsharedState::Tags, has_tags),
+        (UnsharedState::History, has_history),
+    ]
+    .into_iter()
+    .filter_map(|(state, present)| present.then_some(state))
+    .collect()
+}
+
+/// Получение содержимого блокировки, даже если поток, удерживавший её, завершился паникой
+///
+/// Паника в одном обработчике не должна делать недоступным весь дом
+pub(crate) fn recover<T>(result: LockResult<T>) -> T {
+    result.unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room::Room;
+    use crate::smart_device::SmartDeviceErrorCode;
+    use crate::socket::SmartSocket;
+    use std::thread;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn handles_are_thread_safe() {
+        assert_send_sync::<SharedHouse>();
+        assert_send_sync::<Box<dyn SmartDevice>>();
+        fn assert_send<T: Send>() {}
+        assert_send::<House>();
+    }
+
+    #[test]
+    fn concurrent_operations_keep_house_consistent() {
+        let house = SharedHouse::new("House", 16);
+        assert!(house.add_room("Shared", 1).is_ok());
+        assert!(house
+            .add_device("Shared", Box::new(SmartSocket::new("Socket")))
+            .is_ok());
+
+        let workers: Vec<_> = (0..8)
+            .map(|worker| {
+                let house = house.clone();
+                thread::spawn(move || {
+                    let room_name = format!("Room{}", worker);
+                    assert!(house.add_room(&room_name, 4).is_ok());
+
+                    for iteration in 0..200 {
+                        let device_name = format!("Socket{}", iteration % 4);
+                        let _ =
+                            house.add_device(&room_name, Box::new(SmartSocket::new(&device_name)));
+                        let state = if iteration % 2 == 0 {
+                            SmartDevicePowerState::Enabled
+                        } else {
+                            SmartDevicePowerState::Disabled
+                        };
+                        assert!(house.set_power_state("Shared", "Socket", state).is_ok());
+                        if iteration % 3 == 0 {
+                            let _ = house.remove_device(&room_name, &device_name);
+                        }
+                        let _ = house.get_power_consumption();
+                    }
+                })
+            })
+            .collect();
+
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert_eq!(house.get_room_list().len(), 9);
+        for worker in 0..8 {
+            let devices = house.get_device_list(&format!("Room{}", worker)).unwrap();
+            assert!(devices.len() <= 4);
+        }
+        assert!(house.take_device("Shared", "Socket").is_ok());
+        assert_eq!(house.get_device_list("Shared"), Some(Vec::new()));
+    }
+
+    #[test]
+    fn house_is_converted_with_device_state() {
+        let mut house = House::new("House", 2);
+        assert!(house.add_room(Room::new("Kitchen", 2)).is_ok());
+        assert!(house
+            .add_device("Kitchen", Box::new(SmartSocket::new("Socket")))
+            .is_ok());
+        assert!(house
+            .set_power_state("Kitchen", "Socket", SmartDevicePowerState::Enabled)
+            .is_ok());
+
+        let shared = SharedHouse::try_from(house).unwrap();
+        let state = shared
+            .with_device("Kitchen", "Socket", |device| device.get_device_status())
+            .unwrap();
+        assert_eq!(
+            state,
+            crate::smart_device::SmartDeviceStatus::PowerState(SmartDevicePowerState::Enabled)
+        );
+        assert_eq!(
+            shared.add_device("Kitchen", Box::new(SmartSocket::new("Socket"))),
+            Err(ErrorReason::ItemAlreadyPresented)
+        );
+    }
+
+    #[test]
+    fn conversion_keeps_audit_log_and_rejects_unshared_state() {
+        let mut house = House::new("House", 2);
+        house.set_actor("installer");
+        assert!(house.add_room(Room::new("Kitchen", 1)).is_ok());
+        assert!(house.add_zone("Heating").is_ok());
+
+        let Err(error) = SharedHouse::try_from(house) else {
+            panic!("house with zones must not be converted");
+        };
+        assert_eq!(error.unshared, vec![UnsharedState::Zones]);
+        let mut house = *error.house;
+        assert!(house.remove_zone("Heating").is_ok());
+
+        let shared = SharedHouse::try_from(house).unwrap();
+        assert_eq!(shared.get_room_list(), vec!["Kitchen".to_string()]);
+        assert_eq!(shared.get_actor(), "installer");
+        assert!(shared.remove_room("Kitchen").is_ok());
+        let operations: Vec<&str> = shared.with_audit_log(|log| {
+            log.get_entries()
+                .iter()
+                .map(|entry| entry.operation.get_name())
+                .collect()
+        });
+        assert_eq!(
+            operations,
+            vec!["add_room", "add_zone", "remove_zone", "remove_room"]
+        );
+    }
+
+    #[test]
+    fn power_events_follow_switching_order() {
+        use crate::events::EventFilter;
+
+        let house = SharedHouse::new("House", 1);
+        assert!(house.add_room("Kitchen", 1).is_ok());
+        assert!(house
+            .add_device("Kitchen", Box::new(SmartSocket::new("Socket")))
+            .is_ok());
+        let (_, receiver) =
+            house.with_event_bus(|events| events.subscribe_channel(EventFilter::all()));
+
+        let workers: Vec<_> = (0..4)
+            .map(|worker| {
+                let house = house.clone();
+                thread::spawn(move || {
+                    for iteration in 0..100 {
+                        let state = if (worker + iteration) % 2 == 0 {
+                            SmartDevicePowerState::Enabled
+                        } else {
+                            SmartDevicePowerState::Disabled
+                        };
+                        assert!(house.set_power_state("Kitchen", "Socket", state).is_ok());
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let last_event = receiver.try_iter().last().unwrap();
+        let status = house
+            .with_device("Kitchen", "Socket", |device| device.get_device_status())
+            .unwrap();
+        match (last_event.payload, status) {
+            (
+                EventPayload::PowerStateChanged(event_state),
+                SmartDeviceStatus::PowerState(state),
+            ) => {
+                assert_eq!(event_state, state)
+            }
+            other => panic!("unexpected event and status: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn switching_reports_each_fault_once() {
+        use crate::events::EventFilter;
+
+        let house = SharedHouse::new("House", 1);
+        assert!(house.add_room("Kitchen", 2).is_ok());
+        for (name, code) in [
+            ("Kettle", SmartDeviceErrorCode::Overheat),
+            ("Heater", SmartDeviceErrorCode::PoweredOff),
+        ] {
+            let mut socket = SmartSocket::new(name);
+            socket.report_fault(code);
+            assert!(house.add_device("Kitchen", Box::new(socket)).is_ok());
+        }
+        let (_, receiver) =
+            house.with_event_bus(|events| events.subscribe_channel(EventFilter::all()));
+
+        for device in ["Kettle", "Heater", "Kettle", "Heater"] {
+            assert!(house
+                .set_power_state("Kitchen", device, SmartDevicePowerState::Enabled)
+                .is_err());
+        }
+
+        let events: Vec<_> = receiver
+            .try_iter()
+            .map(|event| (event.device, event.payload))
+            .collect();
+        assert_eq!(
+            events,
+            vec![(
+                Some("Kettle".to_string()),
+                EventPayload::Malfunction(SmartDeviceErrorCode::Overheat)
+            )]
+        );
+    }
+
+    #[test]
+    fn commands_are_audited_and_published() {
+        use crate::events::EventFilter;
+
+        let house = SharedHouse::new("House", 1);
+        assert!(house.add_room("Kitchen", 1).is_ok());
+        let mut socket = SmartSocket::new("Kettle");
+        socket.report_fault(SmartDeviceErrorCode::Overheat);
+        assert!(house.add_device("Kitchen", Box::new(socket)).is_ok());
+        let (_, receiver) =
+            house.with_event_bus(|events| events.subscribe_channel(EventFilter::all()));
+
+        let command = SmartDeviceCommand::ResetFault;
+        assert_eq!(
+            house.execute("Kitchen", "Kettle", command.clone()),
+            Ok(Ok(SmartDeviceResponse::Done))
+        );
+        assert_eq!(
+            house.execute("Kitchen", "Lamp", command),
+            Err(ErrorReason::ItemDoesntExist)
+        );
+
+        let outcomes: Vec<(&str, bool)> = house.with_audit_log(|log| {
+            log.get_entries()
+                .iter()
+                .skip(2)
+                .map(|entry| (entry.operation.get_name(), entry.is_success()))
+                .collect()
+        });
+        assert_eq!(outcomes, vec![("execute", true), ("execute", false)]);
+        let events: Vec<_> = receiver.try_iter().map(|event| event.payload).collect();
+        assert_eq!(
+            events,
+            vec![EventPayload::CommandExecuted {
+                command: "reset_fault".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn names_and_explicit_ids_are_validated() {
+        use crate::audit::AuditFilter;
+
+        let house = SharedHouse::new("House", 3);
+        for name in ["", "  ", "Kitchen/Pantry"] {
+            assert_eq!(house.add_room(name, 1), Err(ErrorReason::InvalidName));
+        }
+        assert!(house.add_room("Kitchen", 2).is_ok());
+        assert!(house.add_room("Hall", 2).is_ok());
+        assert_eq!(
+            house.add_device("Kitchen", Box::new(SmartSocket::new("a/b"))),
+            Err(ErrorReason::InvalidName)
+        );
+
+        // Одноимённые устройства с идентификаторами по умолчанию допустимы в разных комнатах
+        for room in ["Kitchen", "Hall"] {
+            assert!(house
+                .add_device(room, Box::new(SmartSocket::new("Socket")))
+                .is_ok());
+        }
+        assert!(house
+            .add_device(
+                "Kitchen",
+                Box::new(SmartSocket::new("Lamp").with_id("lamp"))
+            )
+            .is_ok());
+        assert_eq!(
+            house.add_device("Hall", Box::new(SmartSocket::new("Lamp").with_id("lamp"))),
+            Err(ErrorReason::ItemAlreadyPresented)
+        );
+        assert_eq!(
+            house.add_device(
+                "Hall",
+                Box::new(SmartSocket::new("Lamp").with_id("socket-Socket"))
+            ),
+            Err(ErrorReason::ItemAlreadyPresented)
+        );
+
+        let failures = house.with_audit_log(|log| log.query(&AuditFilter::all().failures()).len());
+        assert_eq!(failures, 6);
+    }
+
+    #[test]
+    fn busy_device_is_not_taken() {
+        let house = SharedHouse::new("House", 1);
+        assert!(house.add_room("Room", 1).is_ok());
+        assert!(house
+            .add_device("Room", Box::new(SmartSocket::new("Socket")))
+            .is_ok());
+
+        let (started, wait_started) = std::sync::mpsc::channel();
+        let worker = {
+            let house = house.clone();
+            thread::spawn(move || {
+                house.with_device("Room", "Socket", |_| {
+                    started.send(()).unwrap();
+                    thread::sleep(DEVICE_RELEASE_TIMEOUT * 2);
+                })
+            })
+        };
+        wait_started.recv().unwrap();
+
+        assert_eq!(
+            house.take_device("Room", "Socket").err(),
+            Some(ErrorReason::DeviceBusy)
+        );
+        assert_eq!(
+            house.get_device_list("Room").unwrap(),
+            vec!["Socket".to_string()]
+        );
+
+        assert!(worker.join().unwrap().is_ok());
+        assert!(house.take_device("Room", "Socket").is_ok());
+        assert!(house.get_device_list("Room").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,344 @@
+//! Модуль, содержащий реализацию устройства "Умные жалюзи"
+//!
+//! > Умные жалюзи (шторы) - это устройство с электроприводом, которое позволяет управлять
+//! > положением полотна от `0%` (полностью закрыто) до `100%` (полностью открыто)
+//! > с помощью команд "Открыть", "Закрыть", "Стоп" и "Установить положение".
+//! > Перемещение полотна занимает время, пропорциональное пройденному расстоянию.
+//! > В случае обнаружения препятствия привод останавливается, а жалюзи переходят
+//! > в состояние "Ошибка" и перестают выполнять команды.
+//!
+use super::command::{
+    execute_common, Capability, SmartDeviceCommand, SmartDeviceCommandError, SmartDeviceResponse,
+    SmartDeviceValue,
+};
+use super::metadata::{default_device_id, DeviceKind, DeviceMetadata};
+use super::smart_device::{
+    SmartDevice, SmartDeviceErrorCode, SmartDevicePowerState, SmartDeviceStatus,
+};
+use crate::units::Percent;
+use std::time::{Duration, Instant};
+
+/// Положение полностью открытых жалюзи
+pub const FULLY_OPEN: Percent = Percent::MAX;
+
+/// Положение полностью закрытых жалюзи
+pub const FULLY_CLOSED: Percent = Percent::MIN;
+
+/// Время перемещения полотна из одного крайнего положения в другое по умолчанию
+const DEFAULT_TRAVEL_TIME: Duration = Duration::from_secs(20);
+
+/// Описание текущего перемещения полотна
+struct Motion {
+    /// Момент начала движения
+    started_at: Instant,
+    /// Положение (%), из которого началось движение
+    from: u8,
+    /// Целевое положение (%)
+    to: u8,
+}
+
+///
+/// Тип описывающий характеристики и поведение девайса "Умные жалюзи"
+///
+pub struct SmartBlinds {
+    /// Пользовательский псевдоним для жалюзи
+    pub name: String,
+
+    /// Метаданные жалюзи (идентификатор, производитель, модель, версия прошивки)
+    metadata: DeviceMetadata,
+
+    /// Положение полотна (%) на момент последней остановки
+    position: u8,
+
+    /// Текущее перемещение полотна, если привод работает
+    motion: Option<Motion>,
+
+    /// Время перемещения полотна из одного крайнего положения в другое
+    travel_time: Duration,
+
+    // Cтатус работы (ВКЛ,ВЫКЛ/ОШИБКА)
+    status: SmartDeviceStatus,
+}
+
+impl SmartBlinds {
+    /// Создание экземпляра жалюзи с псевдонимом `name`
+    ///
+    /// По умолчанию жалюзи выключены и полностью закрыты, время полного хода - `20 с`
+    ///
+    /// ## Пример
+    /// ```ignore
+    /// let my_blinds = SmartBlinds::new("BedroomBlinds");
+    /// ```
+    ///
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            metadata: DeviceMetadata::new(
+                DeviceKind::Blinds,
+                "SmartBlinds",
+                &default_device_id(DeviceKind::Blinds, name),
+            ),
+            position: FULLY_CLOSED.value(),
+            motion: None,
+            travel_time: DEFAULT_TRAVEL_TIME,
+            status: SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled),
+        }
+    }
+
+    /// Установка идентификатора жалюзи `id` вместо идентификатора по умолчанию
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.metadata = self.metadata.with_id(id);
+        self
+    }
+
+    /// Установка времени перемещения полотна из одного крайнего положения в другое
+    pub fn set_travel_time(&mut self, travel_time: Duration) {
+        self.freeze();
+        self.travel_time = travel_time;
+    }
+
+    /// Получение текущего положения полотна
+    pub fn get_position(&self) -> Percent {
+        Percent::new(self.current_position())
+    }
+
+    /// Вычисление текущего положения полотна (%) с учётом времени, прошедшего с начала движения
+    fn current_position(&self) -> u8 {
+        match &self.motion {
+            None => self.position,
+            Some(motion) => {
+                let distance = motion.from.abs_diff(motion.to);
+                let required = self.travel_time.mul_f32(distance as f32 / 100.0);
+                let elapsed = motion.started_at.elapsed();
+
+                if elapsed >= required {
+                    return motion.to;
+                }
+
+                let passed = (elapsed.as_secs_f32() / self.travel_time.as_secs_f32() * 100.0) as u8;
+
+                if motion.to > motion.from {
+                    motion.from + passed
+                } else {
+                    motion.from - passed
+                }
+            }
+        }
+    }
+
+    /// Проверка, движется ли полотно в данный момент
+    pub fn is_moving(&self) -> bool {
+        match &self.motion {
+            None => false,
+            Some(motion) => self.current_position() != motion.to,
+        }
+    }
+
+    /// Перемещение полотна в положение `position`
+    pub fn set_position(&mut self, position: Percent) -> Result<(), SmartDeviceErrorCode> {
+        self.check_ready()?;
+        self.freeze();
+
+        let target = position.value();
+
+        if target != self.position {
+            self.motion = Some(Motion {
+                started_at: Instant::now(),
+                from: self.position,
+                to: target,
+            });
+        }
+        Ok(())
+    }
+
+    /// Полное открытие жалюзи
+    pub fn open(&mut self) -> Result<(), SmartDeviceErrorCode> {
+        self.set_position(FULLY_OPEN)
+    }
+
+    /// Полное закрытие жалюзи
+    pub fn close(&mut self) -> Result<(), SmartDeviceErrorCode> {
+        self.set_position(FULLY_CLOSED)
+    }
+
+    /// Остановка полотна в текущем положении
+    pub fn stop(&mut self) -> Result<(), SmartDeviceErrorCode> {
+        self.check_ready()?;
+        self.freeze();
+        Ok(())
+    }
+
+    /// Сообщение о препятствии на пути полотна
+    ///
+    /// Привод останавливается, жалюзи переходят в состояние ошибки
+    pub fn report_obstruction(&mut self) {
+        self.freeze();
+        self.status = SmartDeviceStatus::Malfunction(SmartDeviceErrorCode::Obstruction);
+    }
+
+    /// Сброс состояния ошибки
+    ///
+    /// После сброса устройство выключено
+    pub fn reset_fault(&mut self) {
+        if let SmartDeviceStatus::Malfunction(_) = self.status {
+            self.status = SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled);
+        }
+    }
+
+    /// Фиксация текущего положения полотна и остановка привода
+    fn freeze(&mut self) {
+        self.position = self.current_position();
+        self.motion = None;
+    }
+
+    /// Проверка возможности выполнения команды на перемещение полотна
+    fn check_ready(&self) -> Result<(), SmartDeviceErrorCode> {
+        match &self.status {
+            SmartDeviceStatus::PowerState(SmartDevicePowerState::Enabled) => Ok(()),
+            SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled) => {
+                Err(SmartDeviceErrorCode::PoweredOff)
+            }
+            SmartDeviceStatus::Malfunction(y) => Err(y.clone()),
+        }
+    }
+}
+
+impl SmartDevice for SmartBlinds {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    fn get_metadata(&self) -> &DeviceMetadata {
+        &self.metadata
+    }
+
+    fn set_power_state(
+        &mut self,
+        state: SmartDevicePowerState,
+    ) -> Result<(), SmartDeviceErrorCode> {
+        match &self.status {
+            SmartDeviceStatus::PowerState(_) => {
+                // При отключении питания привод останавливается
+                if let SmartDevicePowerState::Disabled = state {
+                    self.freeze();
+                }
+                self.status = SmartDeviceStatus::PowerState(state);
+                Ok(())
+            }
+            SmartDeviceStatus::Malfunction(y) => {
+                println!("Cannot perform the operation due to: {}", y);
+                Err((*y).clone())
+            }
+        }
+    }
+
+    fn get_device_status(&self) -> SmartDeviceStatus {
+        self.status.clone()
+    }
+
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![
+            Capability::PowerControl,
+            Capability::Position,
+            Capability::FaultReset,
+        ]
+    }
+
+    fn execute(
+        &mut self,
+        command: SmartDeviceCommand,
+    ) -> Result<SmartDeviceResponse, SmartDeviceCommandError> {
+        match command {
+            SmartDeviceCommand::SetPosition(position) => self.set_position(position)?,
+            SmartDeviceCommand::Stop => self.stop()?,
+            SmartDeviceCommand::ResetFault => self.reset_fault(),
+            SmartDeviceCommand::Read(Capability::Position) => {
+                return Ok(SmartDeviceResponse::Value(SmartDeviceValue::Position(
+                    self.get_position(),
+                )))
+            }
+            other => return execute_common(self, other),
+        }
+        Ok(SmartDeviceResponse::Done)
+    }
+
+    fn get_text_report(&self) -> String {
+        format!(
+            "Current position is {}{}, status: {}\n",
+            self.get_position(),
+            if self.is_moving() { " (moving)" } else { "" },
+            self.status
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_blinds_do_not_move() {
+        let mut blinds = SmartBlinds::new("Blinds_1");
+
+        assert!(matches!(
+            blinds.open(),
+            Err(SmartDeviceErrorCode::PoweredOff)
+        ));
+        assert_eq!(blinds.get_position(), FULLY_CLOSED);
+    }
+
+    #[test]
+    fn blinds_reach_target_position() {
+        let mut blinds = SmartBlinds::new("Blinds_1");
+        blinds.set_travel_time(Duration::ZERO);
+
+        assert!(blinds
+            .set_power_state(SmartDevicePowerState::Enabled)
+            .is_ok());
+        assert!(blinds.set_position(Percent::new(40)).is_ok());
+        assert_eq!(blinds.get_position(), Percent::new(40));
+        assert!(!blinds.is_moving());
+
+        assert!(blinds.open().is_ok());
+        assert_eq!(blinds.get_position(), FULLY_OPEN);
+    }
+
+    #[test]
+    fn stop_keeps_intermediate_position() {
+        let mut blinds = SmartBlinds::new("Blinds_1");
+        blinds.set_travel_time(Duration::from_secs(3600));
+
+        assert!(blinds
+            .set_power_state(SmartDevicePowerState::Enabled)
+            .is_ok());
+        assert!(blinds.open().is_ok());
+        assert!(blinds.is_moving());
+
+        assert!(blinds.stop().is_ok());
+        assert!(!blinds.is_moving());
+        assert!(blinds.get_position() < FULLY_OPEN);
+    }
+
+    #[test]
+    fn obstruction_blocks_commands() {
+        let mut blinds = SmartBlinds::new("Blinds_1");
+
+        assert!(blinds
+            .set_power_state(SmartDevicePowerState::Enabled)
+            .is_ok());
+        blinds.report_obstruction();
+
+        assert!(matches!(
+            blinds.close(),
+            Err(SmartDeviceErrorCode::Obstruction)
+        ));
+        assert!(matches!(
+            blinds.get_device_status(),
+            SmartDeviceStatus::Malfunction(SmartDeviceErrorCode::Obstruction)
+        ));
+    }
+}
@@ -0,0 +1,308 @@
+//! Модуль содержит описание универсального интерфейса команд для "Умных" устройств
+//!
+//! > Каждое устройство сообщает список поддерживаемых возможностей ([`Capability`])
+//! > и выполняет типизированные команды ([`SmartDeviceCommand`]) через
+//! > [`SmartDevice::execute`](super::smart_device::SmartDevice::execute).
+//! > Команды, не поддерживаемые устройством, завершаются ошибкой
+//! > [`SmartDeviceCommandError::Unsupported`].
+//!
+use super::hvac::{FanSpeed, HvacMode};
+use super::lock::LockState;
+use super::smart_device::{
+    SmartDevice, SmartDeviceErrorCode, SmartDevicePowerState, SmartDeviceStatus,
+};
+use crate::units::{Celsius, Percent, Watts};
+use std::fmt::{self, Display};
+
+/// Перечисление возможностей, которые может поддерживать умное устройство
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Включение/выключение питания
+    PowerControl,
+    /// Измерение температуры окружающей среды
+    Temperature,
+    /// Измерение потребляемой мощности
+    PowerMetering,
+    /// Управление положением привода (жалюзи, шторы)
+    Position,
+    /// Запирание/отпирание
+    Locking,
+    /// Поддержание заданной температуры
+    Setpoint,
+    /// Выбор режима работы кондиционера
+    HvacMode,
+    /// Управление скоростью вентилятора
+    FanSpeed,
+    /// Сброс состояния ошибки
+    FaultReset,
+}
+
+/// Перечисление команд, которые могут быть переданы умному устройству
+#[derive(Clone, Debug, PartialEq)]
+pub enum SmartDeviceCommand {
+    /// Включение/выключение устройства
+    SetPowerState(SmartDevicePowerState),
+    /// Перемещение привода в положение
+    SetPosition(Percent),
+    /// Остановка привода
+    Stop,
+    /// Запирание
+    Lock,
+    /// Отпирание
+    Unlock,
+    /// Отпирание по PIN-коду
+    UnlockWithPin(String),
+    /// Установка заданной температуры
+    SetTargetTemperature(Celsius),
+    /// Установка режима работы кондиционера
+    SetHvacMode(HvacMode),
+    /// Установка скорости вентилятора
+    SetFanSpeed(FanSpeed),
+    /// Сброс состояния ошибки
+    ResetFault,
+    /// Чтение текущего значения, относящегося к возможности
+    Read(Capability),
+}
+
+/// Перечисление значений, которые могут быть прочитаны у умного устройства
+#[derive(Clone, Debug, PartialEq)]
+pub enum SmartDeviceValue {
+    /// Статус работы устройства
+    Status(SmartDeviceStatus),
+    /// Температура окружающей среды
+    Temperature(Celsius),
+    /// Потребляемая мощность
+    PowerConsumption(Watts),
+    /// Положение привода
+    Position(Percent),
+    /// Положение запирающего механизма
+    LockState(LockState),
+    /// Заданная температура
+    TargetTemperature(Celsius),
+    /// Режим работы кондиционера
+    HvacMode(HvacMode),
+    /// Скорость вентилятора
+    FanSpeed(FanSpeed),
+}
+
+/// Перечисление возможных ответов умного устройства на команду
+#[derive(Clone, Debug, PartialEq)]
+pub enum SmartDeviceResponse {
+    /// Команда выполнена
+    Done,
+    /// Прочитанное значение
+    Value(SmartDeviceValue),
+}
+
+/// Перечисление ошибок выполнения команды
+#[derive(Clone, Debug, PartialEq)]
+pub enum SmartDeviceCommandError {
+    /// Команда не поддерживается устройством
+    Unsupported,
+    /// Ошибка, возникшая в устройстве при выполнении команды
+    Device(SmartDeviceErrorCode),
+}
+
+impl SmartDeviceCommand {
+    /// Получение имени команды в формате snake_case (без аргументов)
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::SetPowerState(_) => "set_power_state",
+            Self::SetPosition(_) => "set_position",
+            Self::Stop => "stop",
+            Self::Lock => "lock",
+            Self::Unlock => "unlock",
+            Self::UnlockWithPin(_) => "unlock_with_pin",
+            Self::SetTargetTemperature(_) => "set_target_temperature",
+            Self::SetHvacMode(_) => "set_hvac_mode",
+            Self::SetFanSpeed(_) => "set_fan_speed",
+            Self::ResetFault => "reset_fault",
+            Self::Read(_) => "read",
+        }
+    }
+}
+
+impl Capability {
+    /// Список всех возможностей
+    pub const ALL: [Capability; 9] = [
+        Capability::PowerControl,
+        Capability::Temperature,
+        Capability::PowerMetering,
+        Capability::Position,
+        Capability::Locking,
+        Capability::Setpoint,
+        Capability::HvacMode,
+        Capability::FanSpeed,
+        Capability::FaultReset,
+    ];
+}
+
+impl From<SmartDeviceErrorCode> for SmartDeviceCommandError {
+    fn from(code: SmartDeviceErrorCode) -> Self {
+        Self::Device(code)
+    }
+}
+
+/// Выполнение команд, общих для всех умных устройств
+///
+/// Используется реализациями [`SmartDevice::execute`] для команд, не требующих
+/// специфичной для устройства обработки
+pub fn execute_common<D: SmartDevice + ?Sized>(
+    device: &mut D,
+    command: SmartDeviceCommand,
+) -> Result<SmartDeviceResponse, SmartDeviceCommandError> {
+    match command {
+        SmartDeviceCommand::SetPowerState(state) => {
+            device.set_power_state(state)?;
+            Ok(SmartDeviceResponse::Done)
+        }
+        SmartDeviceCommand::Read(Capability::PowerControl) => Ok(SmartDeviceResponse::Value(
+            SmartDeviceValue::Status(device.get_device_status()),
+        )),
+        SmartDeviceCommand::Read(Capability::Temperature)
+            if device.get_capabilities().contains(&Capability::Temperature) =>
+        {
+            let temperature = device
+                .get_temperature()
+                .ok_or(SmartDeviceErrorCode::PoweredOff)?;
+            Ok(SmartDeviceResponse::Value(SmartDeviceValue::Temperature(
+                temperature,
+            )))
+        }
+        SmartDeviceCommand::Read(Capability::PowerMetering)
+            if device
+                .get_capabilities()
+                .contains(&Capability::PowerMetering) =>
+        {
+            Ok(SmartDeviceResponse::Value(
+                SmartDeviceValue::PowerConsumption(device.get_power_consumption()),
+            ))
+        }
+        _ => Err(SmartDeviceCommandError::Unsupported),
+    }
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::PowerControl => write!(f, "power_control"),
+            Self::Temperature => write!(f, "temperature"),
+            Self::PowerMetering => write!(f, "power_metering"),
+            Self::Position => write!(f, "position"),
+            Self::Locking => write!(f, "locking"),
+            Self::Setpoint => write!(f, "setpoint"),
+            Self::HvacMode => write!(f, "hvac_mode"),
+            Self::FanSpeed => write!(f, "fan_speed"),
+            Self::FaultReset => write!(f, "fault_reset"),
+        }
+    }
+}
+
+impl Display for SmartDeviceCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unsupported => write!(f, "Command is not supported by the device."),
+            Self::Device(code) => write!(f, "{}", code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blinds::SmartBlinds;
+    use crate::hvac::SmartHvac;
+    use crate::socket::SmartSocket;
+    use crate::thermometer::SmartThermometer;
+
+    #[test]
+    fn devices_are_driven_uniformly() {
+        let mut devices: Vec<Box<dyn SmartDevice>> = vec![
+            Box::new(SmartSocket::new("Socket_1")),
+            Box::new(SmartThermometer::new("Thermometer_1")),
+            Box::new(SmartBlinds::new("Blinds_1")),
+        ];
+
+        for device in devices.iter_mut() {
+            assert!(device
+                .get_capabilities()
+                .contains(&Capability::PowerControl));
+            assert_eq!(
+                device.execute(SmartDeviceCommand::SetPowerState(
+                    SmartDevicePowerState::Enabled
+                )),
+                Ok(SmartDeviceResponse::Done)
+            );
+            assert_eq!(
+                device.execute(SmartDeviceCommand::Read(Capability::PowerControl)),
+                Ok(SmartDeviceResponse::Value(SmartDeviceValue::Status(
+                    SmartDeviceStatus::PowerState(SmartDevicePowerState::Enabled)
+                )))
+            );
+        }
+    }
+
+    #[test]
+    fn unsupported_command_is_reported() {
+        let mut socket = SmartSocket::new("Socket_1");
+
+        assert!(!socket.get_capabilities().contains(&Capability::Position));
+        assert_eq!(
+            socket.execute(SmartDeviceCommand::SetPosition(Percent::new(50))),
+            Err(SmartDeviceCommandError::Unsupported)
+        );
+        assert_eq!(
+            socket.execute(SmartDeviceCommand::Read(Capability::Temperature)),
+            Err(SmartDeviceCommandError::Unsupported)
+        );
+    }
+
+    #[test]
+    fn fault_is_reset_by_command() {
+        let mut blinds = SmartBlinds::new("Blinds_1");
+        blinds.report_obstruction();
+
+        assert_eq!(
+            blinds.execute(SmartDeviceCommand::SetPosition(Percent::new(50))),
+            Err(SmartDeviceCommandError::Device(
+                SmartDeviceErrorCode::Obstruction
+            ))
+        );
+        assert_eq!(
+            blinds.execute(SmartDeviceCommand::ResetFault),
+            Ok(SmartDeviceResponse::Done)
+        );
+        assert_eq!(
+            blinds.get_device_status(),
+            SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled)
+        );
+    }
+
+    #[test]
+    fn faulty_devices_are_reset_uniformly() {
+        let mut socket = SmartSocket::new("Socket_1");
+        socket.report_fault(SmartDeviceErrorCode::Overcurrent);
+        let mut thermometer = SmartThermometer::new("Thermometer_1");
+        thermometer.report_fault(SmartDeviceErrorCode::Overheat);
+        let mut hvac = SmartHvac::new("Hvac_1");
+        hvac.report_fault(SmartDeviceErrorCode::Overheat);
+
+        let mut devices: Vec<Box<dyn SmartDevice>> =
+            vec![Box::new(socket), Box::new(thermometer), Box::new(hvac)];
+        for device in devices.iter_mut() {
+            assert!(device.get_capabilities().contains(&Capability::FaultReset));
+            assert!(matches!(
+                device.get_device_status(),
+                SmartDeviceStatus::Malfunction(_)
+            ));
+            assert_eq!(
+                device.execute(SmartDeviceCommand::ResetFault),
+                Ok(SmartDeviceResponse::Done)
+            );
+            assert_eq!(
+                device.get_device_status(),
+                SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled)
+            );
+        }
+    }
+}
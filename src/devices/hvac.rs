@@ -0,0 +1,472 @@
+//! Модуль, содержащий реализацию устройства "Умный кондиционер"
+//!
+//! > Умный кондиционер - это устройство для нагрева, охлаждения и вентиляции помещения.
+//! > Кондиционер поддерживает заданную температуру, используя показания термометра
+//! > в комнате: нагрев или охлаждение включаются, когда температура отклоняется
+//! > от заданной больше, чем на величину гистерезиса.
+//! > Потребляемая мощность зависит от выполняемого действия и скорости вентилятора.
+//!
+use super::command::{
+    execute_common, Capability, SmartDeviceCommand, SmartDeviceCommandError, SmartDeviceResponse,
+    SmartDeviceValue,
+};
+use super::metadata::{default_device_id, DeviceKind, DeviceMetadata};
+use super::smart_device::{
+    SmartDevice, SmartDeviceErrorCode, SmartDevicePowerState, SmartDeviceStatus,
+};
+use crate::units::{Celsius, TemperatureScale, Watts};
+use std::fmt::{self, Display};
+
+/// Минимальная допустимая заданная температура
+pub const MIN_TARGET_TEMPERATURE: Celsius = Celsius(16.0);
+
+/// Максимальная допустимая заданная температура
+pub const MAX_TARGET_TEMPERATURE: Celsius = Celsius(30.0);
+
+/// Гистерезис регулирования температуры (°С)
+const HYSTERESIS: f32 = 0.5;
+
+/// Мощность, потребляемая в режиме ожидания
+const STANDBY_POWER: Watts = Watts(5.0);
+
+/// Мощность, потребляемая при нагреве (без учёта вентилятора)
+const HEATING_POWER: Watts = Watts(2000.0);
+
+/// Мощность, потребляемая при охлаждении (без учёта вентилятора)
+const COOLING_POWER: Watts = Watts(1500.0);
+
+/// Перечисление режимов работы кондиционера
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HvacMode {
+    /// Нагрев и охлаждение отключены
+    Off,
+    /// Нагрев
+    Heat,
+    /// Охлаждение
+    Cool,
+    /// Только вентиляция
+    Fan,
+    /// Автоматический выбор между нагревом и охлаждением
+    Auto,
+}
+
+/// Перечисление скоростей вентилятора
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FanSpeed {
+    /// Низкая скорость
+    Low,
+    /// Средняя скорость
+    Medium,
+    /// Высокая скорость
+    High,
+}
+
+impl FanSpeed {
+    /// Мощность, потребляемая вентилятором
+    fn power(&self) -> Watts {
+        match self {
+            Self::Low => Watts(30.0),
+            Self::Medium => Watts(50.0),
+            Self::High => Watts(80.0),
+        }
+    }
+}
+
+/// Перечисление действий, выполняемых кондиционером в данный момент
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HvacAction {
+    /// Простой
+    Idle,
+    /// Нагрев
+    Heating,
+    /// Охлаждение
+    Cooling,
+    /// Вентиляция
+    Ventilating,
+}
+
+///
+/// Тип описывающий характеристики и поведение девайса "Умный кондиционер"
+///
+pub struct SmartHvac {
+    /// Пользовательский псевдоним для кондиционера
+    pub name: String,
+
+    /// Метаданные кондиционера (идентификатор, производитель, модель, версия прошивки)
+    metadata: DeviceMetadata,
+
+    /// Режим работы
+    mode: HvacMode,
+
+    /// Заданная температура
+    target_temperature: Celsius,
+
+    /// Скорость вентилятора
+    fan_speed: FanSpeed,
+
+    /// Текущее действие
+    action: HvacAction,
+
+    /// Последняя известная температура в помещении
+    ambient_temperature: Option<Celsius>,
+
+    // Cтатус работы (ВКЛ,ВЫКЛ/ОШИБКА)
+    status: SmartDeviceStatus,
+}
+
+impl SmartHvac {
+    /// Создание экземпляра кондиционера с псевдонимом `name`
+    ///
+    /// По умолчанию кондиционер выключен, режим - `Off`, заданная температура - `22.0 °С`
+    ///
+    /// ## Пример
+    /// ```ignore
+    /// let my_hvac = SmartHvac::new("LivingRoomAC");
+    /// ```
+    ///
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            metadata: DeviceMetadata::new(
+                DeviceKind::Hvac,
+                "SmartHvac",
+                &default_device_id(DeviceKind::Hvac, name),
+            ),
+            mode: HvacMode::Off,
+            target_temperature: Celsius(22.0),
+            fan_speed: FanSpeed::Medium,
+            action: HvacAction::Idle,
+            ambient_temperature: None,
+            status: SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled),
+        }
+    }
+
+    /// Установка идентификатора кондиционера `id` вместо идентификатора по умолчанию
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.metadata = self.metadata.with_id(id);
+        self
+    }
+
+    /// Получение режима работы
+    pub fn get_mode(&self) -> HvacMode {
+        self.mode
+    }
+
+    /// Установка режима работы
+    pub fn set_mode(&mut self, mode: HvacMode) -> Result<(), SmartDeviceErrorCode> {
+        self.check_ready()?;
+        self.mode = mode;
+        self.regulate();
+        Ok(())
+    }
+
+    /// Получение заданной температуры
+    pub fn get_target_temperature(&self) -> Celsius {
+        self.target_temperature
+    }
+
+    /// Установка заданной температуры
+    ///
+    /// Значение ограничивается диапазоном [`MIN_TARGET_TEMPERATURE`, `MAX_TARGET_TEMPERATURE`]
+    pub fn set_target_temperature(
+        &mut self,
+        temperature: Celsius,
+    ) -> Result<(), SmartDeviceErrorCode> {
+        self.check_ready()?;
+        self.target_temperature = Celsius(
+            temperature
+                .0
+                .clamp(MIN_TARGET_TEMPERATURE.0, MAX_TARGET_TEMPERATURE.0),
+        );
+        self.regulate();
+        Ok(())
+    }
+
+    /// Получение скорости вентилятора
+    pub fn get_fan_speed(&self) -> FanSpeed {
+        self.fan_speed
+    }
+
+    /// Установка скорости вентилятора
+    pub fn set_fan_speed(&mut self, fan_speed: FanSpeed) -> Result<(), SmartDeviceErrorCode> {
+        self.check_ready()?;
+        self.fan_speed = fan_speed;
+        Ok(())
+    }
+
+    /// Получение действия, выполняемого кондиционером в данный момент
+    pub fn get_action(&self) -> HvacAction {
+        self.action
+    }
+
+    /// Проверка, работает ли кондиционер (нагрев, охлаждение или вентиляция)
+    pub fn is_running(&self) -> bool {
+        self.action != HvacAction::Idle
+    }
+
+    /// Сообщение о неисправности `code`
+    ///
+    /// Неисправный кондиционер не выполняет команды до сброса ошибки
+    pub fn report_fault(&mut self, code: SmartDeviceErrorCode) {
+        self.status = SmartDeviceStatus::Malfunction(code);
+        self.regulate();
+    }
+
+    /// Сброс состояния ошибки
+    ///
+    /// После сброса устройство выключено
+    pub fn reset_fault(&mut self) {
+        if let SmartDeviceStatus::Malfunction(_) = self.status {
+            self.status = SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled);
+        }
+    }
+
+    /// Проверка возможности выполнения команды
+    fn check_ready(&self) -> Result<(), SmartDeviceErrorCode> {
+        match &self.status {
+            SmartDeviceStatus::PowerState(SmartDevicePowerState::Enabled) => Ok(()),
+            SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled) => {
+                Err(SmartDeviceErrorCode::PoweredOff)
+            }
+            SmartDeviceStatus::Malfunction(y) => Err(y.clone()),
+        }
+    }
+
+    /// Выбор действия в соответствии с режимом и температурой в помещении
+    fn regulate(&mut self) {
+        if self.check_ready().is_err() {
+            self.action = HvacAction::Idle;
+            return;
+        }
+
+        let target = self.target_temperature.0;
+        let too_cold = |t: f32| t <= target - HYSTERESIS;
+        let too_hot = |t: f32| t >= target + HYSTERESIS;
+        // Нагрев/охлаждение продолжаются до достижения заданной температуры
+        let reached = |t: f32, action: HvacAction| match action {
+            HvacAction::Heating => t >= target,
+            HvacAction::Cooling => t <= target,
+            _ => true,
+        };
+
+        self.action = match (self.mode, self.ambient_temperature.map(|t| t.0)) {
+            (HvacMode::Off, _) => HvacAction::Idle,
+            (HvacMode::Fan, _) => HvacAction::Ventilating,
+            (_, None) => HvacAction::Idle,
+            (HvacMode::Heat, Some(t)) => {
+                if too_cold(t) || (self.action == HvacAction::Heating && !reached(t, self.action)) {
+                    HvacAction::Heating
+                } else {
+                    HvacAction::Idle
+                }
+            }
+            (HvacMode::Cool, Some(t)) => {
+                if too_hot(t) || (self.action == HvacAction::Cooling && !reached(t, self.action)) {
+                    HvacAction::Cooling
+                } else {
+                    HvacAction::Idle
+                }
+            }
+            (HvacMode::Auto, Some(t)) => {
+                if too_cold(t) {
+                    HvacAction::Heating
+                } else if too_hot(t) {
+                    HvacAction::Cooling
+                } else if !reached(t, self.action) {
+                    self.action
+                } else {
+                    HvacAction::Idle
+                }
+            }
+        };
+    }
+}
+
+impl SmartDevice for SmartHvac {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    fn get_metadata(&self) -> &DeviceMetadata {
+        &self.metadata
+    }
+
+    fn set_power_state(
+        &mut self,
+        state: SmartDevicePowerState,
+    ) -> Result<(), SmartDeviceErrorCode> {
+        match &self.status {
+            SmartDeviceStatus::PowerState(_) => {
+                self.status = SmartDeviceStatus::PowerState(state);
+                self.regulate();
+                Ok(())
+            }
+            SmartDeviceStatus::Malfunction(y) => {
+                println!("Cannot perform the operation due to: {}", y);
+                Err((*y).clone())
+            }
+        }
+    }
+
+    fn get_device_status(&self) -> SmartDeviceStatus {
+        self.status.clone()
+    }
+
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![
+            Capability::PowerControl,
+            Capability::PowerMetering,
+            Capability::Setpoint,
+            Capability::HvacMode,
+            Capability::FanSpeed,
+            Capability::FaultReset,
+        ]
+    }
+
+    fn execute(
+        &mut self,
+        command: SmartDeviceCommand,
+    ) -> Result<SmartDeviceResponse, SmartDeviceCommandError> {
+        let value = match command {
+            SmartDeviceCommand::SetTargetTemperature(temperature) => {
+                self.set_target_temperature(temperature)?;
+                return Ok(SmartDeviceResponse::Done);
+            }
+            SmartDeviceCommand::SetHvacMode(mode) => {
+                self.set_mode(mode)?;
+                return Ok(SmartDeviceResponse::Done);
+            }
+            SmartDeviceCommand::SetFanSpeed(fan_speed) => {
+                self.set_fan_speed(fan_speed)?;
+                return Ok(SmartDeviceResponse::Done);
+            }
+            SmartDeviceCommand::ResetFault => {
+                self.reset_fault();
+                return Ok(SmartDeviceResponse::Done);
+            }
+            SmartDeviceCommand::Read(Capability::Setpoint) => {
+                SmartDeviceValue::TargetTemperature(self.target_temperature)
+            }
+            SmartDeviceCommand::Read(Capability::HvacMode) => SmartDeviceValue::HvacMode(self.mode),
+            SmartDeviceCommand::Read(Capability::FanSpeed) => {
+                SmartDeviceValue::FanSpeed(self.fan_speed)
+            }
+            other => return execute_common(self, other),
+        };
+        Ok(SmartDeviceResponse::Value(value))
+    }
+
+    fn get_power_consumption(&self) -> Watts {
+        if self.check_ready().is_err() {
+            return Watts::default();
+        }
+
+        match self.action {
+            HvacAction::Idle => STANDBY_POWER,
+            HvacAction::Ventilating => self.fan_speed.power(),
+            HvacAction::Heating => HEATING_POWER + self.fan_speed.power(),
+            HvacAction::Cooling => COOLING_POWER + self.fan_speed.power(),
+        }
+    }
+
+    fn update_ambient_temperature(&mut self, temperature: Celsius) {
+        self.ambient_temperature = Some(temperature);
+        self.regulate();
+    }
+
+    fn get_text_report(&self) -> String {
+        self.get_text_report_in(TemperatureScale::Celsius)
+    }
+
+    fn get_text_report_in(&self, scale: TemperatureScale) -> String {
+        format!(
+            "Mode is {}, target temperature is {}, fan speed is {}, action: {}, power draw is {}, status: {}\n",
+            self.mode,
+            self.target_temperature.display_in(scale),
+            self.fan_speed,
+            self.action,
+            self.get_power_consumption(),
+            self.status
+        )
+    }
+}
+
+impl Display for HvacMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Off => write!(f, "Off"),
+            Self::Heat => write!(f, "Heat"),
+            Self::Cool => write!(f, "Cool"),
+            Self::Fan => write!(f, "Fan"),
+            Self::Auto => write!(f, "Auto"),
+        }
+    }
+}
+
+impl Display for FanSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Low => write!(f, "Low"),
+            Self::Medium => write!(f, "Medium"),
+            Self::High => write!(f, "High"),
+        }
+    }
+}
+
+impl Display for HvacAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Idle => write!(f, "Idle"),
+            Self::Heating => write!(f, "Heating"),
+            Self::Cooling => write!(f, "Cooling"),
+            Self::Ventilating => write!(f, "Ventilating"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heating_follows_ambient_temperature() {
+        let mut hvac = SmartHvac::new("AC_1");
+
+        assert!(hvac.set_power_state(SmartDevicePowerState::Enabled).is_ok());
+        assert!(hvac.set_mode(HvacMode::Heat).is_ok());
+        assert!(hvac.set_target_temperature(Celsius(22.0)).is_ok());
+
+        hvac.update_ambient_temperature(Celsius(18.0));
+        assert_eq!(hvac.get_action(), HvacAction::Heating);
+        assert_eq!(hvac.get_power_consumption(), HEATING_POWER + Watts(50.0));
+
+        // Внутри гистерезиса нагрев продолжается до заданной температуры
+        hvac.update_ambient_temperature(Celsius(21.8));
+        assert_eq!(hvac.get_action(), HvacAction::Heating);
+
+        hvac.update_ambient_temperature(Celsius(22.0));
+        assert_eq!(hvac.get_action(), HvacAction::Idle);
+        assert_eq!(hvac.get_power_consumption(), STANDBY_POWER);
+    }
+
+    #[test]
+    fn auto_mode_selects_cooling() {
+        let mut hvac = SmartHvac::new("AC_1");
+
+        assert!(hvac.set_power_state(SmartDevicePowerState::Enabled).is_ok());
+        assert!(hvac.set_mode(HvacMode::Auto).is_ok());
+
+        hvac.update_ambient_temperature(Celsius(27.0));
+        assert_eq!(hvac.get_action(), HvacAction::Cooling);
+
+        assert!(hvac
+            .set_power_state(SmartDevicePowerState::Disabled)
+            .is_ok());
+        assert!(!hvac.is_running());
+        assert_eq!(hvac.get_power_consumption(), Watts::default());
+    }
+}
@@ -0,0 +1,463 @@
+//! Модуль, содержащий реализацию устройства "Умный замок"
+//!
+//! > Умный замок - это устройство, которое запирает и отпирает дверь (как правило, входную)
+//! > по команде или по вводу пользовательского PIN-кода. Каждый PIN-код действует
+//! > только в пределах заданного окна времени.
+//! > Все попытки доступа записываются в журнал, из которого записи не удаляются.
+//! > В случае заклинивания механизма замок переходит в состояние "Ошибка"
+//! > и перестаёт выполнять команды.
+//!
+use super::command::{
+    execute_common, Capability, SmartDeviceCommand, SmartDeviceCommandError, SmartDeviceResponse,
+    SmartDeviceValue,
+};
+use super::metadata::{default_device_id, DeviceKind, DeviceMetadata};
+use super::smart_device::{
+    SmartDevice, SmartDeviceErrorCode, SmartDevicePowerState, SmartDeviceStatus,
+};
+use std::fmt::{self, Display};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Перечисление возможных положений запирающего механизма
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LockState {
+    /// Дверь заперта
+    Locked,
+    /// Дверь не заперта
+    Unlocked,
+}
+
+/// PIN-код пользователя с окном действия
+#[derive(Clone)]
+pub struct PinCode {
+    /// Имя пользователя, которому принадлежит код
+    pub user: String,
+
+    /// Значение кода
+    code: String,
+
+    /// Начало действия кода
+    pub valid_from: SystemTime,
+
+    /// Окончание действия кода (`None` - бессрочно)
+    pub valid_until: Option<SystemTime>,
+}
+
+impl PinCode {
+    /// Проверка, действует ли код в момент `moment`
+    pub fn is_valid_at(&self, moment: SystemTime) -> bool {
+        moment >= self.valid_from && self.valid_until.is_none_or(|until| moment < until)
+    }
+}
+
+/// Перечисление событий, фиксируемых в журнале доступа
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccessAction {
+    /// Запирание двери
+    Lock,
+    /// Отпирание двери
+    Unlock,
+    /// Заклинивание механизма
+    Jam,
+}
+
+/// Запись журнала доступа
+#[derive(Clone)]
+pub struct AccessLogEntry {
+    /// Момент события
+    pub timestamp: SystemTime,
+
+    /// Пользователь, инициировавший событие (`None` - команда без PIN-кода)
+    pub user: Option<String>,
+
+    /// Тип события
+    pub action: AccessAction,
+
+    /// Было ли действие выполнено
+    pub granted: bool,
+
+    /// Причина отказа (`None` - действие выполнено)
+    pub error: Option<SmartDeviceErrorCode>,
+}
+
+///
+/// Тип описывающий характеристики и поведение девайса "Умный замок"
+///
+pub struct SmartLock {
+    /// Пользовательский псевдоним для замка
+    pub name: String,
+
+    /// Метаданные замка (идентификатор, производитель, модель, версия прошивки)
+    metadata: DeviceMetadata,
+
+    /// Текущее положение запирающего механизма
+    lock_state: LockState,
+
+    /// Зарегистрированные PIN-коды пользователей
+    pin_codes: Vec<PinCode>,
+
+    /// Журнал доступа (только добавление записей)
+    access_log: Vec<AccessLogEntry>,
+
+    // Cтатус работы (ВКЛ,ВЫКЛ/ОШИБКА)
+    status: SmartDeviceStatus,
+}
+
+impl SmartLock {
+    /// Создание экземпляра замка с псевдонимом `name`
+    ///
+    /// По умолчанию замок выключен и заперт, PIN-коды не заданы
+    ///
+    /// ## Пример
+    /// ```ignore
+    /// let my_lock = SmartLock::new("FrontDoor");
+    /// ```
+    ///
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            metadata: DeviceMetadata::new(
+                DeviceKind::Lock,
+                "SmartLock",
+                &default_device_id(DeviceKind::Lock, name),
+            ),
+            lock_state: LockState::Locked,
+            pin_codes: Vec::new(),
+            access_log: Vec::new(),
+            status: SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled),
+        }
+    }
+
+    /// Установка идентификатора замка `id` вместо идентификатора по умолчанию
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.metadata = self.metadata.with_id(id);
+        self
+    }
+
+    /// Получение текущего положения запирающего механизма
+    pub fn get_lock_state(&self) -> LockState {
+        self.lock_state
+    }
+
+    /// Регистрация PIN-кода `code` для пользователя `user`
+    ///
+    /// Ранее заданный код пользователя заменяется новым
+    pub fn add_pin_code(
+        &mut self,
+        user: &str,
+        code: &str,
+        valid_from: SystemTime,
+        valid_until: Option<SystemTime>,
+    ) {
+        self.remove_pin_code(user);
+        self.pin_codes.push(PinCode {
+            user: user.to_string(),
+            code: code.to_string(),
+            valid_from,
+            valid_until,
+        });
+    }
+
+    /// Удаление PIN-кода пользователя `user`
+    pub fn remove_pin_code(&mut self, user: &str) -> bool {
+        let count = self.pin_codes.len();
+        self.pin_codes.retain(|pin| pin.user != user);
+        count != self.pin_codes.len()
+    }
+
+    /// Получение списка зарегистрированных PIN-кодов
+    pub fn get_pin_codes(&self) -> &[PinCode] {
+        &self.pin_codes
+    }
+
+    /// Получение журнала доступа
+    pub fn get_access_log(&self) -> &[AccessLogEntry] {
+        &self.access_log
+    }
+
+    /// Запирание двери по команде
+    pub fn lock(&mut self) -> Result<(), SmartDeviceErrorCode> {
+        self.perform(None, AccessAction::Lock)
+    }
+
+    /// Отпирание двери по команде
+    pub fn unlock(&mut self) -> Result<(), SmartDeviceErrorCode> {
+        self.perform(None, AccessAction::Unlock)
+    }
+
+    /// Отпирание двери по PIN-коду
+    ///
+    /// Код должен быть зарегистрирован и действовать в текущий момент времени.
+    /// Выключенный или неисправный замок возвращает свою ошибку (а не отказ в доступе);
+    /// каждая попытка записывается в журнал вместе с результатом
+    pub fn unlock_with_pin(&mut self, code: &str) -> Result<(), SmartDeviceErrorCode> {
+        let now = SystemTime::now();
+        let user = self
+            .pin_codes
+            .iter()
+            .find(|pin| pin.code == code && pin.is_valid_at(now))
+            .map(|pin| pin.user.clone());
+
+        match user {
+            Some(user) => self.perform(Some(user), AccessAction::Unlock),
+            None => {
+                let result = self
+                    .check_ready()
+                    .and(Err(SmartDeviceErrorCode::AccessDenied));
+                self.record(None, AccessAction::Unlock, &result);
+                result
+            }
+        }
+    }
+
+    /// Сообщение о заклинивании запирающего механизма
+    pub fn report_jam(&mut self) {
+        self.record(None, AccessAction::Jam, &Err(SmartDeviceErrorCode::Jammed));
+        self.status = SmartDeviceStatus::Malfunction(SmartDeviceErrorCode::Jammed);
+    }
+
+    /// Сброс состояния ошибки
+    ///
+    /// После сброса устройство выключено
+    pub fn reset_fault(&mut self) {
+        if let SmartDeviceStatus::Malfunction(_) = self.status {
+            self.status = SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled);
+        }
+    }
+
+    /// Проверка готовности замка к работе (включён и исправен)
+    fn check_ready(&self) -> Result<(), SmartDeviceErrorCode> {
+        match &self.status {
+            SmartDeviceStatus::PowerState(SmartDevicePowerState::Enabled) => Ok(()),
+            SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled) => {
+                Err(SmartDeviceErrorCode::PoweredOff)
+            }
+            SmartDeviceStatus::Malfunction(y) => Err(y.clone()),
+        }
+    }
+
+    /// Выполнение действия с механизмом и запись результата в журнал
+    fn perform(
+        &mut self,
+        user: Option<String>,
+        action: AccessAction,
+    ) -> Result<(), SmartDeviceErrorCode> {
+        let result = self.check_ready();
+
+        if result.is_ok() {
+            self.lock_state = match action {
+                AccessAction::Unlock => LockState::Unlocked,
+                _ => LockState::Locked,
+            };
+        }
+
+        self.record(user, action, &result);
+        result
+    }
+
+    /// Добавление записи в журнал доступа
+    fn record(
+        &mut self,
+        user: Option<String>,
+        action: AccessAction,
+        result: &Result<(), SmartDeviceErrorCode>,
+    ) {
+        self.access_log.push(AccessLogEntry {
+            timestamp: SystemTime::now(),
+            user,
+            action,
+            granted: result.is_ok(),
+            error: result.clone().err(),
+        });
+    }
+}
+
+impl SmartDevice for SmartLock {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    fn get_metadata(&self) -> &DeviceMetadata {
+        &self.metadata
+    }
+
+    fn set_power_state(
+        &mut self,
+        state: SmartDevicePowerState,
+    ) -> Result<(), SmartDeviceErrorCode> {
+        match &self.status {
+            SmartDeviceStatus::PowerState(_) => {
+                self.status = SmartDeviceStatus::PowerState(state);
+                Ok(())
+            }
+            SmartDeviceStatus::Malfunction(y) => {
+                println!("Cannot perform the operation due to: {}", y);
+                Err((*y).clone())
+            }
+        }
+    }
+
+    fn get_device_status(&self) -> SmartDeviceStatus {
+        self.status.clone()
+    }
+
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![
+            Capability::PowerControl,
+            Capability::Locking,
+            Capability::FaultReset,
+        ]
+    }
+
+    fn execute(
+        &mut self,
+        command: SmartDeviceCommand,
+    ) -> Result<SmartDeviceResponse, SmartDeviceCommandError> {
+        match command {
+            SmartDeviceCommand::Lock => self.lock()?,
+            SmartDeviceCommand::Unlock => self.unlock()?,
+            SmartDeviceCommand::UnlockWithPin(code) => self.unlock_with_pin(&code)?,
+            SmartDeviceCommand::ResetFault => self.reset_fault(),
+            SmartDeviceCommand::Read(Capability::Locking) => {
+                return Ok(SmartDeviceResponse::Value(SmartDeviceValue::LockState(
+                    self.lock_state,
+                )))
+            }
+            other => return execute_common(self, other),
+        }
+        Ok(SmartDeviceResponse::Done)
+    }
+
+    fn get_text_report(&self) -> String {
+        let mut report = format!(
+            "Current lock state is {}, status: {}\n",
+            self.lock_state, self.status
+        );
+
+        for entry in self.access_log.iter() {
+            report.push_str(&format!("  {}\n", entry));
+        }
+        report
+    }
+}
+
+impl Display for LockState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Locked => write!(f, "Locked"),
+            Self::Unlocked => write!(f, "Unlocked"),
+        }
+    }
+}
+
+impl Display for AccessAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Lock => write!(f, "lock"),
+            Self::Unlock => write!(f, "unlock"),
+            Self::Jam => write!(f, "jam"),
+        }
+    }
+}
+
+impl Display for AccessLogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let seconds = self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        write!(
+            f,
+            "[{}] {} by {}: {}",
+            seconds,
+            self.action,
+            self.user.as_deref().unwrap_or("command"),
+            if self.granted { "granted" } else { "denied" }
+        )?;
+        match &self.error {
+            Some(error) => write!(f, " ({})", error),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn pin_code_unlocks_within_window() {
+        let mut lock = SmartLock::new("FrontDoor");
+        let now = SystemTime::now();
+
+        lock.add_pin_code("alice", "1234", now - Duration::from_secs(60), None);
+        lock.add_pin_code(
+            "bob",
+            "0000",
+            now - Duration::from_secs(120),
+            Some(now - Duration::from_secs(60)),
+        );
+        assert!(lock.set_power_state(SmartDevicePowerState::Enabled).is_ok());
+
+        assert!(matches!(
+            lock.unlock_with_pin("0000"),
+            Err(SmartDeviceErrorCode::AccessDenied)
+        ));
+        assert_eq!(lock.get_lock_state(), LockState::Locked);
+
+        assert!(lock.unlock_with_pin("1234").is_ok());
+        assert_eq!(lock.get_lock_state(), LockState::Unlocked);
+
+        let log = lock.get_access_log();
+        assert_eq!(log.len(), 2);
+        assert!(!log[0].granted);
+        assert_eq!(log[1].user.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn jammed_lock_rejects_commands() {
+        let mut lock = SmartLock::new("FrontDoor");
+
+        assert!(lock.set_power_state(SmartDevicePowerState::Enabled).is_ok());
+        lock.report_jam();
+
+        assert!(matches!(lock.unlock(), Err(SmartDeviceErrorCode::Jammed)));
+        assert_eq!(lock.get_lock_state(), LockState::Locked);
+        assert_eq!(lock.get_access_log().len(), 2);
+
+        // Неверный PIN-код не маскирует неисправность, но попытка попадает в журнал
+        assert!(matches!(
+            lock.unlock_with_pin("9999"),
+            Err(SmartDeviceErrorCode::Jammed)
+        ));
+        let log = lock.get_access_log();
+        assert_eq!(log.len(), 3);
+        assert!(!log[2].granted);
+        assert_eq!(log[2].error, Some(SmartDeviceErrorCode::Jammed));
+    }
+
+    #[test]
+    fn attempts_on_powered_off_lock_are_logged() {
+        let mut lock = SmartLock::new("FrontDoor");
+        lock.add_pin_code("alice", "1234", SystemTime::UNIX_EPOCH, None);
+
+        assert!(matches!(
+            lock.unlock_with_pin("1234"),
+            Err(SmartDeviceErrorCode::PoweredOff)
+        ));
+        let log = lock.get_access_log();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].user.as_deref(), Some("alice"));
+        assert_eq!(log[0].error, Some(SmartDeviceErrorCode::PoweredOff));
+        assert!(log[0]
+            .to_string()
+            .ends_with("denied (Device is powered off.)"));
+    }
+}
@@ -0,0 +1,188 @@
+//! Модуль содержит описание метаданных "Умных" устройств
+//!
+//! > Метаданные позволяют устройству описать себя: тип, производителя, модель,
+//! > версию прошивки и идентификатор, не зависящий от пользовательского имени.
+//! > Идентификатор задаётся при создании устройства (по умолчанию - из типа и исходного
+//! > имени, см. [`default_device_id`]) и не меняется ни при переименовании, ни при
+//! > перезапуске программы, поэтому на него можно ссылаться в метках и журнале аудита.
+//!
+use super::command::Capability;
+use crate::units::{Celsius, Percent, Watts};
+use std::fmt::{self, Display};
+
+/// Производитель встроенных устройств библиотеки
+pub const DEFAULT_VENDOR: &str = "OTUS";
+
+/// Alias для уникального идентификатора устройства
+pub type DeviceId = String;
+
+/// Перечисление типов умных устройств
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DeviceKind {
+    /// Умная розетка
+    Socket,
+    /// Умный термометр
+    Thermometer,
+    /// Умные жалюзи
+    Blinds,
+    /// Умный замок
+    Lock,
+    /// Умный кондиционер
+    Hvac,
+    /// Устройство, не относящееся к встроенным типам
+    Other,
+}
+
+/// Тип, описывающий метаданные умного устройства
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceMetadata {
+    /// Идентификатор устройства (неизменяемый)
+    id: DeviceId,
+
+    /// Признак идентификатора, заданного явно через [`DeviceMetadata::with_id`]
+    explicit_id: bool,
+
+    /// Тип устройства
+    pub kind: DeviceKind,
+
+    /// Производитель
+    pub vendor: String,
+
+    /// Модель
+    pub model: String,
+
+    /// Версия прошивки
+    pub firmware_version: String,
+}
+
+impl DeviceMetadata {
+    /// Создание метаданных встроенного устройства типа `kind` модели `model`
+    /// с идентификатором `id`
+    pub fn new(kind: DeviceKind, model: &str, id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            explicit_id: false,
+            kind,
+            vendor: DEFAULT_VENDOR.to_string(),
+            model: model.to_string(),
+            firmware_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// Замена идентификатора устройства на `id`
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = id.to_string();
+        self.explicit_id = true;
+        self
+    }
+
+    /// Получение идентификатора устройства
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    /// Проверка, задан ли идентификатор явно (а не получен по умолчанию)
+    pub fn has_explicit_id(&self) -> bool {
+        self.explicit_id
+    }
+
+    /// Проверка, конфликтует ли идентификатор устройства с идентификатором `other`
+    ///
+    /// Уникальность требуется только от явно заданных идентификаторов: совпадение
+    /// идентификаторов по умолчанию (одноимённые устройства в разных комнатах) допустимо
+    pub fn conflicts_with(&self, other: &DeviceMetadata) -> bool {
+        self.id == other.id && (self.explicit_id || other.explicit_id)
+    }
+}
+
+/// Идентификатор устройства типа `kind` с именем `name` по умолчанию (`<kind>-<name>`)
+///
+/// Одноимённые устройства одного типа в разных комнатах получают одинаковые
+/// идентификаторы по умолчанию; чтобы адресовать их метками и группами по отдельности,
+/// идентификаторы следует задать явно
+pub fn default_device_id(kind: DeviceKind, name: &str) -> DeviceId {
+    format!("{}-{}", kind, name)
+}
+
+impl Capability {
+    /// Получение единицы измерения значений, относящихся к возможности
+    ///
+    /// Для возможностей без числовых показаний возвращается `None`
+    pub fn unit(&self) -> Option<&'static str> {
+        match self {
+            Self::Temperature | Self::Setpoint => Some(Celsius::SYMBOL),
+            Self::PowerMetering => Some(Watts::SYMBOL),
+            Self::Position => Some(Percent::SYMBOL),
+            _ => None,
+        }
+    }
+}
+
+impl Display for DeviceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Socket => write!(f, "socket"),
+            Self::Thermometer => write!(f, "thermometer"),
+            Self::Blinds => write!(f, "blinds"),
+            Self::Lock => write!(f, "lock"),
+            Self::Hvac => write!(f, "hvac"),
+            Self::Other => write!(f, "other"),
+        }
+    }
+}
+
+impl Display for DeviceMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} v{} (id {})",
+            self.kind, self.vendor, self.model, self.firmware_version, self.id
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room::Room;
+    use crate::smart_device::SmartDevice;
+    use crate::socket::SmartSocket;
+    use crate::thermometer::SmartThermometer;
+
+    #[test]
+    fn device_ids_are_stable_and_independent_of_name() {
+        let mut first = SmartSocket::new("Socket");
+        let second = SmartSocket::new("Socket").with_id("socket-hall");
+
+        // Идентификатор воспроизводим между запусками и не зависит от переименования
+        assert_eq!(first.get_metadata().get_id(), "socket-Socket");
+        first.set_name("Lamp");
+        assert_eq!(first.get_metadata().get_id(), "socket-Socket");
+        assert_eq!(second.get_metadata().get_id(), "socket-hall");
+        assert_eq!(first.get_metadata().kind, DeviceKind::Socket);
+        assert_eq!(first.get_metadata().vendor, DEFAULT_VENDOR);
+    }
+
+    #[test]
+    fn room_is_queried_by_metadata() {
+        let socket = SmartSocket::new("Lamp");
+        let thermometer = SmartThermometer::new("Sensor");
+        let thermometer_id = thermometer.get_metadata().get_id().to_string();
+
+        let mut room = Room::new("Kitchen", 2);
+        assert!(room.add_device(Box::new(socket)).is_ok());
+        assert!(room.add_device(Box::new(thermometer)).is_ok());
+
+        assert_eq!(room.find_devices_by_kind(DeviceKind::Socket), vec!["Lamp"]);
+        assert_eq!(
+            room.find_devices_by_capability(Capability::Temperature),
+            vec!["Sensor"]
+        );
+        assert_eq!(
+            room.get_device_by_id(&thermometer_id)
+                .map(|device| device.get_name()),
+            Some("Sensor")
+        );
+        assert_eq!(Capability::Temperature.unit(), Some("°C"));
+    }
+}
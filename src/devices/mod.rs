@@ -1,3 +1,9 @@
+pub mod blinds;
+pub mod command;
+pub mod hvac;
+pub mod lock;
+pub mod metadata;
+pub mod pipeline;
 pub mod smart_device;
 pub mod socket;
 pub mod thermometer;
@@ -0,0 +1,285 @@
+//! Модуль содержит конвейер обработки показаний датчиков умных устройств
+//!
+//! > Конвейер состоит из последовательности стадий: калибровки, отбраковки выбросов,
+//! > сглаживания (скользящим средним или экспоненциальным) и ограничения скорости изменения.
+//! > Каждое новое "сырое" показание последовательно проходит все стадии,
+//! > при этом конвейер хранит как последнее сырое, так и последнее отфильтрованное значение.
+//!
+use std::collections::VecDeque;
+use std::time::Instant;
+
+/// Перечисление стадий обработки показаний
+#[derive(Clone, Debug, PartialEq)]
+pub enum FilterStage {
+    /// Калибровка: `value * gain + offset`
+    Calibration {
+        /// Смещение
+        offset: f32,
+        /// Коэффициент усиления
+        gain: f32,
+    },
+
+    /// Отбраковка показаний, отличающихся от предыдущего принятого значения больше чем на `max_deviation`
+    ///
+    /// После `max_rejections` отбракованных подряд показаний новое значение принимается
+    /// (считается, что изменение реальное)
+    OutlierRejection {
+        /// Максимально допустимое отклонение
+        max_deviation: f32,
+        /// Максимальное количество отбракованных подряд показаний
+        max_rejections: usize,
+    },
+
+    /// Скользящее среднее по последним `window` показаниям
+    MovingAverage {
+        /// Размер окна усреднения
+        window: usize,
+    },
+
+    /// Экспоненциальное сглаживание с коэффициентом `alpha` (от `0.0` до `1.0`)
+    ExponentialSmoothing {
+        /// Вес нового показания
+        alpha: f32,
+    },
+
+    /// Ограничение скорости изменения значения (единиц в секунду)
+    ///
+    /// Отрицательная скорость берётся по модулю, `NaN` отключает ограничение
+    RateLimit {
+        /// Максимальная скорость изменения
+        max_rate: f32,
+    },
+}
+
+/// Состояние стадии обработки между показаниями
+#[derive(Clone, Default)]
+struct StageState {
+    /// Последнее значение на выходе стадии
+    last_output: Option<f32>,
+    /// Количество отбракованных подряд показаний
+    rejections: usize,
+    /// Окно показаний для скользящего среднего
+    window: VecDeque<f32>,
+}
+
+/// Тип, описывающий конвейер обработки показаний датчика
+#[derive(Clone, Default)]
+pub struct ReadingPipeline {
+    /// Стадии обработки
+    stages: Vec<(FilterStage, StageState)>,
+
+    /// Последнее необработанное показание
+    raw: Option<f32>,
+
+    /// Последнее обработанное показание
+    filtered: Option<f32>,
+
+    /// Момент получения последнего показания
+    updated_at: Option<Instant>,
+}
+
+impl ReadingPipeline {
+    /// Создание пустого конвейера (показания проходят без изменений)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Добавление стадии обработки в конец конвейера
+    ///
+    /// ## Пример
+    /// ```ignore
+    /// let pipeline = ReadingPipeline::new()
+    ///     .with_stage(FilterStage::Calibration { offset: -0.5, gain: 1.0 })
+    ///     .with_stage(FilterStage::MovingAverage { window: 5 });
+    /// ```
+    pub fn with_stage(mut self, stage: FilterStage) -> Self {
+        self.stages.push((stage, StageState::default()));
+        self
+    }
+
+    /// Получение списка стадий обработки
+    pub fn get_stages(&self) -> Vec<FilterStage> {
+        self.stages.iter().map(|(stage, _)| stage.clone()).collect()
+    }
+
+    /// Получение последнего необработанного показания
+    pub fn get_raw(&self) -> Option<f32> {
+        self.raw
+    }
+
+    /// Получение последнего обработанного показания
+    pub fn get_filtered(&self) -> Option<f32> {
+        self.filtered
+    }
+
+    /// Сброс накопленного состояния всех стадий
+    pub fn reset(&mut self) {
+        for (_, state) in self.stages.iter_mut() {
+            *state = StageState::default();
+        }
+        self.raw = None;
+        self.filtered = None;
+        self.updated_at = None;
+    }
+
+    /// Обработка нового показания, полученного в текущий момент
+    ///
+    /// Нечисловые и бесконечные показания отбрасываются, в этом случае возвращается `None`
+    pub fn process(&mut self, raw: f32) -> Option<f32> {
+        self.process_at(raw, Instant::now())
+    }
+
+    /// Обработка нового показания, полученного в момент `at`
+    ///
+    /// Нечисловые и бесконечные показания отбрасываются до первой стадии и не меняют
+    /// состояние конвейера, в этом случае возвращается `None`
+    pub fn process_at(&mut self, raw: f32, at: Instant) -> Option<f32> {
+        if !raw.is_finite() {
+            return None;
+        }
+
+        let elapsed = self
+            .updated_at
+            .map(|updated_at| at.saturating_duration_since(updated_at).as_secs_f32());
+
+        let mut value = raw;
+        for (stage, state) in self.stages.iter_mut() {
+            value = Self::apply(stage, state, value, elapsed);
+            state.last_output = Some(value);
+        }
+
+        self.raw = Some(raw);
+        self.filtered = Some(value);
+        self.updated_at = Some(at);
+        Some(value)
+    }
+
+    /// Обработка значения одной стадией
+    fn apply(stage: &FilterStage, state: &mut StageState, value: f32, elapsed: Option<f32>) -> f32 {
+        match *stage {
+            FilterStage::Calibration { offset, gain } => value * gain + offset,
+            FilterStage::OutlierRejection {
+                max_deviation,
+                max_rejections,
+            } => match state.last_output {
+                Some(last)
+                    if (value - last).abs() > max_deviation
+                        && state.rejections < max_rejections =>
+                {
+                    state.rejections += 1;
+                    last
+                }
+                _ => {
+                    state.rejections = 0;
+                    value
+                }
+            },
+            FilterStage::MovingAverage { window } => {
+                state.window.push_back(value);
+                while state.window.len() > window.max(1) {
+                    state.window.pop_front();
+                }
+                state.window.iter().sum::<f32>() / state.window.len() as f32
+            }
+            FilterStage::ExponentialSmoothing { alpha } => match state.last_output {
+                Some(last) => last + alpha.clamp(0.0, 1.0) * (value - last),
+                None => value,
+            },
+            FilterStage::RateLimit { max_rate } => match (state.last_output, elapsed) {
+                (Some(last), Some(elapsed)) => {
+                    // `max`/`min` (в отличие от `clamp`) не паникуют на `NaN` в границах
+                    let max_change = max_rate.abs() * elapsed;
+                    value.max(last - max_change).min(last + max_change)
+                }
+                _ => value,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn calibration_and_moving_average() {
+        let mut pipeline = ReadingPipeline::new()
+            .with_stage(FilterStage::Calibration {
+                offset: 1.0,
+                gain: 2.0,
+            })
+            .with_stage(FilterStage::MovingAverage { window: 2 });
+
+        assert_eq!(pipeline.process(1.0), Some(3.0));
+        assert_eq!(pipeline.process(2.0), Some(4.0));
+        assert_eq!(pipeline.process(3.0), Some(6.0));
+        assert_eq!(pipeline.get_raw(), Some(3.0));
+        assert_eq!(pipeline.get_filtered(), Some(6.0));
+    }
+
+    #[test]
+    fn outliers_are_rejected_until_confirmed() {
+        let mut pipeline = ReadingPipeline::new().with_stage(FilterStage::OutlierRejection {
+            max_deviation: 5.0,
+            max_rejections: 1,
+        });
+
+        assert_eq!(pipeline.process(20.0), Some(20.0));
+        assert_eq!(pipeline.process(80.0), Some(20.0));
+        assert_eq!(pipeline.process(80.0), Some(80.0));
+    }
+
+    #[test]
+    fn rate_of_change_is_limited() {
+        let start = Instant::now();
+        let mut pipeline =
+            ReadingPipeline::new().with_stage(FilterStage::RateLimit { max_rate: 1.0 });
+
+        assert_eq!(pipeline.process_at(20.0, start), Some(20.0));
+        assert_eq!(
+            pipeline.process_at(30.0, start + Duration::from_secs(2)),
+            Some(22.0)
+        );
+        assert_eq!(
+            pipeline.process_at(10.0, start + Duration::from_secs(3)),
+            Some(21.0)
+        );
+    }
+
+    #[test]
+    fn invalid_rate_limit_does_not_panic() {
+        let start = Instant::now();
+        let later = start + Duration::from_secs(2);
+
+        let mut negative =
+            ReadingPipeline::new().with_stage(FilterStage::RateLimit { max_rate: -1.0 });
+        assert_eq!(negative.process_at(20.0, start), Some(20.0));
+        assert_eq!(negative.process_at(30.0, later), Some(22.0));
+
+        let mut nan =
+            ReadingPipeline::new().with_stage(FilterStage::RateLimit { max_rate: f32::NAN });
+        assert_eq!(nan.process_at(20.0, start), Some(20.0));
+        assert_eq!(nan.process_at(30.0, later), Some(30.0));
+
+        let mut infinite = ReadingPipeline::new().with_stage(FilterStage::RateLimit {
+            max_rate: f32::INFINITY,
+        });
+        assert_eq!(infinite.process_at(20.0, start), Some(20.0));
+        assert_eq!(infinite.process_at(30.0, start), Some(30.0));
+    }
+
+    #[test]
+    fn non_finite_readings_are_dropped() {
+        let mut pipeline = ReadingPipeline::new()
+            .with_stage(FilterStage::MovingAverage { window: 2 })
+            .with_stage(FilterStage::ExponentialSmoothing { alpha: 0.5 });
+
+        assert_eq!(pipeline.process(10.0), Some(10.0));
+        assert_eq!(pipeline.process(f32::NAN), None);
+        assert_eq!(pipeline.process(f32::INFINITY), None);
+        assert_eq!(pipeline.get_raw(), Some(10.0));
+        assert_eq!(pipeline.get_filtered(), Some(10.0));
+        assert_eq!(pipeline.process(10.0), Some(10.0));
+    }
+}
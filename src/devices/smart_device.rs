@@ -7,14 +7,14 @@
 
 //TODO: SmartDeviceStatus from PowerState & Malfunction
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum SmartDeviceStatus {
     /// Состояние питания умного устройства
     PowerState(SmartDevicePowerState),
     /// Возможные ошибки в работе умного устройства
     Malfunction(SmartDeviceErrorCode),
 }
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum SmartDeviceErrorCode {
     /// Ошибка: перегрузка по току
     Overcurrent,
@@ -27,10 +27,40 @@ pub enum SmartDeviceErrorCode {
 
     /// Ошибка: слишком низкая температура
     Underheat,
+
+    /// Ошибка: препятствие на пути движения привода
+    Obstruction,
+
+    /// Ошибка: команда не может быть выполнена, т.к. устройство выключено
+    PoweredOff,
+
+    /// Ошибка: заклинивание механизма
+    Jammed,
+
+    /// Ошибка: доступ запрещён (неверный или просроченный код доступа)
+    AccessDenied,
+
+    /// Ошибка: нет связи с удалённым устройством
+    Unreachable,
+}
+
+impl SmartDeviceErrorCode {
+    /// Проверка, является ли ошибка неисправностью оборудования
+    ///
+    /// Отказ в доступе, команда выключенному устройству и потеря связи с удалённым
+    /// устройством неисправностями не считаются
+    pub fn is_fault(&self) -> bool {
+        !matches!(
+            self,
+            SmartDeviceErrorCode::PoweredOff
+                | SmartDeviceErrorCode::AccessDenied
+                | SmartDeviceErrorCode::Unreachable
+        )
+    }
 }
 
 /// Перечисление возможных состояний питания умного устройства
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum SmartDevicePowerState {
     /// Устройство включено
     Enabled,
@@ -38,8 +68,16 @@ pub enum SmartDevicePowerState {
     Disabled,
 }
 
+use super::command::{
+    execute_common, Capability, SmartDeviceCommand, SmartDeviceCommandError, SmartDeviceResponse,
+};
+use super::metadata::DeviceMetadata;
+use crate::units::{Celsius, TemperatureScale, Watts};
+
 /// SmartDevice trait, определяющий общий функционал для "Умных" устройств
-pub trait SmartDevice {
+///
+/// Устройства должны быть `Send + Sync`, чтобы дом можно было разделять между потоками
+pub trait SmartDevice: Send + Sync {
     /// Получение текущего статуса работы устройства
     fn get_device_status(&self) -> SmartDeviceStatus;
 
@@ -52,8 +90,71 @@ pub trait SmartDevice {
     /// Получение текстовой информации о состоянии устройства
     fn get_text_report(&self) -> String;
 
+    /// Получение текстовой информации о состоянии устройства с температурами в шкале `scale`
+    ///
+    /// Устройства, не сообщающие температуру, возвращают [`SmartDevice::get_text_report`]
+    fn get_text_report_in(&self, _scale: TemperatureScale) -> String {
+        self.get_text_report()
+    }
+
     /// Получение имени устройства
     fn get_name(&self) -> &str;
+
+    /// Изменение имени устройства
+    ///
+    /// Имя является ключом устройства в комнате, поэтому для переименования устройства,
+    /// уже добавленного в комнату, следует использовать операции контейнера
+    fn set_name(&mut self, name: &str);
+
+    /// Получение метаданных устройства (тип, производитель, модель, версия прошивки, идентификатор)
+    fn get_metadata(&self) -> &DeviceMetadata;
+
+    /// Получение температуры окружающей среды, измеренной устройством
+    ///
+    /// Устройства, не измеряющие температуру, возвращают `None`
+    fn get_temperature(&self) -> Option<Celsius> {
+        None
+    }
+
+    /// Получение мощности, потребляемой устройством
+    fn get_power_consumption(&self) -> Watts {
+        Watts::default()
+    }
+
+    /// Получение текущих числовых показаний устройства
+    ///
+    /// По умолчанию возвращаются температура и потребляемая мощность,
+    /// если устройство поддерживает соответствующие возможности
+    fn get_readings(&self) -> Vec<(Capability, f32)> {
+        let mut readings = Vec::new();
+        if let Some(temperature) = self.get_temperature() {
+            readings.push((Capability::Temperature, temperature.0));
+        }
+        if self.get_capabilities().contains(&Capability::PowerMetering) {
+            readings.push((Capability::PowerMetering, self.get_power_consumption().0));
+        }
+        readings
+    }
+
+    /// Передача устройству текущей температуры в помещении
+    ///
+    /// Используется устройствами, работа которых зависит от температуры (например, кондиционерами)
+    fn update_ambient_temperature(&mut self, _temperature: Celsius) {}
+
+    /// Получение списка возможностей, поддерживаемых устройством
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![Capability::PowerControl]
+    }
+
+    /// Выполнение универсальной команды
+    ///
+    /// Для команд, не поддерживаемых устройством, возвращается [`SmartDeviceCommandError::Unsupported`]
+    fn execute(
+        &mut self,
+        command: SmartDeviceCommand,
+    ) -> Result<SmartDeviceResponse, SmartDeviceCommandError> {
+        execute_common(self, command)
+    }
 }
 
 use std::fmt::{self, Display};
@@ -73,6 +174,11 @@ impl Display for SmartDeviceErrorCode {
             Self::Overcurrent => write!(f, "Overcurrent error."),
             Self::Overheat => write!(f, "Overheat error."),
             Self::Overvoltage => write!(f, "Overvoltage error."),
+            Self::Obstruction => write!(f, "Obstruction error."),
+            Self::PoweredOff => write!(f, "Device is powered off."),
+            Self::Jammed => write!(f, "Jammed mechanism error."),
+            Self::AccessDenied => write!(f, "Access denied."),
+            Self::Unreachable => write!(f, "Device is unreachable."),
         }
     }
 }
@@ -7,9 +7,15 @@
 //! > в состояние "Ошибка" и перестаёт выполнять команды на включение/выключение.
 //!
 //!
+use super::command::{
+    execute_common, Capability, SmartDeviceCommand, SmartDeviceCommandError, SmartDeviceResponse,
+};
+use super::metadata::{default_device_id, DeviceKind, DeviceMetadata};
+use super::pipeline::ReadingPipeline;
 use super::smart_device::{
     SmartDevice, SmartDeviceErrorCode, SmartDevicePowerState, SmartDeviceStatus,
 };
+use crate::units::Watts;
 
 ///
 /// Тип описывающий характеристики и поведение девайса "Умная розетка"
@@ -18,8 +24,17 @@ pub struct SmartSocket {
     /// Пользовательский псевдоним для розетки
     pub name: String,
 
-    /// Текущая мощность (Вт), потребляемая подключёнными к розетке устройствами
-    power_consumption: f32,
+    /// Метаданные розетки (идентификатор, производитель, модель, версия прошивки)
+    metadata: DeviceMetadata,
+
+    /// Текущая мощность, потребляемая подключёнными к розетке устройствами (после обработки конвейером)
+    power_consumption: Watts,
+
+    /// Последнее необработанное показание измерителя мощности
+    raw_power_consumption: Watts,
+
+    /// Конвейер обработки показаний измерителя мощности
+    pipeline: ReadingPipeline,
 
     // Cтатус работы (ВКЛ,ВЫКЛ/ОШИБКА)
     status: SmartDeviceStatus,
@@ -38,12 +53,68 @@ impl SmartSocket {
     pub fn new(name: &str) -> Self {
         Self {
             name: name.to_string(),
-            power_consumption: 0.0,
+            metadata: DeviceMetadata::new(
+                DeviceKind::Socket,
+                "SmartSocket",
+                &default_device_id(DeviceKind::Socket, name),
+            ),
+            power_consumption: Watts::default(),
+            raw_power_consumption: Watts::default(),
+            pipeline: ReadingPipeline::new(),
             status: SmartDeviceStatus::PowerState(
                 super::smart_device::SmartDevicePowerState::Disabled,
             ),
         }
     }
+
+    /// Установка идентификатора розетки `id` вместо идентификатора по умолчанию
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.metadata = self.metadata.with_id(id);
+        self
+    }
+
+    /// Обновление значения мощности, потребляемой подключёнными устройствами
+    ///
+    /// Показание проходит через конвейер обработки розетки; нечисловые и бесконечные
+    /// показания отбрасываются
+    pub fn set_power_consumption(&mut self, power_consumption: Watts) {
+        let Some(filtered) = self.pipeline.process(power_consumption.0) else {
+            return;
+        };
+        self.raw_power_consumption = power_consumption;
+        self.power_consumption = Watts(filtered);
+    }
+
+    /// Получение последнего необработанного показания измерителя мощности
+    pub fn get_raw_power_consumption(&self) -> Watts {
+        self.raw_power_consumption
+    }
+
+    /// Установка конвейера обработки показаний измерителя мощности
+    pub fn set_pipeline(&mut self, pipeline: ReadingPipeline) {
+        self.pipeline = pipeline;
+    }
+
+    /// Получение конвейера обработки показаний измерителя мощности
+    pub fn get_pipeline(&self) -> &ReadingPipeline {
+        &self.pipeline
+    }
+
+    /// Сообщение о неисправности `code`
+    ///
+    /// Неисправная розетка не выполняет команды до сброса ошибки
+    pub fn report_fault(&mut self, code: SmartDeviceErrorCode) {
+        self.status = SmartDeviceStatus::Malfunction(code);
+    }
+
+    /// Сброс состояния ошибки
+    ///
+    /// После сброса устройство выключено
+    pub fn reset_fault(&mut self) {
+        if let SmartDeviceStatus::Malfunction(_) = self.status {
+            self.status = SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled);
+        }
+    }
 }
 
 impl SmartDevice for SmartSocket {
@@ -51,6 +122,14 @@ impl SmartDevice for SmartSocket {
         &self.name
     }
 
+    fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    fn get_metadata(&self) -> &DeviceMetadata {
+        &self.metadata
+    }
+
     fn set_power_state(
         &mut self,
         state: SmartDevicePowerState,
@@ -71,6 +150,34 @@ impl SmartDevice for SmartSocket {
         self.status.clone()
     }
 
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![
+            Capability::PowerControl,
+            Capability::PowerMetering,
+            Capability::FaultReset,
+        ]
+    }
+
+    fn execute(
+        &mut self,
+        command: SmartDeviceCommand,
+    ) -> Result<SmartDeviceResponse, SmartDeviceCommandError> {
+        match command {
+            SmartDeviceCommand::ResetFault => {
+                self.reset_fault();
+                Ok(SmartDeviceResponse::Done)
+            }
+            other => execute_common(self, other),
+        }
+    }
+
+    fn get_power_consumption(&self) -> Watts {
+        match self.status {
+            SmartDeviceStatus::PowerState(SmartDevicePowerState::Enabled) => self.power_consumption,
+            _ => Watts::default(),
+        }
+    }
+
     fn get_text_report(&self) -> String {
         format!(
             "Current power consumption is {}, status: {} \n",
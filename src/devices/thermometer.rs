@@ -4,9 +4,15 @@
 //! > и может сообщить о ней пользователю.
 //! > В случае, если температура окружающей среды выходит за пределы нормы, умный термометр переходит в состояние ошибки.
 
+use super::command::{
+    execute_common, Capability, SmartDeviceCommand, SmartDeviceCommandError, SmartDeviceResponse,
+};
+use super::metadata::{default_device_id, DeviceKind, DeviceMetadata};
+use super::pipeline::ReadingPipeline;
 use super::smart_device::{
     SmartDevice, SmartDeviceErrorCode, SmartDevicePowerState, SmartDeviceStatus,
 };
+use crate::units::{Celsius, TemperatureScale};
 
 ///
 /// Тип описывающий характеристики и поведение девайса "Умный термометр"
@@ -15,8 +21,20 @@ pub struct SmartThermometer {
     /// Пользовательский псевдоним для термометра
     pub name: String,
 
-    /// Текущая температура окружающей среды(°С)
-    temperature: f32,
+    /// Метаданные термометра (идентификатор, производитель, модель, версия прошивки)
+    metadata: DeviceMetadata,
+
+    /// Текущая температура окружающей среды (после обработки конвейером)
+    temperature: Celsius,
+
+    /// Последнее необработанное показание датчика температуры
+    raw_temperature: Celsius,
+
+    /// Конвейер обработки показаний датчика температуры
+    pipeline: ReadingPipeline,
+
+    /// Допустимый диапазон температуры окружающей среды (нижняя и верхняя границы)
+    limits: Option<(Celsius, Celsius)>,
 
     // Cтатус работы (ВКЛ,ВЫКЛ/ОШИБКА)
     status: SmartDeviceStatus,
@@ -35,12 +53,94 @@ impl SmartThermometer {
     pub fn new(name: &str) -> Self {
         Self {
             name: name.to_string(),
-            temperature: 0.0,
+            metadata: DeviceMetadata::new(
+                DeviceKind::Thermometer,
+                "SmartThermometer",
+                &default_device_id(DeviceKind::Thermometer, name),
+            ),
+            temperature: Celsius::default(),
+            raw_temperature: Celsius::default(),
+            pipeline: ReadingPipeline::new(),
+            limits: None,
             status: SmartDeviceStatus::PowerState(
                 super::smart_device::SmartDevicePowerState::Disabled,
             ),
         }
     }
+
+    /// Установка идентификатора термометра `id` вместо идентификатора по умолчанию
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.metadata = self.metadata.with_id(id);
+        self
+    }
+
+    /// Установка допустимого диапазона температуры окружающей среды от `low` до `high`
+    ///
+    /// Включённый термометр, получивший показание за пределами диапазона, переходит
+    /// в состояние ошибки [`SmartDeviceErrorCode::Underheat`] или [`SmartDeviceErrorCode::Overheat`]
+    pub fn with_limits(mut self, low: Celsius, high: Celsius) -> Self {
+        self.limits = Some((low, high));
+        self
+    }
+
+    /// Получение допустимого диапазона температуры окружающей среды
+    pub fn get_limits(&self) -> Option<(Celsius, Celsius)> {
+        self.limits
+    }
+
+    /// Обновление значения температуры окружающей среды, полученного от датчика
+    ///
+    /// Показание проходит через конвейер обработки термометра; нечисловые и бесконечные
+    /// показания отбрасываются
+    pub fn set_temperature(&mut self, temperature: Celsius) {
+        let Some(filtered) = self.pipeline.process(temperature.0) else {
+            return;
+        };
+        self.raw_temperature = temperature;
+        self.temperature = Celsius(filtered);
+
+        let enabled = self.status == SmartDeviceStatus::PowerState(SmartDevicePowerState::Enabled);
+        match self.limits {
+            Some((low, _)) if enabled && self.temperature < low => {
+                self.report_fault(SmartDeviceErrorCode::Underheat)
+            }
+            Some((_, high)) if enabled && self.temperature > high => {
+                self.report_fault(SmartDeviceErrorCode::Overheat)
+            }
+            _ => {}
+        }
+    }
+
+    /// Получение последнего необработанного показания датчика температуры
+    pub fn get_raw_temperature(&self) -> Celsius {
+        self.raw_temperature
+    }
+
+    /// Установка конвейера обработки показаний датчика температуры
+    pub fn set_pipeline(&mut self, pipeline: ReadingPipeline) {
+        self.pipeline = pipeline;
+    }
+
+    /// Получение конвейера обработки показаний датчика температуры
+    pub fn get_pipeline(&self) -> &ReadingPipeline {
+        &self.pipeline
+    }
+
+    /// Сообщение о неисправности `code`
+    ///
+    /// Неисправный термометр не выполняет команды до сброса ошибки
+    pub fn report_fault(&mut self, code: SmartDeviceErrorCode) {
+        self.status = SmartDeviceStatus::Malfunction(code);
+    }
+
+    /// Сброс состояния ошибки
+    ///
+    /// После сброса устройство выключено
+    pub fn reset_fault(&mut self) {
+        if let SmartDeviceStatus::Malfunction(_) = self.status {
+            self.status = SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled);
+        }
+    }
 }
 
 impl SmartDevice for SmartThermometer {
@@ -48,6 +148,14 @@ impl SmartDevice for SmartThermometer {
         &self.name
     }
 
+    fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    fn get_metadata(&self) -> &DeviceMetadata {
+        &self.metadata
+    }
+
     fn set_power_state(
         &mut self,
         state: SmartDevicePowerState,
@@ -68,10 +176,43 @@ impl SmartDevice for SmartThermometer {
         self.status.clone()
     }
 
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![
+            Capability::PowerControl,
+            Capability::Temperature,
+            Capability::FaultReset,
+        ]
+    }
+
+    fn execute(
+        &mut self,
+        command: SmartDeviceCommand,
+    ) -> Result<SmartDeviceResponse, SmartDeviceCommandError> {
+        match command {
+            SmartDeviceCommand::ResetFault => {
+                self.reset_fault();
+                Ok(SmartDeviceResponse::Done)
+            }
+            other => execute_common(self, other),
+        }
+    }
+
+    fn get_temperature(&self) -> Option<Celsius> {
+        match self.status {
+            SmartDeviceStatus::PowerState(SmartDevicePowerState::Enabled) => Some(self.temperature),
+            _ => None,
+        }
+    }
+
     fn get_text_report(&self) -> String {
+        self.get_text_report_in(TemperatureScale::Celsius)
+    }
+
+    fn get_text_report_in(&self, scale: TemperatureScale) -> String {
         format!(
             "Current temperature is {}, status: {}\n",
-            self.temperature, self.status
+            self.temperature.display_in(scale),
+            self.status
         )
     }
 }
@@ -79,6 +220,7 @@ impl SmartDevice for SmartThermometer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pipeline::FilterStage;
 
     #[test]
     fn stupid_test() {
@@ -100,4 +242,60 @@ mod tests {
 
         assert!(is_device_enabled, "Device must be in an enabled state!");
     }
+
+    #[test]
+    fn raw_and_filtered_temperature() {
+        let mut thermometer = SmartThermometer::new("Thermometer_1");
+        thermometer.set_pipeline(ReadingPipeline::new().with_stage(FilterStage::Calibration {
+            offset: -1.5,
+            gain: 1.0,
+        }));
+
+        assert!(thermometer
+            .set_power_state(SmartDevicePowerState::Enabled)
+            .is_ok());
+        thermometer.set_temperature(Celsius(23.0));
+
+        assert_eq!(thermometer.get_raw_temperature(), Celsius(23.0));
+        assert_eq!(thermometer.get_temperature(), Some(Celsius(21.5)));
+    }
+
+    #[test]
+    fn readings_out_of_limits_are_reported_as_faults() {
+        let mut thermometer =
+            SmartThermometer::new("Thermometer_1").with_limits(Celsius(5.0), Celsius(35.0));
+        assert_eq!(
+            thermometer.get_limits(),
+            Some((Celsius(5.0), Celsius(35.0)))
+        );
+
+        // Выключенный термометр не следит за температурой
+        thermometer.set_temperature(Celsius(40.0));
+        assert_eq!(
+            thermometer.get_device_status(),
+            SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled)
+        );
+
+        assert!(thermometer
+            .set_power_state(SmartDevicePowerState::Enabled)
+            .is_ok());
+        thermometer.set_temperature(Celsius(40.0));
+        assert_eq!(
+            thermometer.get_device_status(),
+            SmartDeviceStatus::Malfunction(SmartDeviceErrorCode::Overheat)
+        );
+
+        thermometer.reset_fault();
+        assert!(thermometer
+            .set_power_state(SmartDevicePowerState::Enabled)
+            .is_ok());
+        thermometer.set_temperature(Celsius(-10.0));
+        assert_eq!(
+            thermometer.get_device_status(),
+            SmartDeviceStatus::Malfunction(SmartDeviceErrorCode::Underheat)
+        );
+        assert!(thermometer
+            .get_text_report_in(TemperatureScale::Fahrenheit)
+            .starts_with("Current temperature is 14 °F"));
+    }
 }
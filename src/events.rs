@@ -0,0 +1,607 @@
+//! Модуль содержит шину событий умного дома
+//!
+//! > Потребители подписываются на события дома с фильтром по комнатам, устройствам
+//! > и видам событий. События доставляются синхронно (вызовом функции-обработчика)
+//! > или через канал `std::sync::mpsc`, из которого их можно читать в другом потоке.
+//! > Шина может хранить ограниченное количество последних событий, чтобы новые
+//! > подписчики получили их повтор при подключении.
+//!
+use crate::command::{Capability, SmartDeviceCommand};
+use crate::containers::ContainerName;
+use crate::json::{device_error_name, power_state_name, JsonValue};
+use crate::smart_device::{SmartDeviceErrorCode, SmartDevicePowerState};
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Alias для идентификатора подписки
+pub type SubscriptionId = u64;
+
+/// Перечисление видов событий, используемых для фильтрации
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// Изменение состава дома (комнаты и устройства)
+    Container,
+    /// Изменение состояния питания устройства
+    PowerChange,
+    /// Новое показание устройства
+    Reading,
+    /// Неисправность устройства
+    Malfunction,
+    /// Выполнение команды устройством
+    Command,
+}
+
+/// Перечисление событий умного дома
+#[derive(Clone, Debug, PartialEq)]
+pub enum EventPayload {
+    /// Комната добавлена в дом
+    RoomAdded,
+    /// Комната удалена из дома
+    RoomRemoved,
+    /// Устройство добавлено в комнату
+    DeviceAdded,
+    /// Устройство удалено из комнаты
+    DeviceRemoved,
+    /// Комната переименована
+    RoomRenamed {
+        /// Прежнее название комнаты
+        old_name: ContainerName,
+    },
+    /// Устройство переименовано
+    DeviceRenamed {
+        /// Прежнее название устройства
+        old_name: ContainerName,
+    },
+    /// Устройство перенесено из другой комнаты
+    DeviceMoved {
+        /// Комната, из которой перенесено устройство
+        from_room: ContainerName,
+    },
+    /// Изменено состояние питания устройства
+    PowerStateChanged(SmartDevicePowerState),
+    /// Получено показание устройства
+    Reading {
+        /// Вид показания
+        capability: Capability,
+        /// Значение показания
+        value: f32,
+    },
+    /// Обнаружена неисправность устройства
+    Malfunction(SmartDeviceErrorCode),
+    /// Этаж добавлен в дом
+    FloorAdded {
+        /// Название этажа
+        floor: ContainerName,
+    },
+    /// Этаж удалён из дома
+    FloorRemoved {
+        /// Название этажа
+        floor: ContainerName,
+    },
+    /// Комната размещена на этаже
+    RoomPlaced {
+        /// Название этажа
+        floor: ContainerName,
+    },
+    /// Зона добавлена в дом
+    ZoneAdded {
+        /// Название зоны
+        zone: ContainerName,
+    },
+    /// Зона удалена из дома
+    ZoneRemoved {
+        /// Название зоны
+        zone: ContainerName,
+    },
+    /// Комната включена в зону
+    RoomAddedToZone {
+        /// Название зоны
+        zone: ContainerName,
+    },
+    /// Комната исключена из зоны
+    RoomRemovedFromZone {
+        /// Название зоны
+        zone: ContainerName,
+    },
+    /// Устройству добавлена метка
+    TagAdded {
+        /// Метка
+        tag: String,
+    },
+    /// Метка устройства удалена
+    TagRemoved {
+        /// Метка
+        tag: String,
+    },
+    /// Группа устройств добавлена в дом
+    GroupAdded {
+        /// Название группы
+        group: ContainerName,
+    },
+    /// Группа устройств удалена из дома
+    GroupRemoved {
+        /// Название группы
+        group: ContainerName,
+    },
+    /// Устройство выполнило команду (кроме включения/выключения и чтения значений)
+    CommandExecuted {
+        /// Имя команды
+        command: String,
+    },
+}
+
+impl EventKind {
+    /// Получение имени вида событий в формате snake_case
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Container => "container",
+            Self::PowerChange => "power_change",
+            Self::Reading => "reading",
+            Self::Malfunction => "malfunction",
+            Self::Command => "command",
+        }
+    }
+
+    /// Получение вида событий по имени в формате snake_case
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Self::Container,
+            Self::PowerChange,
+            Self::Reading,
+            Self::Malfunction,
+            Self::Command,
+        ]
+        .into_iter()
+        .find(|kind| kind.get_name() == name)
+    }
+}
+
+impl EventPayload {
+    /// Получение имени события в формате snake_case
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::RoomAdded => "room_added",
+            Self::RoomRemoved => "room_removed",
+            Self::DeviceAdded => "device_added",
+            Self::DeviceRemoved => "device_removed",
+            Self::RoomRenamed { .. } => "room_renamed",
+            Self::DeviceRenamed { .. } => "device_renamed",
+            Self::DeviceMoved { .. } => "device_moved",
+            Self::PowerStateChanged(_) => "power_state_changed",
+            Self::Reading { .. } => "reading",
+            Self::Malfunction(_) => "malfunction",
+            Self::FloorAdded { .. } => "floor_added",
+            Self::FloorRemoved { .. } => "floor_removed",
+            Self::RoomPlaced { .. } => "room_placed",
+            Self::ZoneAdded { .. } => "zone_added",
+            Self::ZoneRemoved { .. } => "zone_removed",
+            Self::RoomAddedToZone { .. } => "room_added_to_zone",
+            Self::RoomRemovedFromZone { .. } => "room_removed_from_zone",
+            Self::TagAdded { .. } => "tag_added",
+            Self::TagRemoved { .. } => "tag_removed",
+            Self::GroupAdded { .. } => "group_added",
+            Self::GroupRemoved { .. } => "group_removed",
+            Self::CommandExecuted { .. } => "command_executed",
+        }
+    }
+
+    /// Получение события об успешном выполнении команды `command`
+    ///
+    /// Чтение значений не меняет состояния устройства, поэтому событий не порождает
+    pub(crate) fn for_command(command: &SmartDeviceCommand) -> Option<Self> {
+        match command {
+            SmartDeviceCommand::SetPowerState(state) => {
+                Some(Self::PowerStateChanged(state.clone()))
+            }
+            SmartDeviceCommand::Read(_) => None,
+            other => Some(Self::CommandExecuted {
+                command: other.get_name().to_string(),
+            }),
+        }
+    }
+
+    /// Получение вида события
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Self::RoomAdded
+            | Self::RoomRemoved
+            | Self::DeviceAdded
+            | Self::DeviceRemoved
+            | Self::RoomRenamed { .. }
+            | Self::DeviceRenamed { .. }
+            | Self::DeviceMoved { .. }
+            | Self::FloorAdded { .. }
+            | Self::FloorRemoved { .. }
+            | Self::RoomPlaced { .. }
+            | Self::ZoneAdded { .. }
+            | Self::ZoneRemoved { .. }
+            | Self::RoomAddedToZone { .. }
+            | Self::RoomRemovedFromZone { .. }
+            | Self::TagAdded { .. }
+            | Self::TagRemoved { .. }
+            | Self::GroupAdded { .. }
+            | Self::GroupRemoved { .. } => EventKind::Container,
+            Self::PowerStateChanged(_) => EventKind::PowerChange,
+            Self::Reading { .. } => EventKind::Reading,
+            Self::Malfunction(_) => EventKind::Malfunction,
+            Self::CommandExecuted { .. } => EventKind::Command,
+        }
+    }
+}
+
+/// Событие умного дома
+#[derive(Clone, Debug, PartialEq)]
+pub struct HouseEvent {
+    /// Момент события
+    pub timestamp: SystemTime,
+
+    /// Комната, к которой относится событие (пустая строка - событие этажа или зоны)
+    pub room: ContainerName,
+
+    /// Устройство, к которому относится событие (`None` - событие комнаты)
+    pub device: Option<ContainerName>,
+
+    /// Содержание события
+    pub payload: EventPayload,
+}
+
+impl HouseEvent {
+    /// Создание события этажа или зоны дома, произошедшего в текущий момент
+    pub fn house(payload: EventPayload) -> Self {
+        Self::room("", payload)
+    }
+
+    /// Создание события комнаты `room`, произошедшего в текущий момент
+    pub fn room(room: &str, payload: EventPayload) -> Self {
+        Self {
+            timestamp: SystemTime::now(),
+            room: room.to_string(),
+            device: None,
+            payload,
+        }
+    }
+
+    /// Создание события устройства `device` комнаты `room`, произошедшего в текущий момент
+    pub fn device(room: &str, device: &str, payload: EventPayload) -> Self {
+        Self {
+            timestamp: SystemTime::now(),
+            room: room.to_string(),
+            device: Some(device.to_string()),
+            payload,
+        }
+    }
+
+    /// Представление события в виде объекта JSON
+    pub fn to_json(&self) -> JsonValue {
+        let millis = self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or_default();
+
+        let event = JsonValue::object()
+            .with("timestamp", millis)
+            .with("kind", self.payload.kind().get_name())
+            .with("event", self.payload.get_name())
+            .with("room", self.room.as_str())
+            .with("device", self.device.as_deref());
+
+        match &self.payload {
+            EventPayload::RoomRenamed { old_name } | EventPayload::DeviceRenamed { old_name } => {
+                event.with("old_name", old_name.as_str())
+            }
+            EventPayload::DeviceMoved { from_room } => event.with("from_room", from_room.as_str()),
+            EventPayload::PowerStateChanged(state) => {
+                event.with("power_state", power_state_name(state))
+            }
+            EventPayload::Reading { capability, value } => event
+                .with("capability", capability.to_string())
+                .with("value", *value)
+                .with("unit", capability.unit()),
+            EventPayload::Malfunction(code) => event.with("malfunction", device_error_name(code)),
+            EventPayload::FloorAdded { floor }
+            | EventPayload::FloorRemoved { floor }
+            | EventPayload::RoomPlaced { floor } => event.with("floor", floor.as_str()),
+            EventPayload::ZoneAdded { zone }
+            | EventPayload::ZoneRemoved { zone }
+            | EventPayload::RoomAddedToZone { zone }
+            | EventPayload::RoomRemovedFromZone { zone } => event.with("zone", zone.as_str()),
+            EventPayload::TagAdded { tag } | EventPayload::TagRemoved { tag } => {
+                event.with("tag", tag.as_str())
+            }
+            EventPayload::GroupAdded { group } | EventPayload::GroupRemoved { group } => {
+                event.with("group", group.as_str())
+            }
+            EventPayload::CommandExecuted { command } => event.with("command", command.as_str()),
+            _ => event,
+        }
+    }
+}
+
+/// Фильтр событий подписки
+///
+/// Пустой список означает "любое значение"
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EventFilter {
+    /// Комнаты, события которых доставляются подписчику
+    pub rooms: Vec<ContainerName>,
+
+    /// Устройства, события которых доставляются подписчику
+    pub devices: Vec<ContainerName>,
+
+    /// Виды событий, доставляемых подписчику
+    pub kinds: Vec<EventKind>,
+}
+
+impl EventFilter {
+    /// Создание фильтра, пропускающего все события
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Добавление комнаты в фильтр
+    pub fn room(mut self, room: &str) -> Self {
+        self.rooms.push(room.to_string());
+        self
+    }
+
+    /// Добавление устройства в фильтр
+    pub fn device(mut self, device: &str) -> Self {
+        self.devices.push(device.to_string());
+        self
+    }
+
+    /// Добавление вида событий в фильтр
+    pub fn kind(mut self, kind: EventKind) -> Self {
+        self.kinds.push(kind);
+        self
+    }
+
+    /// Проверка, удовлетворяет ли событие фильтру
+    pub fn matches(&self, event: &HouseEvent) -> bool {
+        let room_matches = self.rooms.is_empty() || self.rooms.contains(&event.room);
+        let device_matches = self.devices.is_empty()
+            || event
+                .device
+                .as_ref()
+                .is_some_and(|device| self.devices.contains(device));
+        let kind_matches = self.kinds.is_empty() || self.kinds.contains(&event.payload.kind());
+
+        room_matches && device_matches && kind_matches
+    }
+}
+
+/// Способ доставки событий подписчику
+enum Subscriber {
+    /// Синхронный вызов обработчика
+    Callback(Box<dyn FnMut(&HouseEvent) + Send>),
+    /// Отправка в канал
+    Channel(Sender<HouseEvent>),
+    /// Отправка в канал ограниченной ёмкости (при переполнении подписка удаляется)
+    BoundedChannel(SyncSender<HouseEvent>),
+}
+
+/// Тип, описывающий шину событий
+#[derive(Default)]
+pub struct EventBus {
+    /// Подписки
+    subscriptions: Vec<(SubscriptionId, EventFilter, Subscriber)>,
+
+    /// Идентификатор следующей подписки
+    next_id: SubscriptionId,
+
+    /// Последние опубликованные события
+    backlog: VecDeque<HouseEvent>,
+
+    /// Максимальное количество хранимых последних событий (0 - события не хранятся)
+    backlog_capacity: usize,
+}
+
+impl EventBus {
+    /// Создание шины без подписчиков
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Подписка на события с синхронным вызовом обработчика `callback`
+    ///
+    /// Обработчик должен быть `Send`, чтобы дом можно было передавать между потоками
+    pub fn subscribe<F>(&mut self, filter: EventFilter, callback: F) -> SubscriptionId
+    where
+        F: FnMut(&HouseEvent) + Send + 'static,
+    {
+        self.add(filter, Subscriber::Callback(Box::new(callback)))
+    }
+
+    /// Подписка на события с доставкой через канал
+    ///
+    /// Подписка удаляется автоматически, когда получатель канала уничтожен
+    pub fn subscribe_channel(
+        &mut self,
+        filter: EventFilter,
+    ) -> (SubscriptionId, Receiver<HouseEvent>) {
+        let (sender, receiver) = mpsc::channel();
+        (self.add(filter, Subscriber::Channel(sender)), receiver)
+    }
+
+    /// Подписка на события с доставкой через канал ёмкостью `capacity` событий
+    ///
+    /// Если получатель не успевает забирать события и канал переполнен, подписка удаляется,
+    /// а получатель после разбора оставшихся событий обнаруживает разрыв канала
+    pub fn subscribe_bounded_channel(
+        &mut self,
+        filter: EventFilter,
+        capacity: usize,
+    ) -> (SubscriptionId, Receiver<HouseEvent>) {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        (
+            self.add(filter, Subscriber::BoundedChannel(sender)),
+            receiver,
+        )
+    }
+
+    /// Установка максимального количества хранимых последних событий
+    pub fn set_backlog_capacity(&mut self, capacity: usize) {
+        self.backlog_capacity = capacity;
+        while self.backlog.len() > capacity {
+            self.backlog.pop_front();
+        }
+    }
+
+    /// Получение максимального количества хранимых последних событий
+    pub fn get_backlog_capacity(&self) -> usize {
+        self.backlog_capacity
+    }
+
+    /// Получение сохранённых последних событий, удовлетворяющих фильтру
+    pub fn get_backlog(&self, filter: &EventFilter) -> Vec<&HouseEvent> {
+        self.backlog
+            .iter()
+            .filter(|event| filter.matches(event))
+            .collect()
+    }
+
+    /// Отмена подписки
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let count = self.subscriptions.len();
+        self.subscriptions
+            .retain(|(subscription, _, _)| *subscription != id);
+        count != self.subscriptions.len()
+    }
+
+    /// Получение количества активных подписок
+    pub fn get_subscription_count(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    /// Доставка события всем подписчикам, фильтр которых пропускает событие
+    pub fn publish(&mut self, event: HouseEvent) {
+        if self.backlog_capacity > 0 {
+            if self.backlog.len() == self.backlog_capacity {
+                self.backlog.pop_front();
+            }
+            self.backlog.push_back(event.clone());
+        }
+
+        self.subscriptions.retain_mut(|(_, filter, subscriber)| {
+            if !filter.matches(&event) {
+                return true;
+            }
+            match subscriber {
+                Subscriber::Callback(callback) => {
+                    callback(&event);
+                    true
+                }
+                Subscriber::Channel(sender) => sender.send(event.clone()).is_ok(),
+                Subscriber::BoundedChannel(sender) => sender.try_send(event.clone()).is_ok(),
+            }
+        });
+    }
+
+    /// Добавление подписки
+    fn add(&mut self, filter: EventFilter, subscriber: Subscriber) -> SubscriptionId {
+        self.next_id += 1;
+        self.subscriptions.push((self.next_id, filter, subscriber));
+        self.next_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::house::House;
+    use crate::room::Room;
+    use crate::smart_device::SmartDevicePowerState;
+    use crate::socket::SmartSocket;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn filtered_callback_receives_matching_events() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+
+        let mut house = House::new("House", 2);
+        house.get_event_bus().subscribe(
+            EventFilter::all()
+                .room("Kitchen")
+                .kind(EventKind::Container),
+            move |event| sink.lock().unwrap().push(event.payload.clone()),
+        );
+
+        assert!(house.add_room(Room::new("Kitchen", 1)).is_ok());
+        assert!(house.add_room(Room::new("Hall", 1)).is_ok());
+        assert!(house
+            .add_device("Kitchen", Box::new(SmartSocket::new("Socket")))
+            .is_ok());
+        assert!(house
+            .set_power_state("Kitchen", "Socket", SmartDevicePowerState::Enabled)
+            .is_ok());
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![EventPayload::RoomAdded, EventPayload::DeviceAdded]
+        );
+    }
+
+    #[test]
+    fn channel_subscription_is_dropped_with_receiver() {
+        let mut house = House::new("House", 1);
+        assert!(house.add_room(Room::new("Kitchen", 1)).is_ok());
+        assert!(house
+            .add_device("Kitchen", Box::new(SmartSocket::new("Socket")))
+            .is_ok());
+
+        let (_, receiver) = house
+            .get_event_bus()
+            .subscribe_channel(EventFilter::all().device("Socket"));
+        assert!(house
+            .set_power_state("Kitchen", "Socket", SmartDevicePowerState::Enabled)
+            .is_ok());
+
+        let event = receiver.try_recv().unwrap();
+        assert_eq!(
+            event.payload,
+            EventPayload::PowerStateChanged(SmartDevicePowerState::Enabled)
+        );
+
+        drop(receiver);
+        assert!(house.remove_device("Kitchen", "Socket").is_ok());
+        assert_eq!(house.get_event_bus().get_subscription_count(), 0);
+    }
+
+    #[test]
+    fn overflowing_bounded_channel_is_unsubscribed() {
+        let mut bus = EventBus::new();
+        let (_, receiver) = bus.subscribe_bounded_channel(EventFilter::all(), 2);
+        for room in ["Kitchen", "Hall", "Garage"] {
+            bus.publish(HouseEvent::room(room, EventPayload::RoomAdded));
+        }
+
+        assert_eq!(bus.get_subscription_count(), 0);
+        let rooms: Vec<String> = receiver.iter().map(|event| event.room).collect();
+        assert_eq!(rooms, vec!["Kitchen", "Hall"]);
+    }
+
+    #[test]
+    fn backlog_keeps_latest_events() {
+        let mut bus = EventBus::new();
+        bus.set_backlog_capacity(2);
+        for room in ["Kitchen", "Hall", "Garage"] {
+            bus.publish(HouseEvent::room(room, EventPayload::RoomAdded));
+        }
+
+        let rooms: Vec<&str> = bus
+            .get_backlog(&EventFilter::all())
+            .iter()
+            .map(|event| event.room.as_str())
+            .collect();
+        assert_eq!(rooms, vec!["Hall", "Garage"]);
+        assert_eq!(
+            bus.get_backlog(&EventFilter::all().room("Garage"))[0]
+                .to_json()
+                .get("event"),
+            Some(&JsonValue::from("room_added"))
+        );
+    }
+}
@@ -0,0 +1,340 @@
+//! Модуль содержит хранилище истории показаний умных устройств
+//!
+//! > История хранится в памяти в виде кольцевого буфера для каждого вида показаний
+//! > (температура, потребляемая мощность и т.д.). Старые записи удаляются при превышении
+//! > ёмкости буфера или срока хранения. Для просмотра длительных периодов показания
+//! > могут быть агрегированы по минутам или часам (минимум, максимум, среднее).
+//!
+use crate::command::Capability;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Ёмкость буфера истории по умолчанию (сутки поминутных показаний)
+pub const DEFAULT_HISTORY_CAPACITY: usize = 1440;
+
+/// Срок хранения истории по умолчанию
+pub const DEFAULT_HISTORY_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Тип, описывающий правила хранения истории показаний
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetentionPolicy {
+    /// Максимальное количество показаний каждого вида
+    pub capacity: usize,
+
+    /// Максимальный возраст показаний
+    pub max_age: Duration,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_HISTORY_CAPACITY,
+            max_age: DEFAULT_HISTORY_MAX_AGE,
+        }
+    }
+}
+
+/// Одиночное показание
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sample {
+    /// Момент получения показания
+    pub timestamp: SystemTime,
+
+    /// Значение показания (в единицах, соответствующих виду показания)
+    pub value: f32,
+}
+
+/// Агрегированные показания за интервал времени
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AggregateSample {
+    /// Начало интервала
+    pub start: SystemTime,
+
+    /// Минимальное значение
+    pub min: f32,
+
+    /// Максимальное значение
+    pub max: f32,
+
+    /// Среднее значение
+    pub avg: f32,
+
+    /// Количество показаний в интервале
+    pub count: usize,
+}
+
+/// Перечисление интервалов агрегации показаний
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Resolution {
+    /// Поминутная агрегация
+    Minute,
+    /// Почасовая агрегация
+    Hour,
+}
+
+impl Resolution {
+    /// Длительность интервала агрегации
+    pub fn duration(&self) -> Duration {
+        match self {
+            Self::Minute => Duration::from_secs(60),
+            Self::Hour => Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// Тип, описывающий историю показаний одного устройства
+#[derive(Clone, Debug, Default)]
+pub struct ReadingHistory {
+    /// Правила хранения истории
+    policy: RetentionPolicy,
+
+    /// Показания, сгруппированные по виду
+    series: HashMap<Capability, VecDeque<Sample>>,
+}
+
+impl ReadingHistory {
+    /// Создание пустой истории с правилами хранения `policy`
+    pub fn new(policy: RetentionPolicy) -> Self {
+        Self {
+            policy,
+            series: HashMap::new(),
+        }
+    }
+
+    /// Получение правил хранения истории
+    pub fn get_policy(&self) -> RetentionPolicy {
+        self.policy
+    }
+
+    /// Изменение правил хранения истории; лишние записи удаляются сразу
+    ///
+    /// Срок хранения, как и при записи, отсчитывается от последнего записанного показания
+    pub fn set_policy(&mut self, policy: RetentionPolicy) {
+        self.policy = policy;
+        for samples in self.series.values_mut() {
+            while samples.len() > policy.capacity {
+                samples.pop_front();
+            }
+        }
+
+        let latest = self
+            .series
+            .values()
+            .filter_map(|samples| samples.back().map(|sample| sample.timestamp))
+            .max();
+        if let Some(latest) = latest {
+            self.prune(latest);
+        }
+    }
+
+    /// Получение списка видов показаний, для которых есть история
+    pub fn get_capabilities(&self) -> Vec<Capability> {
+        self.series.keys().copied().collect()
+    }
+
+    /// Запись показания `value` вида `capability`, полученного в момент `timestamp`
+    ///
+    /// Показания, полученные раньше последнего записанного, игнорируются
+    pub fn record(&mut self, capability: Capability, value: f32, timestamp: SystemTime) {
+        let samples = self.series.entry(capability).or_default();
+
+        if samples
+            .back()
+            .is_some_and(|last| last.timestamp > timestamp)
+        {
+            return;
+        }
+
+        samples.push_back(Sample { timestamp, value });
+        while samples.len() > self.policy.capacity {
+            samples.pop_front();
+        }
+        self.prune(timestamp);
+    }
+
+    /// Удаление показаний старше срока хранения относительно момента `now`
+    pub fn prune(&mut self, now: SystemTime) {
+        let Some(oldest) = now.checked_sub(self.policy.max_age) else {
+            return;
+        };
+
+        for samples in self.series.values_mut() {
+            while samples
+                .front()
+                .is_some_and(|first| first.timestamp < oldest)
+            {
+                samples.pop_front();
+            }
+        }
+    }
+
+    /// Получение показаний вида `capability` в интервале `[from, to]`
+    pub fn query(&self, capability: Capability, from: SystemTime, to: SystemTime) -> Vec<Sample> {
+        self.series
+            .get(&capability)
+            .map(|samples| {
+                samples
+                    .iter()
+                    .filter(|sample| sample.timestamp >= from && sample.timestamp <= to)
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Получение показаний вида `capability` в интервале `[from, to]`,
+    /// агрегированных с разрешением `resolution`
+    pub fn query_aggregated(
+        &self,
+        capability: Capability,
+        from: SystemTime,
+        to: SystemTime,
+        resolution: Resolution,
+    ) -> Vec<AggregateSample> {
+        aggregate(&self.query(capability, from, to), resolution)
+    }
+}
+
+/// Агрегация упорядоченных по времени показаний по интервалам `resolution`
+pub fn aggregate(samples: &[Sample], resolution: Resolution) -> Vec<AggregateSample> {
+    let step = resolution.duration().as_secs();
+    let mut aggregates: Vec<AggregateSample> = Vec::new();
+
+    for sample in samples {
+        let seconds = sample
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let start = UNIX_EPOCH + Duration::from_secs(seconds - seconds % step);
+
+        match aggregates.last_mut() {
+            Some(last) if last.start == start => {
+                last.min = last.min.min(sample.value);
+                last.max = last.max.max(sample.value);
+                last.avg = (last.avg * last.count as f32 + sample.value) / (last.count + 1) as f32;
+                last.count += 1;
+            }
+            _ => aggregates.push(AggregateSample {
+                start,
+                min: sample.value,
+                max: sample.value,
+                avg: sample.value,
+                count: 1,
+            }),
+        }
+    }
+    aggregates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn ring_buffer_respects_capacity_and_age() {
+        let mut history = ReadingHistory::new(RetentionPolicy {
+            capacity: 3,
+            max_age: Duration::from_secs(100),
+        });
+
+        for (second, value) in [(0, 1.0), (10, 2.0), (20, 3.0), (30, 4.0)] {
+            history.record(Capability::Temperature, value, at(second));
+        }
+        let values: Vec<f32> = history
+            .query(Capability::Temperature, at(0), at(1000))
+            .iter()
+            .map(|sample| sample.value)
+            .collect();
+        assert_eq!(values, vec![2.0, 3.0, 4.0]);
+
+        history.record(Capability::Temperature, 5.0, at(125));
+        assert_eq!(
+            history
+                .query(Capability::Temperature, at(0), at(1000))
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn stricter_policy_prunes_by_count_and_age() {
+        let mut history = ReadingHistory::new(RetentionPolicy::default());
+        for (second, value) in [(0, 1.0), (10, 2.0), (20, 3.0), (30, 4.0)] {
+            history.record(Capability::Temperature, value, at(second));
+        }
+        history.record(Capability::PowerMetering, 100.0, at(5));
+
+        history.set_policy(RetentionPolicy {
+            capacity: 3,
+            max_age: Duration::from_secs(15),
+        });
+        let values: Vec<f32> = history
+            .query(Capability::Temperature, at(0), at(1000))
+            .iter()
+            .map(|sample| sample.value)
+            .collect();
+        assert_eq!(values, vec![3.0, 4.0]);
+        assert!(history
+            .query(Capability::PowerMetering, at(0), at(1000))
+            .is_empty());
+    }
+
+    #[test]
+    fn samples_are_downsampled_by_minute() {
+        let mut history = ReadingHistory::default();
+        history.set_policy(RetentionPolicy::default());
+
+        for (second, value) in [(0, 20.0), (30, 22.0), (60, 25.0), (90, 23.0), (150, 30.0)] {
+            history.record(Capability::Temperature, value, at(second));
+        }
+
+        let minutes =
+            history.query_aggregated(Capability::Temperature, at(0), at(119), Resolution::Minute);
+        assert_eq!(minutes.len(), 2);
+        assert_eq!(minutes[0].start, at(0));
+        assert_eq!(
+            (minutes[0].min, minutes[0].max, minutes[0].avg),
+            (20.0, 22.0, 21.0)
+        );
+        assert_eq!(
+            (minutes[1].min, minutes[1].max, minutes[1].count),
+            (23.0, 25.0, 2)
+        );
+    }
+
+    #[test]
+    fn room_samples_device_readings() {
+        use crate::room::Room;
+        use crate::smart_device::{SmartDevice, SmartDevicePowerState};
+        use crate::thermometer::SmartThermometer;
+        use crate::units::Celsius;
+
+        let mut thermometer = SmartThermometer::new("Sensor");
+        assert!(thermometer
+            .set_power_state(SmartDevicePowerState::Enabled)
+            .is_ok());
+        thermometer.set_temperature(Celsius(21.0));
+
+        let mut room = Room::new("Kitchen", 1);
+        assert!(room.add_device(Box::new(thermometer)).is_ok());
+        room.sample_readings_at(at(10));
+        room.sample_readings_at(at(20));
+
+        let history = room.query_history(Capability::Temperature, at(0), at(15));
+        assert_eq!(
+            history["Sensor"],
+            vec![Sample {
+                timestamp: at(10),
+                value: 21.0
+            }]
+        );
+        assert!(room
+            .query_history(Capability::PowerMetering, at(0), at(30))
+            .is_empty());
+    }
+}
@@ -0,0 +1,659 @@
+//! Модуль содержит HTTP-сервер REST API умного дома
+//!
+//! > Модуль доступен при включённой cargo-функции `http`. Сервер работает поверх
+//! > [`SharedHouse`] и обрабатывает каждое соединение в отдельном потоке.
+//! > Ресурсы (все ответы - JSON):
+//! >
+//! > | Метод    | Путь                                   | Описание                          |
+//! > |----------|----------------------------------------|-----------------------------------|
+//! > | `GET`    | `/house`                               | название, комнаты, мощность       |
+//! > | `GET`    | `/rooms`                               | список комнат                     |
+//! > | `POST`   | `/rooms`                               | `{"name": .., "device_limit": ..}`|
+//! > | `GET`    | `/rooms/{room}`                        | комната и её устройства           |
+//! > | `DELETE` | `/rooms/{room}`                        | удаление комнаты                  |
+//! > | `GET`    | `/rooms/{room}/devices`                | описания устройств комнаты        |
+//! > | `GET`    | `/rooms/{room}/devices/{device}`       | описание устройства               |
+//! > | `DELETE` | `/rooms/{room}/devices/{device}`       | удаление устройства               |
+//! > | `GET`    | `/rooms/{room}/devices/{device}/status`   | статус работы                  |
+//! > | `GET`    | `/rooms/{room}/devices/{device}/readings` | числовые показания             |
+//! > | `PUT`    | `/rooms/{room}/devices/{device}/power`    | `{"state": "enabled"}`         |
+//! > | `GET`    | `/events`                              | поток событий WebSocket (`websocket`) |
+//! >
+//! > Ошибки возвращаются в виде `{"error": .., "message": ..}` с кодом состояния,
+//! > полученным через [`error_status`] (для ошибок контейнера) и [`device_error_status`]
+//! > (для ошибок устройства). Лимит устройств создаваемой комнаты не может
+//! > превышать [`MAX_DEVICE_LIMIT`].
+//!
+use crate::containers::shared::SharedHouse;
+use crate::containers::ErrorReason;
+use crate::json::{
+    device_error_name, error_name, readings_json, status_json, JsonError, JsonValue,
+};
+use crate::smart_device::{SmartDevice, SmartDeviceErrorCode, SmartDevicePowerState};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Максимальный размер заголовков запроса в байтах
+const MAX_HEADER_SIZE: u64 = 16 * 1024;
+
+/// Максимальный размер тела запроса в байтах
+const MAX_BODY_SIZE: usize = 64 * 1024;
+
+/// Время ожидания данных от клиента
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Максимальное количество устройств в комнате, создаваемой через API
+pub const MAX_DEVICE_LIMIT: usize = 1024;
+
+/// Получение кода состояния HTTP для ошибки контейнера
+pub fn error_status(reason: &ErrorReason) -> u16 {
+    match reason {
+        ErrorReason::ItemDoesntExist => 404,
+        ErrorReason::ItemAlreadyPresented
+        | ErrorReason::ItemLimitExceeded
+        | ErrorReason::DeviceBusy => 409,
+        ErrorReason::InvalidName | ErrorReason::UnsupportedCommand => 422,
+        ErrorReason::DeviceFailure(code) => device_error_status(code),
+    }
+}
+
+/// Получение кода состояния HTTP для ошибки устройства
+///
+/// Ошибки, зависящие от состояния устройства, соответствуют `409 Conflict`,
+/// отказ в доступе - `403 Forbidden`, неисправности оборудования и потеря связи
+/// с удалённым устройством - `503 Service Unavailable`
+pub fn device_error_status(code: &SmartDeviceErrorCode) -> u16 {
+    match code {
+        SmartDeviceErrorCode::PoweredOff => 409,
+        SmartDeviceErrorCode::AccessDenied => 403,
+        SmartDeviceErrorCode::Overcurrent
+        | SmartDeviceErrorCode::Overvoltage
+        | SmartDeviceErrorCode::Overheat
+        | SmartDeviceErrorCode::Underheat
+        | SmartDeviceErrorCode::Obstruction
+        | SmartDeviceErrorCode::Jammed
+        | SmartDeviceErrorCode::Unreachable => 503,
+    }
+}
+
+/// Тип, описывающий HTTP-сервер умного дома
+pub struct HttpServer {
+    /// Слушающий сокет
+    listener: TcpListener,
+
+    /// Обслуживаемый дом
+    house: SharedHouse,
+
+    /// Период публикации показаний устройств на шине событий
+    sampling_interval: Option<Duration>,
+}
+
+impl HttpServer {
+    /// Создание сервера дома `house`, принимающего соединения на адресе `address`
+    pub fn bind<A: ToSocketAddrs>(address: A, house: SharedHouse) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(address)?,
+            house,
+            sampling_interval: None,
+        })
+    }
+
+    /// Периодическая публикация показаний устройств на шине событий дома
+    /// (см. [`SharedHouse::sample_readings`]) с периодом `interval`, пока сервер работает
+    pub fn with_sampling_interval(mut self, interval: Duration) -> Self {
+        self.sampling_interval = Some(interval);
+        self
+    }
+
+    /// Хранение `capacity` последних событий дома для повтора клиентам потока событий
+    /// (см. [`EventBus::set_backlog_capacity`](crate::events::EventBus::set_backlog_capacity))
+    pub fn with_event_backlog(self, capacity: usize) -> Self {
+        self.house
+            .with_event_bus(|bus| bus.set_backlog_capacity(capacity));
+        self
+    }
+
+    /// Получение адреса, на котором сервер принимает соединения
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Обслуживание соединений в текущем потоке (без возврата управления)
+    pub fn run(self) -> io::Result<()> {
+        let running = Arc::new(AtomicBool::new(true));
+        self.start_sampling(&running)?;
+        accept_loop(self.listener, self.house, running)
+    }
+
+    /// Запуск обслуживания соединений в фоновом потоке
+    pub fn spawn(self) -> io::Result<HttpServerHandle> {
+        let address = self.local_addr()?;
+        let running = Arc::new(AtomicBool::new(true));
+        self.start_sampling(&running)?;
+        let flag = running.clone();
+        let thread = thread::Builder::new()
+            .name("iot-http".to_string())
+            .spawn(move || accept_loop(self.listener, self.house, flag))?;
+
+        Ok(HttpServerHandle {
+            address,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// Запуск потока публикации показаний, если задан период
+    fn start_sampling(&self, running: &Arc<AtomicBool>) -> io::Result<()> {
+        let Some(interval) = self.sampling_interval else {
+            return Ok(());
+        };
+        let house = self.house.clone();
+        let running = running.clone();
+        thread::Builder::new()
+            .name("iot-http-sampling".to_string())
+            .spawn(move || {
+                while running.load(Ordering::SeqCst) {
+                    house.sample_readings();
+                    thread::sleep(interval);
+                }
+            })?;
+        Ok(())
+    }
+}
+
+/// Дескриптор HTTP-сервера, запущенного в фоновом потоке
+///
+/// Сервер останавливается вызовом [`HttpServerHandle::shutdown`] или при удалении дескриптора
+pub struct HttpServerHandle {
+    /// Адрес сервера
+    address: SocketAddr,
+
+    /// Признак работы сервера
+    running: Arc<AtomicBool>,
+
+    /// Поток, принимающий соединения
+    thread: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl HttpServerHandle {
+    /// Получение адреса, на котором сервер принимает соединения
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Остановка сервера
+    ///
+    /// Запросы, обрабатываемые в момент остановки, завершаются штатно
+    pub fn shutdown(mut self) -> io::Result<()> {
+        self.stop()
+    }
+
+    /// Остановка приёма соединений и ожидание завершения потока
+    fn stop(&mut self) -> io::Result<()> {
+        let Some(thread) = self.thread.take() else {
+            return Ok(());
+        };
+        self.running.store(false, Ordering::SeqCst);
+
+        // Пробуждение потока, ожидающего соединения
+        let mut wake_address = self.address;
+        if wake_address.ip().is_unspecified() {
+            wake_address.set_ip(match wake_address {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        let _ = TcpStream::connect(wake_address);
+
+        thread
+            .join()
+            .map_err(|_| io::Error::other("HTTP server thread panicked"))?
+    }
+}
+
+impl Drop for HttpServerHandle {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// HTTP-запрос
+pub(crate) struct HttpRequest {
+    /// Метод
+    pub(crate) method: String,
+
+    /// Декодированные сегменты пути
+    pub(crate) segments: Vec<String>,
+
+    /// Декодированные параметры строки запроса
+    #[cfg_attr(not(feature = "websocket"), allow(dead_code))]
+    pub(crate) query: Vec<(String, String)>,
+
+    /// Заголовки (имена в нижнем регистре)
+    #[cfg_attr(not(feature = "websocket"), allow(dead_code))]
+    pub(crate) headers: Vec<(String, String)>,
+
+    /// Тело запроса
+    pub(crate) body: Vec<u8>,
+}
+
+impl HttpRequest {
+    /// Получение значения заголовка `name` (имя в нижнем регистре)
+    #[cfg_attr(not(feature = "websocket"), allow(dead_code))]
+    pub(crate) fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// HTTP-ответ
+pub(crate) struct HttpResponse {
+    /// Код состояния
+    status: u16,
+
+    /// Тело ответа
+    body: JsonValue,
+}
+
+impl HttpResponse {
+    /// Успешный ответ с телом `body`
+    fn ok(body: JsonValue) -> Self {
+        Self { status: 200, body }
+    }
+
+    /// Ответ об ошибке с кодом `status`
+    pub(crate) fn error(status: u16, error: &str, message: &str) -> Self {
+        Self {
+            status,
+            body: JsonValue::object()
+                .with("error", error)
+                .with("message", message),
+        }
+    }
+
+    /// Ответ об ошибке контейнера или устройства
+    fn from_reason(reason: &ErrorReason) -> Self {
+        let mut body = JsonValue::object().with("error", error_name(reason));
+        if let ErrorReason::DeviceFailure(code) = reason {
+            body = body
+                .with("device_error", device_error_name(code))
+                .with("message", code.to_string());
+        }
+        Self {
+            status: error_status(reason),
+            body,
+        }
+    }
+
+    /// Запись ответа в поток
+    pub(crate) fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let body = self.body.to_string();
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason_phrase(self.status),
+            body.len(),
+            body
+        )?;
+        writer.flush()
+    }
+}
+
+impl From<Result<JsonValue, ErrorReason>> for HttpResponse {
+    fn from(result: Result<JsonValue, ErrorReason>) -> Self {
+        match result {
+            Ok(body) => Self::ok(body),
+            Err(reason) => Self::from_reason(&reason),
+        }
+    }
+}
+
+/// Приём соединений, пока установлен признак `running`
+fn accept_loop(
+    listener: TcpListener,
+    house: SharedHouse,
+    running: Arc<AtomicBool>,
+) -> io::Result<()> {
+    for stream in listener.incoming() {
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let house = house.clone();
+        thread::spawn(move || {
+            let _ = handle_connection(stream, &house);
+        });
+    }
+    Ok(())
+}
+
+/// Обработка одного соединения (один запрос на соединение)
+fn handle_connection(stream: TcpStream, house: &SharedHouse) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let response = match read_request(&stream) {
+        #[cfg(feature = "websocket")]
+        Ok(request) if request.segments == ["events"] => {
+            return crate::websocket::serve(stream, house, &request);
+        }
+        Ok(request) => route(house, &request),
+        Err(response) => response,
+    };
+    response.write_to(&mut writer)
+}
+
+/// Чтение и разбор запроса
+fn read_request(stream: &TcpStream) -> Result<HttpRequest, HttpResponse> {
+    let bad_request = |message: &str| HttpResponse::error(400, "bad_request", message);
+    let mut reader = BufReader::new(stream.take(MAX_HEADER_SIZE));
+
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|_| bad_request("malformed request line"))?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target), Some(_version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(bad_request("malformed request line"));
+    };
+    let method = method.to_string();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(percent_decode)
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| bad_request("malformed path"))?;
+    let query = query
+        .split('&')
+        .filter(|parameter| !parameter.is_empty())
+        .map(|parameter| {
+            let (name, value) = parameter.split_once('=').unwrap_or((parameter, ""));
+            Some((percent_decode(name)?, percent_decode(value)?))
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| bad_request("malformed query"))?;
+
+    let mut headers = Vec::new();
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) => return Err(bad_request("unexpected end of headers")),
+            Ok(_) if line.trim_end().is_empty() => break,
+            Ok(_) => {}
+            Err(_) => return Err(bad_request("malformed headers")),
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let (name, value) = (name.trim().to_ascii_lowercase(), value.trim());
+            if name == "content-length" {
+                content_length = value
+                    .parse()
+                    .map_err(|_| bad_request("invalid Content-Length"))?;
+            }
+            headers.push((name, value.to_string()));
+        }
+    }
+
+    if content_length > MAX_BODY_SIZE {
+        return Err(HttpResponse::error(
+            413,
+            "payload_too_large",
+            "request body is too large",
+        ));
+    }
+    let mut body = vec![0; content_length];
+    let buffered = reader.buffer().len().min(content_length);
+    body[..buffered].copy_from_slice(&reader.buffer()[..buffered]);
+    reader.consume(buffered);
+    reader
+        .into_inner()
+        .into_inner()
+        .read_exact(&mut body[buffered..])
+        .map_err(|_| bad_request("incomplete body"))?;
+
+    Ok(HttpRequest {
+        method,
+        segments,
+        query,
+        headers,
+        body,
+    })
+}
+
+/// Выбор обработчика запроса по методу и пути
+fn route(house: &SharedHouse, request: &HttpRequest) -> HttpResponse {
+    let segments: Vec<&str> = request.segments.iter().map(String::as_str).collect();
+    let method = request.method.as_str();
+
+    match (method, segments.as_slice()) {
+        ("GET", ["house"]) => HttpResponse::ok(house_json(house)),
+        ("GET", ["rooms"]) => HttpResponse::ok(sorted(house.get_room_list()).into()),
+        ("POST", ["rooms"]) => match parse_body(&request.body) {
+            Ok(body) => add_room(house, &body),
+            Err(response) => response,
+        },
+        ("GET", ["rooms", room]) => house.get_device_list(room).map_or(
+            HttpResponse::from_reason(&ErrorReason::ItemDoesntExist),
+            |devices| {
+                HttpResponse::ok(
+                    JsonValue::object()
+                        .with("name", *room)
+                        .with("devices", sorted(devices)),
+                )
+            },
+        ),
+        ("DELETE", ["rooms", room]) => house.remove_room(room).map(message_json).into(),
+        ("GET", ["rooms", room, "devices"]) => match house.get_device_list(room) {
+            Some(devices) => HttpResponse::ok(JsonValue::Array(
+                sorted(devices)
+                    .into_iter()
+                    .filter_map(|device| house.with_device(room, &device, device_json).ok())
+                    .collect(),
+            )),
+            None => HttpResponse::from_reason(&ErrorReason::ItemDoesntExist),
+        },
+        ("GET", ["rooms", room, "devices", device]) => {
+            house.with_device(room, device, device_json).into()
+        }
+        ("DELETE", ["rooms", room, "devices", device]) => {
+            house.remove_device(room, device).map(message_json).into()
+        }
+        ("GET", ["rooms", room, "devices", device, "status"]) => house
+            .with_device(room, device, |device| {
+                status_json(&device.get_device_status())
+            })
+            .into(),
+        ("GET", ["rooms", room, "devices", device, "readings"]) => house
+            .with_device(room, device, |device| readings_json(&device.get_readings()))
+            .into(),
+        ("PUT", ["rooms", room, "devices", device, "power"]) => match parse_body(&request.body) {
+            Ok(body) => set_power_state(house, room, device, &body),
+            Err(response) => response,
+        },
+        (_, ["house"])
+        | (_, ["rooms"])
+        | (_, ["rooms", _])
+        | (_, ["rooms", _, "devices"])
+        | (_, ["rooms", _, "devices", _])
+        | (_, ["rooms", _, "devices", _, "status" | "readings" | "power"]) => {
+            HttpResponse::error(405, "method_not_allowed", "method is not allowed")
+        }
+        _ => HttpResponse::error(404, "not_found", "resource not found"),
+    }
+}
+
+/// Обработка запроса на создание комнаты
+fn add_room(house: &SharedHouse, body: &JsonValue) -> HttpResponse {
+    let name = body.get("name").and_then(JsonValue::as_str);
+    let limit = body.get("device_limit").and_then(JsonValue::as_f64);
+    let (Some(name), Some(limit)) = (name, limit) else {
+        return HttpResponse::error(
+            422,
+            "invalid_body",
+            "expected {\"name\": string, \"device_limit\": number}",
+        );
+    };
+    if name.is_empty() || limit < 0.0 || limit.fract() != 0.0 || limit > MAX_DEVICE_LIMIT as f64 {
+        return HttpResponse::error(422, "invalid_body", "invalid room name or device limit");
+    }
+
+    match house.add_room(name, limit as usize) {
+        Ok(message) => HttpResponse {
+            status: 201,
+            body: message_json(message),
+        },
+        Err(reason) => HttpResponse::from_reason(&reason),
+    }
+}
+
+/// Обработка запроса на включение/выключение устройства
+fn set_power_state(
+    house: &SharedHouse,
+    room: &str,
+    device: &str,
+    body: &JsonValue,
+) -> HttpResponse {
+    let state = match body.get("state").and_then(JsonValue::as_str) {
+        Some("enabled") => SmartDevicePowerState::Enabled,
+        Some("disabled") => SmartDevicePowerState::Disabled,
+        _ => {
+            return HttpResponse::error(
+                422,
+                "invalid_body",
+                "expected {\"state\": \"enabled\" | \"disabled\"}",
+            )
+        }
+    };
+
+    house
+        .set_power_state(room, device, state)
+        .and_then(|message| {
+            let status = house.with_device(room, device, |device| device.get_device_status())?;
+            Ok(message_json(message).with("status", status_json(&status)))
+        })
+        .into()
+}
+
+/// Разбор тела запроса в формате JSON
+fn parse_body(body: &[u8]) -> Result<JsonValue, HttpResponse> {
+    let text = std::str::from_utf8(body)
+        .map_err(|_| HttpResponse::error(400, "bad_request", "body is not valid UTF-8"))?;
+    JsonValue::parse(text)
+        .map_err(|error: JsonError| HttpResponse::error(400, "bad_request", &error.to_string()))
+}
+
+/// Описание дома
+fn house_json(house: &SharedHouse) -> JsonValue {
+    JsonValue::object()
+        .with("name", house.get_name())
+        .with("rooms", sorted(house.get_room_list()))
+        .with("power_consumption", house.get_power_consumption().0)
+}
+
+/// Описание умного устройства
+fn device_json(device: &dyn SmartDevice) -> JsonValue {
+    let metadata = device.get_metadata();
+    JsonValue::object()
+        .with("name", device.get_name())
+        .with("id", metadata.get_id())
+        .with("kind", metadata.kind.to_string())
+        .with("vendor", metadata.vendor.as_str())
+        .with("model", metadata.model.as_str())
+        .with("firmware_version", metadata.firmware_version.as_str())
+        .with(
+            "capabilities",
+            device
+                .get_capabilities()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+        )
+        .with("status", status_json(&device.get_device_status()))
+        .with("readings", readings_json(&device.get_readings()))
+}
+
+/// Тело успешного ответа с сообщением контейнера
+fn message_json(message: String) -> JsonValue {
+    JsonValue::object().with("message", message)
+}
+
+/// Сортировка списка имён
+fn sorted(mut names: Vec<String>) -> Vec<String> {
+    names.sort();
+    names
+}
+
+/// Декодирование сегмента пути или параметра запроса (`%XX`-последовательности)
+pub(crate) fn percent_decode(segment: &str) -> Option<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(index + 1..index + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Текстовое описание кода состояния HTTP
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        426 => "Upgrade Required",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_are_mapped_to_status_codes() {
+        assert_eq!(error_status(&ErrorReason::ItemDoesntExist), 404);
+        assert_eq!(error_status(&ErrorReason::ItemAlreadyPresented), 409);
+        assert_eq!(
+            error_status(&ErrorReason::DeviceFailure(
+                SmartDeviceErrorCode::AccessDenied
+            )),
+            403
+        );
+        assert_eq!(
+            error_status(&ErrorReason::DeviceFailure(SmartDeviceErrorCode::Overheat)),
+            503
+        );
+        assert_eq!(
+            error_status(&ErrorReason::DeviceFailure(
+                SmartDeviceErrorCode::Unreachable
+            )),
+            503
+        );
+        assert_eq!(percent_decode("Living%20Room"), Some("Living Room".into()));
+        assert_eq!(percent_decode("bad%2"), None);
+    }
+}
@@ -0,0 +1,612 @@
+//! Модуль содержит минимальную реализацию формата JSON
+//!
+//! > Используется журналом аудита и сетевыми интерфейсами умного дома для
+//! > формирования ответов и разбора запросов. Порядок полей объекта сохраняется.
+//!
+use crate::command::Capability;
+use crate::containers::ErrorReason;
+use crate::smart_device::{
+    SmartDevice, SmartDeviceErrorCode, SmartDevicePowerState, SmartDeviceStatus,
+};
+use std::fmt::{self, Display};
+
+/// Максимальная глубина вложенности разбираемых массивов и объектов
+const MAX_DEPTH: usize = 32;
+
+/// Перечисление значений JSON
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    /// `null`
+    Null,
+    /// Логическое значение
+    Bool(bool),
+    /// Число
+    Number(f64),
+    /// Строка
+    String(String),
+    /// Массив
+    Array(Vec<JsonValue>),
+    /// Объект (поля в порядке добавления)
+    Object(Vec<(String, JsonValue)>),
+}
+
+/// Тип, описывающий ошибку разбора JSON
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonError {
+    /// Позиция (в байтах) во входной строке, на которой обнаружена ошибка
+    pub position: usize,
+
+    /// Описание ошибки
+    pub message: String,
+}
+
+impl JsonValue {
+    /// Создание пустого объекта
+    pub fn object() -> Self {
+        Self::Object(Vec::new())
+    }
+
+    /// Добавление поля `key` со значением `value` в объект
+    ///
+    /// Для значений, не являющихся объектами, вызов не имеет эффекта
+    pub fn with(mut self, key: &str, value: impl Into<JsonValue>) -> Self {
+        if let Self::Object(fields) = &mut self {
+            fields.push((key.to_string(), value.into()));
+        }
+        self
+    }
+
+    /// Разбор строки `text` в формате JSON
+    pub fn parse(text: &str) -> Result<Self, JsonError> {
+        let mut parser = Parser {
+            text,
+            position: 0,
+            depth: 0,
+        };
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.position < text.len() {
+            return Err(parser.error("unexpected trailing characters"));
+        }
+        Ok(value)
+    }
+
+    /// Получение значения поля `key` объекта
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            Self::Object(fields) => fields
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Получение строкового значения
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Получение числового значения
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    /// Получение логического значения
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(flag) => Some(*flag),
+            _ => None,
+        }
+    }
+
+    /// Получение элементов массива
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            Self::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(flag: bool) -> Self {
+        Self::Bool(flag)
+    }
+}
+
+impl From<f64> for JsonValue {
+    fn from(number: f64) -> Self {
+        Self::Number(number)
+    }
+}
+
+impl From<f32> for JsonValue {
+    fn from(number: f32) -> Self {
+        Self::Number(number as f64)
+    }
+}
+
+impl From<usize> for JsonValue {
+    fn from(number: usize) -> Self {
+        Self::Number(number as f64)
+    }
+}
+
+impl From<u64> for JsonValue {
+    fn from(number: u64) -> Self {
+        Self::Number(number as f64)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(text: &str) -> Self {
+        Self::String(text.to_string())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(text: String) -> Self {
+        Self::String(text)
+    }
+}
+
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+impl<T: Into<JsonValue>> From<Vec<T>> for JsonValue {
+    fn from(items: Vec<T>) -> Self {
+        Self::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Bool(flag) => write!(f, "{}", flag),
+            Self::Number(number) if number.is_finite() => write!(f, "{}", number),
+            Self::Number(_) => write!(f, "null"),
+            Self::String(text) => write!(f, "{}", json_string(text)),
+            Self::Array(items) => {
+                write!(f, "[")?;
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Self::Object(fields) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{}", json_string(key), value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+/// Разборщик JSON методом рекурсивного спуска
+struct Parser<'a> {
+    /// Разбираемая строка
+    text: &'a str,
+
+    /// Текущая позиция в строке
+    position: usize,
+
+    /// Текущая глубина вложенности
+    depth: usize,
+}
+
+impl Parser<'_> {
+    /// Создание ошибки в текущей позиции
+    fn error(&self, message: &str) -> JsonError {
+        JsonError {
+            position: self.position,
+            message: message.to_string(),
+        }
+    }
+
+    /// Получение следующего символа без его извлечения
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    /// Извлечение следующего символа
+    fn next(&mut self) -> Option<char> {
+        let symbol = self.peek()?;
+        self.position += symbol.len_utf8();
+        Some(symbol)
+    }
+
+    /// Пропуск пробельных символов
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.position += 1;
+        }
+    }
+
+    /// Извлечение ожидаемого символа `expected`
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("expected '{}'", expected)));
+        }
+        self.position += expected.len_utf8();
+        Ok(())
+    }
+
+    /// Извлечение ключевого слова `keyword`
+    fn keyword(&mut self, keyword: &str, value: JsonValue) -> Result<JsonValue, JsonError> {
+        if self.text[self.position..].starts_with(keyword) {
+            self.position += keyword.len();
+            Ok(value)
+        } else {
+            Err(self.error("unexpected token"))
+        }
+    }
+
+    /// Разбор произвольного значения
+    fn parse_value(&mut self) -> Result<JsonValue, JsonError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.nested(Self::parse_object),
+            Some('[') => self.nested(Self::parse_array),
+            Some('"') => self.parse_string().map(JsonValue::String),
+            Some('t') => self.keyword("true", JsonValue::Bool(true)),
+            Some('f') => self.keyword("false", JsonValue::Bool(false)),
+            Some('n') => self.keyword("null", JsonValue::Null),
+            Some('-' | '0'..='9') => self.parse_number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    /// Разбор вложенного значения с контролем глубины
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<JsonValue, JsonError>,
+    ) -> Result<JsonValue, JsonError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error("nesting is too deep"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    /// Разбор объекта
+    fn parse_object(&mut self) -> Result<JsonValue, JsonError> {
+        self.expect('{')?;
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(JsonValue::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.push((key, self.parse_value()?));
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(JsonValue::Object(fields)),
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    /// Разбор массива
+    fn parse_array(&mut self) -> Result<JsonValue, JsonError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(JsonValue::Array(items));
+        }
+
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(JsonValue::Array(items)),
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    /// Разбор строки
+    fn parse_string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(text),
+                Some('\\') => {
+                    let symbol = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.parse_unicode_escape()?,
+                        _ => return Err(self.error("invalid escape sequence")),
+                    };
+                    text.push(symbol);
+                }
+                Some(symbol) if symbol.is_control() => {
+                    return Err(self.error("control character in string"))
+                }
+                Some(symbol) => text.push(symbol),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    /// Разбор экранированного символа `\uXXXX` (включая суррогатные пары)
+    fn parse_unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.parse_hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("invalid unicode escape"));
+        }
+
+        if !self.text[self.position..].starts_with("\\u") {
+            return Err(self.error("unpaired surrogate"));
+        }
+        self.position += 2;
+        let low = self.parse_hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(self.error("unpaired surrogate"));
+        }
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+            .ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    /// Разбор четырёх шестнадцатеричных цифр
+    fn parse_hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .text
+            .get(self.position..self.position + 4)
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        let code =
+            u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid unicode escape"))?;
+        self.position += 4;
+        Ok(code)
+    }
+
+    /// Разбор числа
+    fn parse_number(&mut self) -> Result<JsonValue, JsonError> {
+        let start = self.position;
+        while matches!(self.peek(), Some('-' | '+' | '.' | 'e' | 'E' | '0'..='9')) {
+            self.position += 1;
+        }
+        self.text[start..self.position]
+            .parse()
+            .map(JsonValue::Number)
+            .map_err(|_| JsonError {
+                position: start,
+                message: "invalid number".to_string(),
+            })
+    }
+}
+
+/// Представление строки в виде строкового литерала JSON
+pub(crate) fn json_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for symbol in text.chars() {
+        match symbol {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            symbol if symbol.is_control() => {
+                escaped.push_str(&format!("\\u{:04x}", symbol as u32));
+            }
+            symbol => escaped.push(symbol),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Имя состояния питания для выгрузки
+pub(crate) fn power_state_name(state: &SmartDevicePowerState) -> &'static str {
+    match state {
+        SmartDevicePowerState::Enabled => "enabled",
+        SmartDevicePowerState::Disabled => "disabled",
+    }
+}
+
+/// Имя ошибки контейнера для выгрузки
+pub(crate) fn error_name(reason: &ErrorReason) -> &'static str {
+    match reason {
+        ErrorReason::ItemLimitExceeded => "item_limit_exceeded",
+        ErrorReason::ItemAlreadyPresented => "item_already_presented",
+        ErrorReason::ItemDoesntExist => "item_doesnt_exist",
+        ErrorReason::InvalidName => "invalid_name",
+        ErrorReason::DeviceFailure(_) => "device_failure",
+        ErrorReason::UnsupportedCommand => "unsupported_command",
+        ErrorReason::DeviceBusy => "device_busy",
+    }
+}
+
+/// Имя кода ошибки устройства для выгрузки
+pub(crate) fn device_error_name(code: &SmartDeviceErrorCode) -> &'static str {
+    match code {
+        SmartDeviceErrorCode::Overcurrent => "overcurrent",
+        SmartDeviceErrorCode::Overvoltage => "overvoltage",
+        SmartDeviceErrorCode::Overheat => "overheat",
+        SmartDeviceErrorCode::Underheat => "underheat",
+        SmartDeviceErrorCode::Obstruction => "obstruction",
+        SmartDeviceErrorCode::PoweredOff => "powered_off",
+        SmartDeviceErrorCode::Jammed => "jammed",
+        SmartDeviceErrorCode::AccessDenied => "access_denied",
+        SmartDeviceErrorCode::Unreachable => "unreachable",
+    }
+}
+
+/// Представление статуса устройства в виде объекта JSON
+pub fn status_json(status: &SmartDeviceStatus) -> JsonValue {
+    match status {
+        SmartDeviceStatus::PowerState(state) => {
+            JsonValue::object().with("power_state", power_state_name(state))
+        }
+        SmartDeviceStatus::Malfunction(code) => {
+            JsonValue::object().with("malfunction", device_error_name(code))
+        }
+    }
+}
+
+/// Представление статуса и показаний устройства в виде объекта JSON:
+/// `{"power_state": .., "readings": {"<capability>": <value>, ..}}`
+pub fn state_json(device: &dyn SmartDevice) -> JsonValue {
+    match status_json(&device.get_device_status()) {
+        JsonValue::Object(mut fields) => {
+            fields.push(("readings".to_string(), readings_map_json(device)));
+            JsonValue::Object(fields)
+        }
+        status => status,
+    }
+}
+
+/// Представление показаний устройства в виде объекта JSON `{"<capability>": <value>, ..}`
+pub fn readings_map_json(device: &dyn SmartDevice) -> JsonValue {
+    JsonValue::Object(
+        device
+            .get_readings()
+            .into_iter()
+            .map(|(capability, value)| (capability.to_string(), JsonValue::from(value)))
+            .collect(),
+    )
+}
+
+/// Разбор команды включения/выключения устройства
+///
+/// Принимаются `ON`/`OFF`, `enabled`/`disabled`, `true`/`false` и `{"state": "enabled"}`
+pub fn parse_power_command(payload: &[u8]) -> Option<SmartDevicePowerState> {
+    let text = std::str::from_utf8(payload).ok()?.trim();
+    let text = match JsonValue::parse(text) {
+        Ok(JsonValue::Object(fields)) => fields
+            .into_iter()
+            .find(|(key, _)| key == "state")
+            .and_then(|(_, value)| value.as_str().map(str::to_string))?,
+        _ => text.to_string(),
+    };
+
+    match text.to_ascii_lowercase().as_str() {
+        "on" | "enabled" | "true" => Some(SmartDevicePowerState::Enabled),
+        "off" | "disabled" | "false" => Some(SmartDevicePowerState::Disabled),
+        _ => None,
+    }
+}
+
+/// Представление числовых показаний устройства в виде массива JSON
+pub fn readings_json(readings: &[(Capability, f32)]) -> JsonValue {
+    JsonValue::Array(
+        readings
+            .iter()
+            .map(|(capability, value)| {
+                JsonValue::object()
+                    .with("capability", capability.to_string())
+                    .with("value", *value)
+                    .with("unit", capability.unit())
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_survive_round_trip() {
+        let value = JsonValue::object()
+            .with("name", "Kitchen \"main\"\n")
+            .with("limit", 3usize)
+            .with("power", 12.5f64)
+            .with("enabled", true)
+            .with("floor", Option::<&str>::None)
+            .with("devices", vec!["Socket", "Термометр"]);
+
+        let text = value.to_string();
+        assert_eq!(JsonValue::parse(&text), Ok(value.clone()));
+        assert_eq!(
+            value
+                .get("devices")
+                .and_then(JsonValue::as_array)
+                .map(<[_]>::len),
+            Some(2)
+        );
+        assert_eq!(
+            JsonValue::parse(r#" {"a": [1, -2.5e1, "é😀"]} "#)
+                .ok()
+                .and_then(|value| value.get("a").cloned()),
+            Some(JsonValue::Array(vec![
+                JsonValue::Number(1.0),
+                JsonValue::Number(-25.0),
+                JsonValue::String("é😀".to_string()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn malformed_input_is_rejected_with_position() {
+        assert_eq!(
+            JsonValue::parse("{\"a\":1,}").map_err(|e| e.position),
+            Err(7)
+        );
+        assert!(JsonValue::parse("[1 2]").is_err());
+        assert!(JsonValue::parse("\"open").is_err());
+        assert!(JsonValue::parse(&"[".repeat(100)).is_err());
+    }
+
+    #[test]
+    fn power_commands_are_parsed() {
+        assert_eq!(
+            parse_power_command(b"ON"),
+            Some(SmartDevicePowerState::Enabled)
+        );
+        assert_eq!(
+            parse_power_command(b" disabled\n"),
+            Some(SmartDevicePowerState::Disabled)
+        );
+        assert_eq!(
+            parse_power_command(br#"{"state": "enabled"}"#),
+            Some(SmartDevicePowerState::Enabled)
+        );
+        assert_eq!(parse_power_command(b"toggle"), None);
+    }
+}
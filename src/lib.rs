@@ -1,10 +1,45 @@
+/// Асинхронный интерфейс устройств и дома (cargo-функция `async`)
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod audit;
+/// CoAP-сервер для устройств умного дома (cargo-функция `coap`)
+#[cfg(feature = "coap")]
+pub mod coap;
 pub mod containers;
 /// Модуль, определяющий поведение устройств в системе "Умных дом"
 /// Также модуль содержит в себе модули, описывающие конкретные устройства
 pub mod devices;
+pub mod events;
+pub mod history;
+/// HTTP-сервер REST API умного дома (cargo-функция `http`)
+#[cfg(feature = "http")]
+pub mod http;
+pub mod json;
+/// Шлюз Modbus TCP для устройств умного дома (cargo-функция `modbus`)
+#[cfg(feature = "modbus")]
+pub mod modbus;
+/// Интеграция умного дома с брокером MQTT (cargo-функция `mqtt`)
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod query;
+pub mod storage;
+pub mod units;
+/// Поток событий умного дома по протоколу WebSocket (cargo-функция `websocket`)
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
+pub use containers::editor;
+pub use containers::floor;
+pub use containers::group;
 pub use containers::house;
 pub use containers::room;
+pub use containers::shared;
+pub use devices::blinds;
+pub use devices::command;
+pub use devices::hvac;
+pub use devices::lock;
+pub use devices::metadata;
+pub use devices::pipeline;
 pub use devices::smart_device;
 pub use devices::socket;
 pub use devices::thermometer;
@@ -0,0 +1,134 @@
+//! Модуль содержит клиент протокола Modbus TCP
+//!
+//! > Клиент выполняет запросы последовательно: очередной запрос отправляется после
+//! > получения ответа на предыдущий. Исключения Modbus возвращаются как ошибки
+//! > ввода-вывода вида [`io::ErrorKind::Other`], содержащие [`ModbusException`].
+//!
+use super::frame::{ModbusException, ModbusFrame, ModbusRequest, ModbusResponse};
+use std::io::{self, BufReader};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+/// Время ожидания подключения и ответа удалённого устройства по умолчанию
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Тип, описывающий подключение к удалённому устройству Modbus TCP
+pub struct ModbusClient {
+    /// Поток для записи запросов
+    writer: TcpStream,
+
+    /// Буферизованный поток для чтения ответов
+    reader: BufReader<TcpStream>,
+
+    /// Идентификатор устройства
+    unit_id: u8,
+
+    /// Идентификатор следующей транзакции
+    next_transaction_id: u16,
+}
+
+impl ModbusClient {
+    /// Подключение к устройству `unit_id` по адресу `address`
+    /// с временем ожидания [`DEFAULT_TIMEOUT`]
+    pub fn connect(address: SocketAddr, unit_id: u8) -> io::Result<Self> {
+        Self::connect_timeout(address, unit_id, DEFAULT_TIMEOUT)
+    }
+
+    /// Подключение к устройству `unit_id` по адресу `address` с временем ожидания `timeout`
+    pub fn connect_timeout(
+        address: SocketAddr,
+        unit_id: u8,
+        timeout: Duration,
+    ) -> io::Result<Self> {
+        let writer = TcpStream::connect_timeout(&address, timeout)?;
+        writer.set_nodelay(true)?;
+        writer.set_read_timeout(Some(timeout))?;
+        writer.set_write_timeout(Some(timeout))?;
+
+        Ok(Self {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+            unit_id,
+            next_transaction_id: 1,
+        })
+    }
+
+    /// Выполнение запроса
+    ///
+    /// Исключение Modbus возвращается как ответ [`ModbusResponse::Exception`]
+    pub fn call(&mut self, request: &ModbusRequest) -> io::Result<ModbusResponse> {
+        let transaction_id = self.next_transaction_id;
+        self.next_transaction_id = self.next_transaction_id.wrapping_add(1);
+        ModbusFrame {
+            transaction_id,
+            unit_id: self.unit_id,
+            pdu: request.encode(),
+        }
+        .write_to(&mut self.writer)?;
+
+        let frame = ModbusFrame::read_from(&mut self.reader)?;
+        if frame.transaction_id != transaction_id {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected transaction identifier",
+            ));
+        }
+        ModbusResponse::decode(request, &frame.pdu)
+    }
+
+    /// Чтение `count` катушек, начиная с адреса `address`
+    pub fn read_coils(&mut self, address: u16, count: u16) -> io::Result<Vec<bool>> {
+        match self.call(&ModbusRequest::ReadCoils { address, count })? {
+            ModbusResponse::Coils(values) => Ok(values),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Чтение `count` регистров хранения, начиная с адреса `address`
+    pub fn read_holding_registers(&mut self, address: u16, count: u16) -> io::Result<Vec<u16>> {
+        match self.call(&ModbusRequest::ReadHoldingRegisters { address, count })? {
+            ModbusResponse::Registers(values) => Ok(values),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Чтение `count` входных регистров, начиная с адреса `address`
+    pub fn read_input_registers(&mut self, address: u16, count: u16) -> io::Result<Vec<u16>> {
+        match self.call(&ModbusRequest::ReadInputRegisters { address, count })? {
+            ModbusResponse::Registers(values) => Ok(values),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Запись катушки
+    pub fn write_single_coil(&mut self, address: u16, value: bool) -> io::Result<()> {
+        match self.call(&ModbusRequest::WriteSingleCoil { address, value })? {
+            ModbusResponse::Written { .. } => Ok(()),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Запись регистра хранения
+    pub fn write_single_register(&mut self, address: u16, value: u16) -> io::Result<()> {
+        match self.call(&ModbusRequest::WriteSingleRegister { address, value })? {
+            ModbusResponse::Written { .. } => Ok(()),
+            response => Err(unexpected(response)),
+        }
+    }
+}
+
+/// Ошибка для ответа, не соответствующего запросу
+fn unexpected(response: ModbusResponse) -> io::Error {
+    match response {
+        ModbusResponse::Exception(exception) => io::Error::other(exception),
+        _ => io::Error::new(io::ErrorKind::InvalidData, "unexpected response"),
+    }
+}
+
+/// Получение исключения Modbus, содержащегося в ошибке клиента
+pub fn get_exception(error: &io::Error) -> Option<ModbusException> {
+    error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<ModbusException>())
+        .copied()
+}
@@ -0,0 +1,483 @@
+//! Модуль содержит кадры протокола Modbus TCP и их двоичное представление
+//!
+//! > Кадр состоит из заголовка MBAP (идентификатор транзакции, идентификатор протокола,
+//! > длина, идентификатор устройства) и блока данных протокола (PDU). Поддерживаются
+//! > функции чтения катушек (`0x01`), регистров хранения (`0x03`) и входных регистров
+//! > (`0x04`), а также записи катушек (`0x05`, `0x0F`) и регистров хранения (`0x06`, `0x10`).
+//!
+use std::fmt::{self, Display};
+use std::io::{self, Read, Write};
+
+/// Максимальный размер блока данных протокола в байтах
+const MAX_PDU_SIZE: usize = 253;
+
+/// Максимальное количество катушек в запросе чтения
+const MAX_READ_COILS: u16 = 2000;
+
+/// Максимальное количество регистров в запросе чтения
+const MAX_READ_REGISTERS: u16 = 125;
+
+/// Максимальное количество катушек в запросе записи
+const MAX_WRITE_COILS: u16 = 1968;
+
+/// Максимальное количество регистров в запросе записи
+const MAX_WRITE_REGISTERS: u16 = 123;
+
+/// Коды функций Modbus
+pub mod function {
+    /// Чтение катушек
+    pub const READ_COILS: u8 = 0x01;
+    /// Чтение регистров хранения
+    pub const READ_HOLDING_REGISTERS: u8 = 0x03;
+    /// Чтение входных регистров
+    pub const READ_INPUT_REGISTERS: u8 = 0x04;
+    /// Запись одной катушки
+    pub const WRITE_SINGLE_COIL: u8 = 0x05;
+    /// Запись одного регистра хранения
+    pub const WRITE_SINGLE_REGISTER: u8 = 0x06;
+    /// Запись нескольких катушек
+    pub const WRITE_MULTIPLE_COILS: u8 = 0x0F;
+    /// Запись нескольких регистров хранения
+    pub const WRITE_MULTIPLE_REGISTERS: u8 = 0x10;
+}
+
+/// Кадр Modbus TCP
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModbusFrame {
+    /// Идентификатор транзакции (копируется в ответ)
+    pub transaction_id: u16,
+
+    /// Идентификатор устройства
+    pub unit_id: u8,
+
+    /// Блок данных протокола
+    pub pdu: Vec<u8>,
+}
+
+impl ModbusFrame {
+    /// Запись кадра в поток
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if self.pdu.is_empty() || self.pdu.len() > MAX_PDU_SIZE {
+            return Err(invalid("invalid PDU size"));
+        }
+        let mut bytes = Vec::with_capacity(7 + self.pdu.len());
+        bytes.extend_from_slice(&self.transaction_id.to_be_bytes());
+        bytes.extend_from_slice(&0u16.to_be_bytes());
+        bytes.extend_from_slice(&(self.pdu.len() as u16 + 1).to_be_bytes());
+        bytes.push(self.unit_id);
+        bytes.extend_from_slice(&self.pdu);
+        writer.write_all(&bytes)?;
+        writer.flush()
+    }
+
+    /// Чтение кадра из потока
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut header = [0; 7];
+        reader.read_exact(&mut header)?;
+        let protocol_id = u16::from_be_bytes([header[2], header[3]]);
+        let length = u16::from_be_bytes([header[4], header[5]]) as usize;
+        if protocol_id != 0 {
+            return Err(invalid("unsupported protocol identifier"));
+        }
+        if length < 2 || length - 1 > MAX_PDU_SIZE {
+            return Err(invalid("invalid frame length"));
+        }
+
+        let mut pdu = vec![0; length - 1];
+        reader.read_exact(&mut pdu)?;
+        Ok(Self {
+            transaction_id: u16::from_be_bytes([header[0], header[1]]),
+            unit_id: header[6],
+            pdu,
+        })
+    }
+}
+
+/// Перечисление исключений Modbus
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModbusException {
+    /// Функция не поддерживается
+    IllegalFunction,
+    /// Адрес не соответствует ни одному устройству
+    IllegalDataAddress,
+    /// Недопустимое значение в запросе
+    IllegalDataValue,
+    /// Устройство не смогло выполнить запрос
+    ServerDeviceFailure,
+}
+
+impl ModbusException {
+    /// Получение кода исключения
+    pub fn get_code(&self) -> u8 {
+        match self {
+            Self::IllegalFunction => 0x01,
+            Self::IllegalDataAddress => 0x02,
+            Self::IllegalDataValue => 0x03,
+            Self::ServerDeviceFailure => 0x04,
+        }
+    }
+
+    /// Получение исключения по коду
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0x01 => Some(Self::IllegalFunction),
+            0x02 => Some(Self::IllegalDataAddress),
+            0x03 => Some(Self::IllegalDataValue),
+            0x04 => Some(Self::ServerDeviceFailure),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ModbusException {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IllegalFunction => write!(f, "Illegal function."),
+            Self::IllegalDataAddress => write!(f, "Illegal data address."),
+            Self::IllegalDataValue => write!(f, "Illegal data value."),
+            Self::ServerDeviceFailure => write!(f, "Server device failure."),
+        }
+    }
+}
+
+impl std::error::Error for ModbusException {}
+
+/// Перечисление запросов Modbus
+#[derive(Clone, Debug, PartialEq)]
+pub enum ModbusRequest {
+    /// Чтение катушек
+    ReadCoils {
+        /// Адрес первой катушки
+        address: u16,
+        /// Количество катушек
+        count: u16,
+    },
+    /// Чтение регистров хранения
+    ReadHoldingRegisters {
+        /// Адрес первого регистра
+        address: u16,
+        /// Количество регистров
+        count: u16,
+    },
+    /// Чтение входных регистров
+    ReadInputRegisters {
+        /// Адрес первого регистра
+        address: u16,
+        /// Количество регистров
+        count: u16,
+    },
+    /// Запись катушки
+    WriteSingleCoil {
+        /// Адрес катушки
+        address: u16,
+        /// Значение катушки
+        value: bool,
+    },
+    /// Запись регистра хранения
+    WriteSingleRegister {
+        /// Адрес регистра
+        address: u16,
+        /// Значение регистра
+        value: u16,
+    },
+    /// Запись нескольких катушек
+    WriteMultipleCoils {
+        /// Адрес первой катушки
+        address: u16,
+        /// Значения катушек
+        values: Vec<bool>,
+    },
+    /// Запись нескольких регистров хранения
+    WriteMultipleRegisters {
+        /// Адрес первого регистра
+        address: u16,
+        /// Значения регистров
+        values: Vec<u16>,
+    },
+}
+
+impl ModbusRequest {
+    /// Получение кода функции запроса
+    pub fn get_function(&self) -> u8 {
+        match self {
+            Self::ReadCoils { .. } => function::READ_COILS,
+            Self::ReadHoldingRegisters { .. } => function::READ_HOLDING_REGISTERS,
+            Self::ReadInputRegisters { .. } => function::READ_INPUT_REGISTERS,
+            Self::WriteSingleCoil { .. } => function::WRITE_SINGLE_COIL,
+            Self::WriteSingleRegister { .. } => function::WRITE_SINGLE_REGISTER,
+            Self::WriteMultipleCoils { .. } => function::WRITE_MULTIPLE_COILS,
+            Self::WriteMultipleRegisters { .. } => function::WRITE_MULTIPLE_REGISTERS,
+        }
+    }
+
+    /// Кодирование запроса в блок данных протокола
+    pub fn encode(&self) -> Vec<u8> {
+        let mut pdu = vec![self.get_function()];
+        match self {
+            Self::ReadCoils { address, count }
+            | Self::ReadHoldingRegisters { address, count }
+            | Self::ReadInputRegisters { address, count } => {
+                push_words(&mut pdu, &[*address, *count]);
+            }
+            Self::WriteSingleCoil { address, value } => {
+                push_words(&mut pdu, &[*address, if *value { 0xFF00 } else { 0 }]);
+            }
+            Self::WriteSingleRegister { address, value } => {
+                push_words(&mut pdu, &[*address, *value]);
+            }
+            Self::WriteMultipleCoils { address, values } => {
+                push_words(&mut pdu, &[*address, values.len() as u16]);
+                let bits = pack_bits(values);
+                pdu.push(bits.len() as u8);
+                pdu.extend_from_slice(&bits);
+            }
+            Self::WriteMultipleRegisters { address, values } => {
+                push_words(&mut pdu, &[*address, values.len() as u16]);
+                pdu.push((values.len() * 2) as u8);
+                push_words(&mut pdu, values);
+            }
+        }
+        pdu
+    }
+
+    /// Разбор блока данных протокола
+    ///
+    /// Ошибка содержит исключение, которое следует вернуть клиенту
+    pub fn decode(pdu: &[u8]) -> Result<Self, ModbusException> {
+        let (&function, data) = pdu.split_first().ok_or(ModbusException::IllegalFunction)?;
+        let word = |index: usize| -> Result<u16, ModbusException> {
+            data.get(index * 2..index * 2 + 2)
+                .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+                .ok_or(ModbusException::IllegalDataValue)
+        };
+
+        let request = match function {
+            function::READ_COILS
+            | function::READ_HOLDING_REGISTERS
+            | function::READ_INPUT_REGISTERS => {
+                let (address, count) = (word(0)?, word(1)?);
+                let limit = match function {
+                    function::READ_COILS => MAX_READ_COILS,
+                    _ => MAX_READ_REGISTERS,
+                };
+                if count == 0 || count > limit {
+                    return Err(ModbusException::IllegalDataValue);
+                }
+                match function {
+                    function::READ_COILS => Self::ReadCoils { address, count },
+                    function::READ_HOLDING_REGISTERS => {
+                        Self::ReadHoldingRegisters { address, count }
+                    }
+                    _ => Self::ReadInputRegisters { address, count },
+                }
+            }
+            function::WRITE_SINGLE_COIL => Self::WriteSingleCoil {
+                address: word(0)?,
+                value: match word(1)? {
+                    0xFF00 => true,
+                    0x0000 => false,
+                    _ => return Err(ModbusException::IllegalDataValue),
+                },
+            },
+            function::WRITE_SINGLE_REGISTER => Self::WriteSingleRegister {
+                address: word(0)?,
+                value: word(1)?,
+            },
+            function::WRITE_MULTIPLE_COILS => {
+                let (address, count) = (word(0)?, word(1)?);
+                let bytes = data.get(5..).unwrap_or_default();
+                if count == 0
+                    || count > MAX_WRITE_COILS
+                    || data.get(4).map(|&size| size as usize) != Some(bytes.len())
+                    || bytes.len() != (count as usize).div_ceil(8)
+                {
+                    return Err(ModbusException::IllegalDataValue);
+                }
+                Self::WriteMultipleCoils {
+                    address,
+                    values: unpack_bits(bytes, count as usize),
+                }
+            }
+            function::WRITE_MULTIPLE_REGISTERS => {
+                let (address, count) = (word(0)?, word(1)?);
+                let bytes = data.get(5..).unwrap_or_default();
+                if count == 0
+                    || count > MAX_WRITE_REGISTERS
+                    || data.get(4).map(|&size| size as usize) != Some(bytes.len())
+                    || bytes.len() != count as usize * 2
+                {
+                    return Err(ModbusException::IllegalDataValue);
+                }
+                Self::WriteMultipleRegisters {
+                    address,
+                    values: bytes
+                        .chunks_exact(2)
+                        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                        .collect(),
+                }
+            }
+            _ => return Err(ModbusException::IllegalFunction),
+        };
+        Ok(request)
+    }
+}
+
+/// Перечисление ответов Modbus
+#[derive(Clone, Debug, PartialEq)]
+pub enum ModbusResponse {
+    /// Значения прочитанных катушек
+    Coils(Vec<bool>),
+    /// Значения прочитанных регистров
+    Registers(Vec<u16>),
+    /// Подтверждение записи
+    Written {
+        /// Адрес первого записанного элемента
+        address: u16,
+        /// Записанное значение (для записи одного элемента)
+        /// или количество записанных элементов
+        value: u16,
+    },
+    /// Исключение
+    Exception(ModbusException),
+}
+
+impl ModbusResponse {
+    /// Кодирование ответа на запрос с кодом функции `function`
+    pub fn encode(&self, function: u8) -> Vec<u8> {
+        let mut pdu = vec![function];
+        match self {
+            Self::Coils(values) => {
+                let bits = pack_bits(values);
+                pdu.push(bits.len() as u8);
+                pdu.extend_from_slice(&bits);
+            }
+            Self::Registers(values) => {
+                pdu.push((values.len() * 2) as u8);
+                push_words(&mut pdu, values);
+            }
+            Self::Written { address, value } => push_words(&mut pdu, &[*address, *value]),
+            Self::Exception(exception) => {
+                pdu[0] |= 0x80;
+                pdu.push(exception.get_code());
+            }
+        }
+        pdu
+    }
+
+    /// Разбор ответа на запрос `request`
+    pub fn decode(request: &ModbusRequest, pdu: &[u8]) -> io::Result<Self> {
+        let (&function, data) = pdu.split_first().ok_or_else(|| invalid("empty PDU"))?;
+        if function == request.get_function() | 0x80 {
+            return data
+                .first()
+                .and_then(|&code| ModbusException::from_code(code))
+                .map(Self::Exception)
+                .ok_or_else(|| invalid("unknown exception code"));
+        }
+        if function != request.get_function() {
+            return Err(invalid("unexpected function code"));
+        }
+
+        match request {
+            ModbusRequest::ReadCoils { count, .. } => {
+                let bytes = data.get(1..).unwrap_or_default();
+                if data.first().map(|&size| size as usize) != Some(bytes.len())
+                    || bytes.len() != (*count as usize).div_ceil(8)
+                {
+                    return Err(invalid("malformed coil values"));
+                }
+                Ok(Self::Coils(unpack_bits(bytes, *count as usize)))
+            }
+            ModbusRequest::ReadHoldingRegisters { count, .. }
+            | ModbusRequest::ReadInputRegisters { count, .. } => {
+                let bytes = data.get(1..).unwrap_or_default();
+                if data.first().map(|&size| size as usize) != Some(bytes.len())
+                    || bytes.len() != *count as usize * 2
+                {
+                    return Err(invalid("malformed register values"));
+                }
+                Ok(Self::Registers(
+                    bytes
+                        .chunks_exact(2)
+                        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                        .collect(),
+                ))
+            }
+            _ => match data {
+                [a, b, c, d] => Ok(Self::Written {
+                    address: u16::from_be_bytes([*a, *b]),
+                    value: u16::from_be_bytes([*c, *d]),
+                }),
+                _ => Err(invalid("malformed write confirmation")),
+            },
+        }
+    }
+}
+
+/// Добавление слов в порядке big-endian
+fn push_words(pdu: &mut Vec<u8>, words: &[u16]) {
+    for word in words {
+        pdu.extend_from_slice(&word.to_be_bytes());
+    }
+}
+
+/// Упаковка значений катушек в байты (младший бит - первая катушка)
+fn pack_bits(values: &[bool]) -> Vec<u8> {
+    let mut bytes = vec![0; values.len().div_ceil(8)];
+    for (index, _) in values.iter().enumerate().filter(|(_, value)| **value) {
+        bytes[index / 8] |= 1 << (index % 8);
+    }
+    bytes
+}
+
+/// Распаковка `count` значений катушек из байтов
+fn unpack_bits(bytes: &[u8], count: usize) -> Vec<bool> {
+    (0..count)
+        .map(|index| bytes[index / 8] & (1 << (index % 8)) != 0)
+        .collect()
+}
+
+/// Ошибка разбора кадра
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_and_responses_round_trip() {
+        let requests = [
+            ModbusRequest::ReadCoils {
+                address: 3,
+                count: 10,
+            },
+            ModbusRequest::WriteMultipleCoils {
+                address: 0,
+                values: vec![true, false, true, true, false, false, false, false, true],
+            },
+            ModbusRequest::WriteMultipleRegisters {
+                address: 7,
+                values: vec![0, 0xBEEF],
+            },
+        ];
+        for request in requests {
+            assert_eq!(ModbusRequest::decode(&request.encode()), Ok(request));
+        }
+
+        let request = ModbusRequest::ReadCoils {
+            address: 0,
+            count: 3,
+        };
+        let response = ModbusResponse::Coils(vec![true, false, true]);
+        let pdu = response.encode(request.get_function());
+        assert_eq!(pdu, [0x01, 0x01, 0b101]);
+        assert_eq!(ModbusResponse::decode(&request, &pdu).unwrap(), response);
+
+        let exception = ModbusResponse::Exception(ModbusException::IllegalDataAddress);
+        let pdu = exception.encode(request.get_function());
+        assert_eq!(pdu, [0x81, 0x02]);
+        assert_eq!(ModbusResponse::decode(&request, &pdu).unwrap(), exception);
+    }
+}
@@ -0,0 +1,289 @@
+//! Модуль содержит шлюз Modbus TCP для устройств умного дома
+//!
+//! > Модуль доступен при включённой cargo-функции `modbus`. Карта регистров строится
+//! > по топологии дома: комнаты и устройства упорядочиваются по названию, и `i`-е
+//! > устройство (начиная с нуля) получает адреса:
+//! >
+//! > | Область              | Адрес       | Доступ | Содержимое                                    |
+//! > |----------------------|-------------|--------|-----------------------------------------------|
+//! > | Катушка              | `i`         | R/W    | питание: `1` - включено, `0` - выключено      |
+//! > | Входной регистр      | `3i`        | R      | температура в 0,1 °C (знаковое), `0x8000` - нет |
+//! > | Входные регистры     | `3i+1..3i+2`| R      | потребляемая мощность в 0,1 Вт (32 бита, старшее слово первым) |
+//! > | Регистр хранения     | `i`         | R/W    | код ошибки (см. [`error_code_register`]); запись `0` - сброс ошибки |
+//! >
+//! > Актуальная карта конкретного дома выводится через [`RegisterMap`] (`Display`).
+//! > Устройства, подключённые по Modbus TCP, могут быть добавлены в комнату
+//! > (см. модуль [`remote`]).
+//!
+pub mod client;
+pub mod frame;
+pub mod remote;
+pub mod server;
+
+use crate::containers::shared::SharedHouse;
+use crate::containers::ContainerName;
+use crate::metadata::DeviceKind;
+use crate::smart_device::{SmartDevice, SmartDeviceErrorCode, SmartDeviceStatus};
+use crate::units::{Celsius, Watts};
+use std::fmt::{self, Display};
+
+/// Количество входных регистров, отводимых одному устройству
+pub const INPUT_REGISTERS_PER_DEVICE: u16 = 3;
+
+/// Значение регистра температуры у устройства без датчика температуры
+pub const TEMPERATURE_UNAVAILABLE: u16 = 0x8000;
+
+/// Максимальное количество устройств в карте регистров
+const MAX_DEVICES: usize = (u16::MAX as usize + 1) / INPUT_REGISTERS_PER_DEVICE as usize;
+
+/// Получение значения регистра для кода ошибки устройства (`0` - ошибки нет)
+pub fn error_code_register(code: Option<&SmartDeviceErrorCode>) -> u16 {
+    match code {
+        None => 0,
+        Some(SmartDeviceErrorCode::Overcurrent) => 1,
+        Some(SmartDeviceErrorCode::Overvoltage) => 2,
+        Some(SmartDeviceErrorCode::Overheat) => 3,
+        Some(SmartDeviceErrorCode::Underheat) => 4,
+        Some(SmartDeviceErrorCode::Obstruction) => 5,
+        Some(SmartDeviceErrorCode::PoweredOff) => 6,
+        Some(SmartDeviceErrorCode::Jammed) => 7,
+        Some(SmartDeviceErrorCode::AccessDenied) => 8,
+        Some(SmartDeviceErrorCode::Unreachable) => 9,
+    }
+}
+
+/// Получение кода ошибки устройства по значению регистра
+///
+/// Для `0` и неизвестных значений возвращается `None`
+pub fn error_code_from_register(value: u16) -> Option<SmartDeviceErrorCode> {
+    match value {
+        1 => Some(SmartDeviceErrorCode::Overcurrent),
+        2 => Some(SmartDeviceErrorCode::Overvoltage),
+        3 => Some(SmartDeviceErrorCode::Overheat),
+        4 => Some(SmartDeviceErrorCode::Underheat),
+        5 => Some(SmartDeviceErrorCode::Obstruction),
+        6 => Some(SmartDeviceErrorCode::PoweredOff),
+        7 => Some(SmartDeviceErrorCode::Jammed),
+        8 => Some(SmartDeviceErrorCode::AccessDenied),
+        9 => Some(SmartDeviceErrorCode::Unreachable),
+        _ => None,
+    }
+}
+
+/// Получение значения регистра температуры (в 0,1 °C)
+pub fn temperature_register(temperature: Option<Celsius>) -> u16 {
+    match temperature {
+        Some(temperature) => {
+            let value = (temperature.0 * 10.0).round();
+            value.clamp(i16::MIN as f32 + 1.0, i16::MAX as f32) as i16 as u16
+        }
+        None => TEMPERATURE_UNAVAILABLE,
+    }
+}
+
+/// Получение температуры по значению регистра
+pub fn temperature_from_register(value: u16) -> Option<Celsius> {
+    match value {
+        TEMPERATURE_UNAVAILABLE => None,
+        value => Some(Celsius(value as i16 as f32 / 10.0)),
+    }
+}
+
+/// Получение значений пары регистров потребляемой мощности (в 0,1 Вт, старшее слово первым)
+pub fn power_registers(power: Watts) -> [u16; 2] {
+    let value = (power.0 * 10.0).round().clamp(0.0, u32::MAX as f32) as u32;
+    [(value >> 16) as u16, value as u16]
+}
+
+/// Получение потребляемой мощности по значениям пары регистров
+pub fn power_from_registers(registers: [u16; 2]) -> Watts {
+    let value = ((registers[0] as u32) << 16) | registers[1] as u32;
+    Watts(value as f32 / 10.0)
+}
+
+/// Получение значений входных регистров устройства
+pub fn input_registers(device: &dyn SmartDevice) -> [u16; INPUT_REGISTERS_PER_DEVICE as usize] {
+    let [high, low] = power_registers(device.get_power_consumption());
+    [temperature_register(device.get_temperature()), high, low]
+}
+
+/// Получение значения регистра хранения (кода ошибки) устройства
+pub fn holding_register(device: &dyn SmartDevice) -> u16 {
+    match device.get_device_status() {
+        SmartDeviceStatus::Malfunction(code) => error_code_register(Some(&code)),
+        SmartDeviceStatus::PowerState(_) => error_code_register(None),
+    }
+}
+
+/// Тип, описывающий адреса одного устройства в карте регистров
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegisterMapEntry {
+    /// Название комнаты
+    pub room: ContainerName,
+
+    /// Название устройства
+    pub device: ContainerName,
+
+    /// Тип устройства
+    pub kind: DeviceKind,
+
+    /// Адрес катушки питания
+    pub coil: u16,
+
+    /// Адрес первого входного регистра
+    pub input_register: u16,
+
+    /// Адрес регистра хранения
+    pub holding_register: u16,
+}
+
+/// Тип, описывающий карту регистров дома
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RegisterMap {
+    /// Устройства в порядке адресов
+    entries: Vec<RegisterMapEntry>,
+}
+
+impl RegisterMap {
+    /// Построение карты регистров по текущей топологии дома `house`
+    pub fn new(house: &SharedHouse) -> Self {
+        let mut room_names = house.get_room_list();
+        room_names.sort();
+
+        let mut entries = Vec::new();
+        for room_name in room_names {
+            let mut device_names = house.get_device_list(&room_name).unwrap_or_default();
+            device_names.sort();
+            for device_name in device_names {
+                let Ok(kind) = house.with_device(&room_name, &device_name, |device| {
+                    device.get_metadata().kind
+                }) else {
+                    continue;
+                };
+                if entries.len() == MAX_DEVICES {
+                    return Self { entries };
+                }
+
+                let index = entries.len() as u16;
+                entries.push(RegisterMapEntry {
+                    room: room_name.clone(),
+                    device: device_name,
+                    kind,
+                    coil: index,
+                    input_register: index * INPUT_REGISTERS_PER_DEVICE,
+                    holding_register: index,
+                });
+            }
+        }
+        Self { entries }
+    }
+
+    /// Получение устройств в порядке адресов
+    pub fn get_entries(&self) -> &[RegisterMapEntry] {
+        &self.entries
+    }
+
+    /// Получение адресов устройства `device_name` комнаты `room_name`
+    pub fn get_entry(&self, room_name: &str, device_name: &str) -> Option<&RegisterMapEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.room == room_name && entry.device == device_name)
+    }
+
+    /// Получение устройства, которому принадлежит катушка или регистр хранения `address`
+    pub fn get_by_coil(&self, address: u16) -> Option<&RegisterMapEntry> {
+        self.entries.get(address as usize)
+    }
+
+    /// Получение устройства, которому принадлежит входной регистр `address`,
+    /// и смещения регистра в блоке устройства
+    pub fn get_by_input_register(&self, address: u16) -> Option<(&RegisterMapEntry, usize)> {
+        let index = (address / INPUT_REGISTERS_PER_DEVICE) as usize;
+        let offset = (address % INPUT_REGISTERS_PER_DEVICE) as usize;
+        self.entries.get(index).map(|entry| (entry, offset))
+    }
+}
+
+impl Display for RegisterMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "| Room | Device | Kind | Coil | Input registers | Holding register |"
+        )?;
+        writeln!(f, "|---|---|---|---|---|---|")?;
+        for entry in &self.entries {
+            writeln!(
+                f,
+                "| {} | {} | {} | {} | {}-{} | {} |",
+                entry.room,
+                entry.device,
+                entry.kind,
+                entry.coil,
+                entry.input_register,
+                entry.input_register + INPUT_REGISTERS_PER_DEVICE - 1,
+                entry.holding_register
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::house::House;
+    use crate::room::Room;
+    use crate::socket::SmartSocket;
+    use crate::thermometer::SmartThermometer;
+
+    #[test]
+    fn register_map_follows_sorted_topology() {
+        let mut house = House::new("House", 2);
+        assert!(house.add_room(Room::new("Living Room", 1)).is_ok());
+        assert!(house.add_room(Room::new("Kitchen", 2)).is_ok());
+        assert!(house
+            .add_device(
+                "Living Room",
+                Box::new(SmartThermometer::new("Thermometer"))
+            )
+            .is_ok());
+        assert!(house
+            .add_device("Kitchen", Box::new(SmartSocket::new("Toaster")))
+            .is_ok());
+        assert!(house
+            .add_device("Kitchen", Box::new(SmartSocket::new("Kettle")))
+            .is_ok());
+
+        let map = RegisterMap::new(&SharedHouse::try_from(house).unwrap());
+        assert_eq!(
+            map.to_string(),
+            "| Room | Device | Kind | Coil | Input registers | Holding register |\n\
+             |---|---|---|---|---|---|\n\
+             | Kitchen | Kettle | socket | 0 | 0-2 | 0 |\n\
+             | Kitchen | Toaster | socket | 1 | 3-5 | 1 |\n\
+             | Living Room | Thermometer | thermometer | 2 | 6-8 | 2 |\n"
+        );
+        assert_eq!(
+            map.get_by_input_register(7)
+                .map(|(entry, offset)| (entry.device.as_str(), offset)),
+            Some(("Thermometer", 1))
+        );
+    }
+
+    #[test]
+    fn register_values_round_trip() {
+        let temperature = temperature_register(Some(Celsius(-12.34)));
+        assert_eq!(temperature_from_register(temperature), Some(Celsius(-12.3)));
+        assert_eq!(temperature_from_register(temperature_register(None)), None);
+        assert_eq!(
+            power_from_registers(power_registers(Watts(7500.5))),
+            Watts(7500.5)
+        );
+        for value in 0..=8 {
+            assert_eq!(
+                error_code_register(error_code_from_register(value).as_ref()),
+                value
+            );
+        }
+    }
+}
@@ -0,0 +1,510 @@
+//! Модуль содержит умные устройства, подключённые по протоколу Modbus TCP
+//!
+//! > [`ModbusSocket`] и [`ModbusThermometer`] - варианты умной розетки и умного термометра,
+//! > состояние и показания которых хранятся в удалённом устройстве Modbus TCP. Адреса
+//! > регистров задаются через [`ModbusRegisters`]; значения кодируются так же, как в
+//! > шлюзе умного дома (температура и мощность - в десятых долях, см. [`super`]).
+//! >
+//! > После первого обращения к устройству показания запрашиваются в фоновом потоке
+//! > с периодом обновления; методы получения статуса и показаний возвращают последние
+//! > полученные значения и не ждут ответа устройства. Немедленный опрос выполняется
+//! > методом `refresh`, а также при включении/выключении. Недоступность удалённого
+//! > устройства сообщается как [`SmartDeviceErrorCode::Unreachable`]; соединение
+//! > восстанавливается при следующем опросе.
+//!
+use super::client::{get_exception, ModbusClient, DEFAULT_TIMEOUT};
+use super::{
+    error_code_from_register, power_from_registers, temperature_from_register, RegisterMapEntry,
+};
+use crate::command::Capability;
+use crate::metadata::{default_device_id, DeviceKind, DeviceMetadata};
+use crate::smart_device::{
+    SmartDevice, SmartDeviceErrorCode, SmartDevicePowerState, SmartDeviceStatus,
+};
+use crate::units::{Celsius, TemperatureScale, Watts};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard, Once, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+/// Период обновления показаний по умолчанию
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Тип, описывающий адреса регистров удалённого устройства
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModbusRegisters {
+    /// Идентификатор устройства
+    pub unit_id: u8,
+
+    /// Адрес катушки питания
+    pub power_coil: u16,
+
+    /// Адрес входного регистра температуры (0,1 °C)
+    pub temperature_register: Option<u16>,
+
+    /// Адрес первого из двух входных регистров потребляемой мощности (0,1 Вт)
+    pub power_register: Option<u16>,
+
+    /// Адрес регистра хранения кода ошибки
+    pub error_register: Option<u16>,
+}
+
+impl ModbusRegisters {
+    /// Создание адресов устройства с катушкой питания `power_coil`
+    /// (идентификатор устройства - `1`, остальные регистры не используются)
+    pub fn new(power_coil: u16) -> Self {
+        Self {
+            unit_id: 1,
+            power_coil,
+            temperature_register: None,
+            power_register: None,
+            error_register: None,
+        }
+    }
+
+    /// Получение адресов устройства из карты регистров шлюза умного дома
+    pub fn from_entry(entry: &RegisterMapEntry) -> Self {
+        Self::new(entry.coil)
+            .with_temperature_register(entry.input_register)
+            .with_power_register(entry.input_register + 1)
+            .with_error_register(entry.holding_register)
+    }
+
+    /// Установка идентификатора устройства
+    pub fn with_unit_id(mut self, unit_id: u8) -> Self {
+        self.unit_id = unit_id;
+        self
+    }
+
+    /// Установка адреса входного регистра температуры
+    pub fn with_temperature_register(mut self, address: u16) -> Self {
+        self.temperature_register = Some(address);
+        self
+    }
+
+    /// Установка адреса первого входного регистра потребляемой мощности
+    pub fn with_power_register(mut self, address: u16) -> Self {
+        self.power_register = Some(address);
+        self
+    }
+
+    /// Установка адреса регистра хранения кода ошибки
+    pub fn with_error_register(mut self, address: u16) -> Self {
+        self.error_register = Some(address);
+        self
+    }
+}
+
+///
+/// Тип описывающий умную розетку, подключённую по протоколу Modbus TCP
+///
+pub struct ModbusSocket {
+    /// Пользовательский псевдоним для розетки
+    pub name: String,
+
+    /// Метаданные розетки (идентификатор, производитель, модель, версия прошивки)
+    metadata: DeviceMetadata,
+
+    /// Удалённое устройство
+    remote: RemoteDevice,
+}
+
+impl ModbusSocket {
+    /// Создание розетки с псевдонимом `name`, подключаемой к устройству по адресу `address`
+    ///
+    /// Подключение выполняется при первом обращении к устройству
+    pub fn new(name: &str, address: SocketAddr, registers: ModbusRegisters) -> Self {
+        Self {
+            name: name.to_string(),
+            metadata: DeviceMetadata::new(
+                DeviceKind::Socket,
+                "ModbusSocket",
+                &default_device_id(DeviceKind::Socket, name),
+            ),
+            remote: RemoteDevice::new(address, registers),
+        }
+    }
+
+    /// Установка идентификатора розетки `id` вместо идентификатора по умолчанию
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.metadata = self.metadata.with_id(id);
+        self
+    }
+
+    /// Установка периода фонового опроса (`Duration::ZERO` - только опрос через `refresh`)
+    pub fn with_refresh_interval(mut self, interval: Duration) -> Self {
+        self.remote.refresh_interval = interval;
+        self
+    }
+
+    /// Получение адресов регистров удалённого устройства
+    pub fn get_registers(&self) -> &ModbusRegisters {
+        &self.remote.endpoint.registers
+    }
+
+    /// Немедленный опрос удалённого устройства
+    pub fn refresh(&self) -> io::Result<()> {
+        self.remote.refresh()
+    }
+}
+
+impl SmartDevice for ModbusSocket {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    fn get_metadata(&self) -> &DeviceMetadata {
+        &self.metadata
+    }
+
+    fn set_power_state(
+        &mut self,
+        state: SmartDevicePowerState,
+    ) -> Result<(), SmartDeviceErrorCode> {
+        self.remote.set_power_state(state)
+    }
+
+    fn get_device_status(&self) -> SmartDeviceStatus {
+        self.remote.get_snapshot().status
+    }
+
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![Capability::PowerControl, Capability::PowerMetering]
+    }
+
+    fn get_power_consumption(&self) -> Watts {
+        self.remote.get_snapshot().power_consumption
+    }
+
+    fn get_text_report(&self) -> String {
+        let snapshot = self.remote.get_snapshot();
+        format!(
+            "Current power consumption is {}, status: {} \n",
+            snapshot.power_consumption, snapshot.status
+        )
+    }
+}
+
+///
+/// Тип описывающий умный термометр, подключённый по протоколу Modbus TCP
+///
+pub struct ModbusThermometer {
+    /// Пользовательский псевдоним для термометра
+    pub name: String,
+
+    /// Метаданные термометра (идентификатор, производитель, модель, версия прошивки)
+    metadata: DeviceMetadata,
+
+    /// Удалённое устройство
+    remote: RemoteDevice,
+}
+
+impl ModbusThermometer {
+    /// Создание термометра с псевдонимом `name`, подключаемого к устройству по адресу `address`
+    ///
+    /// Подключение выполняется при первом обращении к устройству
+    pub fn new(name: &str, address: SocketAddr, registers: ModbusRegisters) -> Self {
+        Self {
+            name: name.to_string(),
+            metadata: DeviceMetadata::new(
+                DeviceKind::Thermometer,
+                "ModbusThermometer",
+                &default_device_id(DeviceKind::Thermometer, name),
+            ),
+            remote: RemoteDevice::new(address, registers),
+        }
+    }
+
+    /// Установка идентификатора термометра `id` вместо идентификатора по умолчанию
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.metadata = self.metadata.with_id(id);
+        self
+    }
+
+    /// Установка периода фонового опроса (`Duration::ZERO` - только опрос через `refresh`)
+    pub fn with_refresh_interval(mut self, interval: Duration) -> Self {
+        self.remote.refresh_interval = interval;
+        self
+    }
+
+    /// Получение адресов регистров удалённого устройства
+    pub fn get_registers(&self) -> &ModbusRegisters {
+        &self.remote.endpoint.registers
+    }
+
+    /// Немедленный опрос удалённого устройства
+    pub fn refresh(&self) -> io::Result<()> {
+        self.remote.refresh()
+    }
+}
+
+impl SmartDevice for ModbusThermometer {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    fn get_metadata(&self) -> &DeviceMetadata {
+        &self.metadata
+    }
+
+    fn set_power_state(
+        &mut self,
+        state: SmartDevicePowerState,
+    ) -> Result<(), SmartDeviceErrorCode> {
+        self.remote.set_power_state(state)
+    }
+
+    fn get_device_status(&self) -> SmartDeviceStatus {
+        self.remote.get_snapshot().status
+    }
+
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![Capability::PowerControl, Capability::Temperature]
+    }
+
+    fn get_temperature(&self) -> Option<Celsius> {
+        self.remote.get_snapshot().temperature
+    }
+
+    fn get_text_report(&self) -> String {
+        self.get_text_report_in(TemperatureScale::Celsius)
+    }
+
+    fn get_text_report_in(&self, scale: TemperatureScale) -> String {
+        let snapshot = self.remote.get_snapshot();
+        match snapshot.temperature {
+            Some(temperature) => format!(
+                "Current temperature is {}, status: {}\n",
+                temperature.display_in(scale),
+                snapshot.status
+            ),
+            None => format!("Temperature is unavailable, status: {}\n", snapshot.status),
+        }
+    }
+}
+
+/// Последние полученные от удалённого устройства значения
+#[derive(Clone, Debug, PartialEq)]
+struct Snapshot {
+    /// Статус работы
+    status: SmartDeviceStatus,
+
+    /// Температура
+    temperature: Option<Celsius>,
+
+    /// Потребляемая мощность
+    power_consumption: Watts,
+}
+
+impl Snapshot {
+    /// Значения недоступного устройства
+    fn unavailable() -> Self {
+        Self {
+            status: SmartDeviceStatus::Malfunction(SmartDeviceErrorCode::Unreachable),
+            temperature: None,
+            power_consumption: Watts::default(),
+        }
+    }
+}
+
+/// Подключение к удалённому устройству и последние полученные значения
+///
+/// Значения защищены отдельной блокировкой, поэтому их чтение не ожидает опроса
+struct RemoteState {
+    /// Подключение (`None` - не установлено или разорвано)
+    client: Mutex<Option<ModbusClient>>,
+
+    /// Последние полученные значения
+    snapshot: Mutex<Snapshot>,
+}
+
+/// Адрес и регистры удалённого устройства
+#[derive(Clone)]
+struct Endpoint {
+    /// Адрес устройства
+    address: SocketAddr,
+
+    /// Адреса регистров
+    registers: ModbusRegisters,
+}
+
+/// Удалённое устройство Modbus TCP
+struct RemoteDevice {
+    /// Адрес и регистры устройства
+    endpoint: Endpoint,
+
+    /// Период фонового опроса (`Duration::ZERO` - фоновый опрос отключён)
+    refresh_interval: Duration,
+
+    /// Подключение и последние значения (разделяются с потоком фонового опроса)
+    state: Arc<RemoteState>,
+
+    /// Запуск потока фонового опроса при первом обращении
+    poller: Once,
+}
+
+impl RemoteDevice {
+    /// Создание удалённого устройства без подключения
+    fn new(address: SocketAddr, registers: ModbusRegisters) -> Self {
+        Self {
+            endpoint: Endpoint { address, registers },
+            refresh_interval: DEFAULT_REFRESH_INTERVAL,
+            state: Arc::new(RemoteState {
+                client: Mutex::new(None),
+                snapshot: Mutex::new(Snapshot::unavailable()),
+            }),
+            poller: Once::new(),
+        }
+    }
+
+    /// Получение последних полученных значений (без обращения к устройству)
+    fn get_snapshot(&self) -> Snapshot {
+        self.start_polling();
+        lock(&self.state.snapshot).clone()
+    }
+
+    /// Немедленный опрос устройства
+    fn refresh(&self) -> io::Result<()> {
+        self.start_polling();
+        self.endpoint
+            .poll(&self.state, &mut lock(&self.state.client))
+    }
+
+    /// Включение/выключение устройства записью катушки питания
+    fn set_power_state(
+        &self,
+        power_state: SmartDevicePowerState,
+    ) -> Result<(), SmartDeviceErrorCode> {
+        self.start_polling();
+        let endpoint = &self.endpoint;
+        let mut client = lock(&self.state.client);
+        if endpoint.poll(&self.state, &mut client).is_err() {
+            return Err(SmartDeviceErrorCode::Unreachable);
+        }
+        if let SmartDeviceStatus::Malfunction(code) = &lock(&self.state.snapshot).status {
+            return Err(code.clone());
+        }
+
+        let value = power_state == SmartDevicePowerState::Enabled;
+        let written = endpoint.with_client(&mut client, |client| {
+            client.write_single_coil(endpoint.registers.power_coil, value)
+        });
+        // Показания после переключения (или причина отказа) запрашиваются сразу
+        let polled = endpoint.poll(&self.state, &mut client);
+        match (written, polled) {
+            (Ok(()), _) => Ok(()),
+            (Err(_), Err(_)) => Err(SmartDeviceErrorCode::Unreachable),
+            (Err(_), Ok(())) => match &lock(&self.state.snapshot).status {
+                SmartDeviceStatus::Malfunction(code) => Err(code.clone()),
+                SmartDeviceStatus::PowerState(_) => Err(SmartDeviceErrorCode::Unreachable),
+            },
+        }
+    }
+
+    /// Запуск потока фонового опроса (однократно, если опрос не отключён)
+    ///
+    /// Поток завершается после удаления устройства
+    fn start_polling(&self) {
+        if self.refresh_interval.is_zero() {
+            return;
+        }
+        self.poller.call_once(|| {
+            let endpoint = self.endpoint.clone();
+            let state = Arc::downgrade(&self.state);
+            let interval = self.refresh_interval;
+            let _ = thread::Builder::new()
+                .name("iot-modbus-poll".to_string())
+                .spawn(move || {
+                    while let Some(state) = state.upgrade() {
+                        let _ = endpoint.poll(&state, &mut lock(&state.client));
+                        drop(state);
+                        thread::sleep(interval);
+                    }
+                });
+        });
+    }
+}
+
+impl Endpoint {
+    /// Опрос устройства и обновление значений
+    fn poll(&self, state: &RemoteState, client: &mut Option<ModbusClient>) -> io::Result<()> {
+        let result = self.with_client(client, |client| self.read(client));
+        *lock(&state.snapshot) = match &result {
+            Ok(snapshot) => snapshot.clone(),
+            Err(_) => Snapshot::unavailable(),
+        };
+        result.map(|_| ())
+    }
+
+    /// Чтение значений всех настроенных регистров
+    fn read(&self, client: &mut ModbusClient) -> io::Result<Snapshot> {
+        let registers = &self.registers;
+        let enabled = client.read_coils(registers.power_coil, 1)?[0];
+        let error_code = match registers.error_register {
+            Some(address) => {
+                error_code_from_register(client.read_holding_registers(address, 1)?[0])
+            }
+            None => None,
+        };
+        let temperature = match registers.temperature_register {
+            Some(address) => temperature_from_register(client.read_input_registers(address, 1)?[0]),
+            None => None,
+        };
+        let power_consumption = match registers.power_register {
+            Some(address) => {
+                let values = client.read_input_registers(address, 2)?;
+                power_from_registers([values[0], values[1]])
+            }
+            None => Watts::default(),
+        };
+
+        Ok(Snapshot {
+            status: match error_code {
+                Some(code) => SmartDeviceStatus::Malfunction(code),
+                None if enabled => SmartDeviceStatus::PowerState(SmartDevicePowerState::Enabled),
+                None => SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled),
+            },
+            temperature,
+            power_consumption,
+        })
+    }
+
+    /// Выполнение действия с подключением (с подключением при необходимости)
+    ///
+    /// После ошибки ввода-вывода подключение разрывается; исключения Modbus
+    /// подключение не разрывают
+    fn with_client<R, F>(&self, client: &mut Option<ModbusClient>, action: F) -> io::Result<R>
+    where
+        F: FnOnce(&mut ModbusClient) -> io::Result<R>,
+    {
+        let connection = match client {
+            Some(connection) => connection,
+            None => client.insert(ModbusClient::connect_timeout(
+                self.address,
+                self.registers.unit_id,
+                DEFAULT_TIMEOUT,
+            )?),
+        };
+        let result = action(connection);
+        if let Err(error) = &result {
+            if get_exception(error).is_none() {
+                *client = None;
+            }
+        }
+        result
+    }
+}
+
+/// Блокировка (значение остаётся согласованным и после паники другого потока)
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
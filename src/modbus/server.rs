@@ -0,0 +1,369 @@
+//! Модуль содержит сервер Modbus TCP умного дома
+//!
+//! > Сервер обслуживает каждое соединение в отдельном потоке и принимает запросы с любым
+//! > идентификатором устройства. Адреса, не входящие в карту регистров, приводят к
+//! > исключению `IllegalDataAddress`, а отказ устройства (в том числе удалённого из дома
+//! > после построения карты) - к исключению `ServerDeviceFailure`.
+//!
+use super::frame::{ModbusException, ModbusFrame, ModbusRequest, ModbusResponse};
+use super::{holding_register, input_registers, RegisterMap, RegisterMapEntry};
+use crate::command::SmartDeviceCommand;
+use crate::containers::shared::SharedHouse;
+use crate::smart_device::{SmartDevice, SmartDevicePowerState, SmartDeviceStatus};
+use std::io::{self, BufReader};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Время бездействия клиента, после которого соединение закрывается
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Тип, описывающий сервер Modbus TCP умного дома
+pub struct ModbusServer {
+    /// Слушающий сокет
+    listener: TcpListener,
+
+    /// Обслуживаемый дом
+    house: SharedHouse,
+
+    /// Карта регистров
+    register_map: Arc<RegisterMap>,
+}
+
+impl ModbusServer {
+    /// Создание сервера дома `house`, принимающего соединения на адресе `address`
+    ///
+    /// Карта регистров строится по топологии дома в момент вызова
+    pub fn bind<A: ToSocketAddrs>(address: A, house: SharedHouse) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(address)?,
+            register_map: Arc::new(RegisterMap::new(&house)),
+            house,
+        })
+    }
+
+    /// Получение адреса, на котором сервер принимает соединения
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Получение карты регистров сервера
+    pub fn get_register_map(&self) -> &RegisterMap {
+        &self.register_map
+    }
+
+    /// Обслуживание соединений в текущем потоке (без возврата управления)
+    pub fn run(self) -> io::Result<()> {
+        accept_loop(
+            self.listener,
+            self.house,
+            self.register_map,
+            Arc::new(AtomicBool::new(true)),
+        )
+    }
+
+    /// Запуск обслуживания соединений в фоновом потоке
+    pub fn spawn(self) -> io::Result<ModbusServerHandle> {
+        let address = self.local_addr()?;
+        let running = Arc::new(AtomicBool::new(true));
+        let flag = running.clone();
+        let thread = thread::Builder::new()
+            .name("iot-modbus".to_string())
+            .spawn(move || accept_loop(self.listener, self.house, self.register_map, flag))?;
+
+        Ok(ModbusServerHandle {
+            address,
+            running,
+            thread: Some(thread),
+        })
+    }
+}
+
+/// Дескриптор сервера Modbus TCP, запущенного в фоновом потоке
+///
+/// Сервер останавливается вызовом [`ModbusServerHandle::shutdown`] или при удалении дескриптора
+pub struct ModbusServerHandle {
+    /// Адрес сервера
+    address: SocketAddr,
+
+    /// Признак работы сервера
+    running: Arc<AtomicBool>,
+
+    /// Поток, принимающий соединения
+    thread: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl ModbusServerHandle {
+    /// Получение адреса, на котором сервер принимает соединения
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Остановка сервера
+    ///
+    /// Открытые соединения закрываются после обработки очередного запроса
+    pub fn shutdown(mut self) -> io::Result<()> {
+        self.stop()
+    }
+
+    /// Остановка приёма соединений и ожидание завершения потока
+    fn stop(&mut self) -> io::Result<()> {
+        let Some(thread) = self.thread.take() else {
+            return Ok(());
+        };
+        self.running.store(false, Ordering::SeqCst);
+
+        // Пробуждение потока, ожидающего соединения
+        let mut wake_address = self.address;
+        if wake_address.ip().is_unspecified() {
+            wake_address.set_ip(match wake_address {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        let _ = TcpStream::connect(wake_address);
+
+        thread
+            .join()
+            .map_err(|_| io::Error::other("Modbus server thread panicked"))?
+    }
+}
+
+impl Drop for ModbusServerHandle {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Приём соединений, пока установлен признак `running`
+fn accept_loop(
+    listener: TcpListener,
+    house: SharedHouse,
+    register_map: Arc<RegisterMap>,
+    running: Arc<AtomicBool>,
+) -> io::Result<()> {
+    for stream in listener.incoming() {
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let house = house.clone();
+        let register_map = register_map.clone();
+        let running = running.clone();
+        thread::spawn(move || {
+            let _ = handle_connection(stream, &house, &register_map, &running);
+        });
+    }
+    Ok(())
+}
+
+/// Обработка запросов одного соединения до его закрытия
+fn handle_connection(
+    stream: TcpStream,
+    house: &SharedHouse,
+    register_map: &RegisterMap,
+    running: &AtomicBool,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+    stream.set_nodelay(true)?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+
+    while running.load(Ordering::SeqCst) {
+        let frame = ModbusFrame::read_from(&mut reader)?;
+        let function = frame.pdu[0] & 0x7F;
+        let response = match ModbusRequest::decode(&frame.pdu) {
+            Ok(request) => handle_request(house, register_map, &request),
+            Err(exception) => ModbusResponse::Exception(exception),
+        };
+
+        ModbusFrame {
+            transaction_id: frame.transaction_id,
+            unit_id: frame.unit_id,
+            pdu: response.encode(function),
+        }
+        .write_to(&mut writer)?;
+    }
+    Ok(())
+}
+
+/// Выполнение запроса
+pub fn handle_request(
+    house: &SharedHouse,
+    register_map: &RegisterMap,
+    request: &ModbusRequest,
+) -> ModbusResponse {
+    let result = match request {
+        ModbusRequest::ReadCoils { address, count } => {
+            read(register_map, *address, *count, RegisterMap::get_by_coil).and_then(|entries| {
+                entries
+                    .into_iter()
+                    .map(|entry| read_coil(house, entry))
+                    .collect::<Result<_, _>>()
+                    .map(ModbusResponse::Coils)
+            })
+        }
+        ModbusRequest::ReadHoldingRegisters { address, count } => {
+            read(register_map, *address, *count, RegisterMap::get_by_coil).and_then(|entries| {
+                entries
+                    .into_iter()
+                    .map(|entry| with_device(house, entry, |device| holding_register(device)))
+                    .collect::<Result<_, _>>()
+                    .map(ModbusResponse::Registers)
+            })
+        }
+        ModbusRequest::ReadInputRegisters { address, count } => read(
+            register_map,
+            *address,
+            *count,
+            RegisterMap::get_by_input_register,
+        )
+        .and_then(|registers| {
+            registers
+                .into_iter()
+                .map(|(entry, offset)| {
+                    with_device(house, entry, |device| input_registers(device)[offset])
+                })
+                .collect::<Result<_, _>>()
+                .map(ModbusResponse::Registers)
+        }),
+        ModbusRequest::WriteSingleCoil { address, value } => {
+            write(register_map, *address, &[*value], |entry, value| {
+                write_coil(house, entry, *value)
+            })
+            .map(|_| ModbusResponse::Written {
+                address: *address,
+                value: if *value { 0xFF00 } else { 0 },
+            })
+        }
+        ModbusRequest::WriteMultipleCoils { address, values } => {
+            write(register_map, *address, values, |entry, value| {
+                write_coil(house, entry, *value)
+            })
+            .map(|_| ModbusResponse::Written {
+                address: *address,
+                value: values.len() as u16,
+            })
+        }
+        ModbusRequest::WriteSingleRegister { address, value } => {
+            write(register_map, *address, &[*value], |entry, value| {
+                reset_fault(house, entry, *value)
+            })
+            .map(|_| ModbusResponse::Written {
+                address: *address,
+                value: *value,
+            })
+        }
+        ModbusRequest::WriteMultipleRegisters { address, values } => {
+            write(register_map, *address, values, |entry, value| {
+                reset_fault(house, entry, *value)
+            })
+            .map(|_| ModbusResponse::Written {
+                address: *address,
+                value: values.len() as u16,
+            })
+        }
+    };
+    result.unwrap_or_else(ModbusResponse::Exception)
+}
+
+/// Поиск устройств для `count` адресов, начиная с `address`
+fn read<'a, T>(
+    register_map: &'a RegisterMap,
+    address: u16,
+    count: u16,
+    lookup: fn(&'a RegisterMap, u16) -> Option<T>,
+) -> Result<Vec<T>, ModbusException> {
+    (0..count)
+        .map(|offset| {
+            address
+                .checked_add(offset)
+                .and_then(|address| lookup(register_map, address))
+                .ok_or(ModbusException::IllegalDataAddress)
+        })
+        .collect()
+}
+
+/// Запись значений `values`, начиная с адреса `address` катушки или регистра хранения
+///
+/// Все адреса проверяются до начала записи
+fn write<T, F>(
+    register_map: &RegisterMap,
+    address: u16,
+    values: &[T],
+    mut action: F,
+) -> Result<(), ModbusException>
+where
+    F: FnMut(&RegisterMapEntry, &T) -> Result<(), ModbusException>,
+{
+    let entries = read(
+        register_map,
+        address,
+        values.len() as u16,
+        RegisterMap::get_by_coil,
+    )?;
+    entries
+        .into_iter()
+        .zip(values)
+        .try_for_each(|(entry, value)| action(entry, value))
+}
+
+/// Выполнение действия с устройством из карты регистров
+fn with_device<R, F>(
+    house: &SharedHouse,
+    entry: &RegisterMapEntry,
+    action: F,
+) -> Result<R, ModbusException>
+where
+    F: FnOnce(&dyn SmartDevice) -> R,
+{
+    house
+        .with_device(&entry.room, &entry.device, action)
+        .map_err(|_| ModbusException::ServerDeviceFailure)
+}
+
+/// Чтение катушки питания
+fn read_coil(house: &SharedHouse, entry: &RegisterMapEntry) -> Result<bool, ModbusException> {
+    with_device(house, entry, |device| {
+        device.get_device_status() == SmartDeviceStatus::PowerState(SmartDevicePowerState::Enabled)
+    })
+}
+
+/// Запись катушки питания
+fn write_coil(
+    house: &SharedHouse,
+    entry: &RegisterMapEntry,
+    value: bool,
+) -> Result<(), ModbusException> {
+    let state = if value {
+        SmartDevicePowerState::Enabled
+    } else {
+        SmartDevicePowerState::Disabled
+    };
+    house
+        .set_power_state(&entry.room, &entry.device, state)
+        .map(|_| ())
+        .map_err(|_| ModbusException::ServerDeviceFailure)
+}
+
+/// Сброс ошибки устройства записью `0` в регистр хранения
+fn reset_fault(
+    house: &SharedHouse,
+    entry: &RegisterMapEntry,
+    value: u16,
+) -> Result<(), ModbusException> {
+    if value != 0 {
+        return Err(ModbusException::IllegalDataValue);
+    }
+    if with_device(house, entry, |device| holding_register(device))? == 0 {
+        return Ok(());
+    }
+    match house.execute(&entry.room, &entry.device, SmartDeviceCommand::ResetFault) {
+        Ok(Ok(_)) => Ok(()),
+        _ => Err(ModbusException::ServerDeviceFailure),
+    }
+}
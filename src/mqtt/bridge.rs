@@ -0,0 +1,410 @@
+//! Модуль содержит мост между умным домом и брокером MQTT
+//!
+//! > Мост публикует состояние каждого устройства в топик `<house>/<room>/<device>/state`
+//! > (с сохранением на брокере) при подключении и после каждого события устройства
+//! > на шине событий дома, а команды из топиков `<house>/<room>/<device>/set`
+//! > выполняет вызовом [`SharedHouse::set_power_state`]. При удалении устройства
+//! > сохранённое состояние удаляется с брокера.
+//! >
+//! > После вызова [`MqttBridge::with_discovery`] мост также публикует конфигурации
+//! > автообнаружения Home Assistant для добавленных устройств и удаляет их вместе с устройством.
+//!
+use super::client::{MqttClient, MqttMessage, MqttOptions};
+use super::discovery::discovery_configs;
+use super::{
+    availability_topic, segment_name, state_topic, topic_segment, AVAILABLE_PAYLOAD,
+    COMMAND_SUFFIX, UNAVAILABLE_PAYLOAD,
+};
+use crate::containers::shared::SharedHouse;
+use crate::containers::{ContainerName, ErrorReason};
+use crate::events::{EventFilter, EventPayload, HouseEvent};
+use crate::json::{status_json, JsonValue};
+use crate::smart_device::{SmartDevice, SmartDevicePowerState};
+use std::collections::HashMap;
+use std::io;
+use std::net::ToSocketAddrs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Период проверки событий дома и признака остановки моста
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Представление состояния устройства для публикации:
+/// `{"power_state": .., "readings": {"<capability>": <value>, ..}}`
+pub fn state_payload(device: &dyn SmartDevice) -> JsonValue {
+    let readings = device
+        .get_readings()
+        .into_iter()
+        .map(|(capability, value)| (capability.to_string(), JsonValue::from(value)))
+        .collect();
+    match status_json(&device.get_device_status()) {
+        JsonValue::Object(mut fields) => {
+            fields.push(("readings".to_string(), JsonValue::Object(readings)));
+            JsonValue::Object(fields)
+        }
+        status => status,
+    }
+}
+
+/// Разбор команды включения/выключения
+///
+/// Принимаются `ON`/`OFF`, `enabled`/`disabled`, `true`/`false` и `{"state": "enabled"}`
+pub fn parse_power_command(payload: &[u8]) -> Option<SmartDevicePowerState> {
+    let text = std::str::from_utf8(payload).ok()?.trim();
+    let text = match JsonValue::parse(text) {
+        Ok(JsonValue::Object(fields)) => fields
+            .into_iter()
+            .find(|(key, _)| key == "state")
+            .and_then(|(_, value)| value.as_str().map(str::to_string))?,
+        _ => text.to_string(),
+    };
+
+    match text.to_ascii_lowercase().as_str() {
+        "on" | "enabled" | "true" => Some(SmartDevicePowerState::Enabled),
+        "off" | "disabled" | "false" => Some(SmartDevicePowerState::Disabled),
+        _ => None,
+    }
+}
+
+/// Тип, описывающий мост между умным домом и брокером MQTT
+pub struct MqttBridge {
+    /// Подключение к брокеру
+    client: MqttClient,
+
+    /// Обслуживаемый дом
+    house: SharedHouse,
+
+    /// Период проверки соединения
+    keep_alive: Duration,
+
+    /// Префикс топиков автообнаружения Home Assistant (`None` - без автообнаружения)
+    discovery_prefix: Option<String>,
+}
+
+impl MqttBridge {
+    /// Подключение моста дома `house` к брокеру по адресу `address`
+    ///
+    /// Идентификатор клиента - `iot_crate-<house>`; последняя воля - `offline`
+    /// в топике доступности дома
+    pub fn connect<A: ToSocketAddrs>(address: A, house: SharedHouse) -> io::Result<Self> {
+        let options = MqttOptions::new(&format!("iot_crate-{}", topic_segment(house.get_name())))
+            .with_will(
+                &availability_topic(house.get_name()),
+                UNAVAILABLE_PAYLOAD.as_bytes(),
+            );
+        Self::connect_with(address, house, options)
+    }
+
+    /// Подключение моста дома `house` к брокеру с параметрами `options`
+    pub fn connect_with<A: ToSocketAddrs>(
+        address: A,
+        house: SharedHouse,
+        options: MqttOptions,
+    ) -> io::Result<Self> {
+        let mut client = MqttClient::connect(address, &options)?;
+        client.subscribe(&format!(
+            "{}/+/+/{}",
+            topic_segment(house.get_name()),
+            COMMAND_SUFFIX
+        ))?;
+
+        Ok(Self {
+            client,
+            house,
+            keep_alive: options.keep_alive,
+            discovery_prefix: None,
+        })
+    }
+
+    /// Включение публикации конфигураций автообнаружения Home Assistant с префиксом `prefix`
+    /// (обычно [`DEFAULT_DISCOVERY_PREFIX`](super::discovery::DEFAULT_DISCOVERY_PREFIX))
+    pub fn with_discovery(mut self, prefix: &str) -> Self {
+        self.discovery_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Обслуживание моста в текущем потоке до разрыва соединения
+    pub fn run(self) -> io::Result<()> {
+        self.serve(Arc::new(AtomicBool::new(true)))
+    }
+
+    /// Запуск обслуживания моста в фоновом потоке
+    pub fn spawn(self) -> io::Result<MqttBridgeHandle> {
+        let running = Arc::new(AtomicBool::new(true));
+        let flag = running.clone();
+        let thread = thread::Builder::new()
+            .name("iot-mqtt".to_string())
+            .spawn(move || self.serve(flag))?;
+
+        Ok(MqttBridgeHandle {
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// Публикация состояний и выполнение команд, пока установлен признак `running`
+    fn serve(self, running: Arc<AtomicBool>) -> io::Result<()> {
+        let (subscription, events) = self
+            .house
+            .with_event_bus(|bus| bus.subscribe_channel(EventFilter::all()));
+
+        let mut publisher = Publisher {
+            client: self.client.try_clone()?,
+            house: self.house.clone(),
+            discovery_prefix: self.discovery_prefix.clone(),
+            published: HashMap::new(),
+            last_packet: Instant::now(),
+        };
+
+        let house = self.house.clone();
+        let commands = self.client;
+        thread::Builder::new()
+            .name("iot-mqtt-commands".to_string())
+            .spawn(move || execute_commands(commands, house))?;
+
+        let result = publisher.publish_loop(&events, &running, self.keep_alive);
+
+        self.house
+            .with_event_bus(|bus| bus.unsubscribe(subscription));
+        let _ = publisher.client.publish(
+            &availability_topic(self.house.get_name()),
+            UNAVAILABLE_PAYLOAD.as_bytes(),
+            true,
+        );
+        let _ = publisher.client.disconnect();
+        result
+    }
+}
+
+/// Дескриптор моста MQTT, запущенного в фоновом потоке
+///
+/// Мост отключается вызовом [`MqttBridgeHandle::shutdown`] или при удалении дескриптора
+pub struct MqttBridgeHandle {
+    /// Признак работы моста
+    running: Arc<AtomicBool>,
+
+    /// Поток моста
+    thread: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl MqttBridgeHandle {
+    /// Отключение моста от брокера
+    pub fn shutdown(mut self) -> io::Result<()> {
+        self.stop()
+    }
+
+    /// Остановка моста и ожидание завершения потока
+    fn stop(&mut self) -> io::Result<()> {
+        let Some(thread) = self.thread.take() else {
+            return Ok(());
+        };
+        self.running.store(false, Ordering::SeqCst);
+        thread
+            .join()
+            .map_err(|_| io::Error::other("MQTT bridge thread panicked"))?
+    }
+}
+
+impl Drop for MqttBridgeHandle {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// Публикация состояний устройств
+struct Publisher {
+    /// Подключение к брокеру (только запись)
+    client: MqttClient,
+
+    /// Обслуживаемый дом
+    house: SharedHouse,
+
+    /// Префикс топиков автообнаружения Home Assistant
+    discovery_prefix: Option<String>,
+
+    /// Устройства, состояние которых сохранено на брокере, и топики их конфигураций
+    published: HashMap<(ContainerName, ContainerName), Vec<String>>,
+
+    /// Момент отправки последнего пакета
+    last_packet: Instant,
+}
+
+impl Publisher {
+    /// Публикация доступности и состояний, затем публикация изменений по событиям
+    fn publish_loop(
+        &mut self,
+        events: &Receiver<HouseEvent>,
+        running: &AtomicBool,
+        keep_alive: Duration,
+    ) -> io::Result<()> {
+        self.publish(
+            &availability_topic(self.house.get_name()),
+            AVAILABLE_PAYLOAD.as_bytes(),
+        )?;
+        let mut room_names = self.house.get_room_list();
+        room_names.sort();
+        for room_name in room_names {
+            for device_name in self.house.get_device_list(&room_name).unwrap_or_default() {
+                self.publish_state(&room_name, &device_name)?;
+            }
+        }
+
+        while running.load(Ordering::SeqCst) {
+            match events.recv_timeout(POLL_INTERVAL) {
+                Ok(event) => {
+                    // События, накопившиеся за время публикации, объединяются:
+                    // состояние каждого устройства публикуется один раз
+                    let mut changed = vec![event];
+                    changed.extend(events.try_iter());
+                    let mut devices = Vec::new();
+                    for event in changed {
+                        match (&event.payload, event.device) {
+                            (EventPayload::RoomRemoved, _) => self.clear_room(&event.room)?,
+                            (_, Some(device)) => {
+                                let key = (event.room, device);
+                                if !devices.contains(&key) {
+                                    devices.push(key);
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    for (room_name, device_name) in devices {
+                        self.publish_state(&room_name, &device_name)?;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            if self.last_packet.elapsed() >= keep_alive / 2 {
+                self.client.ping()?;
+                self.last_packet = Instant::now();
+            }
+        }
+        Ok(())
+    }
+
+    /// Публикация состояния устройства (или удаление сохранённого состояния)
+    ///
+    /// Перед первой публикацией состояния публикуются конфигурации автообнаружения
+    fn publish_state(&mut self, room_name: &str, device_name: &str) -> io::Result<()> {
+        let topic = state_topic(self.house.get_name(), room_name, device_name);
+        let key = (room_name.to_string(), device_name.to_string());
+        let discovery = match &self.discovery_prefix {
+            Some(prefix) if !self.published.contains_key(&key) => Some(prefix.clone()),
+            _ => None,
+        };
+
+        let house_name = self.house.get_name();
+        let result = self.house.with_device(room_name, device_name, |device| {
+            let configs = match &discovery {
+                Some(_) => discovery_configs(house_name, room_name, device),
+                None => Vec::new(),
+            };
+            (state_payload(device), configs)
+        });
+        match result {
+            Ok((payload, configs)) => {
+                if let Some(prefix) = discovery {
+                    let mut topics = Vec::new();
+                    for config in configs {
+                        let config_topic = config.get_topic(&prefix);
+                        self.publish(&config_topic, config.payload.to_string().as_bytes())?;
+                        topics.push(config_topic);
+                    }
+                    self.published.insert(key.clone(), topics);
+                }
+                self.published.entry(key).or_default();
+                self.publish(&topic, payload.to_string().as_bytes())
+            }
+            Err(ErrorReason::ItemDoesntExist) => self.clear_device(key),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Удаление сохранённых состояний и конфигураций устройств удалённой комнаты
+    fn clear_room(&mut self, room_name: &str) -> io::Result<()> {
+        let removed: Vec<_> = self
+            .published
+            .keys()
+            .filter(|(room, _)| room == room_name)
+            .cloned()
+            .collect();
+        for key in removed {
+            self.clear_device(key)?;
+        }
+        Ok(())
+    }
+
+    /// Удаление сохранённого состояния и конфигураций удалённого устройства
+    fn clear_device(&mut self, key: (ContainerName, ContainerName)) -> io::Result<()> {
+        let Some(config_topics) = self.published.remove(&key) else {
+            return Ok(());
+        };
+        for config_topic in config_topics {
+            self.publish(&config_topic, &[])?;
+        }
+        let topic = state_topic(self.house.get_name(), &key.0, &key.1);
+        self.publish(&topic, &[])
+    }
+
+    /// Публикация сохраняемого брокером сообщения
+    fn publish(&mut self, topic: &str, payload: &[u8]) -> io::Result<()> {
+        self.last_packet = Instant::now();
+        self.client.publish(topic, payload, true)
+    }
+}
+
+/// Выполнение команд из топиков `.../set` до разрыва соединения
+fn execute_commands(mut client: MqttClient, house: SharedHouse) {
+    while let Ok(message) = client.receive(None) {
+        if let Some(message) = message {
+            execute_command(&house, &message);
+        }
+    }
+}
+
+/// Выполнение команды включения/выключения устройства
+fn execute_command(house: &SharedHouse, message: &MqttMessage) {
+    let levels: Vec<&str> = message.topic.split('/').collect();
+    let [_, room_segment, device_segment, COMMAND_SUFFIX] = levels.as_slice() else {
+        return;
+    };
+    let Some(state) = parse_power_command(&message.payload) else {
+        return;
+    };
+
+    let (Some(room_name), Some(device_name)) =
+        (segment_name(room_segment), segment_name(device_segment))
+    else {
+        return;
+    };
+    // Результат (включая неисправность) публикуется через шину событий
+    let _ = house.set_power_state(&room_name, &device_name, state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn power_commands_are_parsed() {
+        assert_eq!(
+            parse_power_command(b"ON"),
+            Some(SmartDevicePowerState::Enabled)
+        );
+        assert_eq!(
+            parse_power_command(b" disabled\n"),
+            Some(SmartDevicePowerState::Disabled)
+        );
+        assert_eq!(
+            parse_power_command(br#"{"state": "enabled"}"#),
+            Some(SmartDevicePowerState::Enabled)
+        );
+        assert_eq!(parse_power_command(b"toggle"), None);
+    }
+}
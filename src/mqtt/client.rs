@@ -0,0 +1,247 @@
+//! Модуль содержит клиент протокола MQTT 3.1.1 (QoS 0)
+//!
+use super::packet::{MqttPacket, MqttWill};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Период проверки соединения по умолчанию
+pub const DEFAULT_KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+/// Время ожидания ответа брокера на подключение и подписку
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Тип, описывающий параметры подключения к брокеру
+#[derive(Clone, Debug, PartialEq)]
+pub struct MqttOptions {
+    /// Идентификатор клиента
+    pub client_id: String,
+
+    /// Период проверки соединения
+    pub keep_alive: Duration,
+
+    /// Сообщение "последней воли"
+    pub will: Option<MqttWill>,
+}
+
+impl MqttOptions {
+    /// Создание параметров подключения клиента с идентификатором `client_id`
+    pub fn new(client_id: &str) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            keep_alive: DEFAULT_KEEP_ALIVE,
+            will: None,
+        }
+    }
+
+    /// Установка периода проверки соединения
+    pub fn with_keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// Установка сохраняемого брокером сообщения "последней воли"
+    pub fn with_will(mut self, topic: &str, payload: &[u8]) -> Self {
+        self.will = Some(MqttWill {
+            topic: topic.to_string(),
+            payload: payload.to_vec(),
+            retain: true,
+        });
+        self
+    }
+}
+
+/// Сообщение, полученное от брокера
+#[derive(Clone, Debug, PartialEq)]
+pub struct MqttMessage {
+    /// Топик сообщения
+    pub topic: String,
+
+    /// Содержимое сообщения
+    pub payload: Vec<u8>,
+
+    /// Признак сохранённого брокером сообщения
+    pub retain: bool,
+}
+
+/// Тип, описывающий подключение к брокеру MQTT
+pub struct MqttClient {
+    /// Поток для записи пакетов
+    writer: TcpStream,
+
+    /// Буферизованный поток для чтения пакетов
+    reader: BufReader<TcpStream>,
+
+    /// Сообщения, полученные во время ожидания ответа брокера
+    pending: VecDeque<MqttMessage>,
+
+    /// Идентификатор следующего пакета подписки
+    next_packet_id: u16,
+}
+
+impl MqttClient {
+    /// Подключение к брокеру по адресу `address`
+    pub fn connect<A: ToSocketAddrs>(address: A, options: &MqttOptions) -> io::Result<Self> {
+        let writer = TcpStream::connect(address)?;
+        writer.set_nodelay(true)?;
+        let mut client = Self {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+            pending: VecDeque::new(),
+            next_packet_id: 1,
+        };
+
+        client.send(&MqttPacket::Connect {
+            client_id: options.client_id.clone(),
+            keep_alive: options.keep_alive.as_secs().min(u16::MAX as u64) as u16,
+            will: options.will.clone(),
+        })?;
+        match client.wait_for(|packet| matches!(packet, MqttPacket::ConnAck { .. }))? {
+            MqttPacket::ConnAck { return_code: 0 } => Ok(client),
+            _ => Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                "connection refused by broker",
+            )),
+        }
+    }
+
+    /// Публикация сообщения с QoS 0
+    pub fn publish(&mut self, topic: &str, payload: &[u8], retain: bool) -> io::Result<()> {
+        self.send(&MqttPacket::Publish {
+            topic: topic.to_string(),
+            payload: payload.to_vec(),
+            retain,
+        })
+    }
+
+    /// Подписка на топики, соответствующие фильтру `filter`, с ожиданием подтверждения
+    pub fn subscribe(&mut self, filter: &str) -> io::Result<()> {
+        let packet_id = self.next_packet_id;
+        self.next_packet_id = self.next_packet_id.wrapping_add(1).max(1);
+        self.send(&MqttPacket::Subscribe {
+            packet_id,
+            filters: vec![filter.to_string()],
+        })?;
+
+        match self.wait_for(
+            |packet| matches!(packet, MqttPacket::SubAck { packet_id: id, .. } if *id == packet_id),
+        )? {
+            MqttPacket::SubAck { return_codes, .. }
+                if return_codes.iter().all(|code| *code < 0x80) =>
+            {
+                Ok(())
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "subscription rejected by broker",
+            )),
+        }
+    }
+
+    /// Ожидание следующего сообщения не дольше `timeout` (`None` - без ограничения)
+    ///
+    /// По истечении времени ожидания возвращается `Ok(None)`
+    pub fn receive(&mut self, timeout: Option<Duration>) -> io::Result<Option<MqttMessage>> {
+        if let Some(message) = self.pending.pop_front() {
+            return Ok(Some(message));
+        }
+
+        loop {
+            // Ожидание начала пакета с ограничением времени; сам пакет читается целиком,
+            // чтобы истечение времени не прервало его на середине
+            self.reader.get_ref().set_read_timeout(timeout)?;
+            match self.reader.fill_buf() {
+                Ok([]) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(_) => {}
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return Ok(None)
+                }
+                Err(error) => return Err(error),
+            }
+
+            self.reader
+                .get_ref()
+                .set_read_timeout(Some(RESPONSE_TIMEOUT))?;
+            match MqttPacket::read_from(&mut self.reader)? {
+                MqttPacket::Publish {
+                    topic,
+                    payload,
+                    retain,
+                } => {
+                    return Ok(Some(MqttMessage {
+                        topic,
+                        payload,
+                        retain,
+                    }))
+                }
+                MqttPacket::PingReq => self.send(&MqttPacket::PingResp)?,
+                _ => {}
+            }
+        }
+    }
+
+    /// Проверка соединения (ответ брокера обрабатывается при получении сообщений)
+    pub fn ping(&mut self) -> io::Result<()> {
+        self.send(&MqttPacket::PingReq)
+    }
+
+    /// Создание независимого дескриптора того же подключения
+    ///
+    /// Используется для одновременного чтения и записи из разных потоков: уже полученные,
+    /// но ещё не прочитанные сообщения остаются у исходного дескриптора, поэтому читать
+    /// следует через исходный дескриптор, а копию использовать для записи
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let writer = self.writer.try_clone()?;
+        Ok(Self {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+            pending: VecDeque::new(),
+            next_packet_id: self.next_packet_id,
+        })
+    }
+
+    /// Штатное отключение от брокера (сообщение "последней воли" не публикуется)
+    pub fn disconnect(mut self) -> io::Result<()> {
+        self.send(&MqttPacket::Disconnect)?;
+        self.writer.shutdown(Shutdown::Both)
+    }
+
+    /// Отправка пакета
+    fn send(&mut self, packet: &MqttPacket) -> io::Result<()> {
+        packet.write_to(&mut self.writer)
+    }
+
+    /// Ожидание пакета, удовлетворяющего условию `expected`; сообщения сохраняются
+    fn wait_for<F>(&mut self, expected: F) -> io::Result<MqttPacket>
+    where
+        F: Fn(&MqttPacket) -> bool,
+    {
+        self.reader
+            .get_ref()
+            .set_read_timeout(Some(RESPONSE_TIMEOUT))?;
+        loop {
+            let packet = MqttPacket::read_from(&mut self.reader)?;
+            if expected(&packet) {
+                return Ok(packet);
+            }
+            if let MqttPacket::Publish {
+                topic,
+                payload,
+                retain,
+            } = packet
+            {
+                self.pending.push_back(MqttMessage {
+                    topic,
+                    payload,
+                    retain,
+                });
+            }
+        }
+    }
+}
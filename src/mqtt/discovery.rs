@@ -0,0 +1,250 @@
+//! Модуль содержит сообщения автообнаружения Home Assistant (MQTT discovery)
+//!
+//! > Для каждого устройства формируются конфигурации сущностей Home Assistant, которые
+//! > публикуются с сохранением на брокере в топики
+//! > `<prefix>/<component>/<node_id>/<object_id>/config`. Умная розетка представляется
+//! > выключателем (`switch`) и датчиком мощности, умный термометр - датчиком температуры;
+//! > устройства остальных типов не публикуются. Сущности читают топик состояния устройства
+//! > и считаются недоступными, пока дом не в сети.
+//! >
+//! > Идентификатор сущности составляется из названий комнаты и устройства и хеша
+//! > идентификатора устройства из метаданных, поэтому устройства с похожими названиями
+//! > (`Kettle 1` и `Kettle-1`) получают разные `unique_id`.
+//!
+use super::{
+    availability_topic, command_topic, state_topic, AVAILABLE_PAYLOAD, UNAVAILABLE_PAYLOAD,
+};
+use crate::command::Capability;
+use crate::json::JsonValue;
+use crate::metadata::DeviceKind;
+use crate::smart_device::SmartDevice;
+
+/// Префикс топиков автообнаружения Home Assistant по умолчанию
+pub const DEFAULT_DISCOVERY_PREFIX: &str = "homeassistant";
+
+/// Тип, описывающий конфигурацию сущности Home Assistant
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiscoveryConfig {
+    /// Компонент Home Assistant (`switch`, `sensor`)
+    pub component: &'static str,
+
+    /// Идентификатор узла (дома)
+    pub node_id: String,
+
+    /// Идентификатор сущности в пределах узла
+    pub object_id: String,
+
+    /// Содержимое конфигурации
+    pub payload: JsonValue,
+}
+
+impl DiscoveryConfig {
+    /// Получение топика конфигурации с префиксом автообнаружения `prefix`
+    pub fn get_topic(&self, prefix: &str) -> String {
+        format!(
+            "{}/{}/{}/{}/config",
+            prefix, self.component, self.node_id, self.object_id
+        )
+    }
+}
+
+/// Преобразование названия в идентификатор Home Assistant
+/// (строчные латинские буквы, цифры и `_`)
+pub fn discovery_id(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Получение устойчивого между запусками хеша идентификатора (FNV-1a, 32 бита)
+/// в виде восьми шестнадцатеричных цифр
+fn id_hash(id: &str) -> String {
+    let hash = id.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    });
+    format!("{:08x}", hash)
+}
+
+/// Получение конфигураций сущностей устройства `device` комнаты `room` дома `house`
+pub fn discovery_configs(
+    house: &str,
+    room: &str,
+    device: &dyn SmartDevice,
+) -> Vec<DiscoveryConfig> {
+    let entity = Entity::new(house, room, device);
+    match device.get_metadata().kind {
+        DeviceKind::Socket => vec![
+            entity.switch(),
+            entity.sensor(Capability::PowerMetering, "power", "Power"),
+        ],
+        DeviceKind::Thermometer => {
+            vec![entity.sensor(Capability::Temperature, "temperature", "Temperature")]
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Общие сведения о сущностях одного устройства
+struct Entity {
+    /// Идентификатор узла (дома)
+    node_id: String,
+
+    /// Идентификатор устройства в пределах узла
+    device_id: String,
+
+    /// Топик состояния устройства
+    state_topic: String,
+
+    /// Топик команд устройства
+    command_topic: String,
+
+    /// Топик доступности дома
+    availability_topic: String,
+
+    /// Описание устройства для реестра устройств Home Assistant
+    device: JsonValue,
+}
+
+impl Entity {
+    /// Сбор сведений об устройстве
+    fn new(house: &str, room: &str, device: &dyn SmartDevice) -> Self {
+        let node_id = discovery_id(house);
+        let metadata = device.get_metadata();
+        // Преобразование названий в идентификаторы теряет сведения ("Kettle 1" и "Kettle-1"),
+        // поэтому идентификатор дополняется хешем уникального идентификатора устройства
+        let device_id = format!(
+            "{}_{}_{}",
+            discovery_id(room),
+            discovery_id(device.get_name()),
+            id_hash(metadata.get_id())
+        );
+
+        Self {
+            device: JsonValue::object()
+                .with("identifiers", vec![format!("{}_{}", node_id, device_id)])
+                .with("name", device.get_name())
+                .with("manufacturer", metadata.vendor.as_str())
+                .with("model", metadata.model.as_str())
+                .with("sw_version", metadata.firmware_version.as_str())
+                .with("suggested_area", room),
+            state_topic: state_topic(house, room, device.get_name()),
+            command_topic: command_topic(house, room, device.get_name()),
+            availability_topic: availability_topic(house),
+            node_id,
+            device_id,
+        }
+    }
+
+    /// Конфигурация выключателя питания
+    fn switch(&self) -> DiscoveryConfig {
+        let payload = self
+            .base("switch", JsonValue::Null)
+            .with("command_topic", self.command_topic.as_str())
+            .with("payload_on", "ON")
+            .with("payload_off", "OFF")
+            .with("state_on", "enabled")
+            .with("state_off", "disabled")
+            .with("value_template", "{{ value_json.power_state }}");
+        self.config("switch", "switch", payload)
+    }
+
+    /// Конфигурация датчика показаний `capability` класса `device_class`
+    fn sensor(&self, capability: Capability, device_class: &str, name: &str) -> DiscoveryConfig {
+        let payload = self
+            .base(device_class, JsonValue::from(name))
+            .with("device_class", device_class)
+            .with("state_class", "measurement")
+            .with("unit_of_measurement", capability.unit())
+            .with(
+                "value_template",
+                format!("{{{{ value_json.readings.{} }}}}", capability),
+            );
+        self.config("sensor", device_class, payload)
+    }
+
+    /// Общие поля конфигурации сущности `suffix` с названием `name`
+    /// (`null` - сущность называется по устройству)
+    fn base(&self, suffix: &str, name: JsonValue) -> JsonValue {
+        JsonValue::object()
+            .with("name", name)
+            .with(
+                "unique_id",
+                format!("{}_{}_{}", self.node_id, self.device_id, suffix),
+            )
+            .with("state_topic", self.state_topic.as_str())
+            .with("availability_topic", self.availability_topic.as_str())
+            .with("payload_available", AVAILABLE_PAYLOAD)
+            .with("payload_not_available", UNAVAILABLE_PAYLOAD)
+            .with("device", self.device.clone())
+    }
+
+    /// Создание конфигурации компонента `component`
+    fn config(&self, component: &'static str, suffix: &str, payload: JsonValue) -> DiscoveryConfig {
+        DiscoveryConfig {
+            component,
+            node_id: self.node_id.clone(),
+            object_id: format!("{}_{}", self.device_id, suffix),
+            payload,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::socket::SmartSocket;
+    use crate::thermometer::SmartThermometer;
+
+    #[test]
+    fn configs_depend_on_device_kind() {
+        let socket = SmartSocket::new("Kettle #1");
+        let configs = discovery_configs("My House", "Kitchen", &socket);
+        let topics: Vec<_> = configs
+            .iter()
+            .map(|config| config.get_topic(DEFAULT_DISCOVERY_PREFIX))
+            .collect();
+        assert_eq!(
+            topics,
+            [
+                "homeassistant/switch/my_house/kitchen_kettle__1_b553337c_switch/config",
+                "homeassistant/sensor/my_house/kitchen_kettle__1_b553337c_power/config",
+            ]
+        );
+        assert_eq!(
+            configs[0].payload.get("command_topic"),
+            Some(&JsonValue::from("My House/Kitchen/Kettle %231/set"))
+        );
+        assert_eq!(
+            configs[1].payload.get("unit_of_measurement"),
+            Some(&JsonValue::from("W"))
+        );
+
+        let thermometer = SmartThermometer::new("Thermometer");
+        let configs = discovery_configs("My House", "Kitchen", &thermometer);
+        assert_eq!(configs.len(), 1);
+        assert_eq!(
+            configs[0].payload.get("value_template"),
+            Some(&JsonValue::from("{{ value_json.readings.temperature }}"))
+        );
+    }
+
+    #[test]
+    fn similar_names_get_distinct_unique_ids() {
+        let unique_ids: Vec<_> = ["Kettle 1", "Kettle-1"]
+            .into_iter()
+            .map(|name| {
+                let socket = SmartSocket::new(name);
+                let configs = discovery_configs("My House", "Kitchen", &socket);
+                assert_eq!(discovery_id(name), "kettle_1");
+                configs[0].payload.get("unique_id").cloned()
+            })
+            .collect();
+        assert_ne!(unique_ids[0], unique_ids[1]);
+    }
+}
@@ -0,0 +1,135 @@
+//! Модуль содержит интеграцию умного дома с брокером MQTT
+//!
+//! > Модуль доступен при включённой cargo-функции `mqtt`. Схема топиков:
+//! >
+//! > | Топик                          | Направление | Содержимое                                 |
+//! > |--------------------------------|-------------|--------------------------------------------|
+//! > | `<house>/status`               | дом → брокер | `online` / `offline` (последняя воля)     |
+//! > | `<house>/<room>/<device>/state`| дом → брокер | статус и показания устройства (JSON)      |
+//! > | `<house>/<room>/<device>/set`  | брокер → дом | `ON` / `OFF`, `enabled` / `disabled` или `{"state": ..}` |
+//! >
+//! > Символы `/`, `+`, `#` и `%` в названиях дома, комнат и устройств кодируются как
+//! > `%2F`, `%2B`, `%23` и `%25`, поэтому разные названия не попадают в один топик.
+//! > Дополнительно мост может публиковать конфигурации автообнаружения Home Assistant
+//! > (см. модуль [`discovery`]).
+//!
+pub mod bridge;
+pub mod client;
+pub mod discovery;
+pub mod packet;
+
+/// Суффикс топика состояния устройства
+pub const STATE_SUFFIX: &str = "state";
+
+/// Суффикс топика команд устройства
+pub const COMMAND_SUFFIX: &str = "set";
+
+/// Суффикс топика доступности дома
+pub const AVAILABILITY_SUFFIX: &str = "status";
+
+/// Содержимое топика доступности при работающем мосте
+pub const AVAILABLE_PAYLOAD: &str = "online";
+
+/// Содержимое топика доступности после отключения моста
+pub const UNAVAILABLE_PAYLOAD: &str = "offline";
+
+/// Преобразование названия в сегмент топика (кодирование символов `/`, `+`, `#`, `%`)
+///
+/// Кодирование обратимо: исходное название восстанавливается через [`segment_name`]
+pub fn topic_segment(name: &str) -> String {
+    let mut segment = String::with_capacity(name.len());
+    for symbol in name.chars() {
+        match symbol {
+            '/' | '+' | '#' | '%' => segment.push_str(&format!("%{:02X}", symbol as u8)),
+            symbol => segment.push(symbol),
+        }
+    }
+    segment
+}
+
+/// Восстановление названия по сегменту топика, полученному через [`topic_segment`]
+///
+/// Возвращает `None`, если сегмент содержит некорректную последовательность `%XX`
+pub fn segment_name(segment: &str) -> Option<String> {
+    let mut name = Vec::with_capacity(segment.len());
+    let mut bytes = segment.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'%' {
+            let code = [bytes.next()?, bytes.next()?];
+            let code = std::str::from_utf8(&code).ok()?;
+            name.push(u8::from_str_radix(code, 16).ok()?);
+        } else {
+            name.push(byte);
+        }
+    }
+    String::from_utf8(name).ok()
+}
+
+/// Получение топика состояния устройства
+pub fn state_topic(house: &str, room: &str, device: &str) -> String {
+    device_topic(house, room, device, STATE_SUFFIX)
+}
+
+/// Получение топика команд устройства
+pub fn command_topic(house: &str, room: &str, device: &str) -> String {
+    device_topic(house, room, device, COMMAND_SUFFIX)
+}
+
+/// Получение топика доступности дома
+pub fn availability_topic(house: &str) -> String {
+    format!("{}/{}", topic_segment(house), AVAILABILITY_SUFFIX)
+}
+
+/// Проверка, соответствует ли топик `topic` фильтру `filter` (с символами `+` и `#`)
+pub fn topic_matches(filter: &str, topic: &str) -> bool {
+    let mut levels = topic.split('/');
+    for pattern in filter.split('/') {
+        match (pattern, levels.next()) {
+            ("#", _) => return true,
+            ("+", Some(_)) => {}
+            (pattern, Some(level)) if pattern == level => {}
+            _ => return false,
+        }
+    }
+    levels.next().is_none()
+}
+
+/// Получение топика устройства с суффиксом `suffix`
+fn device_topic(house: &str, room: &str, device: &str, suffix: &str) -> String {
+    format!(
+        "{}/{}/{}/{}",
+        topic_segment(house),
+        topic_segment(room),
+        topic_segment(device),
+        suffix
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topics_are_built_and_matched() {
+        let topic = command_topic("My House", "Kitchen/Left", "Kettle#1");
+        assert_eq!(topic, "My House/Kitchen%2FLeft/Kettle%231/set");
+        assert!(topic_matches("My House/+/+/set", &topic));
+        assert!(topic_matches("My House/#", &topic));
+        assert!(!topic_matches("My House/+/set", &topic));
+        assert!(!topic_matches("My House/+/+/state", &topic));
+    }
+
+    #[test]
+    fn colliding_names_get_distinct_segments() {
+        let names = ["Kettle+1", "Kettle#1", "Kettle_1", "Kettle%2B1"];
+        let segments: Vec<String> = names.iter().map(|name| topic_segment(name)).collect();
+        assert_eq!(
+            segments,
+            vec!["Kettle%2B1", "Kettle%231", "Kettle_1", "Kettle%252B1"]
+        );
+        for (name, segment) in names.iter().zip(&segments) {
+            assert_eq!(segment_name(segment).as_deref(), Some(*name));
+        }
+        assert_eq!(segment_name("Kettle%2"), None);
+    }
+}
@@ -0,0 +1,357 @@
+//! Модуль содержит пакеты протокола MQTT 3.1.1 и их двоичное представление
+//!
+//! > Поддерживается подмножество протокола, достаточное для обмена сообщениями с
+//! > качеством обслуживания QoS 0: подключение (с "последней волей"), публикация,
+//! > подписка, проверка соединения и отключение.
+//!
+use std::io::{self, Read, Write};
+
+/// Версия протокола MQTT 3.1.1
+const PROTOCOL_LEVEL: u8 = 4;
+
+/// Максимальный размер пакета в байтах
+const MAX_PACKET_SIZE: usize = 256 * 1024;
+
+/// Сообщение "последней воли", публикуемое брокером при обрыве соединения
+#[derive(Clone, Debug, PartialEq)]
+pub struct MqttWill {
+    /// Топик сообщения
+    pub topic: String,
+
+    /// Содержимое сообщения
+    pub payload: Vec<u8>,
+
+    /// Признак сохранения сообщения брокером
+    pub retain: bool,
+}
+
+/// Перечисление пакетов протокола MQTT
+#[derive(Clone, Debug, PartialEq)]
+pub enum MqttPacket {
+    /// Запрос на подключение
+    Connect {
+        /// Идентификатор клиента
+        client_id: String,
+        /// Период проверки соединения в секундах
+        keep_alive: u16,
+        /// Сообщение "последней воли"
+        will: Option<MqttWill>,
+    },
+    /// Ответ на запрос подключения
+    ConnAck {
+        /// Код результата (0 - подключение принято)
+        return_code: u8,
+    },
+    /// Публикация сообщения
+    Publish {
+        /// Топик сообщения
+        topic: String,
+        /// Содержимое сообщения
+        payload: Vec<u8>,
+        /// Признак сохранения сообщения брокером
+        retain: bool,
+    },
+    /// Запрос на подписку
+    Subscribe {
+        /// Идентификатор пакета
+        packet_id: u16,
+        /// Фильтры топиков
+        filters: Vec<String>,
+    },
+    /// Ответ на запрос подписки
+    SubAck {
+        /// Идентификатор пакета
+        packet_id: u16,
+        /// Коды результата для каждого фильтра
+        return_codes: Vec<u8>,
+    },
+    /// Проверка соединения
+    PingReq,
+    /// Ответ на проверку соединения
+    PingResp,
+    /// Отключение
+    Disconnect,
+}
+
+impl MqttPacket {
+    /// Запись пакета в поток
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let (header, body) = self.encode();
+        let mut packet = vec![header];
+        let mut length = body.len();
+        loop {
+            let mut byte = (length % 128) as u8;
+            length /= 128;
+            if length > 0 {
+                byte |= 0x80;
+            }
+            packet.push(byte);
+            if length == 0 {
+                break;
+            }
+        }
+        packet.extend_from_slice(&body);
+        writer.write_all(&packet)?;
+        writer.flush()
+    }
+
+    /// Чтение пакета из потока
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut header = [0; 1];
+        reader.read_exact(&mut header)?;
+
+        let mut length = 0usize;
+        for shift in 0..4 {
+            let mut byte = [0; 1];
+            reader.read_exact(&mut byte)?;
+            length |= ((byte[0] & 0x7F) as usize) << (7 * shift);
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+            if shift == 3 {
+                return Err(invalid("malformed remaining length"));
+            }
+        }
+        if length > MAX_PACKET_SIZE {
+            return Err(invalid("packet is too large"));
+        }
+
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        Self::decode(header[0], &body)
+    }
+
+    /// Кодирование пакета: (первый байт фиксированного заголовка, остаток пакета)
+    fn encode(&self) -> (u8, Vec<u8>) {
+        let mut body = Vec::new();
+        match self {
+            Self::Connect {
+                client_id,
+                keep_alive,
+                will,
+            } => {
+                put_string(&mut body, "MQTT");
+                body.push(PROTOCOL_LEVEL);
+                let mut flags = 0x02; // чистая сессия
+                if let Some(will) = will {
+                    flags |= 0x04;
+                    if will.retain {
+                        flags |= 0x20;
+                    }
+                }
+                body.push(flags);
+                body.extend_from_slice(&keep_alive.to_be_bytes());
+                put_string(&mut body, client_id);
+                if let Some(will) = will {
+                    put_string(&mut body, &will.topic);
+                    put_bytes(&mut body, &will.payload);
+                }
+                (0x10, body)
+            }
+            Self::ConnAck { return_code } => (0x20, vec![0, *return_code]),
+            Self::Publish {
+                topic,
+                payload,
+                retain,
+            } => {
+                put_string(&mut body, topic);
+                body.extend_from_slice(payload);
+                (0x30 | u8::from(*retain), body)
+            }
+            Self::Subscribe { packet_id, filters } => {
+                body.extend_from_slice(&packet_id.to_be_bytes());
+                for filter in filters {
+                    put_string(&mut body, filter);
+                    body.push(0); // QoS 0
+                }
+                (0x82, body)
+            }
+            Self::SubAck {
+                packet_id,
+                return_codes,
+            } => {
+                body.extend_from_slice(&packet_id.to_be_bytes());
+                body.extend_from_slice(return_codes);
+                (0x90, body)
+            }
+            Self::PingReq => (0xC0, body),
+            Self::PingResp => (0xD0, body),
+            Self::Disconnect => (0xE0, body),
+        }
+    }
+
+    /// Декодирование пакета по первому байту заголовка и остатку пакета
+    fn decode(header: u8, body: &[u8]) -> io::Result<Self> {
+        let mut cursor = Cursor { body, position: 0 };
+        let packet = match header >> 4 {
+            1 => {
+                if cursor.string()? != "MQTT" || cursor.byte()? != PROTOCOL_LEVEL {
+                    return Err(invalid("unsupported protocol"));
+                }
+                let flags = cursor.byte()?;
+                let keep_alive = cursor.u16()?;
+                let client_id = cursor.string()?;
+                let will = if flags & 0x04 != 0 {
+                    Some(MqttWill {
+                        topic: cursor.string()?,
+                        payload: cursor.bytes()?,
+                        retain: flags & 0x20 != 0,
+                    })
+                } else {
+                    None
+                };
+                Self::Connect {
+                    client_id,
+                    keep_alive,
+                    will,
+                }
+            }
+            2 => {
+                cursor.byte()?;
+                Self::ConnAck {
+                    return_code: cursor.byte()?,
+                }
+            }
+            3 => {
+                let topic = cursor.string()?;
+                if (header >> 1) & 0x03 > 0 {
+                    cursor.u16()?; // идентификатор пакета для QoS 1 и 2
+                }
+                Self::Publish {
+                    topic,
+                    payload: cursor.rest(),
+                    retain: header & 0x01 != 0,
+                }
+            }
+            8 => {
+                let packet_id = cursor.u16()?;
+                let mut filters = Vec::new();
+                while !cursor.is_empty() {
+                    filters.push(cursor.string()?);
+                    cursor.byte()?;
+                }
+                Self::Subscribe { packet_id, filters }
+            }
+            9 => Self::SubAck {
+                packet_id: cursor.u16()?,
+                return_codes: cursor.rest(),
+            },
+            12 => Self::PingReq,
+            13 => Self::PingResp,
+            14 => Self::Disconnect,
+            _ => return Err(invalid("unsupported packet type")),
+        };
+        Ok(packet)
+    }
+}
+
+/// Последовательное чтение полей пакета
+struct Cursor<'a> {
+    /// Остаток пакета
+    body: &'a [u8],
+
+    /// Текущая позиция
+    position: usize,
+}
+
+impl Cursor<'_> {
+    /// Проверка, прочитан ли пакет полностью
+    fn is_empty(&self) -> bool {
+        self.position >= self.body.len()
+    }
+
+    /// Чтение `count` байт
+    fn take(&mut self, count: usize) -> io::Result<&[u8]> {
+        let end = self.position + count;
+        let slice = self
+            .body
+            .get(self.position..end)
+            .ok_or_else(|| invalid("truncated packet"))?;
+        self.position = end;
+        Ok(slice)
+    }
+
+    /// Чтение байта
+    fn byte(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Чтение двухбайтового числа
+    fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Чтение данных с двухбайтовой длиной
+    fn bytes(&mut self) -> io::Result<Vec<u8>> {
+        let length = self.u16()? as usize;
+        Ok(self.take(length)?.to_vec())
+    }
+
+    /// Чтение строки UTF-8 с двухбайтовой длиной
+    fn string(&mut self) -> io::Result<String> {
+        String::from_utf8(self.bytes()?).map_err(|_| invalid("string is not valid UTF-8"))
+    }
+
+    /// Чтение остатка пакета
+    fn rest(&mut self) -> Vec<u8> {
+        let rest = self.body[self.position.min(self.body.len())..].to_vec();
+        self.position = self.body.len();
+        rest
+    }
+}
+
+/// Запись данных с двухбайтовой длиной
+fn put_bytes(body: &mut Vec<u8>, bytes: &[u8]) {
+    body.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+    body.extend_from_slice(bytes);
+}
+
+/// Запись строки с двухбайтовой длиной
+fn put_string(body: &mut Vec<u8>, text: &str) {
+    put_bytes(body, text.as_bytes());
+}
+
+/// Ошибка разбора пакета
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packets_survive_round_trip() {
+        let packets = [
+            MqttPacket::Connect {
+                client_id: "bridge".to_string(),
+                keep_alive: 30,
+                will: Some(MqttWill {
+                    topic: "House/status".to_string(),
+                    payload: b"offline".to_vec(),
+                    retain: true,
+                }),
+            },
+            MqttPacket::Publish {
+                topic: "House/Kitchen/Kettle/state".to_string(),
+                payload: vec![b'x'; 300],
+                retain: true,
+            },
+            MqttPacket::Subscribe {
+                packet_id: 7,
+                filters: vec!["House/+/+/set".to_string()],
+            },
+            MqttPacket::PingReq,
+        ];
+
+        for packet in packets {
+            let mut encoded = Vec::new();
+            assert!(packet.write_to(&mut encoded).is_ok());
+            assert_eq!(
+                MqttPacket::read_from(&mut encoded.as_slice()).ok(),
+                Some(packet)
+            );
+        }
+    }
+}
@@ -0,0 +1,697 @@
+//! Модуль содержит язык запросов для поиска умных устройств в доме
+//!
+//! > Запрос состоит из условий вида `поле оператор значение`, объединённых
+//! > операторами `AND`, `OR`, `NOT` и скобками, например:
+//! > `kind=socket AND power>100 AND room~"Kitchen*"`.
+//! >
+//! > Поля: `name`, `room`, `floor`, `id`, `kind`, `vendor`, `model`, `firmware`, `status`
+//! > (`enabled`, `disabled` или `malfunction`), `tag`, `capability`, `power` (Вт)
+//! > и `temperature` (°C). Операторы: `=`, `!=`, `~` (шаблон с `*` и `?`),
+//! > а для числовых полей также `>`, `>=`, `<`, `<=`. Строки сравниваются без учёта регистра.
+//! > Для полей с несколькими значениями (`tag`, `capability`) условие `=` выполняется,
+//! > если запросу равно хотя бы одно значение, а условие `!=` - если запросу не равно
+//! > ни одно значение (у устройства без меток условие `tag!=x` выполняется).
+//! > Ключевое слово `ALL` (как и пустой запрос) соответствует любому устройству.
+//!
+use crate::containers::group::Tag;
+use crate::smart_device::{SmartDevice, SmartDevicePowerState, SmartDeviceStatus};
+use std::fmt::{self, Display};
+use std::ops::Not;
+use std::str::FromStr;
+
+/// Перечисление полей, доступных в условиях запроса
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueryField {
+    /// Название устройства
+    Name,
+    /// Название комнаты
+    Room,
+    /// Название этажа
+    Floor,
+    /// Уникальный идентификатор устройства
+    Id,
+    /// Тип устройства
+    Kind,
+    /// Производитель
+    Vendor,
+    /// Модель
+    Model,
+    /// Версия прошивки
+    Firmware,
+    /// Статус работы устройства
+    Status,
+    /// Метка устройства
+    Tag,
+    /// Возможность, поддерживаемая устройством
+    Capability,
+    /// Потребляемая мощность (Вт)
+    Power,
+    /// Измеренная температура (°C)
+    Temperature,
+}
+
+impl QueryField {
+    /// Все поля запроса
+    pub const ALL: [QueryField; 13] = [
+        Self::Name,
+        Self::Room,
+        Self::Floor,
+        Self::Id,
+        Self::Kind,
+        Self::Vendor,
+        Self::Model,
+        Self::Firmware,
+        Self::Status,
+        Self::Tag,
+        Self::Capability,
+        Self::Power,
+        Self::Temperature,
+    ];
+
+    /// Проверка, является ли поле числовым
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Self::Power | Self::Temperature)
+    }
+}
+
+impl Display for QueryField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Name => "name",
+            Self::Room => "room",
+            Self::Floor => "floor",
+            Self::Id => "id",
+            Self::Kind => "kind",
+            Self::Vendor => "vendor",
+            Self::Model => "model",
+            Self::Firmware => "firmware",
+            Self::Status => "status",
+            Self::Tag => "tag",
+            Self::Capability => "capability",
+            Self::Power => "power",
+            Self::Temperature => "temperature",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Перечисление операторов сравнения
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueryOperator {
+    /// Равно
+    Equal,
+    /// Не равно
+    NotEqual,
+    /// Соответствие шаблону с `*` и `?`
+    Like,
+    /// Больше
+    Greater,
+    /// Больше или равно
+    GreaterOrEqual,
+    /// Меньше
+    Less,
+    /// Меньше или равно
+    LessOrEqual,
+}
+
+impl QueryOperator {
+    /// Проверка, применим ли оператор только к числовым полям
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Self::Greater | Self::GreaterOrEqual | Self::Less | Self::LessOrEqual
+        )
+    }
+}
+
+impl Display for QueryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            Self::Equal => "=",
+            Self::NotEqual => "!=",
+            Self::Like => "~",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+        };
+        write!(f, "{}", symbol)
+    }
+}
+
+/// Ошибка разбора текстового запроса
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryError {
+    /// Позиция (в символах) в тексте запроса
+    pub position: usize,
+
+    /// Описание ошибки
+    pub message: String,
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "query error at {}: {}", self.position, self.message)
+    }
+}
+
+/// Данные умного устройства, по которым вычисляется запрос
+pub struct DeviceContext<'a> {
+    /// Название комнаты
+    pub room: &'a str,
+
+    /// Название этажа (если комната размещена на этаже)
+    pub floor: Option<&'a str>,
+
+    /// Умное устройство
+    pub device: &'a dyn SmartDevice,
+
+    /// Метки устройства
+    pub tags: &'a [Tag],
+}
+
+/// Запрос для поиска умных устройств
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    /// Любое устройство
+    All,
+    /// Условие `поле оператор значение`
+    Condition(QueryField, QueryOperator, String),
+    /// Выполнение обоих запросов
+    And(Box<Query>, Box<Query>),
+    /// Выполнение хотя бы одного из запросов
+    Or(Box<Query>, Box<Query>),
+    /// Отрицание запроса
+    Not(Box<Query>),
+}
+
+impl Query {
+    /// Создание условия `field operator value`
+    ///
+    /// ## Пример
+    /// ```ignore
+    /// let query = Query::condition(QueryField::Kind, QueryOperator::Equal, "socket")
+    ///     .and(!Query::condition(QueryField::Power, QueryOperator::Greater, "100"));
+    /// ```
+    pub fn condition(field: QueryField, operator: QueryOperator, value: &str) -> Self {
+        Self::Condition(field, operator, value.to_string())
+    }
+
+    /// Объединение запросов условием "и"
+    pub fn and(self, other: Query) -> Self {
+        Self::And(Box::new(self), Box::new(other))
+    }
+
+    /// Объединение запросов условием "или"
+    pub fn or(self, other: Query) -> Self {
+        Self::Or(Box::new(self), Box::new(other))
+    }
+
+    /// Разбор текстового запроса; пустой текст соответствует любому устройству
+    pub fn parse(text: &str) -> Result<Self, QueryError> {
+        let tokens = tokenize(text)?;
+        if tokens.is_empty() {
+            return Ok(Self::All);
+        }
+
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            length: text.chars().count(),
+        };
+        let query = parser.parse_or()?;
+        match parser.peek() {
+            None => Ok(query),
+            Some((position, token)) => Err(QueryError {
+                position,
+                message: format!("unexpected {}", token),
+            }),
+        }
+    }
+
+    /// Проверка, удовлетворяет ли устройство запросу
+    pub fn matches(&self, context: &DeviceContext) -> bool {
+        match self {
+            Self::All => true,
+            Self::Condition(field, operator, value) => evaluate(*field, *operator, value, context),
+            Self::And(left, right) => left.matches(context) && right.matches(context),
+            Self::Or(left, right) => left.matches(context) || right.matches(context),
+            Self::Not(query) => !query.matches(context),
+        }
+    }
+}
+
+impl Not for Query {
+    type Output = Query;
+
+    /// Отрицание запроса
+    fn not(self) -> Self::Output {
+        Self::Not(Box::new(self))
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::parse(text)
+    }
+}
+
+impl Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::All => write!(f, "ALL"),
+            Self::Condition(field, operator, value) => {
+                write!(f, "{}{}\"{}\"", field, operator, value.replace('"', "\\\""))
+            }
+            Self::And(left, right) => write!(f, "({} AND {})", left, right),
+            Self::Or(left, right) => write!(f, "({} OR {})", left, right),
+            Self::Not(query) => write!(f, "NOT {}", query),
+        }
+    }
+}
+
+/// Вычисление условия для устройства
+fn evaluate(
+    field: QueryField,
+    operator: QueryOperator,
+    value: &str,
+    context: &DeviceContext,
+) -> bool {
+    let device = context.device;
+
+    if field.is_numeric() {
+        let actual = match field {
+            QueryField::Power => Some(device.get_power_consumption().0),
+            _ => device.get_temperature().map(|temperature| temperature.0),
+        };
+        return match (actual, value.parse::<f32>()) {
+            (Some(actual), Ok(expected)) => compare_numbers(actual, operator, expected),
+            _ => false,
+        };
+    }
+
+    let metadata = device.get_metadata();
+    let actual: Vec<String> = match field {
+        QueryField::Name => vec![device.get_name().to_string()],
+        QueryField::Room => vec![context.room.to_string()],
+        QueryField::Floor => context.floor.map(str::to_string).into_iter().collect(),
+        QueryField::Id => vec![metadata.get_id().to_string()],
+        QueryField::Kind => vec![metadata.kind.to_string()],
+        QueryField::Vendor => vec![metadata.vendor.clone()],
+        QueryField::Model => vec![metadata.model.clone()],
+        QueryField::Firmware => vec![metadata.firmware_version.clone()],
+        QueryField::Status => vec![match device.get_device_status() {
+            SmartDeviceStatus::PowerState(SmartDevicePowerState::Enabled) => "enabled",
+            SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled) => "disabled",
+            SmartDeviceStatus::Malfunction(_) => "malfunction",
+        }
+        .to_string()],
+        QueryField::Tag => context.tags.to_vec(),
+        QueryField::Capability => device
+            .get_capabilities()
+            .iter()
+            .map(|capability| capability.to_string())
+            .collect(),
+        QueryField::Power | QueryField::Temperature => Vec::new(),
+    };
+
+    let value = value.to_lowercase();
+    let mut candidates = actual.iter().map(|text| text.to_lowercase());
+    match operator {
+        QueryOperator::Equal => candidates.any(|text| text == value),
+        QueryOperator::NotEqual => candidates.all(|text| text != value),
+        QueryOperator::Like => candidates.any(|text| glob_match(&value, &text)),
+        _ => false,
+    }
+}
+
+/// Сравнение чисел оператором `operator`
+fn compare_numbers(actual: f32, operator: QueryOperator, expected: f32) -> bool {
+    match operator {
+        QueryOperator::Equal | QueryOperator::Like => actual == expected,
+        QueryOperator::NotEqual => actual != expected,
+        QueryOperator::Greater => actual > expected,
+        QueryOperator::GreaterOrEqual => actual >= expected,
+        QueryOperator::Less => actual < expected,
+        QueryOperator::LessOrEqual => actual <= expected,
+    }
+}
+
+/// Проверка соответствия текста шаблону (`*` - любая последовательность, `?` - любой символ)
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // matched[j] - соответствует ли обработанная часть шаблона первым j символам текста
+    let mut matched = vec![false; text.len() + 1];
+    matched[0] = true;
+
+    for symbol in pattern {
+        let mut next = vec![false; text.len() + 1];
+        match symbol {
+            '*' => {
+                let mut any = false;
+                for j in 0..=text.len() {
+                    any |= matched[j];
+                    next[j] = any;
+                }
+            }
+            _ => {
+                for j in 1..=text.len() {
+                    next[j] = matched[j - 1] && (symbol == '?' || symbol == text[j - 1]);
+                }
+            }
+        }
+        matched = next;
+    }
+    matched[text.len()]
+}
+
+/// Лексема текстового запроса
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    /// Слово (поле, значение или ключевое слово)
+    Word(String),
+    /// Строка в кавычках
+    Quoted(String),
+    /// Оператор сравнения
+    Operator(QueryOperator),
+    /// Открывающая скобка
+    Open,
+    /// Закрывающая скобка
+    Close,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Word(word) => write!(f, "'{}'", word),
+            Self::Quoted(text) => write!(f, "\"{}\"", text),
+            Self::Operator(operator) => write!(f, "'{}'", operator),
+            Self::Open => write!(f, "'('"),
+            Self::Close => write!(f, "')'"),
+        }
+    }
+}
+
+/// Разбиение текста запроса на лексемы с позициями
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let symbols: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < symbols.len() {
+        let start = i;
+        let symbol = symbols[i];
+        let next = symbols.get(i + 1).copied();
+
+        let token = match symbol {
+            symbol if symbol.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '=' => Token::Operator(QueryOperator::Equal),
+            '~' => Token::Operator(QueryOperator::Like),
+            '!' if next == Some('=') => {
+                i += 1;
+                Token::Operator(QueryOperator::NotEqual)
+            }
+            '>' if next == Some('=') => {
+                i += 1;
+                Token::Operator(QueryOperator::GreaterOrEqual)
+            }
+            '<' if next == Some('=') => {
+                i += 1;
+                Token::Operator(QueryOperator::LessOrEqual)
+            }
+            '>' => Token::Operator(QueryOperator::Greater),
+            '<' => Token::Operator(QueryOperator::Less),
+            '"' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match symbols.get(i) {
+                        None => {
+                            return Err(QueryError {
+                                position: start,
+                                message: "unterminated string".to_string(),
+                            })
+                        }
+                        Some('"') => break,
+                        Some('\\') if i + 1 < symbols.len() => {
+                            value.push(symbols[i + 1]);
+                            i += 2;
+                        }
+                        Some(&symbol) => {
+                            value.push(symbol);
+                            i += 1;
+                        }
+                    }
+                }
+                Token::Quoted(value)
+            }
+            symbol if is_word_symbol(symbol) => {
+                while i + 1 < symbols.len() && is_word_symbol(symbols[i + 1]) {
+                    i += 1;
+                }
+                Token::Word(symbols[start..=i].iter().collect())
+            }
+            symbol => {
+                return Err(QueryError {
+                    position: start,
+                    message: format!("unexpected symbol '{}'", symbol),
+                })
+            }
+        };
+
+        tokens.push((start, token));
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+/// Проверка, может ли символ входить в слово запроса
+fn is_word_symbol(symbol: char) -> bool {
+    symbol.is_alphanumeric() || "_-.*?:/+".contains(symbol)
+}
+
+/// Синтаксический анализатор запроса (рекурсивный спуск)
+///
+/// Приоритет операторов: `NOT`, затем `AND`, затем `OR`
+struct Parser {
+    /// Лексемы с позициями
+    tokens: Vec<(usize, Token)>,
+    /// Индекс текущей лексемы
+    position: usize,
+    /// Длина текста запроса (для сообщений об ошибках в конце текста)
+    length: usize,
+}
+
+impl Parser {
+    /// Получение текущей лексемы без перехода к следующей
+    fn peek(&self) -> Option<(usize, Token)> {
+        self.tokens.get(self.position).cloned()
+    }
+
+    /// Получение текущей лексемы с переходом к следующей
+    fn next(&mut self) -> Result<(usize, Token), QueryError> {
+        let token = self.peek().ok_or(QueryError {
+            position: self.length,
+            message: "unexpected end of query".to_string(),
+        })?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    /// Переход к следующей лексеме, если текущая - ключевое слово `keyword`
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some((_, Token::Word(word))) if word.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// `or := and (OR and)*`
+    fn parse_or(&mut self) -> Result<Query, QueryError> {
+        let mut query = self.parse_and()?;
+        while self.accept_keyword("OR") {
+            query = query.or(self.parse_and()?);
+        }
+        Ok(query)
+    }
+
+    /// `and := unary (AND unary)*`
+    fn parse_and(&mut self) -> Result<Query, QueryError> {
+        let mut query = self.parse_unary()?;
+        while self.accept_keyword("AND") {
+            query = query.and(self.parse_unary()?);
+        }
+        Ok(query)
+    }
+
+    /// `unary := NOT unary | ALL | '(' or ')' | condition`
+    fn parse_unary(&mut self) -> Result<Query, QueryError> {
+        if self.accept_keyword("NOT") {
+            return Ok(self.parse_unary()?.not());
+        }
+        if self.accept_keyword("ALL") {
+            return Ok(Query::All);
+        }
+
+        if let Some((_, Token::Open)) = self.peek() {
+            self.position += 1;
+            let query = self.parse_or()?;
+            return match self.next()? {
+                (_, Token::Close) => Ok(query),
+                (position, token) => Err(QueryError {
+                    position,
+                    message: format!("expected ')' but found {}", token),
+                }),
+            };
+        }
+
+        self.parse_condition()
+    }
+
+    /// `condition := field operator value`
+    fn parse_condition(&mut self) -> Result<Query, QueryError> {
+        let (position, token) = self.next()?;
+        let field = match &token {
+            Token::Word(word) => QueryField::ALL
+                .into_iter()
+                .find(|field| field.to_string().eq_ignore_ascii_case(word)),
+            _ => None,
+        }
+        .ok_or(QueryError {
+            position,
+            message: format!("expected field name but found {}", token),
+        })?;
+
+        let operator = match self.next()? {
+            (_, Token::Operator(operator)) => operator,
+            (position, token) => {
+                return Err(QueryError {
+                    position,
+                    message: format!("expected operator but found {}", token),
+                })
+            }
+        };
+
+        let (position, value) = match self.next()? {
+            (position, Token::Word(value)) | (position, Token::Quoted(value)) => (position, value),
+            (position, token) => {
+                return Err(QueryError {
+                    position,
+                    message: format!("expected value but found {}", token),
+                })
+            }
+        };
+
+        if field.is_numeric() && operator != QueryOperator::Like && value.parse::<f32>().is_err() {
+            return Err(QueryError {
+                position,
+                message: format!("field '{}' expects a number", field),
+            });
+        }
+        if operator.is_numeric() && !field.is_numeric() {
+            return Err(QueryError {
+                position,
+                message: format!(
+                    "operator '{}' is not applicable to field '{}'",
+                    operator, field
+                ),
+            });
+        }
+        if operator == QueryOperator::Like && field.is_numeric() {
+            return Err(QueryError {
+                position,
+                message: format!("operator '~' is not applicable to field '{}'", field),
+            });
+        }
+
+        Ok(Query::Condition(field, operator, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::house::House;
+    use crate::room::Room;
+    use crate::socket::SmartSocket;
+    use crate::thermometer::SmartThermometer;
+    use crate::units::{Celsius, Watts};
+
+    #[test]
+    fn queries_are_parsed_with_precedence() {
+        let query = Query::parse("kind=socket AND power>100 OR NOT room~\"Kitchen*\"").unwrap();
+        assert_eq!(
+            query.to_string(),
+            "((kind=\"socket\" AND power>\"100\") OR NOT room~\"Kitchen*\")"
+        );
+
+        assert_eq!(Query::parse("  ").unwrap(), Query::All);
+        let query = Query::All.and(!Query::All);
+        assert_eq!(query.to_string(), "(ALL AND NOT ALL)");
+        assert_eq!(Query::parse(&query.to_string()).unwrap(), query);
+        assert_eq!(Query::parse("power>high").unwrap_err().position, 6);
+        assert_eq!(Query::parse("name<x").unwrap_err().position, 5);
+        assert_eq!(Query::parse("(kind=socket").unwrap_err().position, 12);
+        assert!(Query::parse("colour=red").is_err());
+        assert!(glob_match("kit*en?", "kitchen1"));
+        assert!(!glob_match("kit*en", "kitchens"));
+    }
+
+    #[test]
+    fn house_devices_are_found_by_query() {
+        let mut house = House::new("house", 2);
+        assert!(house.add_room(Room::new("Kitchen", 3)).is_ok());
+        assert!(house.add_room(Room::new("Hall", 3)).is_ok());
+
+        for (room, name, power) in [("Kitchen", "Kettle", 2000.0), ("Hall", "Lamp", 60.0)] {
+            let mut socket = SmartSocket::new(name);
+            assert!(socket
+                .set_power_state(SmartDevicePowerState::Enabled)
+                .is_ok());
+            socket.set_power_consumption(Watts(power));
+            assert!(house.add_device(room, Box::new(socket)).is_ok());
+        }
+        let mut thermometer = SmartThermometer::new("Sensor");
+        assert!(thermometer
+            .set_power_state(SmartDevicePowerState::Enabled)
+            .is_ok());
+        thermometer.set_temperature(Celsius(23.5));
+        assert!(house.add_device("Kitchen", Box::new(thermometer)).is_ok());
+        assert!(house.add_tag("Hall", "Lamp", "outdoor").is_ok());
+
+        let find = |text: &str| {
+            let mut devices: Vec<String> = house
+                .find_devices_str(text)
+                .unwrap()
+                .into_iter()
+                .map(|(_, device)| device)
+                .collect();
+            devices.sort();
+            devices
+        };
+
+        assert_eq!(
+            find("kind=socket AND power>100 AND room~\"kitch*\""),
+            vec!["Kettle"]
+        );
+        assert_eq!(find("temperature>=20"), vec!["Sensor"]);
+        assert_eq!(
+            find("tag=outdoor OR capability=temperature"),
+            vec!["Lamp", "Sensor"]
+        );
+        assert_eq!(find("NOT (status=enabled)"), Vec::<String>::new());
+        assert_eq!(find("").len(), 3);
+    }
+}
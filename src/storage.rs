@@ -0,0 +1,813 @@
+//! Модуль содержит локальное файловое хранилище телеметрии умного дома
+//!
+//! > Хранилище записывает показания устройств и изменения их состояния в каталог на диске.
+//! > Записи только добавляются в конец текущего сегмента; при превышении размера
+//! > сегмента открывается следующий. Каждая запись - одна строка с контрольной суммой,
+//! > поэтому запись, оборванная аварийным завершением процесса, обнаруживается
+//! > и отбрасывается при следующем открытии хранилища. Повреждённые строки внутри
+//! > сегментов пропускаются при чтении, не затрагивая соседние записи.
+//! > Устаревшие записи удаляются компактизацией сегментов. Создание, замена и удаление
+//! > файлов сегментов фиксируются синхронизацией каталога хранилища.
+//!
+use crate::command::Capability;
+use crate::containers::ContainerName;
+use crate::smart_device::{SmartDeviceErrorCode, SmartDevicePowerState, SmartDeviceStatus};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Максимальный размер сегмента по умолчанию (байт)
+pub const DEFAULT_SEGMENT_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Префикс имени файла сегмента
+const SEGMENT_PREFIX: &str = "segment-";
+
+/// Расширение имени файла сегмента
+const SEGMENT_EXTENSION: &str = ".log";
+
+/// Расширение имени временного файла, создаваемого при компактизации сегмента
+const TEMPORARY_EXTENSION: &str = ".tmp";
+
+/// Перечисление видов записей телеметрии
+#[derive(Clone, Debug, PartialEq)]
+pub enum TelemetryEvent {
+    /// Показание устройства
+    Reading {
+        /// Вид показания
+        capability: Capability,
+        /// Значение показания
+        value: f32,
+    },
+    /// Изменение статуса работы устройства
+    StateChange(SmartDeviceStatus),
+}
+
+/// Запись телеметрии
+#[derive(Clone, Debug, PartialEq)]
+pub struct TelemetryRecord {
+    /// Момент события (с точностью до миллисекунды)
+    pub timestamp: SystemTime,
+
+    /// Название комнаты
+    pub room: ContainerName,
+
+    /// Название устройства
+    pub device: ContainerName,
+
+    /// Событие
+    pub event: TelemetryEvent,
+}
+
+/// Настройки хранилища телеметрии
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StoreOptions {
+    /// Максимальный размер сегмента (байт)
+    pub max_segment_size: u64,
+
+    /// Сбрасывать ли данные на диск после каждой записи
+    pub sync_on_write: bool,
+}
+
+impl Default for StoreOptions {
+    fn default() -> Self {
+        Self {
+            max_segment_size: DEFAULT_SEGMENT_SIZE,
+            sync_on_write: true,
+        }
+    }
+}
+
+/// Описание сегмента хранилища
+struct Segment {
+    /// Порядковый номер сегмента
+    number: u64,
+    /// Размер файла сегмента (байт)
+    size: u64,
+    /// Время самой ранней записи в сегменте
+    first: Option<SystemTime>,
+    /// Время самой поздней записи в сегменте
+    last: Option<SystemTime>,
+}
+
+/// Тип, описывающий файловое хранилище телеметрии
+pub struct TelemetryStore {
+    /// Каталог хранилища
+    directory: PathBuf,
+
+    /// Настройки хранилища
+    options: StoreOptions,
+
+    /// Сегменты хранилища в порядке создания; последний - активный
+    segments: Vec<Segment>,
+
+    /// Файл активного сегмента, открытый на дозапись
+    active: File,
+
+    /// Последний записанный статус каждого устройства (комната, устройство)
+    last_status: HashMap<(ContainerName, ContainerName), SmartDeviceStatus>,
+
+    /// Количество повреждённых строк, пропущенных при открытии хранилища
+    corrupted: usize,
+}
+
+impl TelemetryStore {
+    /// Открытие (или создание) хранилища в каталоге `directory`
+    ///
+    /// Оборванная запись в конце активного сегмента отбрасывается, повреждённые строки
+    /// пропускаются, а временные файлы незавершённой компактизации удаляются
+    pub fn open<P: AsRef<Path>>(directory: P, options: StoreOptions) -> io::Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory)?;
+
+        let mut numbers = Vec::new();
+        for entry in fs::read_dir(&directory)? {
+            let entry = entry?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if let Some(number) = parse_segment_number(&file_name) {
+                numbers.push(number);
+            } else if file_name.starts_with(SEGMENT_PREFIX)
+                && file_name.ends_with(TEMPORARY_EXTENSION)
+            {
+                // Исходный сегмент не тронут: переименование временного файла не состоялось
+                fs::remove_file(entry.path())?;
+            }
+        }
+        numbers.sort_unstable();
+        if numbers.is_empty() {
+            numbers.push(1);
+        }
+        let active_number = numbers[numbers.len() - 1];
+
+        let mut segments = Vec::with_capacity(numbers.len());
+        let mut last_status = HashMap::new();
+        let mut corrupted = 0;
+        for number in numbers {
+            let path = segment_path(&directory, number);
+            let contents = read_segment(&path)?;
+            corrupted += contents.corrupted;
+
+            // Отбрасывание оборванной записи в конце активного сегмента,
+            // чтобы следующая запись начиналась с новой строки
+            let mut size = contents.length;
+            if number == active_number && contents.complete_length != contents.length {
+                OpenOptions::new()
+                    .write(true)
+                    .open(&path)?
+                    .set_len(contents.complete_length)?;
+                size = contents.complete_length;
+            }
+            let records = contents.records;
+
+            for record in records.iter() {
+                if let TelemetryEvent::StateChange(status) = &record.event {
+                    last_status
+                        .insert((record.room.clone(), record.device.clone()), status.clone());
+                }
+            }
+
+            let (first, last) = time_bounds(records.iter());
+            segments.push(Segment {
+                number,
+                size,
+                first,
+                last,
+            });
+        }
+
+        let active = open_for_append(&segment_path(&directory, active_number))?;
+        sync_directory(&directory)?;
+
+        Ok(Self {
+            directory,
+            options,
+            segments,
+            active,
+            last_status,
+            corrupted,
+        })
+    }
+
+    /// Получение каталога хранилища
+    pub fn get_directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Получение количества сегментов хранилища
+    pub fn get_segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Получение количества повреждённых строк, пропущенных при открытии хранилища
+    pub fn get_corrupted_count(&self) -> usize {
+        self.corrupted
+    }
+
+    /// Добавление записи в хранилище
+    pub fn append(&mut self, record: &TelemetryRecord) -> io::Result<()> {
+        let line = encode_record(record);
+        let length = line.len() as u64;
+
+        let needs_rotation = self.segments.last().is_some_and(|segment| {
+            segment.size > 0 && segment.size + length > self.options.max_segment_size
+        });
+        if needs_rotation {
+            self.rotate()?;
+        }
+
+        self.active.write_all(line.as_bytes())?;
+        if self.options.sync_on_write {
+            self.active.sync_data()?;
+        }
+
+        if let Some(segment) = self.segments.last_mut() {
+            segment.size += length;
+            segment.first = Some(
+                segment
+                    .first
+                    .map_or(record.timestamp, |first| first.min(record.timestamp)),
+            );
+            segment.last = Some(
+                segment
+                    .last
+                    .map_or(record.timestamp, |last| last.max(record.timestamp)),
+            );
+        }
+        if let TelemetryEvent::StateChange(status) = &record.event {
+            self.last_status
+                .insert((record.room.clone(), record.device.clone()), status.clone());
+        }
+        Ok(())
+    }
+
+    /// Добавление записи об изменении статуса устройства,
+    /// если статус отличается от последнего записанного
+    ///
+    /// Возвращает `true`, если запись была добавлена
+    pub fn append_status(
+        &mut self,
+        timestamp: SystemTime,
+        room: &str,
+        device: &str,
+        status: SmartDeviceStatus,
+    ) -> io::Result<bool> {
+        let key = (room.to_string(), device.to_string());
+        if self.last_status.get(&key) == Some(&status) {
+            return Ok(false);
+        }
+
+        self.append(&TelemetryRecord {
+            timestamp,
+            room: key.0,
+            device: key.1,
+            event: TelemetryEvent::StateChange(status),
+        })?;
+        Ok(true)
+    }
+
+    /// Получение записей в интервале `[from, to]` в порядке добавления
+    pub fn query(&self, from: SystemTime, to: SystemTime) -> io::Result<Vec<TelemetryRecord>> {
+        let mut result = Vec::new();
+
+        for segment in self.segments.iter() {
+            let overlaps = match (segment.first, segment.last) {
+                (Some(first), Some(last)) => first <= to && last >= from,
+                _ => false,
+            };
+            if !overlaps {
+                continue;
+            }
+
+            let records = read_segment(&segment_path(&self.directory, segment.number))?.records;
+            result.extend(
+                records
+                    .into_iter()
+                    .filter(|record| record.timestamp >= from && record.timestamp <= to),
+            );
+        }
+        Ok(result)
+    }
+
+    /// Получение записей устройства `device` комнаты `room` в интервале `[from, to]`
+    pub fn query_device(
+        &self,
+        room: &str,
+        device: &str,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> io::Result<Vec<TelemetryRecord>> {
+        Ok(self
+            .query(from, to)?
+            .into_iter()
+            .filter(|record| record.room == room && record.device == device)
+            .collect())
+    }
+
+    /// Удаление записей старше `cutoff` из всех сегментов, включая активный
+    ///
+    /// Каждый сегмент переписывается во временный файл, который затем атомарно
+    /// заменяет исходный; опустевшие закрытые сегменты удаляются, а активный
+    /// остаётся открытым на дозапись.
+    /// Возвращает количество удалённых записей
+    pub fn compact(&mut self, cutoff: SystemTime) -> io::Result<usize> {
+        let mut removed = 0;
+        let active_index = self.segments.len().saturating_sub(1);
+
+        for (index, segment) in self.segments.iter_mut().enumerate() {
+            if segment.first.is_none_or(|first| first >= cutoff) {
+                continue;
+            }
+            let is_active = index == active_index;
+
+            let path = segment_path(&self.directory, segment.number);
+            let records = read_segment(&path)?.records;
+            let kept: Vec<&TelemetryRecord> = records
+                .iter()
+                .filter(|record| record.timestamp >= cutoff)
+                .collect();
+            removed += records.len() - kept.len();
+
+            if kept.is_empty() && !is_active {
+                fs::remove_file(&path)?;
+                sync_directory(&self.directory)?;
+                segment.size = 0;
+                segment.first = None;
+                segment.last = None;
+                continue;
+            }
+
+            let temporary = path.with_extension(&TEMPORARY_EXTENSION[1..]);
+            let mut file = File::create(&temporary)?;
+            let mut size = 0;
+            for record in kept.iter() {
+                let line = encode_record(record);
+                size += line.len() as u64;
+                file.write_all(line.as_bytes())?;
+            }
+            file.sync_all()?;
+            fs::rename(&temporary, &path)?;
+            sync_directory(&self.directory)?;
+
+            // Дескриптор активного сегмента указывает на заменённый файл
+            if is_active {
+                self.active = open_for_append(&path)?;
+            }
+
+            (segment.first, segment.last) = time_bounds(kept.into_iter());
+            segment.size = size;
+        }
+
+        // Удалённые сегменты исключаются из списка
+        let active_number = self.segments.last().map(|segment| segment.number);
+        self.segments
+            .retain(|segment| segment.size > 0 || Some(segment.number) == active_number);
+        Ok(removed)
+    }
+
+    /// Закрытие активного сегмента и создание следующего
+    fn rotate(&mut self) -> io::Result<()> {
+        self.active.sync_all()?;
+
+        let number = self.segments.last().map_or(1, |segment| segment.number + 1);
+        self.active = open_for_append(&segment_path(&self.directory, number))?;
+        sync_directory(&self.directory)?;
+        self.segments.push(Segment {
+            number,
+            size: 0,
+            first: None,
+            last: None,
+        });
+        Ok(())
+    }
+}
+
+/// Получение времени самой ранней и самой поздней из записей
+fn time_bounds<'a, I: Iterator<Item = &'a TelemetryRecord>>(
+    records: I,
+) -> (Option<SystemTime>, Option<SystemTime>) {
+    records.fold((None, None), |(first, last), record| {
+        (
+            Some(first.map_or(record.timestamp, |first: SystemTime| {
+                first.min(record.timestamp)
+            })),
+            Some(last.map_or(record.timestamp, |last: SystemTime| {
+                last.max(record.timestamp)
+            })),
+        )
+    })
+}
+
+/// Получение пути к файлу сегмента с номером `number`
+fn segment_path(directory: &Path, number: u64) -> PathBuf {
+    directory.join(format!(
+        "{}{:06}{}",
+        SEGMENT_PREFIX, number, SEGMENT_EXTENSION
+    ))
+}
+
+/// Получение номера сегмента по имени файла
+fn parse_segment_number(file_name: &str) -> Option<u64> {
+    file_name
+        .strip_prefix(SEGMENT_PREFIX)?
+        .strip_suffix(SEGMENT_EXTENSION)?
+        .parse()
+        .ok()
+}
+
+/// Открытие файла сегмента на дозапись
+fn open_for_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Синхронизация каталога `directory`, фиксирующая создание, переименование
+/// и удаление файлов в нём
+///
+/// На платформах, где каталог нельзя открыть как файл, синхронизация не выполняется
+fn sync_directory(directory: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    File::open(directory)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = directory;
+    Ok(())
+}
+
+/// Содержимое файла сегмента
+#[derive(Default)]
+struct SegmentContents {
+    /// Корректные записи
+    records: Vec<TelemetryRecord>,
+    /// Количество пропущенных повреждённых строк
+    corrupted: usize,
+    /// Длина файла (байт)
+    length: u64,
+    /// Длина части файла, состоящей из завершённых строк (байт)
+    complete_length: u64,
+}
+
+/// Чтение корректных записей сегмента
+///
+/// Повреждённые строки (не UTF-8 или с неверной контрольной суммой) пропускаются;
+/// незавершённая последняя строка в записи не учитывается
+fn read_segment(path: &Path) -> io::Result<SegmentContents> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            return Ok(SegmentContents::default())
+        }
+        Err(error) => return Err(error),
+    };
+
+    let mut reader = BufReader::new(file);
+    let mut contents = SegmentContents::default();
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)? as u64;
+        if read == 0 {
+            break;
+        }
+        contents.length += read;
+        if line.last() != Some(&b'\n') {
+            break;
+        }
+        contents.complete_length += read;
+
+        let record = std::str::from_utf8(&line[..line.len() - 1])
+            .ok()
+            .and_then(decode_record);
+        match record {
+            Some(record) => contents.records.push(record),
+            None => contents.corrupted += 1,
+        }
+    }
+    Ok(contents)
+}
+
+/// Кодирование записи в строку формата
+/// `<мс>\t<комната>\t<устройство>\t<событие>\t<контрольная сумма>\n`
+fn encode_record(record: &TelemetryRecord) -> String {
+    let millis = record
+        .timestamp
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+
+    let event = match &record.event {
+        TelemetryEvent::Reading { capability, value } => format!("R\t{}\t{}", capability, value),
+        TelemetryEvent::StateChange(status) => format!("S\t{}", encode_status(status)),
+    };
+
+    let payload = format!(
+        "{}\t{}\t{}\t{}",
+        millis,
+        escape(&record.room),
+        escape(&record.device),
+        event
+    );
+    format!("{}\t{:08x}\n", payload, checksum(&payload))
+}
+
+/// Декодирование строки в запись; при повреждении возвращается `None`
+fn decode_record(line: &str) -> Option<TelemetryRecord> {
+    let (payload, sum) = line.rsplit_once('\t')?;
+    if u32::from_str_radix(sum, 16).ok()? != checksum(payload) {
+        return None;
+    }
+
+    let fields: Vec<&str> = payload.split('\t').collect();
+    let event = match fields.as_slice() {
+        [_, _, _, "R", capability, value] => TelemetryEvent::Reading {
+            capability: parse_capability(capability)?,
+            value: value.parse().ok()?,
+        },
+        [_, _, _, "S", status] => TelemetryEvent::StateChange(decode_status(status)?),
+        _ => return None,
+    };
+
+    Some(TelemetryRecord {
+        timestamp: UNIX_EPOCH + Duration::from_millis(fields[0].parse().ok()?),
+        room: unescape(fields[1]),
+        device: unescape(fields[2]),
+        event,
+    })
+}
+
+/// Кодирование статуса устройства
+fn encode_status(status: &SmartDeviceStatus) -> String {
+    match status {
+        SmartDeviceStatus::PowerState(SmartDevicePowerState::Enabled) => "enabled".to_string(),
+        SmartDeviceStatus::PowerState(SmartDevicePowerState::Disabled) => "disabled".to_string(),
+        SmartDeviceStatus::Malfunction(code) => {
+            format!("malfunction:{}", error_code_index(code))
+        }
+    }
+}
+
+/// Декодирование статуса устройства
+fn decode_status(text: &str) -> Option<SmartDeviceStatus> {
+    match text {
+        "enabled" => Some(SmartDeviceStatus::PowerState(
+            SmartDevicePowerState::Enabled,
+        )),
+        "disabled" => Some(SmartDeviceStatus::PowerState(
+            SmartDevicePowerState::Disabled,
+        )),
+        _ => {
+            let index = text.strip_prefix("malfunction:")?.parse().ok()?;
+            error_code_from_index(index).map(SmartDeviceStatus::Malfunction)
+        }
+    }
+}
+
+/// Номер кода ошибки устройства в хранилище
+///
+/// Номера не меняются; новые коды получают следующий свободный номер
+/// (сопоставление исчерпывающее, поэтому новый код нельзя забыть)
+fn error_code_index(code: &SmartDeviceErrorCode) -> u8 {
+    match code {
+        SmartDeviceErrorCode::Overcurrent => 0,
+        SmartDeviceErrorCode::Overvoltage => 1,
+        SmartDeviceErrorCode::Overheat => 2,
+        SmartDeviceErrorCode::Underheat => 3,
+        SmartDeviceErrorCode::Obstruction => 4,
+        SmartDeviceErrorCode::PoweredOff => 5,
+        SmartDeviceErrorCode::Jammed => 6,
+        SmartDeviceErrorCode::AccessDenied => 7,
+        SmartDeviceErrorCode::Unreachable => 8,
+    }
+}
+
+/// Код ошибки устройства по номеру в хранилище
+fn error_code_from_index(index: u8) -> Option<SmartDeviceErrorCode> {
+    match index {
+        0 => Some(SmartDeviceErrorCode::Overcurrent),
+        1 => Some(SmartDeviceErrorCode::Overvoltage),
+        2 => Some(SmartDeviceErrorCode::Overheat),
+        3 => Some(SmartDeviceErrorCode::Underheat),
+        4 => Some(SmartDeviceErrorCode::Obstruction),
+        5 => Some(SmartDeviceErrorCode::PoweredOff),
+        6 => Some(SmartDeviceErrorCode::Jammed),
+        7 => Some(SmartDeviceErrorCode::AccessDenied),
+        8 => Some(SmartDeviceErrorCode::Unreachable),
+        _ => None,
+    }
+}
+
+/// Получение возможности по её текстовому обозначению
+fn parse_capability(text: &str) -> Option<Capability> {
+    Capability::ALL
+        .into_iter()
+        .find(|capability| capability.to_string() == text)
+}
+
+/// Экранирование служебных символов в названиях
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Восстановление экранированных служебных символов
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Контрольная сумма FNV-1a (32 бита)
+fn checksum(text: &str) -> u32 {
+    text.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    fn reading(seconds: u64, value: f32) -> TelemetryRecord {
+        TelemetryRecord {
+            timestamp: at(seconds),
+            room: "Kitchen".to_string(),
+            device: "Sensor\t1".to_string(),
+            event: TelemetryEvent::Reading {
+                capability: Capability::Temperature,
+                value,
+            },
+        }
+    }
+
+    fn temporary_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("iot_crate_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    fn records_survive_reopen_and_torn_write() {
+        let directory = temporary_directory("reopen");
+        {
+            let mut store = TelemetryStore::open(&directory, StoreOptions::default()).unwrap();
+            store.append(&reading(10, 21.5)).unwrap();
+            store.append(&reading(20, 22.5)).unwrap();
+        }
+
+        // Имитация оборванной записи
+        let mut file = open_for_append(&segment_path(&directory, 1)).unwrap();
+        file.write_all(b"30000\tKitchen\tSen").unwrap();
+
+        let mut store = TelemetryStore::open(&directory, StoreOptions::default()).unwrap();
+        store.append(&reading(30, 23.5)).unwrap();
+
+        let records = store.query(at(0), at(100)).unwrap();
+        assert_eq!(
+            records,
+            vec![reading(10, 21.5), reading(20, 22.5), reading(30, 23.5)]
+        );
+        assert_eq!(
+            store.query(at(15), at(25)).unwrap(),
+            vec![reading(20, 22.5)]
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn corrupted_lines_are_skipped_without_losing_later_records() {
+        let directory = temporary_directory("corrupted");
+        fs::create_dir_all(&directory).unwrap();
+        let sealed = format!(
+            "{}garbage\n{}",
+            encode_record(&reading(10, 1.0)),
+            encode_record(&reading(20, 2.0))
+        );
+        fs::write(segment_path(&directory, 1), sealed).unwrap();
+        let active = format!("{}30000\tKitchen\tSen", encode_record(&reading(30, 3.0)));
+        fs::write(segment_path(&directory, 2), active).unwrap();
+        let temporary = segment_path(&directory, 1).with_extension("tmp");
+        fs::write(&temporary, "partial compaction").unwrap();
+
+        let mut store = TelemetryStore::open(&directory, StoreOptions::default()).unwrap();
+        assert_eq!(store.get_corrupted_count(), 1);
+        assert!(!temporary.exists());
+        store.append(&reading(40, 4.0)).unwrap();
+
+        assert_eq!(
+            store.query(at(0), at(100)).unwrap(),
+            vec![
+                reading(10, 1.0),
+                reading(20, 2.0),
+                reading(30, 3.0),
+                reading(40, 4.0)
+            ]
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn segments_rotate_and_compact() {
+        let directory = temporary_directory("compact");
+        let options = StoreOptions {
+            max_segment_size: 64,
+            sync_on_write: false,
+        };
+        let mut store = TelemetryStore::open(&directory, options).unwrap();
+
+        for second in 0..6 {
+            store.append(&reading(second * 10, second as f32)).unwrap();
+        }
+        assert!(store.get_segment_count() > 1);
+
+        let removed = store.compact(at(30)).unwrap();
+        assert_eq!(removed, 3);
+        let records = store.query(at(0), at(100)).unwrap();
+        assert_eq!(records.first(), Some(&reading(30, 3.0)));
+        assert_eq!(records.len(), 3);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn active_segment_is_compacted() {
+        let directory = temporary_directory("compact-active");
+        let mut store = TelemetryStore::open(&directory, StoreOptions::default()).unwrap();
+
+        for second in 0..4 {
+            store.append(&reading(second * 10, second as f32)).unwrap();
+        }
+        assert_eq!(store.get_segment_count(), 1);
+
+        assert_eq!(store.compact(at(20)).unwrap(), 2);
+        store.append(&reading(40, 4.0)).unwrap();
+        let records = store.query(at(0), at(100)).unwrap();
+        assert_eq!(
+            records,
+            vec![reading(20, 2.0), reading(30, 3.0), reading(40, 4.0)]
+        );
+
+        // Полностью устаревший активный сегмент остаётся и принимает новые записи
+        assert_eq!(store.compact(at(100)).unwrap(), 3);
+        assert_eq!(store.get_segment_count(), 1);
+        store.append(&reading(110, 5.0)).unwrap();
+
+        let store = TelemetryStore::open(&directory, StoreOptions::default()).unwrap();
+        assert_eq!(
+            store.query(at(0), at(200)).unwrap(),
+            vec![reading(110, 5.0)]
+        );
+        assert_eq!(store.get_corrupted_count(), 0);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn unchanged_status_is_not_duplicated() {
+        let directory = temporary_directory("status");
+        let mut store = TelemetryStore::open(&directory, StoreOptions::default()).unwrap();
+        let enabled = SmartDeviceStatus::PowerState(SmartDevicePowerState::Enabled);
+        let jammed = SmartDeviceStatus::Malfunction(SmartDeviceErrorCode::Jammed);
+
+        assert!(store
+            .append_status(at(1), "Hall", "Lock", enabled.clone())
+            .unwrap());
+        assert!(!store.append_status(at(2), "Hall", "Lock", enabled).unwrap());
+        assert!(store
+            .append_status(at(3), "Hall", "Lock", jammed.clone())
+            .unwrap());
+
+        let store = TelemetryStore::open(&directory, StoreOptions::default()).unwrap();
+        let records = store.query_device("Hall", "Lock", at(0), at(10)).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].event, TelemetryEvent::StateChange(jammed));
+
+        for index in 0..=u8::MAX {
+            if let Some(code) = error_code_from_index(index) {
+                assert_eq!(error_code_index(&code), index);
+            }
+        }
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,251 @@
+//! Модуль содержит типы физических величин, используемых умными устройствами
+//!
+//! > Каждая величина хранится в отдельном типе, что исключает случайное смешивание
+//! > единиц измерения (например, ватт и градусов) в API устройств и отчётах.
+//! > Температура хранится в градусах Цельсия и может быть преобразована
+//! > в градусы Фаренгейта и кельвины.
+//!
+use std::fmt::{self, Display};
+use std::iter::Sum;
+use std::ops::{Add, Sub};
+use std::time::Duration;
+
+/// Мощность (Вт)
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Watts(pub f32);
+
+/// Энергия (Вт·ч)
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct WattHours(pub f32);
+
+/// Температура (°C)
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Celsius(pub f32);
+
+/// Температура (°F)
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Fahrenheit(pub f32);
+
+/// Температура (K)
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Kelvin(pub f32);
+
+/// Доля от полного значения (%), от `0` до `100`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Percent(u8);
+
+/// Перечисление температурных шкал для отображения показаний
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TemperatureScale {
+    /// Шкала Цельсия
+    Celsius,
+    /// Шкала Фаренгейта
+    Fahrenheit,
+    /// Шкала Кельвина
+    Kelvin,
+}
+
+impl Watts {
+    /// Обозначение единицы измерения
+    pub const SYMBOL: &'static str = "W";
+
+    /// Энергия, потреблённая при данной мощности за время `duration`
+    pub fn over(self, duration: Duration) -> WattHours {
+        WattHours(self.0 * duration.as_secs_f32() / 3600.0)
+    }
+}
+
+impl WattHours {
+    /// Обозначение единицы измерения
+    pub const SYMBOL: &'static str = "Wh";
+}
+
+impl Celsius {
+    /// Обозначение единицы измерения
+    pub const SYMBOL: &'static str = "°C";
+
+    /// Преобразование в градусы Фаренгейта
+    pub fn to_fahrenheit(self) -> Fahrenheit {
+        Fahrenheit(self.0 * 9.0 / 5.0 + 32.0)
+    }
+
+    /// Преобразование в кельвины
+    pub fn to_kelvin(self) -> Kelvin {
+        Kelvin(self.0 + 273.15)
+    }
+
+    /// Форматирование значения в выбранной шкале
+    pub fn display_in(self, scale: TemperatureScale) -> String {
+        match scale {
+            TemperatureScale::Celsius => self.to_string(),
+            TemperatureScale::Fahrenheit => self.to_fahrenheit().to_string(),
+            TemperatureScale::Kelvin => self.to_kelvin().to_string(),
+        }
+    }
+}
+
+impl Fahrenheit {
+    /// Обозначение единицы измерения
+    pub const SYMBOL: &'static str = "°F";
+
+    /// Преобразование в градусы Цельсия
+    pub fn to_celsius(self) -> Celsius {
+        Celsius((self.0 - 32.0) * 5.0 / 9.0)
+    }
+}
+
+impl Kelvin {
+    /// Обозначение единицы измерения
+    pub const SYMBOL: &'static str = "K";
+
+    /// Преобразование в градусы Цельсия
+    pub fn to_celsius(self) -> Celsius {
+        Celsius(self.0 - 273.15)
+    }
+}
+
+impl Percent {
+    /// Обозначение единицы измерения
+    pub const SYMBOL: &'static str = "%";
+
+    /// Минимальное значение (`0%`)
+    pub const MIN: Percent = Percent(0);
+
+    /// Максимальное значение (`100%`)
+    pub const MAX: Percent = Percent(100);
+
+    /// Создание значения; величины больше `100` ограничиваются `100%`
+    pub const fn new(value: u8) -> Self {
+        if value > 100 {
+            Self(100)
+        } else {
+            Self(value)
+        }
+    }
+
+    /// Получение значения в процентах
+    pub fn value(self) -> u8 {
+        self.0
+    }
+}
+
+impl From<Fahrenheit> for Celsius {
+    fn from(value: Fahrenheit) -> Self {
+        value.to_celsius()
+    }
+}
+
+impl From<Kelvin> for Celsius {
+    fn from(value: Kelvin) -> Self {
+        value.to_celsius()
+    }
+}
+
+impl From<Celsius> for Fahrenheit {
+    fn from(value: Celsius) -> Self {
+        value.to_fahrenheit()
+    }
+}
+
+impl From<Celsius> for Kelvin {
+    fn from(value: Celsius) -> Self {
+        value.to_kelvin()
+    }
+}
+
+impl Add for Watts {
+    type Output = Watts;
+
+    fn add(self, other: Watts) -> Watts {
+        Watts(self.0 + other.0)
+    }
+}
+
+impl Sub for Watts {
+    type Output = Watts;
+
+    fn sub(self, other: Watts) -> Watts {
+        Watts(self.0 - other.0)
+    }
+}
+
+impl Sum for Watts {
+    fn sum<I: Iterator<Item = Watts>>(iter: I) -> Watts {
+        Watts(iter.map(|value| value.0).sum())
+    }
+}
+
+impl Add for WattHours {
+    type Output = WattHours;
+
+    fn add(self, other: WattHours) -> WattHours {
+        WattHours(self.0 + other.0)
+    }
+}
+
+impl Sum for WattHours {
+    fn sum<I: Iterator<Item = WattHours>>(iter: I) -> WattHours {
+        WattHours(iter.map(|value| value.0).sum())
+    }
+}
+
+impl Display for Watts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.0, Self::SYMBOL)
+    }
+}
+
+impl Display for WattHours {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.0, Self::SYMBOL)
+    }
+}
+
+impl Display for Celsius {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.0, Self::SYMBOL)
+    }
+}
+
+impl Display for Fahrenheit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.0, Self::SYMBOL)
+    }
+}
+
+impl Display for Kelvin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.0, Self::SYMBOL)
+    }
+}
+
+impl Display for Percent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.0, Self::SYMBOL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temperature_conversions() {
+        assert_eq!(Celsius(100.0).to_fahrenheit(), Fahrenheit(212.0));
+        assert_eq!(Celsius::from(Fahrenheit(32.0)), Celsius(0.0));
+        assert_eq!(Celsius(0.0).to_kelvin(), Kelvin(273.15));
+        assert_eq!(
+            Celsius(20.0).display_in(TemperatureScale::Fahrenheit),
+            "68 °F"
+        );
+    }
+
+    #[test]
+    fn power_and_energy() {
+        let total: Watts = vec![Watts(100.0), Watts(50.0)].into_iter().sum();
+
+        assert_eq!(total, Watts(150.0));
+        assert_eq!(total.over(Duration::from_secs(7200)), WattHours(300.0));
+        assert_eq!(Percent::new(150), Percent::MAX);
+    }
+}
@@ -0,0 +1,504 @@
+//! Модуль содержит поток событий умного дома по протоколу WebSocket
+//!
+//! > Модуль доступен при включённой cargo-функции `websocket` и подключается к
+//! > HTTP-серверу ([`HttpServer`](crate::http::HttpServer)) по пути `GET /events`.
+//! > Каждое событие шины ([`HouseEvent`]) отправляется клиенту текстовым сообщением
+//! > с объектом JSON ([`HouseEvent::to_json`]).
+//! >
+//! > Начальный фильтр задаётся параметрами строки запроса:
+//! > `/events?room=Kitchen&device=Kettle&kind=reading` (параметры можно повторять).
+//! > Во время работы клиент может заменить фильтр, отправив сообщение
+//! > `{"rooms": [..], "devices": [..], "kinds": [..]}`; сервер подтверждает его
+//! > сообщением `{"filter": {..}}`.
+//! >
+//! > При подключении клиенту повторяются сохранённые шиной последние события,
+//! > удовлетворяющие фильтру (с полем `"replay": true`); их количество задаётся
+//! > [`HttpServer::with_event_backlog`](crate::http::HttpServer::with_event_backlog).
+//! >
+//! > События для каждого клиента ставятся в очередь ограниченной ёмкости; клиент,
+//! > не успевающий их получать, отключается с кодом закрытия 1008.
+//!
+use crate::containers::shared::SharedHouse;
+use crate::events::{EventFilter, EventKind, HouseEvent, SubscriptionId};
+use crate::http::{HttpRequest, HttpResponse};
+use crate::json::JsonValue;
+use std::io::{self, BufReader, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// GUID, используемый при вычислении ключа подтверждения рукопожатия (RFC 6455)
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Максимальный размер сообщения клиента в байтах
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// Максимальное количество событий, ожидающих отправки клиенту
+///
+/// Клиент, не успевающий получать события, отключается
+const CLIENT_QUEUE_CAPACITY: usize = 1024;
+
+/// Код закрытия соединения при переполнении очереди событий клиента (1008, нарушение политики)
+const CLOSE_QUEUE_OVERFLOW: u16 = 1008;
+
+/// Период проверки управляющих сообщений при отсутствии событий
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Коды операций кадров WebSocket
+mod opcode {
+    /// Продолжение фрагментированного сообщения
+    pub const CONTINUATION: u8 = 0x0;
+    /// Текстовое сообщение
+    pub const TEXT: u8 = 0x1;
+    /// Двоичное сообщение
+    pub const BINARY: u8 = 0x2;
+    /// Закрытие соединения
+    pub const CLOSE: u8 = 0x8;
+    /// Проверка соединения
+    pub const PING: u8 = 0x9;
+    /// Ответ на проверку соединения
+    pub const PONG: u8 = 0xA;
+}
+
+/// Управляющее сообщение потока чтения для потока записи
+enum Control {
+    /// Замена фильтра клиента
+    Filter(EventFilter),
+    /// Ответ на проверку соединения
+    Pong(Vec<u8>),
+    /// Ошибочное сообщение клиента
+    Invalid(String),
+    /// Закрытие соединения
+    Close,
+}
+
+/// Разбор фильтра событий из объекта JSON `{"rooms": [..], "devices": [..], "kinds": [..]}`
+///
+/// Отсутствующее поле означает "любое значение"
+pub fn parse_filter(value: &JsonValue) -> Option<EventFilter> {
+    let names = |key: &str| -> Option<Vec<String>> {
+        match value.get(key) {
+            None => Some(Vec::new()),
+            Some(items) => items
+                .as_array()?
+                .iter()
+                .map(|item| item.as_str().map(str::to_string))
+                .collect(),
+        }
+    };
+
+    Some(EventFilter {
+        rooms: names("rooms")?,
+        devices: names("devices")?,
+        kinds: names("kinds")?
+            .iter()
+            .map(|name| EventKind::from_name(name))
+            .collect::<Option<_>>()?,
+    })
+}
+
+/// Представление фильтра событий в виде объекта JSON
+pub fn filter_json(filter: &EventFilter) -> JsonValue {
+    JsonValue::object()
+        .with("rooms", filter.rooms.clone())
+        .with("devices", filter.devices.clone())
+        .with(
+            "kinds",
+            filter
+                .kinds
+                .iter()
+                .map(EventKind::get_name)
+                .collect::<Vec<_>>(),
+        )
+}
+
+/// Обслуживание запроса `GET /events`: рукопожатие и отправка событий до закрытия соединения
+pub(crate) fn serve(
+    stream: TcpStream,
+    house: &SharedHouse,
+    request: &HttpRequest,
+) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let filter = match handshake_filter(request) {
+        Ok(filter) => filter,
+        Err(response) => return response.write_to(&mut writer),
+    };
+    let Some(key) = request.get_header("sec-websocket-key") else {
+        return HttpResponse::error(400, "bad_request", "missing Sec-WebSocket-Key")
+            .write_to(&mut writer);
+    };
+
+    write!(
+        writer,
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    )?;
+    writer.flush()?;
+
+    // Повтор и подписка выполняются под одной блокировкой шины,
+    // поэтому события не теряются и не дублируются
+    let (backlog, subscription) = house.with_event_bus(|bus| {
+        let backlog: Vec<HouseEvent> = bus.get_backlog(&filter).into_iter().cloned().collect();
+        (
+            backlog,
+            bus.subscribe_bounded_channel(filter, CLIENT_QUEUE_CAPACITY),
+        )
+    });
+
+    let (control, commands) = mpsc::channel();
+    stream.set_read_timeout(None)?;
+    let reader = stream.try_clone()?;
+    thread::spawn(move || read_messages(reader, control));
+
+    let mut stream_state = ClientStream {
+        house,
+        subscription,
+    };
+    let result = stream_state.run(&mut writer, backlog, &commands);
+
+    let (subscription, _) = stream_state.subscription;
+    house.with_event_bus(|bus| bus.unsubscribe(subscription));
+    let _ = stream.shutdown(Shutdown::Both);
+    result
+}
+
+/// Проверка запроса на установку соединения и разбор начального фильтра
+fn handshake_filter(request: &HttpRequest) -> Result<EventFilter, HttpResponse> {
+    if request.method != "GET" {
+        return Err(HttpResponse::error(
+            405,
+            "method_not_allowed",
+            "method is not allowed",
+        ));
+    }
+    let upgrade = request
+        .get_header("upgrade")
+        .is_some_and(|value| value.eq_ignore_ascii_case("websocket"));
+    if !upgrade {
+        return Err(HttpResponse::error(
+            426,
+            "upgrade_required",
+            "WebSocket upgrade is required",
+        ));
+    }
+
+    let mut filter = EventFilter::all();
+    for (name, value) in &request.query {
+        filter = match name.as_str() {
+            "room" => filter.room(value),
+            "device" => filter.device(value),
+            "kind" => match EventKind::from_name(value) {
+                Some(kind) => filter.kind(kind),
+                None => {
+                    return Err(HttpResponse::error(
+                        400,
+                        "bad_request",
+                        &format!("unknown event kind {}", value),
+                    ))
+                }
+            },
+            _ => filter,
+        };
+    }
+    Ok(filter)
+}
+
+/// Поток событий одного клиента
+struct ClientStream<'a> {
+    /// Обслуживаемый дом
+    house: &'a SharedHouse,
+
+    /// Подписка на шине событий с текущим фильтром клиента
+    subscription: (SubscriptionId, mpsc::Receiver<HouseEvent>),
+}
+
+impl ClientStream<'_> {
+    /// Отправка повтора и новых событий, обработка управляющих сообщений
+    fn run(
+        &mut self,
+        writer: &mut TcpStream,
+        backlog: Vec<HouseEvent>,
+        commands: &mpsc::Receiver<Control>,
+    ) -> io::Result<()> {
+        for event in backlog {
+            let message = event.to_json().with("replay", true);
+            write_frame(writer, opcode::TEXT, message.to_string().as_bytes())?;
+        }
+
+        loop {
+            while let Ok(command) = commands.try_recv() {
+                match command {
+                    Control::Filter(update) => self.replace_filter(writer, update)?,
+                    Control::Pong(payload) => write_frame(writer, opcode::PONG, &payload)?,
+                    Control::Invalid(message) => {
+                        let message = JsonValue::object()
+                            .with("error", "invalid_message")
+                            .with("message", message);
+                        write_frame(writer, opcode::TEXT, message.to_string().as_bytes())?;
+                    }
+                    Control::Close => return write_frame(writer, opcode::CLOSE, &[]),
+                }
+            }
+
+            match self.subscription.1.recv_timeout(POLL_INTERVAL) {
+                Ok(event) => {
+                    write_frame(writer, opcode::TEXT, event.to_json().to_string().as_bytes())?;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                // Шина удаляет подписку, очередь которой переполнена
+                Err(RecvTimeoutError::Disconnected) => {
+                    let mut payload = CLOSE_QUEUE_OVERFLOW.to_be_bytes().to_vec();
+                    payload.extend_from_slice(b"event queue overflow");
+                    return write_frame(writer, opcode::CLOSE, &payload);
+                }
+            }
+        }
+    }
+
+    /// Замена фильтра клиента
+    ///
+    /// События, полученные по прежней подписке до замены, отправляются до подтверждения
+    fn replace_filter(&mut self, writer: &mut TcpStream, filter: EventFilter) -> io::Result<()> {
+        let (previous, _) = self.subscription;
+        let subscription = self.house.with_event_bus(|bus| {
+            bus.unsubscribe(previous);
+            bus.subscribe_bounded_channel(filter.clone(), CLIENT_QUEUE_CAPACITY)
+        });
+        let (_, pending) = std::mem::replace(&mut self.subscription, subscription);
+        for event in pending.try_iter() {
+            write_frame(writer, opcode::TEXT, event.to_json().to_string().as_bytes())?;
+        }
+
+        let message = JsonValue::object().with("filter", filter_json(&filter));
+        write_frame(writer, opcode::TEXT, message.to_string().as_bytes())
+    }
+}
+
+/// Чтение сообщений клиента до закрытия соединения
+fn read_messages(stream: TcpStream, control: mpsc::Sender<Control>) {
+    let mut reader = BufReader::new(stream);
+    let mut message = Vec::new();
+
+    loop {
+        let Ok((fin, code, payload)) = read_frame(&mut reader) else {
+            let _ = control.send(Control::Close);
+            return;
+        };
+        let command = match code {
+            opcode::PING => Control::Pong(payload),
+            opcode::PONG => continue,
+            opcode::CLOSE => Control::Close,
+            opcode::TEXT | opcode::BINARY | opcode::CONTINUATION => {
+                message.extend_from_slice(&payload);
+                if message.len() > MAX_MESSAGE_SIZE {
+                    Control::Close
+                } else if !fin {
+                    continue;
+                } else {
+                    let command = parse_message(&message);
+                    message.clear();
+                    command
+                }
+            }
+            _ => Control::Close,
+        };
+
+        let closing = matches!(command, Control::Close);
+        if control.send(command).is_err() || closing {
+            return;
+        }
+    }
+}
+
+/// Разбор сообщения клиента с новым фильтром
+fn parse_message(message: &[u8]) -> Control {
+    let Ok(text) = std::str::from_utf8(message) else {
+        return Control::Invalid("message is not valid UTF-8".to_string());
+    };
+    match JsonValue::parse(text) {
+        Ok(value) => parse_filter(&value).map_or_else(
+            || Control::Invalid("expected {\"rooms\", \"devices\", \"kinds\"}".to_string()),
+            Control::Filter,
+        ),
+        Err(error) => Control::Invalid(error.to_string()),
+    }
+}
+
+/// Чтение кадра: (последний кадр сообщения, код операции, данные)
+fn read_frame<R: Read>(reader: &mut R) -> io::Result<(bool, u8, Vec<u8>)> {
+    let mut header = [0; 2];
+    reader.read_exact(&mut header)?;
+    let fin = header[0] & 0x80 != 0;
+    let code = header[0] & 0x0F;
+    let masked = header[1] & 0x80 != 0;
+
+    let length = match header[1] & 0x7F {
+        126 => {
+            let mut extended = [0; 2];
+            reader.read_exact(&mut extended)?;
+            u16::from_be_bytes(extended) as u64
+        }
+        127 => {
+            let mut extended = [0; 8];
+            reader.read_exact(&mut extended)?;
+            u64::from_be_bytes(extended)
+        }
+        length => length as u64,
+    };
+    if length > MAX_MESSAGE_SIZE as u64 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "frame is too large",
+        ));
+    }
+
+    let mut mask = [0; 4];
+    if masked {
+        reader.read_exact(&mut mask)?;
+    }
+    let mut payload = vec![0; length as usize];
+    reader.read_exact(&mut payload)?;
+    if masked {
+        for (index, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[index % 4];
+        }
+    }
+    Ok((fin, code, payload))
+}
+
+/// Запись одиночного (нефрагментированного) кадра сервера
+fn write_frame<W: Write>(writer: &mut W, code: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | code);
+    match payload.len() {
+        length if length < 126 => frame.push(length as u8),
+        length if length <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame)?;
+    writer.flush()
+}
+
+/// Вычисление ключа подтверждения рукопожатия по ключу клиента
+fn accept_key(key: &str) -> String {
+    base64(&sha1(
+        format!("{}{}", key.trim(), HANDSHAKE_GUID).as_bytes(),
+    ))
+}
+
+/// Вычисление хеша SHA-1
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut words = [0u32; 80];
+        for (index, word) in block.chunks(4).enumerate() {
+            words[index] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for index in 16..80 {
+            words[index] =
+                (words[index - 3] ^ words[index - 8] ^ words[index - 14] ^ words[index - 16])
+                    .rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (index, word) in words.iter().enumerate() {
+            let (f, k) = match index {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (value, added) in state.iter_mut().zip([a, b, c, d, e]) {
+            *value = value.wrapping_add(added);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (chunk, value) in digest.chunks_mut(4).zip(state) {
+        chunk.copy_from_slice(&value.to_be_bytes());
+    }
+    digest
+}
+
+/// Кодирование данных в Base64
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let triple = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(triple >> (18 - 6 * index) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handshake_key_matches_rfc_example() {
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+        assert_eq!(base64(b"ab"), "YWI=");
+    }
+
+    #[test]
+    fn filter_messages_are_parsed() {
+        let message = JsonValue::parse(r#"{"rooms": ["Kitchen"], "kinds": ["reading"]}"#);
+        let filter = message.ok().as_ref().and_then(parse_filter);
+        assert_eq!(
+            filter,
+            Some(EventFilter::all().room("Kitchen").kind(EventKind::Reading))
+        );
+        assert_eq!(
+            JsonValue::parse(r#"{"kinds": ["weather"]}"#)
+                .ok()
+                .as_ref()
+                .and_then(parse_filter),
+            None
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! Интеграционные тесты сервера CoAP (запуск: `cargo test --features coap`)
+use iot_crate::coap::message::{code, content_format, option, CoapMessage, CoapType};
+use iot_crate::coap::server::{CoapServer, CoapServerHandle, MAX_RETRANSMIT};
+use iot_crate::house::House;
+use iot_crate::json::JsonValue;
+use iot_crate::room::Room;
+use iot_crate::shared::SharedHouse;
+use iot_crate::smart_device::{SmartDevicePowerState, SmartDeviceStatus};
+use iot_crate::socket::SmartSocket;
+use iot_crate::thermometer::SmartThermometer;
+use iot_crate::units::Watts;
+use std::net::UdpSocket;
+use std::time::Duration;
+
+/// Запуск сервера демонстрационного дома на случайном порту localhost
+fn start_server() -> (SharedHouse, CoapServerHandle) {
+    start_server_with(|server| server)
+}
+
+/// Запуск сервера демонстрационного дома с параметрами, заданными `configure`
+fn start_server_with<F>(configure: F) -> (SharedHouse, CoapServerHandle)
+where
+    F: FnOnce(CoapServer) -> CoapServer,
+{
+    let mut house = House::new("TestHouse", 1);
+    assert!(house.add_room(Room::new("Kitchen", 2)).is_ok());
+
+    let mut socket = SmartSocket::new("Kettle");
+    socket.set_power_consumption(Watts(1500.0));
+    assert!(house.add_device("Kitchen", Box::new(socket)).is_ok());
+    assert!(house
+        .add_device("Kitchen", Box::new(SmartThermometer::new("Thermometer")))
+        .is_ok());
+
+    let house = SharedHouse::try_from(house).expect("house must convert");
+    let server = CoapServer::bind("127.0.0.1:0", house.clone())
+        .map(configure)
+        .and_then(CoapServer::spawn)
+        .expect("server must start");
+    (house, server)
+}
+
+/// Создание клиента, подключённого к серверу `server`
+fn connect(server: &CoapServerHandle) -> UdpSocket {
+    let client = UdpSocket::bind("127.0.0.1:0").expect("client must bind");
+    client
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .expect("timeout must be set");
+    client
+        .connect(server.local_addr())
+        .expect("client must connect");
+    client
+}
+
+/// Получение очередного сообщения сервера
+fn receive(client: &UdpSocket) -> Option<CoapMessage> {
+    let mut buffer = [0u8; 1152];
+    let length = client.recv(&mut buffer).ok()?;
+    Some(CoapMessage::decode(&buffer[..length]).expect("message must be valid"))
+}
+
+/// Выполнение подтверждаемого запроса
+fn request(client: &UdpSocket, message: CoapMessage) -> CoapMessage {
+    client
+        .send(&message.encode())
+        .expect("request must be sent");
+    let response = receive(client).expect("response must be received");
+    assert_eq!(response.message_type, CoapType::Acknowledgement);
+    assert_eq!(response.message_id, message.message_id);
+    assert_eq!(response.token, message.token);
+    response
+}
+
+/// Разбор содержимого ответа в формате JSON
+fn json(message: &CoapMessage) -> JsonValue {
+    assert_eq!(
+        message.get_uint_option(option::CONTENT_FORMAT),
+        Some(content_format::JSON as u32)
+    );
+    JsonValue::parse(std::str::from_utf8(&message.payload).expect("payload must be UTF-8"))
+        .expect("payload must be JSON")
+}
+
+#[test]
+fn device_resources_are_listed_read_and_controlled() {
+    let (house, server) = start_server();
+    let client = connect(&server);
+
+    let links = request(
+        &client,
+        CoapMessage::new(CoapType::Confirmable, code::GET, 1).with_path(&[".well-known", "core"]),
+    );
+    assert_eq!(links.code, code::CONTENT);
+    let links = String::from_utf8(links.payload).expect("links must be UTF-8");
+    assert!(links.contains("</Kitchen/Kettle>;rt=\"socket\";ct=50;obs"));
+    assert!(links.contains("</Kitchen/Thermometer/readings>;ct=50;obs"));
+
+    let state = request(
+        &client,
+        CoapMessage::new(CoapType::Confirmable, code::GET, 2)
+            .with_token(&[7])
+            .with_path(&["Kitchen", "Kettle"]),
+    );
+    assert_eq!(state.code, code::CONTENT);
+    let state = json(&state);
+    assert_eq!(
+        state.get("power_state").and_then(JsonValue::as_str),
+        Some("disabled")
+    );
+
+    let power = CoapMessage::new(CoapType::Confirmable, code::PUT, 3)
+        .with_path(&["Kitchen", "Kettle", "power"])
+        .with_payload(content_format::TEXT, b"ON".to_vec());
+    assert_eq!(request(&client, power).code, code::CHANGED);
+    assert_eq!(
+        house.with_device("Kitchen", "Kettle", |device| device.get_device_status()),
+        Ok(SmartDeviceStatus::PowerState(
+            SmartDevicePowerState::Enabled
+        ))
+    );
+
+    let invalid_power = CoapMessage::new(CoapType::Confirmable, code::PUT, 4)
+        .with_path(&["Kitchen", "Kettle", "power"])
+        .with_payload(content_format::TEXT, b"maybe".to_vec());
+    assert_eq!(request(&client, invalid_power).code, code::BAD_REQUEST);
+
+    let missing =
+        CoapMessage::new(CoapType::Confirmable, code::GET, 5).with_path(&["Kitchen", "Toaster"]);
+    assert_eq!(request(&client, missing).code, code::NOT_FOUND);
+
+    let delete =
+        CoapMessage::new(CoapType::Confirmable, code::DELETE, 6).with_path(&["Kitchen", "Kettle"]);
+    assert_eq!(request(&client, delete).code, code::METHOD_NOT_ALLOWED);
+
+    // Неподтверждаемый запрос получает неподтверждаемый ответ
+    let non_confirmable = CoapMessage::new(CoapType::NonConfirmable, code::GET, 7)
+        .with_token(&[8])
+        .with_path(&["Kitchen", "Kettle", "readings"]);
+    client
+        .send(&non_confirmable.encode())
+        .expect("request must be sent");
+    let response = receive(&client).expect("response must be received");
+    assert_eq!(response.message_type, CoapType::NonConfirmable);
+    assert_eq!(response.token, [8]);
+    let readings = json(&response);
+    assert_eq!(
+        readings.get("power_metering").and_then(JsonValue::as_f64),
+        Some(1500.0)
+    );
+    server.shutdown().expect("server must stop");
+}
+
+#[test]
+fn observers_are_notified_until_device_is_removed() {
+    let (house, server) = start_server();
+    let client = connect(&server);
+
+    let registration = request(
+        &client,
+        CoapMessage::new(CoapType::Confirmable, code::GET, 1)
+            .with_token(&[1, 2])
+            .with_path(&["Kitchen", "Thermometer", "readings"])
+            .with_uint_option(option::OBSERVE, 0),
+    );
+    assert_eq!(registration.code, code::CONTENT);
+    assert_eq!(registration.get_uint_option(option::OBSERVE), Some(0));
+    assert_eq!(json(&registration).get("temperature"), None);
+
+    assert!(house
+        .set_power_state("Kitchen", "Thermometer", SmartDevicePowerState::Enabled)
+        .is_ok());
+    let notification = receive(&client).expect("notification must be received");
+    assert_eq!(notification.message_type, CoapType::NonConfirmable);
+    assert_eq!(notification.code, code::CONTENT);
+    assert_eq!(notification.token, [1, 2]);
+    assert_eq!(notification.get_uint_option(option::OBSERVE), Some(1));
+    assert!(json(&notification).get("temperature").is_some());
+
+    assert!(house.remove_device("Kitchen", "Thermometer").is_ok());
+    let notification = receive(&client).expect("notification must be received");
+    assert_eq!(notification.code, code::NOT_FOUND);
+    assert_eq!(notification.token, [1, 2]);
+    assert_eq!(notification.get_uint_option(option::OBSERVE), None);
+    server.shutdown().expect("server must stop");
+}
+
+#[test]
+fn observation_is_cancelled_by_deregistration() {
+    let (house, server) = start_server();
+    let client = connect(&server);
+
+    let observe = |message_id, value| {
+        CoapMessage::new(CoapType::Confirmable, code::GET, message_id)
+            .with_token(&[3])
+            .with_path(&["Kitchen", "Kettle"])
+            .with_uint_option(option::OBSERVE, value)
+    };
+    assert_eq!(
+        request(&client, observe(1, 0)).get_uint_option(option::OBSERVE),
+        Some(0)
+    );
+    let deregistration = request(&client, observe(2, 1));
+    assert_eq!(deregistration.code, code::CONTENT);
+    assert_eq!(deregistration.get_uint_option(option::OBSERVE), None);
+
+    assert!(house
+        .set_power_state("Kitchen", "Kettle", SmartDevicePowerState::Enabled)
+        .is_ok());
+    client
+        .set_read_timeout(Some(Duration::from_millis(300)))
+        .expect("timeout must be set");
+    assert_eq!(receive(&client), None);
+    server.shutdown().expect("server must stop");
+}
+
+#[test]
+fn observers_that_stop_acknowledging_are_dropped() {
+    let (house, server) = start_server_with(|server| {
+        server
+            .with_confirmable_interval(Duration::from_millis(100))
+            .with_ack_timeout(Duration::from_millis(20))
+    });
+    let client = connect(&server);
+    let registration = request(
+        &client,
+        CoapMessage::new(CoapType::Confirmable, code::GET, 1)
+            .with_token(&[5])
+            .with_path(&["Kitchen", "Kettle"])
+            .with_uint_option(option::OBSERVE, 0),
+    );
+    assert_eq!(registration.get_uint_option(option::OBSERVE), Some(0));
+
+    // Без изменений наблюдатель получает текущее состояние подтверждаемым уведомлением
+    let first = receive(&client).expect("confirmable notification must be received");
+    assert_eq!(first.message_type, CoapType::Confirmable);
+    assert_eq!(first.token, [5]);
+    assert_eq!(first.get_uint_option(option::OBSERVE), Some(1));
+    client
+        .send(&CoapMessage::new(CoapType::Acknowledgement, code::EMPTY, first.message_id).encode())
+        .expect("acknowledgement must be sent");
+
+    // Подтверждённый наблюдатель остаётся зарегистрированным
+    let second = receive(&client).expect("confirmable notification must be received");
+    assert_eq!(second.message_type, CoapType::Confirmable);
+    assert_ne!(second.message_id, first.message_id);
+    assert_eq!(second.get_uint_option(option::OBSERVE), Some(2));
+
+    // Неподтверждённое уведомление повторяется, после чего наблюдатель удаляется
+    client
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .expect("timeout must be set");
+    let mut retransmissions = 0;
+    while let Some(message) = receive(&client) {
+        assert_eq!(message.message_id, second.message_id);
+        retransmissions += 1;
+    }
+    assert_eq!(retransmissions, MAX_RETRANSMIT);
+
+    assert!(house
+        .set_power_state("Kitchen", "Kettle", SmartDevicePowerState::Enabled)
+        .is_ok());
+    client
+        .set_read_timeout(Some(Duration::from_millis(300)))
+        .expect("timeout must be set");
+    assert_eq!(receive(&client), None);
+    server.shutdown().expect("server must stop");
+}
@@ -0,0 +1,144 @@
+//! Локальная замена брокера MQTT для интеграционных тестов
+//!
+//! Поддерживает подключение (с последней волей), подписку с символами `+` и `#`,
+//! сохранение сообщений (`retain`) и их доставку новым подписчикам
+use iot_crate::mqtt::packet::{MqttPacket, MqttWill};
+use iot_crate::mqtt::topic_matches;
+use std::collections::BTreeMap;
+use std::io::BufReader;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Состояние брокера
+#[derive(Default)]
+struct State {
+    /// Подписки: поток клиента и фильтры
+    subscribers: Vec<(TcpStream, Vec<String>)>,
+
+    /// Сохранённые сообщения
+    retained: BTreeMap<String, Vec<u8>>,
+}
+
+/// Брокер, принимающий соединения на случайном порту localhost
+pub struct Broker {
+    address: SocketAddr,
+    state: Arc<Mutex<State>>,
+}
+
+impl Broker {
+    /// Запуск брокера в фоновом потоке
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("broker must bind");
+        let address = listener.local_addr().expect("broker address");
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let shared = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let state = shared.clone();
+                thread::spawn(move || serve(stream, state));
+            }
+        });
+        Self { address, state }
+    }
+
+    /// Адрес брокера
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Сохранённое сообщение топика
+    pub fn retained(&self, topic: &str) -> Option<Vec<u8>> {
+        self.state.lock().unwrap().retained.get(topic).cloned()
+    }
+
+    /// Топики сохранённых сообщений, соответствующие фильтру
+    pub fn retained_topics(&self, filter: &str) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .retained
+            .keys()
+            .filter(|topic| topic_matches(filter, topic))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Обслуживание соединения клиента
+fn serve(stream: TcpStream, state: Arc<Mutex<State>>) {
+    let mut writer = stream.try_clone().expect("stream must be cloned");
+    let mut reader = BufReader::new(stream);
+    let mut will: Option<MqttWill> = None;
+
+    loop {
+        let Ok(packet) = MqttPacket::read_from(&mut reader) else {
+            if let Some(will) = will.take() {
+                route(&state, will.topic, will.payload, will.retain);
+            }
+            return;
+        };
+        match packet {
+            MqttPacket::Connect {
+                will: last_will, ..
+            } => {
+                will = last_will;
+                let _ = MqttPacket::ConnAck { return_code: 0 }.write_to(&mut writer);
+            }
+            MqttPacket::Subscribe { packet_id, filters } => {
+                let mut state = state.lock().unwrap();
+                let _ = MqttPacket::SubAck {
+                    packet_id,
+                    return_codes: vec![0; filters.len()],
+                }
+                .write_to(&mut writer);
+                for (topic, payload) in &state.retained {
+                    if filters.iter().any(|filter| topic_matches(filter, topic)) {
+                        let _ = MqttPacket::Publish {
+                            topic: topic.clone(),
+                            payload: payload.clone(),
+                            retain: true,
+                        }
+                        .write_to(&mut writer);
+                    }
+                }
+                let subscriber = writer.try_clone().expect("stream must be cloned");
+                state.subscribers.push((subscriber, filters));
+            }
+            MqttPacket::Publish {
+                topic,
+                payload,
+                retain,
+            } => route(&state, topic, payload, retain),
+            MqttPacket::PingReq => {
+                // Запись под блокировкой не перемешивается с доставкой сообщений
+                let _state = state.lock().unwrap();
+                let _ = MqttPacket::PingResp.write_to(&mut writer);
+            }
+            MqttPacket::Disconnect => return,
+            _ => {}
+        }
+    }
+}
+
+/// Сохранение и доставка сообщения подписчикам
+fn route(state: &Mutex<State>, topic: String, payload: Vec<u8>, retain: bool) {
+    let mut state = state.lock().unwrap();
+    if retain {
+        if payload.is_empty() {
+            state.retained.remove(&topic);
+        } else {
+            state.retained.insert(topic.clone(), payload.clone());
+        }
+    }
+
+    let packet = MqttPacket::Publish {
+        topic: topic.clone(),
+        payload,
+        retain: false,
+    };
+    state.subscribers.retain_mut(|(stream, filters)| {
+        !filters.iter().any(|filter| topic_matches(filter, &topic))
+            || packet.write_to(stream).is_ok()
+    });
+}
@@ -0,0 +1,22 @@
+//! Общие вспомогательные средства интеграционных тестов
+#![allow(dead_code)]
+
+#[cfg(feature = "mqtt")]
+pub mod broker;
+#[cfg(feature = "modbus")]
+pub mod modbus_device;
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Ожидание выполнения условия `condition` не дольше пяти секунд
+pub fn wait_until<F: FnMut() -> bool>(mut condition: F) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    false
+}
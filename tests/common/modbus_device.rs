@@ -0,0 +1,139 @@
+//! Локальная замена удалённого устройства Modbus TCP для интеграционных тестов
+//!
+//! Устройство хранит по 16 катушек, входных регистров и регистров хранения;
+//! обращение за их пределы приводит к исключению `IllegalDataAddress`
+use iot_crate::modbus::frame::{ModbusException, ModbusFrame, ModbusRequest, ModbusResponse};
+use std::io::BufReader;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Количество элементов каждой области
+const SIZE: usize = 16;
+
+/// Содержимое областей устройства
+#[derive(Default)]
+struct Memory {
+    coils: [bool; SIZE],
+    input_registers: [u16; SIZE],
+    holding_registers: [u16; SIZE],
+}
+
+/// Устройство, принимающее соединения на случайном порту localhost
+pub struct ModbusDevice {
+    address: SocketAddr,
+    memory: Arc<Mutex<Memory>>,
+    online: Arc<AtomicBool>,
+}
+
+impl ModbusDevice {
+    /// Запуск устройства в фоновом потоке
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("device must bind");
+        let address = listener.local_addr().expect("device address");
+        let memory = Arc::new(Mutex::new(Memory::default()));
+        let online = Arc::new(AtomicBool::new(true));
+
+        let (shared, flag) = (memory.clone(), online.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if flag.load(Ordering::SeqCst) {
+                    let (memory, online) = (shared.clone(), flag.clone());
+                    thread::spawn(move || serve(stream, memory, online));
+                }
+            }
+        });
+        Self {
+            address,
+            memory,
+            online,
+        }
+    }
+
+    /// Адрес устройства
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Отключение устройства от сети (`false`) и подключение обратно (`true`)
+    ///
+    /// Отключённое устройство закрывает соединения, не отвечая на запросы
+    pub fn set_online(&self, online: bool) {
+        self.online.store(online, Ordering::SeqCst);
+    }
+
+    /// Значение катушки
+    pub fn coil(&self, address: usize) -> bool {
+        self.memory.lock().unwrap().coils[address]
+    }
+
+    /// Установка значения входного регистра
+    pub fn set_input_register(&self, address: usize, value: u16) {
+        self.memory.lock().unwrap().input_registers[address] = value;
+    }
+
+    /// Установка значения регистра хранения
+    pub fn set_holding_register(&self, address: usize, value: u16) {
+        self.memory.lock().unwrap().holding_registers[address] = value;
+    }
+}
+
+/// Обслуживание соединения клиента
+fn serve(stream: TcpStream, memory: Arc<Mutex<Memory>>, online: Arc<AtomicBool>) {
+    let mut writer = stream.try_clone().expect("stream must be cloned");
+    let mut reader = BufReader::new(stream);
+
+    while let Ok(frame) = ModbusFrame::read_from(&mut reader) {
+        if !online.load(Ordering::SeqCst) {
+            return;
+        }
+        let response = match ModbusRequest::decode(&frame.pdu) {
+            Ok(request) => execute(&mut memory.lock().unwrap(), &request),
+            Err(exception) => ModbusResponse::Exception(exception),
+        };
+        let reply = ModbusFrame {
+            transaction_id: frame.transaction_id,
+            unit_id: frame.unit_id,
+            pdu: response.encode(frame.pdu[0]),
+        };
+        if reply.write_to(&mut writer).is_err() {
+            return;
+        }
+    }
+}
+
+/// Выполнение запроса
+fn execute(memory: &mut Memory, request: &ModbusRequest) -> ModbusResponse {
+    let range = |address: u16, count: usize| {
+        let start = address as usize;
+        (start + count <= SIZE)
+            .then_some(start..start + count)
+            .ok_or(ModbusException::IllegalDataAddress)
+    };
+
+    let result = match request {
+        ModbusRequest::ReadCoils { address, count } => range(*address, *count as usize)
+            .map(|range| ModbusResponse::Coils(memory.coils[range].to_vec())),
+        ModbusRequest::ReadInputRegisters { address, count } => range(*address, *count as usize)
+            .map(|range| ModbusResponse::Registers(memory.input_registers[range].to_vec())),
+        ModbusRequest::ReadHoldingRegisters { address, count } => range(*address, *count as usize)
+            .map(|range| ModbusResponse::Registers(memory.holding_registers[range].to_vec())),
+        ModbusRequest::WriteSingleCoil { address, value } => range(*address, 1).map(|range| {
+            memory.coils[range.start] = *value;
+            ModbusResponse::Written {
+                address: *address,
+                value: if *value { 0xFF00 } else { 0 },
+            }
+        }),
+        ModbusRequest::WriteSingleRegister { address, value } => range(*address, 1).map(|range| {
+            memory.holding_registers[range.start] = *value;
+            ModbusResponse::Written {
+                address: *address,
+                value: *value,
+            }
+        }),
+        _ => Err(ModbusException::IllegalFunction),
+    };
+    result.unwrap_or_else(ModbusResponse::Exception)
+}
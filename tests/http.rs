@@ -0,0 +1,190 @@
+//! Интеграционные тесты HTTP-сервера REST API (запуск: `cargo test --features http`)
+use iot_crate::house::House;
+use iot_crate::http::{HttpServer, HttpServerHandle};
+use iot_crate::json::JsonValue;
+use iot_crate::lock::SmartLock;
+use iot_crate::room::Room;
+use iot_crate::shared::SharedHouse;
+use iot_crate::socket::SmartSocket;
+use iot_crate::thermometer::SmartThermometer;
+use iot_crate::units::Watts;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+
+/// Запуск сервера демонстрационного дома на случайном порту localhost
+fn start_server() -> HttpServerHandle {
+    let mut house = House::new("TestHouse", 3);
+    assert!(house.add_room(Room::new("Kitchen", 3)).is_ok());
+    assert!(house.add_room(Room::new("Living Room", 1)).is_ok());
+
+    let mut socket = SmartSocket::new("Kettle");
+    socket.set_power_consumption(Watts(1500.0));
+    assert!(house.add_device("Kitchen", Box::new(socket)).is_ok());
+    assert!(house
+        .add_device("Kitchen", Box::new(SmartThermometer::new("Thermometer")))
+        .is_ok());
+    assert!(house
+        .add_device("Living Room", Box::new(SmartLock::new("Door")))
+        .is_ok());
+
+    HttpServer::bind(
+        "127.0.0.1:0",
+        SharedHouse::try_from(house).expect("house must convert"),
+    )
+    .and_then(HttpServer::spawn)
+    .expect("server must start")
+}
+
+/// Выполнение запроса; возвращаются код состояния и тело ответа
+fn request(
+    server: &HttpServerHandle,
+    method: &str,
+    path: &str,
+    body: Option<&str>,
+) -> (u16, JsonValue) {
+    let mut stream = TcpStream::connect(server.local_addr()).expect("server must accept");
+    let body = body.unwrap_or_default();
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        body.len(),
+        body
+    )
+    .expect("request must be sent");
+
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .expect("response must be received");
+    let (head, body) = response.split_once("\r\n\r\n").expect("malformed response");
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .expect("malformed status line");
+    (status, JsonValue::parse(body).expect("body must be JSON"))
+}
+
+/// Получение строкового поля ответа
+fn field<'a>(body: &'a JsonValue, key: &str) -> Option<&'a str> {
+    body.get(key).and_then(JsonValue::as_str)
+}
+
+#[test]
+fn rooms_and_devices_are_exposed_as_resources() {
+    let server = start_server();
+
+    let (status, house) = request(&server, "GET", "/house", None);
+    assert_eq!(status, 200);
+    assert_eq!(field(&house, "name"), Some("TestHouse"));
+    assert_eq!(
+        house.get("rooms"),
+        Some(&JsonValue::from(vec!["Kitchen", "Living Room"]))
+    );
+
+    let (status, room) = request(&server, "GET", "/rooms/Living%20Room", None);
+    assert_eq!(status, 200);
+    assert_eq!(room.get("devices"), Some(&JsonValue::from(vec!["Door"])));
+
+    let (status, devices) = request(&server, "GET", "/rooms/Kitchen/devices", None);
+    assert_eq!(status, 200);
+    let kinds: Vec<_> = devices
+        .as_array()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|device| field(device, "kind"))
+        .collect();
+    assert_eq!(kinds, vec!["socket", "thermometer"]);
+
+    let (status, created) = request(
+        &server,
+        "POST",
+        "/rooms",
+        Some(r#"{"name": "Hall", "device_limit": 2}"#),
+    );
+    assert_eq!(status, 201);
+    assert!(field(&created, "message").is_some());
+    let (status, _) = request(&server, "DELETE", "/rooms/Hall", None);
+    assert_eq!(status, 200);
+}
+
+#[test]
+fn power_control_updates_status_and_readings() {
+    let server = start_server();
+    let path = "/rooms/Kitchen/devices/Kettle";
+
+    let (status, body) = request(
+        &server,
+        "PUT",
+        &format!("{}/power", path),
+        Some(r#"{"state": "enabled"}"#),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(
+        body.get("status")
+            .and_then(|status| field(status, "power_state")),
+        Some("enabled")
+    );
+
+    let (status, readings) = request(&server, "GET", &format!("{}/readings", path), None);
+    assert_eq!(status, 200);
+    let reading = &readings.as_array().unwrap_or_default()[0];
+    assert_eq!(field(reading, "capability"), Some("power_metering"));
+    assert_eq!(
+        reading.get("value").and_then(JsonValue::as_f64),
+        Some(1500.0)
+    );
+
+    let (status, device_status) = request(&server, "GET", &format!("{}/status", path), None);
+    assert_eq!(status, 200);
+    assert_eq!(field(&device_status, "power_state"), Some("enabled"));
+}
+
+#[test]
+fn errors_are_mapped_to_http_status_codes() {
+    let server = start_server();
+
+    let (status, body) = request(&server, "GET", "/rooms/Garage", None);
+    assert_eq!(status, 404);
+    assert_eq!(field(&body, "error"), Some("item_doesnt_exist"));
+
+    let (status, body) = request(
+        &server,
+        "POST",
+        "/rooms",
+        Some(r#"{"name": "Kitchen", "device_limit": 1}"#),
+    );
+    assert_eq!(status, 409);
+    assert_eq!(field(&body, "error"), Some("item_already_presented"));
+
+    let (status, _) = request(&server, "POST", "/rooms", Some("{not json"));
+    assert_eq!(status, 400);
+
+    // Лимит устройств из запроса не должен приводить к выделению памяти под него
+    let (status, body) = request(
+        &server,
+        "POST",
+        "/rooms",
+        Some(r#"{"name": "Hangar", "device_limit": 1e15}"#),
+    );
+    assert_eq!(status, 422);
+    assert_eq!(field(&body, "error"), Some("invalid_body"));
+
+    let (status, _) = request(
+        &server,
+        "PUT",
+        "/rooms/Kitchen/devices/Kettle/power",
+        Some(r#"{"state": "sideways"}"#),
+    );
+    assert_eq!(status, 422);
+
+    let (status, _) = request(&server, "PATCH", "/rooms", None);
+    assert_eq!(status, 405);
+
+    let (status, _) = request(&server, "GET", "/garden", None);
+    assert_eq!(status, 404);
+
+    server.shutdown().expect("server must stop");
+}
@@ -0,0 +1,357 @@
+//! Интеграционные тесты шлюза Modbus TCP (запуск: `cargo test --features modbus`)
+mod common;
+
+use common::modbus_device::ModbusDevice;
+use common::wait_until;
+use iot_crate::containers::ErrorReason;
+use iot_crate::house::House;
+use iot_crate::modbus::frame::{ModbusException, ModbusFrame, ModbusRequest, ModbusResponse};
+use iot_crate::modbus::remote::{ModbusRegisters, ModbusSocket, ModbusThermometer};
+use iot_crate::modbus::server::{ModbusServer, ModbusServerHandle};
+use iot_crate::modbus::{
+    power_from_registers, temperature_from_register, RegisterMap, TEMPERATURE_UNAVAILABLE,
+};
+use iot_crate::room::Room;
+use iot_crate::shared::SharedHouse;
+use iot_crate::smart_device::{
+    SmartDevice, SmartDeviceErrorCode, SmartDevicePowerState, SmartDeviceStatus,
+};
+use iot_crate::socket::SmartSocket;
+use iot_crate::thermometer::SmartThermometer;
+use iot_crate::units::{Celsius, Watts};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Период фонового опроса удалённых устройств в тестах
+const REMOTE_REFRESH_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Запуск сервера демонстрационного дома на случайном порту localhost
+///
+/// Карта регистров: `Kitchen/Kettle` - устройство 0, `Kitchen/Thermometer` - устройство 1
+fn start_server() -> (SharedHouse, ModbusServerHandle) {
+    let mut house = House::new("TestHouse", 1);
+    assert!(house.add_room(Room::new("Kitchen", 2)).is_ok());
+
+    let mut socket = SmartSocket::new("Kettle");
+    socket.set_power_consumption(Watts(1500.0));
+    assert!(house.add_device("Kitchen", Box::new(socket)).is_ok());
+    assert!(house
+        .add_device("Kitchen", Box::new(SmartThermometer::new("Thermometer")))
+        .is_ok());
+
+    let house = SharedHouse::try_from(house).expect("house must convert");
+    let server = ModbusServer::bind("127.0.0.1:0", house.clone())
+        .and_then(ModbusServer::spawn)
+        .expect("server must start");
+    (house, server)
+}
+
+/// Выполнение запроса по соединению `stream`
+fn call(stream: &mut TcpStream, transaction_id: u16, request: &ModbusRequest) -> ModbusResponse {
+    ModbusFrame {
+        transaction_id,
+        unit_id: 1,
+        pdu: request.encode(),
+    }
+    .write_to(stream)
+    .expect("request must be sent");
+
+    let frame = ModbusFrame::read_from(stream).expect("response must be received");
+    assert_eq!(frame.transaction_id, transaction_id);
+    assert_eq!(frame.unit_id, 1);
+    ModbusResponse::decode(request, &frame.pdu).expect("response must be valid")
+}
+
+#[test]
+fn coils_control_power_and_registers_report_readings() {
+    let (house, server) = start_server();
+    let mut stream = TcpStream::connect(server.local_addr()).expect("client must connect");
+
+    let coils = ModbusRequest::ReadCoils {
+        address: 0,
+        count: 2,
+    };
+    assert_eq!(
+        call(&mut stream, 1, &coils),
+        ModbusResponse::Coils(vec![false, false])
+    );
+
+    let enable = ModbusRequest::WriteSingleCoil {
+        address: 0,
+        value: true,
+    };
+    assert_eq!(
+        call(&mut stream, 2, &enable),
+        ModbusResponse::Written {
+            address: 0,
+            value: 0xFF00
+        }
+    );
+    assert_eq!(
+        house.with_device("Kitchen", "Kettle", |device| device.get_device_status()),
+        Ok(SmartDeviceStatus::PowerState(
+            SmartDevicePowerState::Enabled
+        ))
+    );
+    assert_eq!(
+        call(&mut stream, 3, &coils),
+        ModbusResponse::Coils(vec![true, false])
+    );
+
+    let inputs = ModbusRequest::ReadInputRegisters {
+        address: 0,
+        count: 6,
+    };
+    let ModbusResponse::Registers(registers) = call(&mut stream, 4, &inputs) else {
+        panic!("input registers must be read");
+    };
+    assert_eq!(registers[0], TEMPERATURE_UNAVAILABLE);
+    assert_eq!(
+        power_from_registers([registers[1], registers[2]]),
+        Watts(1500.0)
+    );
+    let temperature = house
+        .with_device("Kitchen", "Thermometer", |device| device.get_temperature())
+        .expect("thermometer must exist");
+    assert_eq!(temperature_from_register(registers[3]), temperature);
+
+    let errors = ModbusRequest::ReadHoldingRegisters {
+        address: 0,
+        count: 2,
+    };
+    assert_eq!(
+        call(&mut stream, 5, &errors),
+        ModbusResponse::Registers(vec![0, 0])
+    );
+    server.shutdown().expect("server must stop");
+}
+
+#[test]
+fn invalid_requests_produce_exceptions() {
+    let (_house, server) = start_server();
+    let mut stream = TcpStream::connect(server.local_addr()).expect("client must connect");
+
+    let out_of_map = ModbusRequest::ReadCoils {
+        address: 1,
+        count: 2,
+    };
+    assert_eq!(
+        call(&mut stream, 1, &out_of_map),
+        ModbusResponse::Exception(ModbusException::IllegalDataAddress)
+    );
+
+    let invalid_error_code = ModbusRequest::WriteSingleRegister {
+        address: 0,
+        value: 3,
+    };
+    assert_eq!(
+        call(&mut stream, 2, &invalid_error_code),
+        ModbusResponse::Exception(ModbusException::IllegalDataValue)
+    );
+
+    // Неподдерживаемая функция (чтение дискретных входов)
+    ModbusFrame {
+        transaction_id: 3,
+        unit_id: 1,
+        pdu: vec![0x02, 0, 0, 0, 1],
+    }
+    .write_to(&mut stream)
+    .expect("request must be sent");
+    let frame = ModbusFrame::read_from(&mut stream).expect("response must be received");
+    assert_eq!(
+        frame.pdu,
+        [0x82, ModbusException::IllegalFunction.get_code()]
+    );
+}
+
+#[test]
+fn writing_zero_to_error_register_resets_fault() {
+    let mut house = House::new("TestHouse", 1);
+    assert!(house.add_room(Room::new("Kitchen", 1)).is_ok());
+    let mut socket = SmartSocket::new("Kettle");
+    socket.report_fault(SmartDeviceErrorCode::Overheat);
+    assert!(house.add_device("Kitchen", Box::new(socket)).is_ok());
+
+    let house = SharedHouse::try_from(house).expect("house must convert");
+    let server = ModbusServer::bind("127.0.0.1:0", house.clone())
+        .and_then(ModbusServer::spawn)
+        .expect("server must start");
+    let mut stream = TcpStream::connect(server.local_addr()).expect("client must connect");
+
+    let error = ModbusRequest::ReadHoldingRegisters {
+        address: 0,
+        count: 1,
+    };
+    let ModbusResponse::Registers(registers) = call(&mut stream, 1, &error) else {
+        panic!("holding register must be read");
+    };
+    assert_ne!(registers[0], 0);
+
+    let reset = ModbusRequest::WriteSingleRegister {
+        address: 0,
+        value: 0,
+    };
+    assert_eq!(
+        call(&mut stream, 2, &reset),
+        ModbusResponse::Written {
+            address: 0,
+            value: 0
+        }
+    );
+    assert_eq!(
+        house.with_device("Kitchen", "Kettle", |device| device.get_device_status()),
+        Ok(SmartDeviceStatus::PowerState(
+            SmartDevicePowerState::Disabled
+        ))
+    );
+    assert_eq!(
+        call(&mut stream, 3, &error),
+        ModbusResponse::Registers(vec![0])
+    );
+    server.shutdown().expect("server must stop");
+}
+
+/// Создание дома с розеткой и термометром, подключёнными к устройству `device`
+///
+/// Розетка: катушка 2, мощность - входные регистры 4-5, ошибка - регистр хранения 1;
+/// термометр: катушка 3, температура - входной регистр 7.
+/// Устройства опрашиваются в фоновом режиме с периодом `REMOTE_REFRESH_INTERVAL`
+fn create_remote_house(device: &ModbusDevice) -> SharedHouse {
+    let socket = ModbusSocket::new(
+        "Heater",
+        device.address(),
+        ModbusRegisters::new(2)
+            .with_power_register(4)
+            .with_error_register(1),
+    )
+    .with_refresh_interval(REMOTE_REFRESH_INTERVAL);
+    let thermometer = ModbusThermometer::new(
+        "Thermometer",
+        device.address(),
+        ModbusRegisters::new(3).with_temperature_register(7),
+    )
+    .with_refresh_interval(REMOTE_REFRESH_INTERVAL);
+
+    let mut house = House::new("RemoteHouse", 1);
+    assert!(house.add_room(Room::new("Garage", 2)).is_ok());
+    assert!(house.add_device("Garage", Box::new(socket)).is_ok());
+    assert!(house.add_device("Garage", Box::new(thermometer)).is_ok());
+    SharedHouse::try_from(house).expect("house must convert")
+}
+
+#[test]
+fn remote_devices_follow_device_registers() {
+    let device = ModbusDevice::start();
+    let house = create_remote_house(&device);
+
+    assert!(house
+        .set_power_state("Garage", "Heater", SmartDevicePowerState::Enabled)
+        .is_ok());
+    assert!(device.coil(2));
+    device.set_input_register(4, 0);
+    device.set_input_register(5, 20000);
+    assert!(wait_until(|| house.with_device(
+        "Garage",
+        "Heater",
+        |heater| heater.get_power_consumption()
+    ) == Ok(Watts(2000.0))));
+
+    assert!(house
+        .set_power_state("Garage", "Thermometer", SmartDevicePowerState::Enabled)
+        .is_ok());
+    device.set_input_register(7, (-55i16) as u16);
+    assert!(wait_until(|| house.with_device(
+        "Garage",
+        "Thermometer",
+        |thermometer| { thermometer.get_temperature() }
+    ) == Ok(Some(Celsius(-5.5)))));
+
+    device.set_holding_register(1, 3);
+    assert!(wait_until(|| house.with_device(
+        "Garage",
+        "Heater",
+        |heater| heater.get_device_status()
+    ) == Ok(SmartDeviceStatus::Malfunction(
+        SmartDeviceErrorCode::Overheat
+    ))));
+    assert_eq!(
+        house.set_power_state("Garage", "Heater", SmartDevicePowerState::Disabled),
+        Err(ErrorReason::DeviceFailure(SmartDeviceErrorCode::Overheat))
+    );
+    assert!(device.coil(2));
+}
+
+#[test]
+fn unreachable_remote_device_is_reported_as_unreachable() {
+    let device = ModbusDevice::start();
+    let house = create_remote_house(&device);
+    let status = || house.with_device("Garage", "Heater", |heater| heater.get_device_status());
+    assert!(wait_until(|| status()
+        == Ok(SmartDeviceStatus::PowerState(
+            SmartDevicePowerState::Disabled
+        ))));
+
+    device.set_online(false);
+    assert!(wait_until(|| status()
+        == Ok(SmartDeviceStatus::Malfunction(
+            SmartDeviceErrorCode::Unreachable
+        ))));
+    assert_eq!(
+        house.set_power_state("Garage", "Heater", SmartDevicePowerState::Enabled),
+        Err(ErrorReason::DeviceFailure(
+            SmartDeviceErrorCode::Unreachable
+        ))
+    );
+
+    device.set_online(true);
+    assert!(house
+        .set_power_state("Garage", "Heater", SmartDevicePowerState::Enabled)
+        .is_ok());
+    assert!(device.coil(2));
+}
+
+#[test]
+fn remote_socket_controls_house_behind_gateway() {
+    let (gateway_house, server) = start_server();
+    let registers = RegisterMap::new(&gateway_house)
+        .get_entry("Kitchen", "Kettle")
+        .map(ModbusRegisters::from_entry)
+        .expect("kettle must be mapped");
+    let mut kettle = ModbusSocket::new("Kettle", server.local_addr(), registers);
+
+    assert!(kettle
+        .set_power_state(SmartDevicePowerState::Enabled)
+        .is_ok());
+    assert_eq!(
+        gateway_house.with_device("Kitchen", "Kettle", |device| device.get_device_status()),
+        Ok(SmartDeviceStatus::PowerState(
+            SmartDevicePowerState::Enabled
+        ))
+    );
+    assert_eq!(kettle.get_power_consumption(), Watts(1500.0));
+}
+
+#[test]
+fn remote_getters_return_cached_values_until_refresh() {
+    let device = ModbusDevice::start();
+    let socket = ModbusSocket::new(
+        "Heater",
+        device.address(),
+        ModbusRegisters::new(2).with_power_register(4),
+    )
+    .with_refresh_interval(Duration::ZERO);
+    device.set_input_register(5, 100);
+
+    // Без фонового опроса получение статуса не обращается к устройству
+    assert_eq!(
+        socket.get_device_status(),
+        SmartDeviceStatus::Malfunction(SmartDeviceErrorCode::Unreachable)
+    );
+    assert!(socket.refresh().is_ok());
+    assert_eq!(socket.get_power_consumption(), Watts(10.0));
+
+    device.set_input_register(5, 200);
+    assert_eq!(socket.get_power_consumption(), Watts(10.0));
+    assert!(socket.refresh().is_ok());
+    assert_eq!(socket.get_power_consumption(), Watts(20.0));
+}
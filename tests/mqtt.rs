@@ -0,0 +1,239 @@
+//! Интеграционные тесты моста MQTT (запуск: `cargo test --features mqtt`)
+mod common;
+
+use common::broker::Broker;
+use common::wait_until;
+use iot_crate::house::House;
+use iot_crate::json::JsonValue;
+use iot_crate::mqtt::bridge::MqttBridge;
+use iot_crate::mqtt::client::{MqttClient, MqttOptions};
+use iot_crate::mqtt::discovery::DEFAULT_DISCOVERY_PREFIX;
+use iot_crate::room::Room;
+use iot_crate::shared::SharedHouse;
+use iot_crate::smart_device::{SmartDevicePowerState, SmartDeviceStatus};
+use iot_crate::socket::SmartSocket;
+use iot_crate::thermometer::SmartThermometer;
+use iot_crate::units::Watts;
+use std::time::Duration;
+
+/// Создание демонстрационного дома
+fn create_house() -> SharedHouse {
+    let mut house = House::new("TestHouse", 1);
+    assert!(house.add_room(Room::new("Kitchen", 2)).is_ok());
+
+    let mut socket = SmartSocket::new("Kettle");
+    socket.set_power_consumption(Watts(1500.0));
+    assert!(house.add_device("Kitchen", Box::new(socket)).is_ok());
+    assert!(house
+        .add_device("Kitchen", Box::new(SmartThermometer::new("Thermometer")))
+        .is_ok());
+    SharedHouse::try_from(house).expect("house must convert")
+}
+
+/// Разбор сохранённого брокером состояния устройства
+fn retained_state(broker: &Broker, topic: &str) -> Option<JsonValue> {
+    let payload = broker.retained(topic)?;
+    JsonValue::parse(std::str::from_utf8(&payload).ok()?).ok()
+}
+
+/// Получение строкового поля состояния
+fn power_state(state: &JsonValue) -> Option<&str> {
+    state.get("power_state").and_then(JsonValue::as_str)
+}
+
+#[test]
+fn device_states_are_published_and_commands_executed() {
+    let broker = Broker::start();
+    let house = create_house();
+    let bridge = MqttBridge::connect(broker.address(), house.clone())
+        .and_then(MqttBridge::spawn)
+        .expect("bridge must connect");
+
+    let kettle_topic = "TestHouse/Kitchen/Kettle/state";
+    assert!(wait_until(|| broker.retained(kettle_topic).is_some()));
+    assert_eq!(
+        broker.retained("TestHouse/status"),
+        Some(b"online".to_vec())
+    );
+    let state = retained_state(&broker, kettle_topic).expect("state must be JSON");
+    assert_eq!(power_state(&state), Some("disabled"));
+
+    let mut controller = MqttClient::connect(broker.address(), &MqttOptions::new("controller"))
+        .expect("controller must connect");
+    controller
+        .publish("TestHouse/Kitchen/Kettle/set", b"ON", false)
+        .expect("command must be published");
+
+    assert!(wait_until(|| {
+        house.with_device("Kitchen", "Kettle", |device| device.get_device_status())
+            == Ok(SmartDeviceStatus::PowerState(
+                SmartDevicePowerState::Enabled,
+            ))
+    }));
+    assert!(wait_until(|| {
+        retained_state(&broker, kettle_topic).is_some_and(|state| {
+            power_state(&state) == Some("enabled")
+                && state
+                    .get("readings")
+                    .and_then(|readings| readings.get("power_metering"))
+                    == Some(&JsonValue::Number(1500.0))
+        })
+    }));
+
+    assert!(house.remove_device("Kitchen", "Thermometer").is_ok());
+    assert!(wait_until(|| broker
+        .retained("TestHouse/Kitchen/Thermometer/state")
+        .is_none()));
+
+    bridge.shutdown().expect("bridge must disconnect");
+    assert_eq!(
+        broker.retained("TestHouse/status"),
+        Some(b"offline".to_vec())
+    );
+}
+
+#[test]
+fn subscribers_receive_sampled_readings() {
+    let broker = Broker::start();
+    let house = create_house();
+    let _bridge = MqttBridge::connect(broker.address(), house.clone())
+        .and_then(MqttBridge::spawn)
+        .expect("bridge must connect");
+    assert!(wait_until(|| broker
+        .retained_topics("TestHouse/+/+/state")
+        .len()
+        == 2));
+
+    let mut dashboard = MqttClient::connect(broker.address(), &MqttOptions::new("dashboard"))
+        .expect("dashboard must connect");
+    dashboard
+        .subscribe("TestHouse/Kitchen/Thermometer/state")
+        .expect("subscription must succeed");
+    let retained = dashboard
+        .receive(Some(Duration::from_secs(5)))
+        .expect("connection must stay open")
+        .expect("retained state must be delivered");
+    assert!(retained.retain);
+
+    assert!(house
+        .set_power_state("Kitchen", "Thermometer", SmartDevicePowerState::Enabled)
+        .is_ok());
+    house.sample_readings();
+
+    let mut live = None;
+    while let Ok(Some(message)) = dashboard.receive(Some(Duration::from_secs(5))) {
+        let state = JsonValue::parse(std::str::from_utf8(&message.payload).unwrap_or_default());
+        if state.as_ref().is_ok_and(|state| {
+            power_state(state) == Some("enabled")
+                && state
+                    .get("readings")
+                    .and_then(|readings| readings.get("temperature"))
+                    .is_some()
+        }) {
+            live = Some(message);
+            break;
+        }
+    }
+    assert!(live.is_some_and(|message| !message.retain));
+}
+
+#[test]
+fn home_assistant_discovery_configs_are_published() {
+    let broker = Broker::start();
+    let house = create_house();
+    let bridge = MqttBridge::connect(broker.address(), house.clone())
+        .map(|bridge| bridge.with_discovery(DEFAULT_DISCOVERY_PREFIX))
+        .and_then(MqttBridge::spawn)
+        .expect("bridge must connect");
+
+    assert!(wait_until(|| broker
+        .retained_topics("homeassistant/#")
+        .len()
+        == 3));
+    let switch = retained_state(
+        &broker,
+        "homeassistant/switch/testhouse/kitchen_kettle_1767a30c_switch/config",
+    )
+    .expect("switch config must be JSON");
+    assert_eq!(
+        switch.get("command_topic").and_then(JsonValue::as_str),
+        Some("TestHouse/Kitchen/Kettle/set")
+    );
+    assert_eq!(
+        switch.get("state_topic").and_then(JsonValue::as_str),
+        Some("TestHouse/Kitchen/Kettle/state")
+    );
+
+    let power = retained_state(
+        &broker,
+        "homeassistant/sensor/testhouse/kitchen_kettle_1767a30c_power/config",
+    )
+    .expect("power sensor config must be JSON");
+    assert_eq!(
+        power.get("device_class").and_then(JsonValue::as_str),
+        Some("power")
+    );
+    assert_eq!(
+        power.get("unit_of_measurement").and_then(JsonValue::as_str),
+        Some("W")
+    );
+
+    let temperature = retained_state(
+        &broker,
+        "homeassistant/sensor/testhouse/kitchen_thermometer_1ebd5a02_temperature/config",
+    )
+    .expect("temperature sensor config must be JSON");
+    assert_eq!(
+        temperature.get("device_class").and_then(JsonValue::as_str),
+        Some("temperature")
+    );
+    assert_eq!(
+        temperature
+            .get("unit_of_measurement")
+            .and_then(JsonValue::as_str),
+        Some("°C")
+    );
+
+    assert!(house.remove_device("Kitchen", "Kettle").is_ok());
+    assert!(wait_until(|| broker
+        .retained_topics("homeassistant/#")
+        .len()
+        == 1));
+    bridge.shutdown().expect("bridge must disconnect");
+}
+
+#[test]
+fn similar_device_names_get_separate_topics() {
+    let broker = Broker::start();
+    let house = SharedHouse::new("TestHouse", 1);
+    assert!(house.add_room("Kitchen", 2).is_ok());
+    for name in ["Kettle+1", "Kettle#1"] {
+        assert!(house
+            .add_device("Kitchen", Box::new(SmartSocket::new(name)))
+            .is_ok());
+    }
+    let _bridge = MqttBridge::connect(broker.address(), house.clone())
+        .and_then(MqttBridge::spawn)
+        .expect("bridge must connect");
+    assert!(wait_until(|| broker
+        .retained_topics("TestHouse/+/+/state")
+        .len()
+        == 2));
+
+    let mut controller = MqttClient::connect(broker.address(), &MqttOptions::new("controller"))
+        .expect("controller must connect");
+    controller
+        .publish("TestHouse/Kitchen/Kettle%231/set", b"ON", false)
+        .expect("command must be published");
+
+    assert!(wait_until(|| {
+        retained_state(&broker, "TestHouse/Kitchen/Kettle%231/state")
+            .is_some_and(|state| power_state(&state) == Some("enabled"))
+    }));
+    assert_eq!(
+        house.with_device("Kitchen", "Kettle+1", |device| device.get_device_status()),
+        Ok(SmartDeviceStatus::PowerState(
+            SmartDevicePowerState::Disabled
+        ))
+    );
+}
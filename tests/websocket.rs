@@ -0,0 +1,168 @@
+//! Интеграционные тесты потока событий WebSocket (запуск: `cargo test --features websocket`)
+use iot_crate::house::House;
+use iot_crate::http::{HttpServer, HttpServerHandle};
+use iot_crate::json::JsonValue;
+use iot_crate::lock::SmartLock;
+use iot_crate::room::Room;
+use iot_crate::shared::SharedHouse;
+use iot_crate::smart_device::SmartDevicePowerState;
+use iot_crate::socket::SmartSocket;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Запуск сервера демонстрационного дома; возвращаются дескрипторы сервера и дома
+fn start_server() -> (HttpServerHandle, SharedHouse) {
+    let mut house = House::new("TestHouse", 2);
+    assert!(house.add_room(Room::new("Kitchen", 1)).is_ok());
+    assert!(house.add_room(Room::new("Living Room", 1)).is_ok());
+    assert!(house
+        .add_device("Kitchen", Box::new(SmartSocket::new("Kettle")))
+        .is_ok());
+    assert!(house
+        .add_device("Living Room", Box::new(SmartLock::new("Door")))
+        .is_ok());
+
+    let house = SharedHouse::try_from(house).expect("house must convert");
+    let server = HttpServer::bind("127.0.0.1:0", house.clone())
+        .map(|server| server.with_event_backlog(16))
+        .and_then(HttpServer::spawn)
+        .expect("server must start");
+    (server, house)
+}
+
+/// Клиент WebSocket
+struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Client {
+    /// Подключение к `/events` с параметрами запроса `query`
+    fn connect(server: &HttpServerHandle, query: &str) -> Self {
+        let mut writer = TcpStream::connect(server.local_addr()).expect("server must accept");
+        writer
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .expect("timeout must be set");
+        write!(
+            writer,
+            "GET /events{} HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+            query
+        )
+        .expect("handshake must be sent");
+
+        let mut reader = BufReader::new(writer.try_clone().expect("stream must be cloned"));
+        let mut line = String::new();
+        reader.read_line(&mut line).expect("status must be read");
+        assert!(
+            line.starts_with("HTTP/1.1 101"),
+            "unexpected status {}",
+            line
+        );
+        let mut accept = None;
+        loop {
+            line.clear();
+            reader.read_line(&mut line).expect("header must be read");
+            if line.trim_end().is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Sec-WebSocket-Accept:") {
+                accept = Some(value.trim().to_string());
+            }
+        }
+        assert_eq!(accept.as_deref(), Some("s3pPLMBiTxaQ9kYGzzhZRbK+xOo="));
+        Self { reader, writer }
+    }
+
+    /// Отправка текстового сообщения (кадры клиента маскируются)
+    fn send(&mut self, text: &str) {
+        let mask = [0x12, 0x34, 0x56, 0x78];
+        let mut frame = vec![0x81, 0x80 | text.len() as u8];
+        frame.extend_from_slice(&mask);
+        frame.extend(
+            text.bytes()
+                .enumerate()
+                .map(|(index, byte)| byte ^ mask[index % 4]),
+        );
+        self.writer.write_all(&frame).expect("frame must be sent");
+    }
+
+    /// Получение следующего текстового сообщения
+    fn receive(&mut self) -> JsonValue {
+        let mut header = [0; 2];
+        self.reader
+            .read_exact(&mut header)
+            .expect("frame must be received");
+        assert_eq!(header[0], 0x81, "expected a text frame");
+        let length = match header[1] {
+            126 => {
+                let mut extended = [0; 2];
+                self.reader.read_exact(&mut extended).expect("length");
+                u16::from_be_bytes(extended) as usize
+            }
+            length => length as usize,
+        };
+        let mut payload = vec![0; length];
+        self.reader.read_exact(&mut payload).expect("payload");
+        JsonValue::parse(std::str::from_utf8(&payload).expect("UTF-8")).expect("JSON")
+    }
+}
+
+/// Получение строкового поля сообщения
+fn field<'a>(message: &'a JsonValue, key: &str) -> Option<&'a str> {
+    message.get(key).and_then(JsonValue::as_str)
+}
+
+#[test]
+fn backlog_is_replayed_before_live_events() {
+    let (server, house) = start_server();
+    assert!(house
+        .set_power_state("Kitchen", "Kettle", SmartDevicePowerState::Enabled)
+        .is_ok());
+    assert!(house
+        .set_power_state("Living Room", "Door", SmartDevicePowerState::Enabled)
+        .is_ok());
+
+    let mut client = Client::connect(&server, "?device=Kettle&kind=power_change");
+    let replayed = client.receive();
+    assert_eq!(field(&replayed, "event"), Some("power_state_changed"));
+    assert_eq!(field(&replayed, "power_state"), Some("enabled"));
+    assert_eq!(replayed.get("replay"), Some(&JsonValue::Bool(true)));
+
+    assert!(house
+        .set_power_state("Living Room", "Door", SmartDevicePowerState::Disabled)
+        .is_ok());
+    assert!(house
+        .set_power_state("Kitchen", "Kettle", SmartDevicePowerState::Disabled)
+        .is_ok());
+    let live = client.receive();
+    assert_eq!(field(&live, "device"), Some("Kettle"));
+    assert_eq!(field(&live, "power_state"), Some("disabled"));
+    assert_eq!(live.get("replay"), None);
+}
+
+#[test]
+fn client_filter_can_be_replaced() {
+    let (server, house) = start_server();
+    let mut client = Client::connect(&server, "?kind=reading");
+
+    client.send(r#"{"rooms": ["Living Room"], "kinds": ["power_change", "malfunction"]}"#);
+    let ack = client.receive();
+    assert_eq!(
+        ack.get("filter").and_then(|filter| filter.get("rooms")),
+        Some(&JsonValue::from(vec!["Living Room"]))
+    );
+
+    assert!(house
+        .set_power_state("Kitchen", "Kettle", SmartDevicePowerState::Enabled)
+        .is_ok());
+    assert!(house
+        .set_power_state("Living Room", "Door", SmartDevicePowerState::Enabled)
+        .is_ok());
+    let event = client.receive();
+    assert_eq!(field(&event, "room"), Some("Living Room"));
+    assert_eq!(field(&event, "kind"), Some("power_change"));
+
+    client.send("not json");
+    assert_eq!(field(&client.receive(), "error"), Some("invalid_message"));
+}